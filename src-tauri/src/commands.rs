@@ -2,20 +2,36 @@ use log::{info, warn};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{Emitter, Manager, State};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
 
+use crate::device::fec::FecCommandStatus;
 use crate::device::manager::DeviceManager;
-use crate::device::types::{DeviceDetails, DeviceInfo, DeviceType, SensorReading};
+use crate::device::types::{DeviceDetails, DeviceHealth, DeviceInfo, DeviceType, SensorReading, TrainerCapabilities};
 use crate::error::AppError;
 use crate::prerequisites;
-use crate::session::analysis::{self, PowerCurvePoint, SessionAnalysis};
+use crate::session::analysis::{
+    self, BestEffort, Interval, PowerCurveComparison, PowerCurvePoint, PowerSourceComparison,
+    SessionAnalysis,
+};
+use crate::session::csv_export;
 use crate::session::fit_export;
+use crate::session::fit_import;
 use crate::session::manager::SessionManager;
-use crate::session::storage::Storage;
-use crate::session::types::{SessionConfig, SessionSummary};
+use crate::session::strava::{self, StravaClient};
+use crate::session::storage::{
+    CalibrationEntry, FtpHistoryEntry, LoadPoint, PeriodGranularity, PeriodSummary, SharedStorage,
+    Storage,
+};
+use crate::session::types::{
+    SessionConfig, SessionSegment, SessionSummary, StructuredRpe, TaskIntervals,
+};
 use crate::session::analysis::{compute_hr_power_regression, TimeseriesPoint};
+use crate::session::ramp_test::{RampTestParams, RampTestResult, RampTestRunner, RampTestStatus};
+use crate::session::workout::{self, Workout, WorkoutRunner, WorkoutStatus};
 use crate::session::zone_control::controller::ZoneController;
-use crate::session::zone_control::types::{StopReason, ZoneControlStatus, ZoneMode, ZoneTarget};
+use crate::session::zone_control::types::{
+    StopReason, ZoneControlConfig, ZoneControlStatus, ZoneMode, ZoneTarget, MAX_POWER_BIAS_PCT,
+};
 
 /// Validate that a session ID from the frontend is a safe UUID string.
 /// Prevents path traversal via crafted IDs like "../../etc/passwd".
@@ -40,6 +56,29 @@ fn validate_zones_ascending<T: PartialOrd>(zones: &[T], label: &str) -> Result<(
     Ok(())
 }
 
+/// Validate a background task interval against its minimum.
+fn validate_interval_secs(secs: u32, min_secs: u64, label: &str) -> Result<(), AppError> {
+    if (secs as u64) < min_secs {
+        return Err(AppError::Session(format!(
+            "{} must be at least {}s",
+            label, min_secs
+        )));
+    }
+    Ok(())
+}
+
+/// Pass new task periods to the background tasks. Unchanged periods don't
+/// notify, so saving unrelated settings doesn't restart their waits.
+fn publish_task_intervals(sender: &watch::Sender<TaskIntervals>, intervals: TaskIntervals) {
+    sender.send_if_modified(|current| {
+        if *current == intervals {
+            return false;
+        }
+        *current = intervals;
+        true
+    });
+}
+
 /// Format primary devices map for the frontend (DeviceType → stable string keys).
 fn format_primaries(primaries: &HashMap<DeviceType, String>) -> HashMap<String, String> {
     primaries
@@ -51,10 +90,14 @@ fn format_primaries(primaries: &HashMap<DeviceType, String>) -> HashMap<String,
 pub struct AppState {
     pub device_manager: Arc<tokio::sync::Mutex<DeviceManager>>,
     pub session_manager: Arc<SessionManager>,
-    pub storage: Arc<Storage>,
+    pub storage: SharedStorage,
     pub sensor_tx: broadcast::Sender<SensorReading>,
     pub primary_devices: Arc<std::sync::RwLock<HashMap<DeviceType, String>>>,
     pub zone_controller: Arc<tokio::sync::Mutex<ZoneController>>,
+    pub workout_runner: Arc<tokio::sync::Mutex<WorkoutRunner>>,
+    pub ramp_test: Arc<tokio::sync::Mutex<RampTestRunner>>,
+    /// Autosave/watchdog periods from user config, observed by those tasks
+    pub task_intervals: watch::Sender<TaskIntervals>,
     #[cfg(not(feature = "production"))]
     pub simulator: Arc<tokio::sync::Mutex<crate::simulator::Simulator>>,
 }
@@ -63,14 +106,43 @@ pub struct AppState {
 pub async fn scan_devices(
     state: State<'_, AppState>,
     app: tauri::AppHandle,
+    scan_duration_secs: Option<u64>,
+    ant_retries: Option<u32>,
 ) -> Result<Vec<DeviceInfo>, AppError> {
-    info!("Scanning for devices");
+    let scan_duration_secs = scan_duration_secs.unwrap_or(crate::config::BLE_SCAN_DURATION_SECS);
+    if !(1..=crate::config::MAX_SCAN_DURATION_SECS).contains(&scan_duration_secs) {
+        return Err(AppError::Session(format!(
+            "Scan duration must be between 1 and {} seconds",
+            crate::config::MAX_SCAN_DURATION_SECS
+        )));
+    }
+    let ant_retries = ant_retries.unwrap_or(0);
+    if ant_retries > crate::config::MAX_ANT_SCAN_RETRIES {
+        return Err(AppError::Session(format!(
+            "ANT+ scan retries must be at most {}",
+            crate::config::MAX_ANT_SCAN_RETRIES
+        )));
+    }
+    info!("Scanning for devices ({}s, {} ANT+ retries)", scan_duration_secs, ant_retries);
     let mut dm = state.device_manager.lock().await;
-    let devices = dm.scan_all().await?;
+    let devices = dm.scan_all(scan_duration_secs, ant_retries).await?;
     let _ = app.emit("device_list_updated", &devices);
     Ok(devices)
 }
 
+/// Re-select the primary after a connect and tell the frontend if it moved.
+pub(crate) async fn emit_primary_change(app: &tauri::AppHandle, dm: &DeviceManager, device_id: &str) {
+    if let Some((device_type, primary_id)) = dm.select_primary(device_id).await {
+        let _ = app.emit(
+            "primary_changed",
+            &serde_json::json!({
+                "device_type": device_type,
+                "device_id": primary_id,
+            }),
+        );
+    }
+}
+
 #[tauri::command]
 pub async fn connect_device(
     state: State<'_, AppState>,
@@ -81,6 +153,26 @@ pub async fn connect_device(
     let tx = state.sensor_tx.clone();
     let mut dm = state.device_manager.lock().await;
     let info = dm.connect(&device_id, tx).await?;
+    emit_primary_change(&app, &dm, &info.id).await;
+
+    let all = dm.list_current().await;
+    let _ = app.emit("device_list_updated", &all);
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn pair_ant_device(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    device_type: DeviceType,
+    device_number: u16,
+) -> Result<DeviceInfo, AppError> {
+    info!("Pairing ANT+ {:?} device number {}", device_type, device_number);
+    let tx = state.sensor_tx.clone();
+    let mut dm = state.device_manager.lock().await;
+    let info = dm.pair_ant(device_type, device_number, tx).await?;
+    emit_primary_change(&app, &dm, &info.id).await;
 
     let all = dm.list_current().await;
     let _ = app.emit("device_list_updated", &all);
@@ -105,16 +197,42 @@ pub async fn disconnect_device(
     Ok(())
 }
 
+/// Disconnect (if needed) and permanently remove a device from the known list.
+#[tauri::command]
+pub async fn forget_device(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    device_id: String,
+) -> Result<(), AppError> {
+    info!("Forgetting device: {}", device_id);
+    let mut dm = state.device_manager.lock().await;
+    dm.clear_reconnect_target(&device_id);
+    if dm.is_connected(&device_id) {
+        dm.disconnect(&device_id).await?;
+    }
+    dm.remove_primary(&device_id);
+    dm.unpin_primary(&device_id);
+    state.storage.current().delete_known_device(&device_id).await?;
+
+    let all = dm.list_current().await;
+    let _ = app.emit("device_list_updated", &all);
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn start_session(state: State<'_, AppState>) -> Result<String, AppError> {
     info!("Starting session");
-    let config = state.storage.get_user_config().await?;
+    let config = state.storage.current().get_user_config().await?;
     let id = state.session_manager.start_session(config).await?;
     Ok(id)
 }
 
 #[tauri::command]
-pub async fn stop_session(state: State<'_, AppState>) -> Result<Option<SessionSummary>, AppError> {
+pub async fn stop_session(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<SessionSummary>, AppError> {
     let result = state.session_manager.stop_session_with_log().await;
 
     if let Some((ref summary, ref sensor_log)) = result {
@@ -124,18 +242,26 @@ pub async fn stop_session(state: State<'_, AppState>) -> Result<Option<SessionSu
         );
         let raw_data = bincode::serialize(sensor_log)
             .map_err(|e| AppError::Serialization(e.to_string()))?;
-        state.storage.save_session(summary, &raw_data).await?;
-        state.storage.remove_autosave(&summary.id);
+        state.storage.current().save_session(summary, &raw_data).await?;
+        state.storage.current().remove_autosave(&summary.id);
 
-        // Save power curve in background
-        let storage = state.storage.clone();
+        // Save power curve in background and announce any records it broke
+        let storage = state.storage.current();
         let session_id = summary.id.clone();
         let readings = sensor_log.clone();
         tokio::spawn(async move {
             let curve = analysis::compute_power_curve_from_readings(&readings);
             if !curve.is_empty() {
-                if let Err(e) = storage.save_power_curve(&session_id, &curve).await {
-                    log::warn!("Failed to save power curve: {}", e);
+                match storage.save_power_curve_with_records(&session_id, &curve).await {
+                    Ok(records) if !records.is_empty() => {
+                        info!("Session {} set {} power records", session_id, records.len());
+                        let _ = app.emit(
+                            "new_personal_best",
+                            &analysis::NewPersonalBests { session_id, records },
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Failed to save power curve: {}", e),
                 }
             }
         });
@@ -146,6 +272,57 @@ pub async fn stop_session(state: State<'_, AppState>) -> Result<Option<SessionSu
     Ok(result.map(|(summary, _)| summary))
 }
 
+/// Drop the active session without saving it, e.g. after an accidental start.
+#[tauri::command]
+pub async fn discard_session(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    let id = state.session_manager.discard_session().await;
+    match &id {
+        Some(id) => {
+            info!("Session discarded: id={}", id);
+            state.storage.current().remove_autosave(id);
+        }
+        None => info!("Discard session: no active session"),
+    }
+    Ok(id)
+}
+
+/// Record power from every connected meter (not just the primary) for the
+/// rest of the active session, for side-by-side comparison afterwards.
+#[tauri::command]
+pub async fn set_dual_record(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    state.session_manager.set_dual_record(enabled).await
+}
+
+/// Average heart rate across every connected strap instead of using only the
+/// primary one.
+#[tauri::command]
+pub async fn set_hr_fusion(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    state.session_manager.set_hr_fusion(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_hr_fusion(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.session_manager.hr_fusion_enabled())
+}
+
+/// Log raw BLE notifications and ANT+ pages as hex, for debugging a flaky sensor.
+#[tauri::command]
+pub async fn set_packet_trace(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    state.device_manager.lock().await.set_packet_trace(enabled);
+    Ok(())
+}
+
+/// Start a new lap in the active session; returns the new lap number.
+#[tauri::command]
+pub async fn new_lap(state: State<'_, AppState>) -> Result<u32, AppError> {
+    state
+        .session_manager
+        .new_lap()
+        .await
+        .ok_or_else(|| AppError::Session("No active session".into()))
+}
+
 #[tauri::command]
 pub async fn pause_session(state: State<'_, AppState>) -> Result<(), AppError> {
     state.session_manager.pause_session().await;
@@ -159,8 +336,23 @@ pub async fn resume_session(state: State<'_, AppState>) -> Result<(), AppError>
 }
 
 #[tauri::command]
-pub async fn list_sessions(state: State<'_, AppState>) -> Result<Vec<SessionSummary>, AppError> {
-    state.storage.list_sessions().await.map_err(AppError::from)
+pub async fn list_sessions(
+    state: State<'_, AppState>,
+    favorites_only: Option<bool>,
+) -> Result<Vec<SessionSummary>, AppError> {
+    state
+        .storage
+        .current()
+        .list_sessions_filtered(favorites_only.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<SessionSummary>, AppError> {
+    state.storage.current().search_sessions(&query).await
 }
 
 #[tauri::command]
@@ -169,30 +361,234 @@ pub async fn get_session(
     session_id: String,
 ) -> Result<SessionSummary, AppError> {
     validate_session_id(&session_id)?;
-    state.storage.get_session(&session_id).await
+    state.storage.current().get_session(&session_id).await
 }
 
 #[tauri::command]
 pub async fn get_session_analysis(
     state: State<'_, AppState>,
     session_id: String,
+    smoothing_secs: Option<u32>,
+    units: Option<String>,
+    as_ftp_pct: Option<bool>,
 ) -> Result<SessionAnalysis, AppError> {
     validate_session_id(&session_id)?;
-    let session = state.storage.get_session(&session_id).await?;
-    let config = state.storage.get_user_config().await?;
-    let storage = state.storage.clone();
+    let session = state.storage.current().get_session(&session_id).await?;
+    let config = state.storage.current().get_user_config().await?;
+    // Default to the user's display units
+    let units = units.unwrap_or_else(|| config.units.clone());
+    let ftp = session.ftp.unwrap_or(config.ftp);
+    let segments = state.storage.current().list_session_segments(&session_id).await?;
+    let storage = state.storage.current();
     let sid = session_id.clone();
+    let mut analysis = tokio::task::spawn_blocking(move || {
+        let samples = storage.load_analysis_samples(&sid)?;
+        Ok::<_, AppError>(analysis::compute_analysis(
+            &samples,
+            &session,
+            &config,
+            smoothing_secs,
+            &units,
+        ))
+    })
+    .await
+    .map_err(|e| AppError::Session(format!("Analysis failed: {}", e)))??;
+    analysis.segments = segments;
+    if as_ftp_pct.unwrap_or(false) {
+        analysis::annotate_ftp_pct(&mut analysis.power_curve, ftp);
+    }
+    Ok(analysis)
+}
+
+/// Label a time range of a saved session (e.g. warmup, work, cooldown).
+#[tauri::command]
+pub async fn add_session_segment(
+    state: State<'_, AppState>,
+    session_id: String,
+    start_secs: u64,
+    end_secs: u64,
+    label: String,
+) -> Result<SessionSegment, AppError> {
+    validate_session_id(&session_id)?;
+    let session = state.storage.current().get_session(&session_id).await?;
+    if end_secs > session.duration_secs {
+        return Err(AppError::Session(format!(
+            "Segment end {}s is past the end of the session ({}s)",
+            end_secs, session.duration_secs
+        )));
+    }
+    state
+        .storage
+        .current()
+        .add_session_segment(&session_id, start_secs, end_secs, &label)
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_session_segment(
+    state: State<'_, AppState>,
+    segment_id: i64,
+) -> Result<(), AppError> {
+    state.storage.current().delete_session_segment(segment_id).await
+}
+
+#[tauri::command]
+pub async fn get_session_segments(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<SessionSegment>, AppError> {
+    validate_session_id(&session_id)?;
+    state.storage.current().list_session_segments(&session_id).await
+}
+
+/// Compute a session's FTP estimate off the async runtime.
+async fn session_ftp_estimate(
+    storage: &Arc<Storage>,
+    session_id: &str,
+) -> Result<Option<u16>, AppError> {
+    validate_session_id(session_id)?;
+    let storage = storage.clone();
+    let sid = session_id.to_string();
     tokio::task::spawn_blocking(move || {
         let readings = storage.load_sensor_data(&sid)?;
-        Ok::<_, AppError>(analysis::compute_analysis(&readings, &session, &config))
+        let curve = analysis::compute_power_curve_from_readings(&readings);
+        Ok::<_, AppError>(analysis::estimate_ftp(&curve))
     })
     .await
-    .map_err(|e| AppError::Session(format!("Analysis failed: {}", e)))?
+    .map_err(|e| AppError::Session(format!("FTP estimate failed: {}", e)))?
+}
+
+/// Estimate FTP from a session's best 20-minute power.
+#[tauri::command]
+pub async fn estimate_ftp(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<u16>, AppError> {
+    session_ftp_estimate(&state.storage.current(), &session_id).await
+}
+
+/// Record a session's FTP estimate in the history, dated to the session.
+#[tauri::command]
+pub async fn accept_ftp_estimate(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<u16, AppError> {
+    let ftp = session_ftp_estimate(&state.storage.current(), &session_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::Session(format!(
+                "Session {} has no 20-minute effort to estimate FTP from",
+                session_id
+            ))
+        })?;
+    let session = state.storage.current().get_session(&session_id).await?;
+    let date = session.start_time.format("%Y-%m-%d").to_string();
+    state.storage.current().record_ftp(ftp, &date, "estimate").await?;
+    info!("Accepted FTP estimate {}W from session {}", ftp, session_id);
+    Ok(ftp)
+}
+
+/// Re-score a saved ride against a corrected FTP.
+#[tauri::command]
+pub async fn recompute_session_metrics(
+    state: State<'_, AppState>,
+    session_id: String,
+    ftp: u16,
+) -> Result<SessionSummary, AppError> {
+    validate_session_id(&session_id)?;
+    if ftp == 0 {
+        return Err(AppError::Session("FTP must be greater than 0W".into()));
+    }
+    state.storage.current().recompute_session_metrics(&session_id, ftp).await
+}
+
+/// Re-score every ride from `date` (`YYYY-MM-DD`) on; returns how many were updated.
+#[tauri::command]
+pub async fn recompute_all_after(
+    state: State<'_, AppState>,
+    date: String,
+    ftp: u16,
+) -> Result<usize, AppError> {
+    if ftp == 0 {
+        return Err(AppError::Session("FTP must be greater than 0W".into()));
+    }
+    state.storage.current().recompute_all_after(&date, ftp).await
+}
+
+#[tauri::command]
+pub async fn get_ftp_history(state: State<'_, AppState>) -> Result<Vec<FtpHistoryEntry>, AppError> {
+    state.storage.current().get_ftp_history().await
+}
+
+/// Store the result of a trainer calibration (zero offset and/or spin-down time)
+/// against the current time.
+#[tauri::command]
+pub async fn record_calibration(
+    state: State<'_, AppState>,
+    device_id: String,
+    zero_offset: Option<u16>,
+    spindown_ms: Option<u16>,
+) -> Result<(), AppError> {
+    if zero_offset.is_none() && spindown_ms.is_none() {
+        return Err(AppError::Session("Calibration result has no values".into()));
+    }
+    info!(
+        "Calibration for {}: zero offset {:?}, spin-down {:?}ms",
+        device_id, zero_offset, spindown_ms
+    );
+    let date = chrono::Utc::now().to_rfc3339();
+    state
+        .storage
+        .current()
+        .record_calibration(&device_id, &date, zero_offset, spindown_ms)
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_calibration_history(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> Result<Vec<CalibrationEntry>, AppError> {
+    state.storage.current().get_calibration_history(&device_id).await
+}
+
+#[tauri::command]
+pub async fn get_session_intervals(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<Interval>, AppError> {
+    validate_session_id(&session_id)?;
+    let session = state.storage.current().get_session(&session_id).await?;
+    let config = state.storage.current().get_user_config().await?;
+    let ftp = session.ftp.unwrap_or(config.ftp);
+    let storage = state.storage.current();
+    tokio::task::spawn_blocking(move || {
+        let readings = storage.load_sensor_data(&session_id)?;
+        Ok::<_, AppError>(analysis::detect_intervals(&readings, ftp))
+    })
+    .await
+    .map_err(|e| AppError::Session(format!("Interval detection failed: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn get_power_source_comparison(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<PowerSourceComparison>, AppError> {
+    validate_session_id(&session_id)?;
+    let storage = state.storage.current();
+    tokio::task::spawn_blocking(move || {
+        let readings = storage.load_sensor_data(&session_id)?;
+        Ok::<_, AppError>(analysis::compute_power_source_comparison(&readings))
+    })
+    .await
+    .map_err(|e| AppError::Session(format!("Power source comparison failed: {}", e)))?
 }
 
 #[tauri::command]
 pub async fn get_user_config(state: State<'_, AppState>) -> Result<SessionConfig, AppError> {
-    state.storage.get_user_config().await.map_err(AppError::from)
+    state.storage.current().get_user_config().await.map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -201,12 +597,68 @@ pub async fn save_user_config(
     config: SessionConfig,
 ) -> Result<(), AppError> {
     validate_zones_ascending(&config.hr_zones, "HR zones")?;
-    validate_zones_ascending(&config.power_zones, "Power zones")?;
-    state
-        .storage
-        .save_user_config(&config)
-        .await
-        .map_err(AppError::from)
+    validate_zones_ascending(
+        config.power_zone_model.boundaries(&config.power_zones),
+        "Power zones",
+    )?;
+    if config.pace_zones.windows(2).any(|w| w[0] <= w[1]) {
+        return Err(AppError::Session(
+            "Pace zones must be strictly faster (fewer seconds per km) from zone to zone".into(),
+        ));
+    }
+    if !(100.0..=220.0).contains(&config.crank_length_mm) {
+        return Err(AppError::Session(format!(
+            "Crank length {}mm is out of range (100–220)",
+            config.crank_length_mm
+        )));
+    }
+    if !crate::config::MAX_GAP_SECS_RANGE.contains(&config.max_gap_secs) {
+        return Err(AppError::Session(format!(
+            "Max reading gap {}s is out of range (1–30)",
+            config.max_gap_secs
+        )));
+    }
+    if config.ftp_running == Some(0) || config.ftp_rowing == Some(0) {
+        return Err(AppError::Session("Sport FTP must be greater than 0W".into()));
+    }
+    if config.auto_pause_secs == Some(0) {
+        return Err(AppError::Session("Auto-pause delay must be at least 1s".into()));
+    }
+    if !(0.0..=30.0).contains(&config.bike_weight_kg) {
+        return Err(AppError::Session(format!(
+            "Bike weight {}kg is out of range (0–30)",
+            config.bike_weight_kg
+        )));
+    }
+    if !(0.001..=0.05).contains(&config.virtual_crr) || !(0.1..=1.0).contains(&config.virtual_cda) {
+        return Err(AppError::Session(
+            "Virtual speed needs Crr within 0.001–0.05 and CdA within 0.1–1.0 m²".into(),
+        ));
+    }
+    if config.battery_low_threshold > 100 {
+        return Err(AppError::Session(format!(
+            "Battery warning threshold {}% is out of range (0–100)",
+            config.battery_low_threshold
+        )));
+    }
+    validate_interval_secs(
+        config.autosave_interval_secs,
+        crate::config::MIN_AUTOSAVE_INTERVAL_SECS,
+        "Autosave interval",
+    )?;
+    validate_interval_secs(
+        config.connection_check_interval_secs,
+        crate::config::MIN_CONNECTION_CHECK_INTERVAL_SECS,
+        "Connection check interval",
+    )?;
+    let previous_ftp = state.storage.current().get_user_config().await?.ftp;
+    state.storage.current().save_user_config(&config).await?;
+    publish_task_intervals(&state.task_intervals, config.task_intervals());
+    if config.ftp != previous_ftp {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        state.storage.current().record_ftp(config.ftp, &today, "manual").await?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -224,6 +676,37 @@ pub async fn get_device_details(
     dm.get_device_details(&device_id).await
 }
 
+/// Uptime and watchdog drop count for a device, for connection diagnostics.
+#[tauri::command]
+pub async fn get_device_health(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> Result<DeviceHealth, AppError> {
+    Ok(state.device_manager.lock().await.get_device_health(&device_id))
+}
+
+/// Zero-offset calibrate a BLE power meter; the cranks must be unloaded.
+#[tauri::command]
+pub async fn set_power_zero_offset(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> Result<i16, AppError> {
+    info!("Zero offset requested for {}", device_id);
+    // Release the manager before the calibration, which can take seconds
+    let peripheral = state.device_manager.lock().await.zero_offset_peripheral(&device_id).await?;
+    crate::device::ble::set_power_zero_offset(&device_id, peripheral).await
+}
+
+/// Last command status an ANT+ FE-C trainer reported, `None` until it sends one.
+#[tauri::command]
+pub async fn get_trainer_command_status(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> Result<Option<FecCommandStatus>, AppError> {
+    let dm = state.device_manager.lock().await;
+    Ok(dm.last_command_status(&device_id))
+}
+
 #[tauri::command]
 pub async fn set_primary_device(
     state: State<'_, AppState>,
@@ -231,11 +714,8 @@ pub async fn set_primary_device(
     device_id: String,
 ) -> Result<(), AppError> {
     info!("Set primary device: {:?} = {}", device_type, device_id);
-    let mut primaries = state
-        .primary_devices
-        .write()
-        .map_err(|e| AppError::Session(format!("Lock poisoned: {e}")))?;
-    primaries.insert(device_type, device_id);
+    let mut dm = state.device_manager.lock().await;
+    dm.set_primary_manual(device_type, device_id);
     Ok(())
 }
 
@@ -250,6 +730,23 @@ pub async fn get_primary_devices(
     Ok(format_primaries(&primaries))
 }
 
+/// Persist a speed sensor's wheel circumference. Applied on the next connect.
+#[tauri::command]
+pub async fn set_wheel_circumference(
+    state: State<'_, AppState>,
+    device_id: String,
+    mm: u32,
+) -> Result<(), AppError> {
+    if !(500..=3500).contains(&mm) {
+        return Err(AppError::Session(format!(
+            "Wheel circumference must be between 500 and 3500 mm, got {}",
+            mm
+        )));
+    }
+    info!("Set wheel circumference: {} = {}mm", device_id, mm);
+    state.storage.current().set_wheel_circumference(&device_id, mm).await
+}
+
 #[tauri::command]
 pub async fn set_trainer_power(state: State<'_, AppState>, watts: i16) -> Result<(), AppError> {
     info!("Set trainer power: {}W", watts);
@@ -282,7 +779,22 @@ pub async fn set_trainer_simulation(
     let trainer_id = dm
         .connected_trainer_id()
         .ok_or_else(|| AppError::Session("No trainer connected".into()))?;
-    dm.set_simulation(&trainer_id, grade, crr, cw).await
+    dm.set_simulation(&trainer_id, grade, crr, cw).await?;
+    // Log the grade so the session can derive elevation gain
+    let _ = state.sensor_tx.send(SensorReading::Grade {
+        percent: grade,
+        epoch_ms: chrono::Utc::now().timestamp_millis() as u64,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_trainer_capabilities(
+    state: State<'_, AppState>,
+    device_id: String,
+) -> Result<TrainerCapabilities, AppError> {
+    let mut dm = state.device_manager.lock().await;
+    dm.get_trainer_capabilities(&device_id).await
 }
 
 #[tauri::command]
@@ -308,7 +820,21 @@ pub async fn unlink_devices(
     state: State<'_, AppState>,
     device_id: String,
 ) -> Result<(), AppError> {
-    state.storage.clear_device_group(&device_id).await
+    state.storage.current().clear_device_group(&device_id).await
+}
+
+/// Set a display alias for a known device. An empty alias clears it.
+#[tauri::command]
+pub async fn set_device_alias(
+    state: State<'_, AppState>,
+    device_id: String,
+    alias: Option<String>,
+) -> Result<(), AppError> {
+    let alias = alias
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty());
+    info!("Set device alias: {} = {:?}", device_id, alias);
+    state.storage.current().set_device_alias(&device_id, alias).await
 }
 
 #[tauri::command]
@@ -319,11 +845,36 @@ pub async fn update_session_metadata(
     activity_type: Option<String>,
     rpe: Option<u8>,
     notes: Option<String>,
+    structured_rpe: Option<StructuredRpe>,
+) -> Result<(), AppError> {
+    validate_session_id(&session_id)?;
+    state
+        .storage
+        .current()
+        .update_session_metadata(&session_id, title, activity_type, rpe, notes, structured_rpe)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_structured_rpe(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<StructuredRpe>, AppError> {
+    validate_session_id(&session_id)?;
+    state.storage.current().get_structured_rpe(&session_id).await
+}
+
+#[tauri::command]
+pub async fn set_session_favorite(
+    state: State<'_, AppState>,
+    session_id: String,
+    is_favorite: bool,
 ) -> Result<(), AppError> {
     validate_session_id(&session_id)?;
     state
         .storage
-        .update_session_metadata(&session_id, title, activity_type, rpe, notes)
+        .current()
+        .set_session_favorite(&session_id, is_favorite)
         .await
 }
 
@@ -334,21 +885,70 @@ pub async fn delete_session(
 ) -> Result<(), AppError> {
     validate_session_id(&session_id)?;
     info!("Deleting session: {}", session_id);
-    state.storage.delete_session(&session_id).await
+    state.storage.current().delete_session(&session_id).await
+}
+
+#[tauri::command]
+pub async fn find_duplicate_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<(String, String)>, AppError> {
+    state.storage.current().find_duplicate_sessions().await
+}
+
+/// Keep `keep` and delete its duplicate `drop`, carrying over any metadata
+/// only the duplicate has.
+#[tauri::command]
+pub async fn merge_sessions(
+    state: State<'_, AppState>,
+    keep: String,
+    drop: String,
+) -> Result<(), AppError> {
+    validate_session_id(&keep)?;
+    validate_session_id(&drop)?;
+    state.storage.current().merge_sessions(&keep, &drop).await
+}
+
+/// Split a ride at `at_secs` into two sessions; returns their IDs.
+#[tauri::command]
+pub async fn split_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    at_secs: u64,
+) -> Result<(String, String), AppError> {
+    validate_session_id(&session_id)?;
+    state.storage.current().split_session(&session_id, at_secs).await
+}
+
+/// Join two recordings of one ride; sessions over an hour apart need `force`.
+#[tauri::command]
+pub async fn concat_sessions(
+    state: State<'_, AppState>,
+    first: String,
+    second: String,
+    force: Option<bool>,
+) -> Result<String, AppError> {
+    validate_session_id(&first)?;
+    validate_session_id(&second)?;
+    state
+        .storage
+        .current()
+        .concat_sessions(&first, &second, force.unwrap_or(false))
+        .await
 }
 
 #[tauri::command]
 pub async fn export_session_fit(
     state: State<'_, AppState>,
     session_id: String,
+    fill_gaps: Option<bool>,
 ) -> Result<String, AppError> {
     validate_session_id(&session_id)?;
     info!("Exporting session to FIT: {}", session_id);
-    let summary = state.storage.get_session(&session_id).await?;
-    let readings = state.storage.load_sensor_data(&session_id)?;
-    let fit_data = fit_export::export_fit(&summary, &readings)?;
+    let summary = state.storage.current().get_session(&session_id).await?;
+    let readings = state.storage.current().load_sensor_data(&session_id)?;
+    let fit_data = fit_export::export_fit(&summary, &readings, fill_gaps.unwrap_or(false))?;
 
-    let fit_path = std::path::Path::new(state.storage.data_dir())
+    let fit_path = std::path::Path::new(state.storage.current().data_dir())
         .join("sessions")
         .join(format!("{}.fit", session_id));
     tokio::fs::write(&fit_path, &fit_data)
@@ -358,6 +958,113 @@ pub async fn export_session_fit(
     Ok(fit_path.to_string_lossy().to_string())
 }
 
+/// Dump a session's raw sensor log, un-resampled and in recording order, as CSV.
+#[tauri::command]
+pub async fn export_session_raw_csv(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<String, AppError> {
+    validate_session_id(&session_id)?;
+    info!("Exporting raw sensor log to CSV: {}", session_id);
+    let readings = state.storage.current().load_sensor_data(&session_id)?;
+    Ok(csv_export::raw_sensor_csv(&readings))
+}
+
+/// Strava page to visit for the one-time code `connect_strava` expects.
+#[tauri::command]
+pub async fn get_strava_authorize_url(client_id: String) -> Result<String, AppError> {
+    Ok(strava::authorize_url(client_id.trim()))
+}
+
+/// Link a Strava account using the code from the authorization redirect.
+#[tauri::command]
+pub async fn connect_strava(
+    state: State<'_, AppState>,
+    client_id: String,
+    client_secret: String,
+    code: String,
+) -> Result<(), AppError> {
+    let tokens = StravaClient::new()
+        .exchange_code(client_id.trim(), client_secret.trim(), code.trim())
+        .await?;
+    state.storage.current().save_strava_tokens(&tokens).await?;
+    info!("Connected Strava account");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn disconnect_strava(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.storage.current().clear_strava_tokens().await
+}
+
+#[tauri::command]
+pub async fn get_strava_connected(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.storage.current().get_strava_tokens().await?.is_some())
+}
+
+/// Upload a session to Strava as FIT and wait for Strava to create the
+/// activity. Returns the Strava activity id, which is also stored on the
+/// session; a session that was already uploaded returns its existing id.
+#[tauri::command]
+pub async fn upload_session_strava(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<u64, AppError> {
+    validate_session_id(&session_id)?;
+    let storage = state.storage.current();
+    if let Some(activity_id) = storage.get_session_strava_activity(&session_id).await? {
+        return Ok(activity_id);
+    }
+    let mut tokens = storage
+        .get_strava_tokens()
+        .await?
+        .ok_or_else(|| AppError::Session("Strava account is not connected".into()))?;
+    let client = StravaClient::new();
+    if tokens.needs_refresh(chrono::Utc::now().timestamp()) {
+        tokens = client.refresh(&tokens).await?;
+        storage.save_strava_tokens(&tokens).await?;
+    }
+
+    info!("Uploading session to Strava: {}", session_id);
+    let summary = storage.get_session(&session_id).await?;
+    let readings = storage.load_sensor_data(&session_id)?;
+    let fit_data = fit_export::export_fit(&summary, &readings, false)?;
+    let form = strava::upload_form(fit_data, &session_id, summary.title.as_deref());
+    let upload = client.upload(&tokens.access_token, form).await?;
+    let activity_id = client.wait_for_activity(&tokens.access_token, upload.id).await?;
+    storage.set_session_strava_activity(&session_id, activity_id).await?;
+    info!("Session {} uploaded as Strava activity {}", session_id, activity_id);
+    Ok(activity_id)
+}
+
+#[tauri::command]
+pub async fn import_fit(state: State<'_, AppState>, path: String) -> Result<String, AppError> {
+    info!("Importing FIT file: {}", path);
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| AppError::Serialization(format!("Failed to read {}: {}", path, e)))?;
+    let activity = fit_import::parse_fit(&data)?;
+    let config = state.storage.current().get_user_config().await?;
+    let summary = fit_import::summarize(uuid::Uuid::new_v4().to_string(), &activity, &config)?;
+
+    let raw_data = bincode::serialize(&activity.readings)
+        .map_err(|e| AppError::Serialization(e.to_string()))?;
+    state.storage.current().save_session(&summary, &raw_data).await?;
+
+    let curve = analysis::compute_power_curve_from_readings(&activity.readings);
+    if !curve.is_empty() {
+        if let Err(e) = state.storage.current().save_power_curve(&summary.id, &curve).await {
+            warn!("Failed to save power curve: {}", e);
+        }
+    }
+    info!(
+        "Imported FIT file as session {} ({} readings)",
+        summary.id,
+        activity.readings.len()
+    );
+    Ok(summary.id)
+}
+
 /// Load recent sessions with both HR and power data, compute regression,
 /// and estimate the power needed to produce `target_hr`.
 async fn estimate_power_from_history(
@@ -413,14 +1120,21 @@ pub async fn start_zone_control(
         "Start zone control: {:?} zone {} ({}-{})",
         target.mode, target.zone, target.lower_bound, target.upper_bound
     );
-    let config = state.storage.get_user_config().await?;
+    if state.workout_runner.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the workout before starting zone control".into()));
+    }
+    if state.ramp_test.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the ramp test before starting zone control".into()));
+    }
+    let config = state.storage.current().get_user_config().await?;
     let ftp = Some(config.ftp);
-    let max_hr = config.max_hr;
+    let control_config = state.storage.current().get_zone_control_config().await?;
+    let hr_ceiling = control_config.hr_ceiling(config.resting_hr, config.max_hr);
 
     // For HR mode, try to estimate initial power from historical data
     let initial_power_estimate = if target.mode == ZoneMode::HeartRate {
         let target_hr = ((target.lower_bound + target.upper_bound) / 2) as u8;
-        match estimate_power_from_history(&state.storage, target_hr).await {
+        match estimate_power_from_history(&state.storage.current(), target_hr).await {
             Ok(v) => v,
             Err(e) => {
                 warn!("Failed to estimate initial power from history: {}", e);
@@ -435,7 +1149,55 @@ pub async fn start_zone_control(
     let dm = state.device_manager.clone();
     let tx = state.sensor_tx.clone();
     let mut zc = state.zone_controller.lock().await;
-    zc.start_with_config(target, dm, tx, ftp, max_hr, initial_power_estimate, power_zones).await
+    zc.start_with_config(
+        target,
+        dm,
+        tx,
+        ftp,
+        hr_ceiling,
+        initial_power_estimate,
+        power_zones,
+        control_config,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn get_zone_control_config(
+    state: State<'_, AppState>,
+) -> Result<ZoneControlConfig, AppError> {
+    state.storage.current().get_zone_control_config().await
+}
+
+/// Takes effect the next time zone control starts.
+#[tauri::command]
+pub async fn save_zone_control_config(
+    state: State<'_, AppState>,
+    mut config: ZoneControlConfig,
+) -> Result<(), AppError> {
+    let storage = state.storage.current();
+    // The bias is set live through set_power_bias; don't let a config form
+    // that never shows it reset the stored value
+    config.power_bias_pct = storage.get_zone_control_config().await?.power_bias_pct;
+    config.validate()?;
+    storage.save_zone_control_config(&config).await
+}
+
+/// Set the ERG power-match bias (clamped to ±15%). Applies immediately to a
+/// running zone control loop or workout and is saved for later runs.
+#[tauri::command]
+pub async fn set_power_bias(state: State<'_, AppState>, pct: i8) -> Result<i8, AppError> {
+    let pct = pct.clamp(-MAX_POWER_BIAS_PCT, MAX_POWER_BIAS_PCT);
+    info!("Set power bias: {}%", pct);
+    let storage = state.storage.current();
+    let mut config = storage.get_zone_control_config().await?;
+    config.power_bias_pct = pct;
+    storage.save_zone_control_config(&config).await?;
+
+    let dm = state.device_manager.clone();
+    state.zone_controller.lock().await.set_power_bias(pct, &dm).await;
+    state.workout_runner.lock().await.set_power_bias(pct).await;
+    Ok(pct)
 }
 
 #[tauri::command]
@@ -443,7 +1205,7 @@ pub async fn estimate_initial_power(
     state: State<'_, AppState>,
     target_hr: u8,
 ) -> Result<Option<u16>, AppError> {
-    estimate_power_from_history(&state.storage, target_hr).await
+    estimate_power_from_history(&state.storage.current(), target_hr).await
 }
 
 #[tauri::command]
@@ -470,6 +1232,16 @@ pub async fn resume_zone_control(state: State<'_, AppState>) -> Result<(), AppEr
     Ok(())
 }
 
+#[tauri::command]
+pub async fn set_zone_control_debug(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let zc = state.zone_controller.lock().await;
+    zc.set_pid_debug(enabled).await;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_zone_control_status(
     state: State<'_, AppState>,
@@ -478,6 +1250,113 @@ pub async fn get_zone_control_status(
     Ok(zc.status().await)
 }
 
+#[tauri::command]
+pub async fn start_workout(state: State<'_, AppState>, workout: Workout) -> Result<(), AppError> {
+    info!("Start workout: {} steps", workout.steps.len());
+    if state.zone_controller.lock().await.status().await.active {
+        return Err(AppError::Session("Stop zone control before starting a workout".into()));
+    }
+    if state.ramp_test.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the ramp test before starting a workout".into()));
+    }
+    let config = state.storage.current().get_user_config().await?;
+    let zone_config = state.storage.current().get_zone_control_config().await?;
+    let dm = state.device_manager.clone();
+    let tx = state.sensor_tx.clone();
+    let mut runner = state.workout_runner.lock().await;
+    runner.set_power_bias(zone_config.power_bias_pct).await;
+    runner.start(workout, config.ftp, dm, tx).await
+}
+
+/// Parse a Zwift `.zwo` file into a workout ready for `start_workout`.
+#[tauri::command]
+pub async fn import_workout(path: String) -> Result<Workout, AppError> {
+    info!("Import workout: {}", path);
+    let xml = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AppError::Serialization(format!("Failed to read {}: {}", path, e)))?;
+    workout::parse_zwo(&xml)
+}
+
+#[tauri::command]
+pub async fn stop_workout(state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut runner = state.workout_runner.lock().await;
+    runner.stop().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pause_workout(state: State<'_, AppState>) -> Result<(), AppError> {
+    let runner = state.workout_runner.lock().await;
+    runner.pause().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_workout(state: State<'_, AppState>) -> Result<(), AppError> {
+    let runner = state.workout_runner.lock().await;
+    runner.resume().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn skip_workout_step(state: State<'_, AppState>) -> Result<(), AppError> {
+    let runner = state.workout_runner.lock().await;
+    runner.skip_step().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_workout_status(state: State<'_, AppState>) -> Result<WorkoutStatus, AppError> {
+    let runner = state.workout_runner.lock().await;
+    Ok(runner.status().await)
+}
+
+/// Start an ERG ramp test. It ends when cadence collapses or on `stop_ramp_test`,
+/// and the resulting FTP estimate is added to the FTP history.
+#[tauri::command]
+pub async fn start_ramp_test(
+    state: State<'_, AppState>,
+    start_watts: u16,
+    step_watts: u16,
+    step_secs: u32,
+) -> Result<(), AppError> {
+    info!("Start ramp test: {}W +{}W every {}s", start_watts, step_watts, step_secs);
+    if state.zone_controller.lock().await.status().await.active {
+        return Err(AppError::Session("Stop zone control before starting a ramp test".into()));
+    }
+    if state.workout_runner.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the workout before starting a ramp test".into()));
+    }
+    let zone_config = state.storage.current().get_zone_control_config().await?;
+    let params = RampTestParams {
+        start_watts,
+        step_watts,
+        step_secs,
+    };
+    let dm = state.device_manager.clone();
+    let tx = state.sensor_tx.clone();
+    let storage = state.storage.current();
+    let mut runner = state.ramp_test.lock().await;
+    runner
+        .start(params, zone_config.cadence_zero_secs, dm, tx, storage)
+        .await
+}
+
+#[tauri::command]
+pub async fn stop_ramp_test(
+    state: State<'_, AppState>,
+) -> Result<Option<RampTestResult>, AppError> {
+    let mut runner = state.ramp_test.lock().await;
+    Ok(runner.stop().await)
+}
+
+#[tauri::command]
+pub async fn get_ramp_test_status(state: State<'_, AppState>) -> Result<RampTestStatus, AppError> {
+    let runner = state.ramp_test.lock().await;
+    Ok(runner.status().await)
+}
+
 #[tauri::command]
 pub async fn save_zone_ride_config(
     state: State<'_, AppState>,
@@ -485,7 +1364,7 @@ pub async fn save_zone_ride_config(
     zone_config: String,
 ) -> Result<(), AppError> {
     validate_session_id(&session_id)?;
-    state.storage.save_zone_config(&session_id, &zone_config).await
+    state.storage.current().save_zone_config(&session_id, &zone_config).await
 }
 
 #[tauri::command]
@@ -494,7 +1373,7 @@ pub async fn get_zone_ride_config(
     session_id: String,
 ) -> Result<Option<String>, AppError> {
     validate_session_id(&session_id)?;
-    state.storage.get_zone_config(&session_id).await
+    state.storage.current().get_zone_config(&session_id).await
 }
 
 #[tauri::command]
@@ -515,19 +1394,244 @@ pub async fn get_best_power_curve(
     };
     state
         .storage
+        .current()
         .get_best_power_curve(after_date.as_deref())
         .await
 }
 
+/// A session's power curve next to the all-time best, flagging new records.
+#[tauri::command]
+pub async fn get_power_curve_with_best(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<PowerCurveComparison, AppError> {
+    validate_session_id(&session_id)?;
+    let previous_best = state
+        .storage
+        .current()
+        .get_best_power_curve_excluding(&session_id)
+        .await?;
+    let storage = state.storage.current();
+    tokio::task::spawn_blocking(move || {
+        let readings = storage.load_sensor_data(&session_id)?;
+        let curve = analysis::compute_power_curve_from_readings(&readings);
+        Ok::<_, AppError>(analysis::compare_power_curves(&curve, &previous_best))
+    })
+    .await
+    .map_err(|e| AppError::Session(format!("Power curve comparison failed: {}", e)))?
+}
+
+/// Longest list of durations `get_best_efforts` accepts in one call.
+const MAX_BEST_EFFORT_DURATIONS: usize = 32;
+
+/// Best average power across all stored sessions for each requested duration
+/// (seconds). Durations no session is long enough for are omitted.
+#[tauri::command]
+pub async fn get_best_efforts(
+    state: State<'_, AppState>,
+    durations: Vec<u32>,
+) -> Result<Vec<BestEffort>, AppError> {
+    if durations.is_empty() || durations.len() > MAX_BEST_EFFORT_DURATIONS {
+        return Err(AppError::Session(format!(
+            "Request between 1 and {} durations",
+            MAX_BEST_EFFORT_DURATIONS
+        )));
+    }
+    if durations.contains(&0) {
+        return Err(AppError::Session("Durations must be positive".into()));
+    }
+    let storage = state.storage.current();
+    let sessions = storage.list_sessions().await?;
+    tokio::task::spawn_blocking(move || {
+        let mut best: Vec<Option<BestEffort>> = vec![None; durations.len()];
+        for session in sessions {
+            let readings = match storage.load_sensor_data(&session.id) {
+                Ok(r) => r,
+                Err(e) => {
+                    warn!("Skipping session {} for best efforts: {}", session.id, e);
+                    continue;
+                }
+            };
+            for (slot, &d) in best.iter_mut().zip(&durations) {
+                let Some(watts) = analysis::best_power_for(&readings, d) else {
+                    continue;
+                };
+                if slot.as_ref().map_or(true, |b| watts > b.watts) {
+                    *slot = Some(BestEffort {
+                        duration_secs: d,
+                        watts,
+                        session_id: session.id.clone(),
+                    });
+                }
+            }
+        }
+        Ok::<_, AppError>(best.into_iter().flatten().collect())
+    })
+    .await
+    .map_err(|e| AppError::Session(format!("Best efforts failed: {}", e)))?
+}
+
+/// Daily fitness (CTL), fatigue (ATL) and form (TSB) between two `YYYY-MM-DD` dates.
+#[tauri::command]
+pub async fn get_training_load(
+    state: State<'_, AppState>,
+    from: String,
+    to: String,
+) -> Result<Vec<LoadPoint>, AppError> {
+    state.storage.current().get_training_load(&from, &to).await
+}
+
+/// Weekly or monthly training totals between two `YYYY-MM-DD` dates.
+#[tauri::command]
+pub async fn get_period_summary(
+    state: State<'_, AppState>,
+    granularity: PeriodGranularity,
+    from: String,
+    to: String,
+) -> Result<Vec<PeriodSummary>, AppError> {
+    state
+        .storage
+        .current()
+        .get_period_summary(granularity, &from, &to)
+        .await
+}
+
+/// Export the best power curve as CSV. Writes to `path` if given, otherwise
+/// to `power_curve.csv` in the data directory. Returns the written path.
+#[tauri::command]
+pub async fn export_power_curve_csv(
+    state: State<'_, AppState>,
+    after_date: Option<String>,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    let curve = state
+        .storage
+        .current()
+        .get_best_power_curve(after_date.as_deref())
+        .await?;
+    let config = state.storage.current().get_user_config().await?;
+    let csv = csv_export::power_curve_csv(&curve, config.weight_kg)?;
+
+    let csv_path = match path {
+        Some(p) => std::path::PathBuf::from(p),
+        None => std::path::Path::new(state.storage.current().data_dir()).join("power_curve.csv"),
+    };
+    tokio::fs::write(&csv_path, csv)
+        .await
+        .map_err(|e| AppError::Serialization(format!("Failed to write CSV file: {}", e)))?;
+
+    info!("Exported power curve to {}", csv_path.display());
+    Ok(csv_path.to_string_lossy().to_string())
+}
+
+/// Write the database and all raw session files to a single zip archive.
+#[tauri::command]
+pub async fn export_backup(state: State<'_, AppState>, path: String) -> Result<(), AppError> {
+    info!("Export backup: {}", path);
+    state.storage.current().export_backup(std::path::Path::new(&path)).await
+}
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<String, AppError> {
+    app.path()
+        .app_data_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|e| AppError::Session(format!("Failed to get app data dir: {}", e)))
+}
+
+#[tauri::command]
+pub async fn list_profiles(app: tauri::AppHandle) -> Result<Vec<String>, AppError> {
+    Ok(Storage::list_profiles(&app_data_dir(&app)?))
+}
+
+#[tauri::command]
+pub async fn get_active_profile(state: State<'_, AppState>) -> Result<String, AppError> {
+    Ok(state.storage.profile())
+}
+
+/// Switch to another profile's database and sessions, creating it on first use.
+/// Background tasks and the device manager pick up the new storage; see
+/// `SharedStorage` for the lock ordering.
+#[tauri::command]
+pub async fn switch_profile(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    name: String,
+) -> Result<(), AppError> {
+    info!("Switch profile: {}", name);
+    if state.session_manager.is_active().await {
+        return Err(AppError::Session("Stop the current session before switching profiles".into()));
+    }
+    if state.zone_controller.lock().await.status().await.active {
+        return Err(AppError::Session("Stop zone control before switching profiles".into()));
+    }
+    if state.workout_runner.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the workout before switching profiles".into()));
+    }
+    if state.ramp_test.lock().await.status().await.active {
+        return Err(AppError::Session("Stop the ramp test before switching profiles".into()));
+    }
+    let app_dir = app_data_dir(&app)?;
+    let storage = Storage::open_profile(&app_dir, &name).await?;
+    match storage.recover_autosaved_sessions().await {
+        Ok(0) => {}
+        Ok(n) => info!("Recovered {} autosaved session(s) for profile {}", n, name),
+        Err(e) => warn!("Autosave recovery failed for profile {}: {}", name, e),
+    }
+    if let Err(e) = storage
+        .prune_stale_autosaves(crate::config::STALE_AUTOSAVE_MAX_AGE_HOURS)
+        .await
+    {
+        warn!("Autosave pruning failed for profile {}: {}", name, e);
+    }
+    let intervals = storage.get_user_config().await?.task_intervals();
+    let storage = Arc::new(storage);
+
+    let mut dm = state.device_manager.lock().await;
+    dm.set_storage(storage.clone());
+    state.storage.replace(&name, storage);
+    publish_task_intervals(&state.task_intervals, intervals);
+    if let Err(e) = Storage::save_active_profile(&app_dir, &name) {
+        warn!("{}", e);
+    }
+    Ok(())
+}
+
+/// Replace all data with a backup archive, then restart so every component
+/// picks up the restored database.
+#[tauri::command]
+pub async fn import_backup(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<(), AppError> {
+    info!("Import backup: {}", path);
+    if state.session_manager.is_active().await {
+        return Err(AppError::Session("Stop the current session before restoring a backup".into()));
+    }
+    let data_dir = state.storage.current().data_dir().to_string();
+    // Validate the archive before touching the live database
+    let staging = Storage::stage_backup(&data_dir, std::path::Path::new(&path)).await?;
+    state.storage.current().pool.close().await;
+    if let Err(e) = Storage::swap_in_backup(&data_dir, &staging) {
+        // The previous data was put back; reopen it and report the failure
+        log::error!("Backup restore failed: {}", e);
+        let storage = Arc::new(Storage::new(&data_dir).await?);
+        state.device_manager.lock().await.set_storage(storage.clone());
+        state.storage.replace(&state.storage.profile(), storage);
+        return Err(e);
+    }
+    app.restart();
+}
+
 #[tauri::command]
 pub async fn backfill_power_curves(state: State<'_, AppState>) -> Result<u32, AppError> {
-    let sessions = state.storage.list_sessions().await?;
+    let sessions = state.storage.current().list_sessions().await?;
     let mut filled = 0u32;
     for session in &sessions {
-        if state.storage.has_power_curve(&session.id).await? {
+        if state.storage.current().has_power_curve(&session.id).await? {
             continue;
         }
-        let storage = state.storage.clone();
+        let storage = state.storage.current();
         let sid = session.id.clone();
         let curve = tokio::task::spawn_blocking(move || {
             let readings = storage.load_sensor_data(&sid)?;
@@ -539,23 +1643,37 @@ pub async fn backfill_power_curves(state: State<'_, AppState>) -> Result<u32, Ap
         if curve.is_empty() {
             continue;
         }
-        state.storage.save_power_curve(&session.id, &curve).await?;
+        state.storage.current().save_power_curve(&session.id, &curve).await?;
         filled += 1;
     }
     Ok(filled)
 }
 
 #[tauri::command]
-pub async fn check_prerequisites() -> Result<prerequisites::PrereqStatus, AppError> {
-    tokio::task::spawn_blocking(prerequisites::check)
+pub async fn check_prerequisites(
+    state: State<'_, AppState>,
+) -> Result<prerequisites::PrereqStatus, AppError> {
+    let mut status = tokio::task::spawn_blocking(prerequisites::check)
         .await
-        .map_err(|e| AppError::Session(format!("Prereq check failed: {}", e)))
+        .map_err(|e| AppError::Session(format!("Prereq check failed: {}", e)))?;
+    let dm = state.device_manager.lock().await;
+    prerequisites::apply_ble_state(&mut status, dm.ble_available(), dm.ble_permission_denied());
+    Ok(status)
 }
 
+/// Probe for a Bluetooth adapter again after an earlier probe found none,
+/// then report the updated prerequisites.
 #[tauri::command]
-pub async fn fix_prerequisites(
-    app: tauri::AppHandle,
-) -> Result<prerequisites::FixResult, AppError> {
+pub async fn retry_bluetooth(
+    state: State<'_, AppState>,
+) -> Result<prerequisites::PrereqStatus, AppError> {
+    info!("Retrying Bluetooth adapter probe");
+    state.device_manager.lock().await.retry_ble().await;
+    check_prerequisites(state).await
+}
+
+#[cfg(target_os = "linux")]
+fn stage_udev_rules(app: &tauri::AppHandle) -> Result<Option<std::path::PathBuf>, AppError> {
     let resource_dir = app
         .path()
         .resource_dir()
@@ -568,15 +1686,42 @@ pub async fn fix_prerequisites(
     std::fs::copy(&bundle_path, &tmp_path).map_err(|e| {
         AppError::Session(format!("Failed to copy udev rules to /tmp: {}", e))
     })?;
-    let source = tmp_path.to_string_lossy().to_string();
+    Ok(Some(tmp_path))
+}
 
-    tokio::task::spawn_blocking(move || {
+/// The udev rules are only bundled on Linux.
+#[cfg(not(target_os = "linux"))]
+fn stage_udev_rules(_app: &tauri::AppHandle) -> Result<Option<std::path::PathBuf>, AppError> {
+    Ok(None)
+}
+
+#[tauri::command]
+pub async fn fix_prerequisites(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<prerequisites::FixResult, AppError> {
+    let tmp_path = stage_udev_rules(&app)?;
+    let source = tmp_path
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut result = tokio::task::spawn_blocking(move || {
         let result = prerequisites::fix(&source);
-        let _ = std::fs::remove_file(&tmp_path);
+        if let Some(path) = tmp_path {
+            let _ = std::fs::remove_file(&path);
+        }
         result
     })
     .await
-    .map_err(|e| AppError::Session(format!("Prereq fix failed: {}", e)))
+    .map_err(|e| AppError::Session(format!("Prereq fix failed: {}", e)))?;
+    let dm = state.device_manager.lock().await;
+    prerequisites::apply_ble_state(
+        &mut result.status,
+        dm.ble_available(),
+        dm.ble_permission_denied(),
+    );
+    Ok(result)
 }
 
 #[cfg(not(feature = "production"))]
@@ -651,6 +1796,42 @@ mod tests {
         assert!(validate_session_id("abc\0def").is_err());
     }
 
+    // --- validate_interval_secs ---
+
+    #[test]
+    fn autosave_interval_below_minimum_rejected() {
+        let min = crate::config::MIN_AUTOSAVE_INTERVAL_SECS;
+        assert!(validate_interval_secs(4, min, "Autosave interval").is_err());
+    }
+
+    #[test]
+    fn autosave_interval_at_or_above_minimum_accepted() {
+        let min = crate::config::MIN_AUTOSAVE_INTERVAL_SECS;
+        assert!(validate_interval_secs(15, min, "Autosave interval").is_ok());
+        assert!(validate_interval_secs(5, min, "Autosave interval").is_ok());
+    }
+
+    #[test]
+    fn connection_check_interval_lower_bound() {
+        let min = crate::config::MIN_CONNECTION_CHECK_INTERVAL_SECS;
+        assert!(validate_interval_secs(0, min, "Connection check interval").is_err());
+        assert!(validate_interval_secs(1, min, "Connection check interval").is_err());
+        assert!(validate_interval_secs(2, min, "Connection check interval").is_ok());
+    }
+
+    #[test]
+    fn task_intervals_clamp_zero_stored_periods() {
+        // Config loaded from storage skips validation; a zero must never reach the watchdog
+        let config = SessionConfig {
+            connection_check_interval_secs: 0,
+            autosave_interval_secs: 0,
+            ..SessionConfig::default()
+        };
+        let intervals = config.task_intervals();
+        assert_eq!(intervals.connection_check_secs, crate::config::MIN_CONNECTION_CHECK_INTERVAL_SECS);
+        assert_eq!(intervals.autosave_secs, crate::config::MIN_AUTOSAVE_INTERVAL_SECS);
+    }
+
     // --- validate_zones_ascending ---
 
     #[test]
@@ -703,4 +1884,19 @@ mod tests {
         assert_eq!(result["HeartRate"], "hr-1");
         assert_eq!(result["FitnessTrainer"], "trainer-1");
     }
+
+    // --- publish_task_intervals ---
+
+    #[test]
+    fn publish_task_intervals_skips_unchanged_periods() {
+        let (tx, mut rx) = watch::channel(TaskIntervals::default());
+        rx.borrow_and_update();
+        publish_task_intervals(&tx, TaskIntervals::default());
+        assert!(!rx.has_changed().unwrap());
+
+        let longer = TaskIntervals { autosave_secs: 120, ..TaskIntervals::default() };
+        publish_task_intervals(&tx, longer);
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), longer);
+    }
 }
@@ -1,15 +1,52 @@
 /// Connection watchdog interval — how often we check for stale/disconnected devices.
+/// Default for the user-configurable `connection_check_interval_secs`.
 pub const CONNECTION_CHECK_INTERVAL_SECS: u64 = 5;
+/// Shortest watchdog interval accepted from user config.
+pub const MIN_CONNECTION_CHECK_INTERVAL_SECS: u64 = 2;
 
 /// Autosave interval — how often we snapshot the active session to disk.
+/// Default for the user-configurable `autosave_interval_secs`.
 pub const AUTOSAVE_INTERVAL_SECS: u64 = 30;
+/// Shortest autosave interval accepted from user config.
+pub const MIN_AUTOSAVE_INTERVAL_SECS: u64 = 5;
+
+/// Default for the user-configurable `max_gap_secs`: analysis counts at most
+/// this long between two readings, so dropouts don't inflate zone time.
+pub const DEFAULT_MAX_GAP_SECS: u64 = 5;
+/// Accepted range for `max_gap_secs` in user config.
+pub const MAX_GAP_SECS_RANGE: std::ops::RangeInclusive<u32> = 1..=30;
+
+/// Virtual speed model defaults for the user-configurable bike weight, rolling
+/// resistance and drag area: a road bike ridden on the hoods.
+pub const DEFAULT_BIKE_WEIGHT_KG: f32 = 8.0;
+pub const DEFAULT_VIRTUAL_CRR: f32 = 0.005;
+pub const DEFAULT_VIRTUAL_CDA_M2: f32 = 0.36;
+
+/// How long to wait for a power meter to finish a zero-offset calibration.
+pub const POWER_ZERO_OFFSET_TIMEOUT_SECS: u64 = 10;
+
+/// Autosave files older than this are deleted at startup once their session
+/// is safely in the database.
+pub const STALE_AUTOSAVE_MAX_AGE_HOURS: u64 = 24;
 
 /// Live metrics push interval — how often we emit metrics to the frontend.
 pub const LIVE_METRICS_PUSH_MS: u64 = 250;
 
-/// BLE scan duration — how long a BLE scan runs before returning results.
+/// Default BLE scan duration — how long a scan runs before returning results.
 pub const BLE_SCAN_DURATION_SECS: u64 = 3;
 
+/// Longest scan duration a user can request, for weak RF environments
+pub const MAX_SCAN_DURATION_SECS: u64 = 15;
+
+/// Most extra ANT+ scan passes a user can request; each pass listens ~4s
+pub const MAX_ANT_SCAN_RETRIES: u32 = 3;
+
+/// Longest a single startup auto-connect attempt may take before moving on
+pub const AUTO_CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Longest the whole startup auto-connect pass may take; later devices are skipped
+pub const AUTO_CONNECT_TOTAL_TIMEOUT_SECS: u64 = 30;
+
 /// Device disconnect timeout — ANT+ device considered lost after this many seconds
 /// without data. Intentionally longer than READING_FRESHNESS_SECS: we stop using
 /// stale data for metrics quickly (5s) but give the device more time (10s) before
@@ -21,6 +58,39 @@ pub const DEVICE_DISCONNECT_TIMEOUT_SECS: u64 = 10;
 /// responsive even while the watchdog still considers the device connected.
 pub const READING_FRESHNESS_SECS: u64 = 5;
 
+/// Battery poll interval — how often the watchdog reads connected devices' battery levels.
+pub const BATTERY_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Battery read timeout — cap on each GATT read so an unresponsive device can't stall the watchdog.
+pub const BATTERY_READ_TIMEOUT_MS: u64 = 1000;
+
+/// Weak signal threshold — RSSI below this (dBm) counts as a weak sample.
+pub const WEAK_SIGNAL_RSSI_DBM: i16 = -85;
+
+/// Weak signal duration — how long RSSI must stay below the threshold before
+/// the watchdog warns. Sampled once per watchdog tick and measured in time, so
+/// it holds whatever the tick interval.
+pub const WEAK_SIGNAL_DURATION_SECS: u64 = 10;
+
+/// Trainer vs power meter mismatch — percentage difference in their averaged
+/// power that counts as a mismatched sample.
+pub const POWER_MISMATCH_PCT: f64 = 10.0;
+
+/// Trainer vs power meter averaging window — each watchdog tick compares the
+/// two sources' average power over this many seconds.
+pub const POWER_MISMATCH_WINDOW_SECS: u64 = 10;
+
+/// Trainer vs power meter mismatch duration — how long the difference must
+/// persist before the watchdog emits `power_mismatch`.
+pub const POWER_MISMATCH_DURATION_SECS: u64 = 30;
+
+/// FTMS capability read timeout — cap on each range/feature read so a silent
+/// trainer can't stall the first ERG command.
+pub const FTMS_CAPABILITY_READ_TIMEOUT_MS: u64 = 1000;
+
+/// RSSI read timeout — cap on each property read so a slow adapter can't stall the watchdog.
+pub const RSSI_READ_TIMEOUT_MS: u64 = 500;
+
 /// Reconnect initial backoff — delay before first reconnect attempt.
 pub const RECONNECT_INITIAL_BACKOFF_MS: u64 = 2000;
 
@@ -29,3 +99,7 @@ pub const RECONNECT_MAX_BACKOFF_MS: u64 = 30000;
 
 /// Reconnect backoff multiplier.
 pub const RECONNECT_BACKOFF_MULTIPLIER: u64 = 2;
+
+/// How long zone control or a workout holds for a dropped trainer to
+/// reconnect before giving up.
+pub const TRAINER_RECONNECT_GRACE_SECS: u64 = 60;
@@ -65,7 +65,7 @@ pub struct AntChannelConfig {
 
 /// Initialize the ANT stick: reset + set network key.
 /// Called before the router thread starts, so reads directly from USB.
-pub fn init_ant_stick(usb: &AntUsb) -> Result<(), AppError> {
+pub fn init_ant_stick(usb: &dyn AntLink) -> Result<(), AppError> {
     // System reset
     usb.send(&AntMessage {
         msg_id: MSG_SYSTEM_RESET,
@@ -98,7 +98,7 @@ pub fn init_ant_stick(usb: &AntUsb) -> Result<(), AppError> {
 /// If the channel is in a bad state (e.g. leftover from a previous scan),
 /// automatically closes+unassigns and retries.
 pub fn open_channel(
-    usb: &AntUsb,
+    usb: &dyn AntLink,
     config: &AntChannelConfig,
     response_queue: &Arc<Mutex<Vec<AntMessage>>>,
 ) -> Result<(), AppError> {
@@ -195,7 +195,7 @@ pub fn open_channel(
 /// Close and unassign a channel.
 /// Uses the response_queue (router must be running).
 pub fn close_channel(
-    usb: &AntUsb,
+    usb: &dyn AntLink,
     channel_number: u8,
     response_queue: &Arc<Mutex<Vec<AntMessage>>>,
 ) -> Result<(), AppError> {
@@ -233,7 +233,7 @@ pub fn close_channel(
 
 /// Send an acknowledged data message on a channel (used for FE-C control)
 pub fn send_acknowledged(
-    usb: &AntUsb,
+    usb: &dyn AntLink,
     channel_number: u8,
     data: &[u8; 8],
 ) -> Result<(), AppError> {
@@ -285,7 +285,7 @@ pub fn poll_response(
 
 /// Wait for a channel response by reading directly from USB.
 /// Used only during init_ant_stick (before the router thread starts).
-fn wait_for_response_direct(usb: &AntUsb, expected_msg_id: u8) -> Result<(), AppError> {
+fn wait_for_response_direct(usb: &dyn AntLink, expected_msg_id: u8) -> Result<(), AppError> {
     for _ in 0..50 {
         let messages = usb.receive_all()?;
         for msg in messages {
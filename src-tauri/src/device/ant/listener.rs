@@ -4,8 +4,9 @@ use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 
-use super::protocol::{AntDecoder, DEFAULT_WHEEL_CIRCUMFERENCE_MM};
-use crate::device::types::{is_dominated, AntDeviceMetadata, DeviceType, SensorReading};
+use super::protocol::AntDecoder;
+use crate::device::fec::decode_command_status;
+use crate::device::types::{AntDeviceMetadata, DeviceType, ReadingFilter, SensorReading};
 
 /// Monotonic reference epoch for lock-free timestamps.
 /// All `last_seen` values are stored as nanos elapsed since this instant.
@@ -87,7 +88,8 @@ pub fn listen_ant_channel(
     metadata_store: Arc<Mutex<HashMap<String, AntDeviceMetadata>>>,
     device_type_id: u8,
     last_seen: Arc<AtomicI64>,
-    primaries: Option<Arc<std::sync::RwLock<HashMap<DeviceType, String>>>>,
+    filter: Option<ReadingFilter>,
+    wheel_circumference_mm: u32,
 ) {
     let mut decoder = AntDecoder::new();
 
@@ -116,12 +118,21 @@ pub fn listen_ant_channel(
         // Update last-data timestamp for connection watchdog (lock-free, every page)
         let page_num = data[0];
         atomic_now(&last_seen);
+        if let Some(ref f) = filter {
+            f.trace_packet(&device_id, format_args!("page 0x{:02X}", page_num), &data);
+        }
 
-        // Decode ANT+ Common Data Pages — only lock metadata for these rare pages
-        if page_num == 0x50 || page_num == 0x51 || page_num == 0x52 {
+        // Decode ANT+ Common Data Pages and FE-C command status — only lock
+        // metadata for these rare pages
+        if matches!(page_num, 0x47 | 0x50 | 0x51 | 0x52) {
             let mut store = metadata_store.lock().unwrap_or_else(|e| e.into_inner());
             let meta = store.entry(device_id.clone()).or_default();
             match page_num {
+                0x47 => {
+                    if let Some(status) = decode_command_status(&data) {
+                        meta.fec_command_status = Some(status);
+                    }
+                }
                 0x50 => decode_common_page_80(&data, meta),
                 0x51 => decode_common_page_81(&data, meta),
                 0x52 => decode_common_page_82(&data, meta),
@@ -131,12 +142,25 @@ pub fn listen_ant_channel(
         }
 
         let readings: Vec<SensorReading> = match device_type {
-            DeviceType::HeartRate => decoder.decode_hr(&data, &device_id).into_iter().collect(),
+            DeviceType::HeartRate => {
+                let reading = decoder.decode_hr(&data, &device_id);
+                if let Some(battery) = decoder.take_hr_battery() {
+                    let mut store = metadata_store.lock().unwrap_or_else(|e| e.into_inner());
+                    let meta = store.entry(device_id.clone()).or_default();
+                    if battery.level.is_some() {
+                        meta.battery_level = battery.level;
+                    }
+                    if battery.voltage.is_some() {
+                        meta.battery_voltage = battery.voltage;
+                    }
+                }
+                reading.into_iter().collect()
+            }
             DeviceType::Power => decoder.decode_power(&data, &device_id).into_iter().collect(),
             DeviceType::CadenceSpeed => {
                 if device_type_id == 123 {
                     decoder
-                        .decode_speed(&data, &device_id, DEFAULT_WHEEL_CIRCUMFERENCE_MM)
+                        .decode_speed(&data, &device_id, wheel_circumference_mm)
                         .into_iter()
                         .collect()
                 } else {
@@ -144,18 +168,18 @@ pub fn listen_ant_channel(
                 }
             }
             DeviceType::FitnessTrainer => decoder.decode_fec_trainer(&data, &device_id),
+            // ANT+ muscle oxygen, radar and environment profiles are not supported
+            DeviceType::MuscleOxygen | DeviceType::Radar | DeviceType::Environment => Vec::new(),
         };
 
         for reading in readings {
-            if let Some(ref p) = primaries {
-                let dominated = {
-                    let guard = p.read().unwrap_or_else(|e| e.into_inner());
-                    is_dominated(&guard, &reading)
-                };
-                if dominated {
-                    continue;
-                }
-            }
+            let reading = match &filter {
+                Some(f) => match f.forward(reading) {
+                    Some(reading) => reading,
+                    None => continue,
+                },
+                None => reading,
+            };
             if tx.send(reading).is_err() {
                 warn!("[{}] No receivers for ANT+ readings, stopping listener", device_id);
                 return;
@@ -20,6 +20,55 @@ struct DiscoveredDevice {
     profile: AntProfile,
 }
 
+impl DiscoveredDevice {
+    /// Channel configuration that pairs with exactly this device.
+    fn channel_config(&self, channel_number: u8) -> AntChannelConfig {
+        AntChannelConfig {
+            channel_number,
+            profile: self.profile,
+            device_number: self.device_number,
+            transmission_type: self.transmission_type,
+        }
+    }
+}
+
+/// Build a discovery record for a device the user identified by the number
+/// printed on the sensor, so it can be paired without a wildcard search.
+/// The transmission type stays wildcard since it isn't printed on sensors.
+fn manual_device(
+    device_type: DeviceType,
+    device_number: u16,
+) -> Result<(String, DiscoveredDevice), AppError> {
+    if device_number == 0 {
+        return Err(AntError::Channel("Device number 0 is the search wildcard".into()).into());
+    }
+    let mut profiles = ALL_SCAN_PROFILES.iter().filter(|p| p.device_type == device_type);
+    let profile = match (profiles.next(), profiles.next()) {
+        (Some(profile), None) => *profile,
+        (Some(_), Some(_)) => {
+            return Err(AntError::NotSupported(format!(
+                "manual pairing for {:?} (ambiguous ANT+ profile; use scan)",
+                device_type
+            ))
+            .into())
+        }
+        (None, _) => {
+            return Err(
+                AntError::NotSupported(format!("ANT+ pairing for {:?}", device_type)).into(),
+            )
+        }
+    };
+    let id = format!("ant:{}:{}", profile.device_type_id, device_number);
+    Ok((
+        id,
+        DiscoveredDevice {
+            device_number,
+            transmission_type: 0,
+            profile,
+        },
+    ))
+}
+
 /// An active ANT+ connection
 struct ActiveConnection {
     channel_number: u8,
@@ -37,7 +86,7 @@ const MAX_CHANNELS: u8 = 8;
 /// Uses a single router thread that reads all USB messages and dispatches
 /// broadcast data to per-channel mpsc senders.
 pub struct AntManager {
-    usb: Arc<AntUsb>,
+    usb: Arc<dyn AntLink>,
     router_stop: Arc<AtomicBool>,
     router_handle: Option<std::thread::JoinHandle<()>>,
     channel_senders: Arc<Mutex<HashMap<u8, std::sync::mpsc::Sender<Vec<u8>>>>>,
@@ -75,7 +124,13 @@ impl AntManager {
             return None;
         }
 
-        let usb = Arc::new(usb);
+        let manager = Self::with_link(Arc::new(usb));
+        info!("ANT+ USB stick initialized with router thread");
+        Some(manager)
+    }
+
+    /// Start the router thread over an initialized stick link.
+    pub(crate) fn with_link(usb: Arc<dyn AntLink>) -> Self {
         let router_stop = Arc::new(AtomicBool::new(false));
         let channel_senders: Arc<Mutex<HashMap<u8, std::sync::mpsc::Sender<Vec<u8>>>>> =
             Arc::new(Mutex::new(HashMap::new()));
@@ -93,8 +148,7 @@ impl AntManager {
             })
         };
 
-        info!("ANT+ USB stick initialized with router thread");
-        Some(Self {
+        Self {
             usb,
             router_stop,
             router_handle: Some(router_handle),
@@ -104,7 +158,7 @@ impl AntManager {
             connected: HashMap::new(),
             device_metadata: Arc::new(Mutex::new(HashMap::new())),
             last_seen: Arc::new(Mutex::new(HashMap::new())),
-        })
+        }
     }
 
     /// Find the lowest free channel number above the scan-reserved range.
@@ -164,7 +218,7 @@ impl AntManager {
                     device_number: 0,     // wildcard
                     transmission_type: 0, // wildcard
                 };
-                if let Err(e) = open_channel(&self.usb, &config, &self.response_queue) {
+                if let Err(e) = open_channel(self.usb.as_ref(), &config, &self.response_queue) {
                     warn!(
                         "Failed to open scan channel {} for {:?}: {}",
                         ch, profile.device_type, e
@@ -248,7 +302,7 @@ impl AntManager {
 
         // Close scan channels
         for (ch, _) in &scan_channels {
-            let _ = close_channel(&self.usb, *ch, &self.response_queue);
+            let _ = close_channel(self.usb.as_ref(), *ch, &self.response_queue);
         }
 
         // Build device info list
@@ -271,6 +325,7 @@ impl AntManager {
                 model_number: None,
                 serial_number: None,
                 device_group: None,
+                alias: None,
                 in_range: true,
             })
             .collect();
@@ -289,7 +344,8 @@ impl AntManager {
         &mut self,
         device_id: &str,
         tx: broadcast::Sender<SensorReading>,
-        primaries: Option<Arc<std::sync::RwLock<HashMap<DeviceType, String>>>>,
+        filter: Option<ReadingFilter>,
+        wheel_circumference_mm: u32,
     ) -> Result<DeviceInfo, AppError> {
         let discovered = self
             .discovered
@@ -299,13 +355,8 @@ impl AntManager {
 
         let channel_number = self.allocate_channel()?;
 
-        let config = AntChannelConfig {
-            channel_number,
-            profile: discovered.profile,
-            device_number: discovered.device_number,
-            transmission_type: discovered.transmission_type,
-        };
-        open_channel(&self.usb, &config, &self.response_queue)?;
+        let config = discovered.channel_config(channel_number);
+        open_channel(self.usb.as_ref(), &config, &self.response_queue)?;
 
         // Create mpsc channel for this device and register with router
         let (data_tx, data_rx) = std::sync::mpsc::channel();
@@ -329,7 +380,18 @@ impl AntManager {
         }
 
         let listener_handle = tokio::task::spawn_blocking(move || {
-            listen_ant_channel(data_rx, device_type, tx, stop_clone, did, metadata, dtype_id, last_seen_ts, primaries);
+            listen_ant_channel(
+                data_rx,
+                device_type,
+                tx,
+                stop_clone,
+                did,
+                metadata,
+                dtype_id,
+                last_seen_ts,
+                filter,
+                wheel_circumference_mm,
+            );
         });
 
         let info = DeviceInfo {
@@ -348,6 +410,7 @@ impl AntManager {
             model_number: None,
             serial_number: None,
             device_group: None,
+            alias: None,
             in_range: true,
         };
 
@@ -380,11 +443,24 @@ impl AntManager {
             if let Some(handle) = conn.listener_handle.take() {
                 handle.abort();
             }
-            close_channel(&self.usb, conn.channel_number, &self.response_queue)?;
+            close_channel(self.usb.as_ref(), conn.channel_number, &self.response_queue)?;
         }
         Ok(())
     }
 
+    /// Register a device by its printed device number so `connect` opens a
+    /// channel paired to that number. Returns the `ant:<type>:<number>` id.
+    pub fn register_manual(
+        &mut self,
+        device_type: DeviceType,
+        device_number: u16,
+    ) -> Result<String, AppError> {
+        let (id, device) = manual_device(device_type, device_number)?;
+        info!("[{}] Registered for manual pairing", id);
+        self.discovered.insert(id.clone(), device);
+        Ok(id)
+    }
+
     pub fn is_discovered(&self, device_id: &str) -> bool {
         self.discovered.contains_key(device_id)
     }
@@ -406,7 +482,7 @@ impl AntManager {
     }
 
     /// Get the USB handle and channel number for a connected FE-C device (for trainer control)
-    pub fn get_fec_channel(&self, device_id: &str) -> Option<(Arc<AntUsb>, u8)> {
+    pub fn get_fec_channel(&self, device_id: &str) -> Option<(Arc<dyn AntLink>, u8)> {
         let conn = self.connected.get(device_id)?;
         if conn.profile.device_type != DeviceType::FitnessTrainer {
             return None;
@@ -440,7 +516,7 @@ impl Drop for AntManager {
 /// - Broadcast data (MSG_BROADCAST_DATA): extract channel + 8-byte data page, send to per-channel mpsc
 /// - Everything else (responses, Channel IDs, etc.): push to response_queue
 fn router_loop(
-    usb: Arc<AntUsb>,
+    usb: Arc<dyn AntLink>,
     channel_senders: Arc<Mutex<HashMap<u8, std::sync::mpsc::Sender<Vec<u8>>>>>,
     response_queue: Arc<Mutex<Vec<AntMessage>>>,
     stop: Arc<AtomicBool>,
@@ -500,3 +576,31 @@ fn router_loop(
 
     info!("ANT+ router thread stopped");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_device_pairs_with_given_number() {
+        let (id, device) = manual_device(DeviceType::Power, 12345).unwrap();
+        assert_eq!(id, "ant:11:12345");
+        let config = device.channel_config(3);
+        assert_eq!(config.channel_number, 3);
+        assert_eq!(config.device_number, 12345);
+        assert_eq!(config.transmission_type, 0);
+        assert_eq!(config.profile.device_type_id, PROFILE_POWER.device_type_id);
+    }
+
+    #[test]
+    fn manual_device_rejects_wildcard_number() {
+        assert!(manual_device(DeviceType::HeartRate, 0).is_err());
+    }
+
+    #[test]
+    fn manual_device_rejects_ambiguous_or_unsupported_types() {
+        // Cadence and speed sensors share DeviceType::CadenceSpeed
+        assert!(manual_device(DeviceType::CadenceSpeed, 100).is_err());
+        assert!(manual_device(DeviceType::Radar, 100).is_err());
+    }
+}
@@ -5,6 +5,20 @@ use crate::device::types::SensorReading;
 /// Default wheel circumference in mm (700x25c)
 pub const DEFAULT_WHEEL_CIRCUMFERENCE_MM: u32 = 2105;
 
+/// FE-C page 0x1A sub-type byte used by gear-emulating trainers. Any other
+/// value in byte 1 is the standard Specific Trainer Torque update event count.
+const FEC_GEAR_SUBTYPE: u8 = 0xF1;
+
+/// ANT+ HRM Battery Status page. Bit 7 of the page byte is the page-change toggle.
+const HR_PAGE_BATTERY_STATUS: u8 = 0x07;
+
+/// Battery fields from an ANT+ HRM Battery Status page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrBatteryStatus {
+    pub level: Option<u8>,
+    pub voltage: Option<f32>,
+}
+
 fn now_epoch_ms() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -25,6 +39,7 @@ pub struct AntDecoder {
     prev_power_event_count: u8,
     prev_power_accumulated: u16,
     power_initialized: bool,
+    prev_pedaling_event_count: Option<u8>,
 
     // Cadence profile state
     prev_cadence_event_time: u16,
@@ -35,6 +50,9 @@ pub struct AntDecoder {
     prev_speed_event_time: u16,
     prev_speed_revs: u16,
     speed_initialized: bool,
+
+    // HR battery page, held until the listener stores it
+    hr_battery: Option<HrBatteryStatus>,
 }
 
 impl AntDecoder {
@@ -43,8 +61,20 @@ impl AntDecoder {
     }
 
     /// Decode ANT+ Heart Rate data page
-    /// All HR pages have computed HR in byte 7
-    pub fn decode_hr(&self, data: &[u8; 8], device_id: &str) -> Option<SensorReading> {
+    /// All HR pages have computed HR in byte 7. Page 0x07 also carries battery
+    /// status, kept for `take_hr_battery`:
+    /// Byte 1: battery level % (0xFF = not available)
+    /// Byte 2: fractional battery voltage (1/256 V)
+    /// Byte 3: coarse battery voltage (bits 0-3, 0xF = invalid)
+    pub fn decode_hr(&mut self, data: &[u8; 8], device_id: &str) -> Option<SensorReading> {
+        if data[0] & 0x7F == HR_PAGE_BATTERY_STATUS {
+            let coarse = data[3] & 0x0F;
+            self.hr_battery = Some(HrBatteryStatus {
+                level: (data[1] <= 100).then_some(data[1]),
+                voltage: (coarse != 0x0F).then(|| coarse as f32 + data[2] as f32 / 256.0),
+            });
+        }
+
         let bpm = data[7];
         if bpm == 0 {
             return None;
@@ -57,6 +87,11 @@ impl AntDecoder {
         })
     }
 
+    /// Battery status from the last HR battery page, if not yet taken.
+    pub fn take_hr_battery(&mut self) -> Option<HrBatteryStatus> {
+        self.hr_battery.take()
+    }
+
     /// Decode ANT+ Cycling Power Standard Power page (0x10)
     /// Byte 1: update event count
     /// Byte 2: pedal power (bit 7 = differentiation, bits 0-6 = right pedal %)
@@ -64,6 +99,9 @@ impl AntDecoder {
     /// Byte 6-7: instantaneous power (u16 LE)
     pub fn decode_power(&mut self, data: &[u8; 8], device_id: &str) -> Option<SensorReading> {
         let page = data[0];
+        if page == 0x13 {
+            return self.decode_pedaling(data, device_id);
+        }
         if page != 0x10 {
             debug!("ANT+ power: unhandled page 0x{:02X} from {}", page, device_id);
             return None; // Only decode standard power page
@@ -111,6 +149,43 @@ impl AntDecoder {
         })
     }
 
+    /// Decode ANT+ Cycling Power Torque Effectiveness and Pedal Smoothness page (0x13)
+    /// Byte 1: update event count
+    /// Byte 2-3: left/right torque effectiveness (0.5%, 0xFF = invalid)
+    /// Byte 4: left or combined pedal smoothness (0.5%, 0xFF = invalid)
+    /// Byte 5: right pedal smoothness (0.5%, 0xFE = byte 4 is combined, 0xFF = invalid)
+    /// Left and right values are averaged.
+    fn decode_pedaling(&mut self, data: &[u8; 8], device_id: &str) -> Option<SensorReading> {
+        let event_count = data[1];
+        if self.prev_pedaling_event_count == Some(event_count) {
+            return None;
+        }
+        self.prev_pedaling_event_count = Some(event_count);
+
+        let half_pct = |byte: u8| (byte <= 200).then_some(byte as f32 / 2.0);
+        let average = |left: Option<f32>, right: Option<f32>| match (left, right) {
+            (Some(l), Some(r)) => Some((l + r) / 2.0),
+            (l, r) => l.or(r),
+        };
+        let torque_effectiveness = average(half_pct(data[2]), half_pct(data[3]));
+        let smoothness = if data[5] == 0xFE {
+            half_pct(data[4])
+        } else {
+            average(half_pct(data[4]), half_pct(data[5]))
+        };
+        if torque_effectiveness.is_none() && smoothness.is_none() {
+            return None;
+        }
+        Some(SensorReading::Pedaling {
+            torque_effectiveness,
+            smoothness,
+            top_dead_spot_deg: None,
+            bottom_dead_spot_deg: None,
+            epoch_ms: now_epoch_ms(),
+            device_id: device_id.to_string(),
+        })
+    }
+
     /// Decode ANT+ Cadence sensor data page (page 0 or default)
     /// Byte 4-5: cadence event time (1/1024 s, u16 LE)
     /// Byte 6-7: cumulative cadence revolutions (u16 LE)
@@ -249,6 +324,19 @@ impl AntDecoder {
                     device_id: did,
                 });
             }
+        } else if page == 0x1A && data[1] == FEC_GEAR_SUBTYPE {
+            // Gear Data: byte 2 = front gear, byte 3 = rear gear (1-based, 0xFF/0 = not present)
+            let (front, rear) = (data[2], data[3]);
+            if front != 0xFF && front != 0 && rear != 0xFF && rear != 0 {
+                readings.push(SensorReading::Gear {
+                    front,
+                    rear,
+                    epoch_ms,
+                    device_id: did,
+                });
+            }
+        } else if page != 0x1A {
+            debug!("FE-C page 0x{:02X} not decoded", page);
         }
 
         readings
@@ -261,7 +349,7 @@ mod tests {
 
     #[test]
     fn test_decode_hr() {
-        let decoder = AntDecoder::new();
+        let mut decoder = AntDecoder::new();
         let data: [u8; 8] = [0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 142];
         let reading = decoder.decode_hr(&data, "test").unwrap();
         match reading {
@@ -272,11 +360,35 @@ mod tests {
 
     #[test]
     fn test_decode_hr_zero_bpm() {
-        let decoder = AntDecoder::new();
+        let mut decoder = AntDecoder::new();
         let data: [u8; 8] = [0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0];
         assert!(decoder.decode_hr(&data, "test").is_none());
     }
 
+    #[test]
+    fn decode_hr_battery_page() {
+        let mut decoder = AntDecoder::new();
+        // Page 7 with the toggle bit set: 85%, 2 + 192/256 = 2.75V, HR 142
+        let data: [u8; 8] = [0x87, 85, 192, 0x32, 0x00, 0x00, 0x00, 142];
+        match decoder.decode_hr(&data, "test").unwrap() {
+            SensorReading::HeartRate { bpm, .. } => assert_eq!(bpm, 142),
+            _ => panic!("Expected HeartRate"),
+        }
+        let battery = decoder.take_hr_battery().unwrap();
+        assert_eq!(battery.level, Some(85));
+        assert_eq!(battery.voltage, Some(2.75));
+        assert!(decoder.take_hr_battery().is_none());
+
+        // Other pages leave battery untouched; unavailable fields stay None
+        decoder.decode_hr(&[0x04, 0, 0, 0, 0, 0, 0, 140], "test");
+        assert!(decoder.take_hr_battery().is_none());
+        decoder.decode_hr(&[0x07, 0xFF, 0x00, 0x0F, 0, 0, 0, 140], "test");
+        assert_eq!(
+            decoder.take_hr_battery(),
+            Some(HrBatteryStatus { level: None, voltage: None })
+        );
+    }
+
     #[test]
     fn decode_power_first_sample_returns_instant_power() {
         let mut decoder = AntDecoder::new();
@@ -461,6 +573,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_pedaling_page_averages_left_and_right() {
+        let mut decoder = AntDecoder::new();
+        // TE 80% / 90%, PS 20% / 30%
+        let data: [u8; 8] = [0x13, 1, 160, 180, 40, 60, 0xFF, 0xFF];
+        match decoder.decode_power(&data, "test") {
+            Some(SensorReading::Pedaling { torque_effectiveness, smoothness, .. }) => {
+                assert_eq!(torque_effectiveness, Some(85.0));
+                assert_eq!(smoothness, Some(25.0));
+            }
+            other => panic!("Expected Pedaling, got {:?}", other),
+        }
+        // Same event count is a repeat of the same data
+        assert!(decoder.decode_power(&data, "test").is_none());
+    }
+
+    #[test]
+    fn decode_pedaling_page_combined_smoothness_and_invalid_fields() {
+        let mut decoder = AntDecoder::new();
+        // Left TE only, combined PS 22.5%
+        let data: [u8; 8] = [0x13, 7, 150, 0xFF, 45, 0xFE, 0xFF, 0xFF];
+        match decoder.decode_power(&data, "test") {
+            Some(SensorReading::Pedaling { torque_effectiveness, smoothness, .. }) => {
+                assert_eq!(torque_effectiveness, Some(75.0));
+                assert_eq!(smoothness, Some(22.5));
+            }
+            other => panic!("Expected Pedaling, got {:?}", other),
+        }
+        // Nothing valid: no reading
+        let empty: [u8; 8] = [0x13, 8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(decoder.decode_power(&empty, "test").is_none());
+    }
+
     #[test]
     fn decode_power_wrong_page_returns_none() {
         let mut decoder = AntDecoder::new();
@@ -611,4 +756,34 @@ mod tests {
         assert_eq!(readings2.len(), 1); // speed only
         assert!(matches!(&readings2[0], SensorReading::Speed { .. }));
     }
+
+    #[test]
+    fn decode_fec_trainer_page_0x1a_gear_data() {
+        let decoder = AntDecoder::new();
+        let data: [u8; 8] = [0x1A, FEC_GEAR_SUBTYPE, 2, 7, 0xFF, 0xFF, 0xFF, 0x30];
+        let readings = decoder.decode_fec_trainer(&data, "test");
+        assert_eq!(readings.len(), 1);
+        match &readings[0] {
+            SensorReading::Gear { front, rear, .. } => {
+                assert_eq!(*front, 2);
+                assert_eq!(*rear, 7);
+            }
+            other => panic!("expected Gear, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_fec_trainer_page_0x1a_torque_and_unknown_pages_ignored() {
+        let decoder = AntDecoder::new();
+        // Standard Specific Trainer Torque page (byte 1 = event count)
+        let torque: [u8; 8] = [0x1A, 12, 40, 0x00, 0x08, 0x10, 0x27, 0x30];
+        assert!(decoder.decode_fec_trainer(&torque, "test").is_empty());
+        // Gear sub-type without gear indices present
+        let no_gears: [u8; 8] = [0x1A, FEC_GEAR_SUBTYPE, 0xFF, 0xFF, 0, 0, 0, 0];
+        assert!(decoder.decode_fec_trainer(&no_gears, "test").is_empty());
+        // Unrecognized pages don't disturb 0x19 decoding
+        assert!(decoder.decode_fec_trainer(&[0x30, 0, 0, 0, 0, 0, 0, 0], "test").is_empty());
+        let power: [u8; 8] = [0x19, 5, 90, 0, 0, 0xC8, 0x00, 0x00];
+        assert_eq!(decoder.decode_fec_trainer(&power, "test").len(), 2);
+    }
 }
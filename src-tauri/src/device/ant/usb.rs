@@ -42,6 +42,15 @@ pub struct AntMessage {
     pub data: Vec<u8>,
 }
 
+/// Message transport to an ANT stick. Implemented by `AntUsb`; tests swap in
+/// `MockAntLink` to exercise channel setup without hardware.
+pub trait AntLink: Send + Sync {
+    /// Send a raw ANT message
+    fn send(&self, msg: &AntMessage) -> Result<(), AppError>;
+    /// Receive the messages from one read; empty when nothing arrived in time
+    fn receive_all(&self) -> Result<Vec<AntMessage>, AppError>;
+}
+
 /// Low-level USB driver for ANT sticks.
 /// Thread safety: libusb is thread-safe for concurrent operations on different
 /// endpoints, so read_bulk (router thread) and write_bulk (scan/connect thread)
@@ -134,8 +143,24 @@ impl AntUsb {
         Err(AntError::NoUsbStick.into())
     }
 
+    /// Check if an ANT USB stick is available without opening it
+    pub fn is_available() -> bool {
+        let Ok(devices) = rusb::devices() else {
+            return false;
+        };
+        devices.iter().any(|d| {
+            d.device_descriptor().map_or(false, |desc| {
+                desc.vendor_id() == GARMIN_VENDOR_ID
+                    && (desc.product_id() == ANTUSB_M_PRODUCT_ID
+                        || desc.product_id() == ANTUSB_2_PRODUCT_ID)
+            })
+        })
+    }
+}
+
+impl AntLink for AntUsb {
     /// Send a raw ANT message
-    pub fn send(&self, msg: &AntMessage) -> Result<(), AppError> {
+    fn send(&self, msg: &AntMessage) -> Result<(), AppError> {
         let packet = encode_message(msg);
         self.handle
             .write_bulk(self.endpoint_out, &packet, USB_TIMEOUT)
@@ -145,7 +170,7 @@ impl AntUsb {
 
     /// Try to receive all ANT messages from one USB read (non-blocking, returns empty Vec on timeout).
     /// A single USB read may contain multiple concatenated ANT messages.
-    pub fn receive_all(&self) -> Result<Vec<AntMessage>, AppError> {
+    fn receive_all(&self) -> Result<Vec<AntMessage>, AppError> {
         let mut buf = [0u8; 64];
         match self
             .handle
@@ -157,20 +182,6 @@ impl AntUsb {
             Err(e) => Err(AntError::Usb(format!("USB read failed: {}", e)).into()),
         }
     }
-
-    /// Check if an ANT USB stick is available without opening it
-    pub fn is_available() -> bool {
-        let Ok(devices) = rusb::devices() else {
-            return false;
-        };
-        devices.iter().any(|d| {
-            d.device_descriptor().map_or(false, |desc| {
-                desc.vendor_id() == GARMIN_VENDOR_ID
-                    && (desc.product_id() == ANTUSB_M_PRODUCT_ID
-                        || desc.product_id() == ANTUSB_2_PRODUCT_ID)
-            })
-        })
-    }
 }
 
 impl Drop for AntUsb {
@@ -184,6 +195,44 @@ impl Drop for AntUsb {
     }
 }
 
+/// Stick stand-in for tests: records every message sent and acknowledges
+/// each channel command, the way a stick does when the command succeeds.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MockAntLink {
+    pub sent: std::sync::Mutex<Vec<AntMessage>>,
+    replies: std::sync::Mutex<Vec<AntMessage>>,
+}
+
+#[cfg(test)]
+impl AntLink for MockAntLink {
+    fn send(&self, msg: &AntMessage) -> Result<(), AppError> {
+        self.sent.lock().unwrap().push(msg.clone());
+        if msg.msg_id != MSG_ACKNOWLEDGED_DATA {
+            let channel = msg.data.first().copied().unwrap_or(0);
+            let code = if msg.msg_id == MSG_CLOSE_CHANNEL {
+                EVENT_CHANNEL_CLOSED
+            } else {
+                RESPONSE_NO_ERROR
+            };
+            self.replies.lock().unwrap().push(AntMessage {
+                msg_id: MSG_CHANNEL_RESPONSE,
+                data: vec![channel, msg.msg_id, code],
+            });
+        }
+        Ok(())
+    }
+
+    fn receive_all(&self) -> Result<Vec<AntMessage>, AppError> {
+        let replies = std::mem::take(&mut *self.replies.lock().unwrap());
+        if replies.is_empty() {
+            // Stand in for the real read timeout so the router doesn't spin
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        Ok(replies)
+    }
+}
+
 /// Encode an AntMessage into wire format
 fn encode_message(msg: &AntMessage) -> Vec<u8> {
     let len = msg.data.len() as u8;
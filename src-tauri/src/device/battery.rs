@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+/// Tracks which devices are currently below the low-battery threshold so the
+/// watchdog warns once per downward crossing instead of on every poll.
+pub struct BatteryMonitor {
+    low: HashSet<String>,
+}
+
+impl BatteryMonitor {
+    pub fn new() -> Self {
+        Self {
+            low: HashSet::new(),
+        }
+    }
+
+    /// Record a battery reading. Returns true only when the device has just
+    /// dropped below `threshold`; the warning re-arms once the level is back
+    /// at or above the threshold (e.g. after a battery swap or charge).
+    pub fn observe(&mut self, device_id: &str, level: u8, threshold: u8) -> bool {
+        if level < threshold {
+            self.low.insert(device_id.to_string())
+        } else {
+            self.low.remove(device_id);
+            false
+        }
+    }
+
+    /// Forget a device, e.g. once it disconnects, so a reconnect with a
+    /// still-low battery warns again.
+    pub fn forget(&mut self, device_id: &str) {
+        self.low.remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_per_crossing() {
+        let mut monitor = BatteryMonitor::new();
+        assert!(!monitor.observe("hr", 40, 15));
+        assert!(monitor.observe("hr", 14, 15));
+        assert!(!monitor.observe("hr", 12, 15));
+        assert!(!monitor.observe("hr", 10, 15));
+    }
+
+    #[test]
+    fn rearms_after_recovering_above_threshold() {
+        let mut monitor = BatteryMonitor::new();
+        assert!(monitor.observe("hr", 10, 15));
+        assert!(!monitor.observe("hr", 15, 15));
+        assert!(monitor.observe("hr", 9, 15));
+    }
+
+    #[test]
+    fn tracks_devices_independently() {
+        let mut monitor = BatteryMonitor::new();
+        assert!(monitor.observe("hr", 5, 15));
+        assert!(monitor.observe("power", 5, 15));
+        assert!(!monitor.observe("hr", 5, 15));
+    }
+
+    #[test]
+    fn forget_rearms_warning() {
+        let mut monitor = BatteryMonitor::new();
+        assert!(monitor.observe("hr", 5, 15));
+        monitor.forget("hr");
+        assert!(monitor.observe("hr", 5, 15));
+    }
+}
@@ -1,10 +1,14 @@
-use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
+use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter, WriteType};
 use btleplug::platform::{Adapter, Manager, Peripheral};
+use futures::StreamExt;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid as BtUuid;
 
+use super::protocol::{
+    encode_offset_compensation, parse_offset_compensation_response, CYCLING_POWER_CONTROL_POINT,
+};
 use super::types::{
     CharacteristicInfo, ConnectionStatus, DeviceDetails, DeviceInfo, DeviceType, ServiceInfo,
     Transport,
@@ -15,6 +19,12 @@ const HEART_RATE_SERVICE: BtUuid = BtUuid::from_u128(0x0000180D_0000_1000_8000_0
 const CYCLING_POWER_SERVICE: BtUuid = BtUuid::from_u128(0x00001818_0000_1000_8000_00805f9b34fb);
 const CSC_SERVICE: BtUuid = BtUuid::from_u128(0x00001816_0000_1000_8000_00805f9b34fb);
 const FTMS_SERVICE: BtUuid = BtUuid::from_u128(0x00001826_0000_1000_8000_00805f9b34fb);
+const ENVIRONMENTAL_SENSING_SERVICE: BtUuid =
+    BtUuid::from_u128(0x0000181A_0000_1000_8000_00805f9b34fb);
+/// Moxy muscle oxygen monitor service (vendor 128-bit UUID).
+const SMO2_SERVICE: BtUuid = BtUuid::from_u128(0x6404d801_4cb9_11e8_b566_0800200c9a66);
+/// Garmin Varia rear radar service (vendor 128-bit UUID).
+const RADAR_SERVICE: BtUuid = BtUuid::from_u128(0x6a4e3200_667b_11e3_949a_0800200c9a66);
 const BATTERY_LEVEL_CHAR: BtUuid = BtUuid::from_u128(0x00002A19_0000_1000_8000_00805f9b34fb);
 
 // Device Information Service characteristics
@@ -31,15 +41,24 @@ pub struct BleManager {
     connected: Arc<Mutex<HashMap<String, Peripheral>>>,
 }
 
+/// Keep CoreBluetooth authorization failures distinct so the prerequisite
+/// check can point the user at System Settings.
+fn init_error(context: &str, e: btleplug::Error) -> BleError {
+    match e {
+        btleplug::Error::PermissionDenied => BleError::PermissionDenied,
+        e => BleError::Btleplug(format!("{}: {}", context, e)),
+    }
+}
+
 impl BleManager {
     pub async fn new() -> Result<Self, AppError> {
         let manager = Manager::new()
             .await
-            .map_err(|e| BleError::Btleplug(format!("Failed to create BLE manager: {}", e)))?;
+            .map_err(|e| init_error("Failed to create BLE manager", e))?;
         let adapters = manager
             .adapters()
             .await
-            .map_err(|e| BleError::Btleplug(format!("Failed to get adapters: {}", e)))?;
+            .map_err(|e| init_error("Failed to get adapters", e))?;
         let adapter = adapters
             .into_iter()
             .next()
@@ -109,6 +128,7 @@ impl BleManager {
                 model_number: None,
                 serial_number: None,
                 device_group: None,
+                alias: None,
                 in_range: true,
             };
             discovered.insert(id, (peripheral, info.clone()));
@@ -175,6 +195,7 @@ impl BleManager {
                 model_number: None,
                 serial_number: None,
                 device_group: None,
+                alias: None,
                 in_range: true,
             };
 
@@ -311,6 +332,45 @@ impl BleManager {
         self.connected.clone()
     }
 
+    /// Sample the RSSI of every connected peripheral. Each property read is
+    /// bounded by RSSI_READ_TIMEOUT_MS; peripherals that time out, error, or
+    /// report no RSSI are skipped.
+    pub async fn read_rssi_all(&self) -> Vec<(String, i16)> {
+        let peripherals: Vec<(String, Peripheral)> = self
+            .connected
+            .lock()
+            .await
+            .iter()
+            .map(|(id, p)| (id.clone(), p.clone()))
+            .collect();
+        let timeout = std::time::Duration::from_millis(crate::config::RSSI_READ_TIMEOUT_MS);
+        let mut samples = Vec::new();
+        for (id, peripheral) in peripherals {
+            match tokio::time::timeout(timeout, peripheral.properties()).await {
+                Ok(Ok(Some(props))) => {
+                    if let Some(rssi) = props.rssi {
+                        samples.push((id, rssi));
+                    }
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => log::debug!("[{}] RSSI read failed: {}", id, e),
+                Err(_) => log::debug!("[{}] RSSI read timed out", id),
+            }
+        }
+        samples
+    }
+
+    /// Clone a connected peripheral out of the map so long GATT exchanges can
+    /// run without holding the device manager or the connected map.
+    pub async fn connected_peripheral(&self, device_id: &str) -> Result<Peripheral, AppError> {
+        self.connected
+            .lock()
+            .await
+            .get(device_id)
+            .cloned()
+            .ok_or_else(|| AppError::DeviceNotFound(device_id.to_string()))
+    }
+
     /// Read detailed information from a connected BLE peripheral including
     /// GATT services, characteristics, and Device Information Service fields.
     pub async fn get_device_details(&self, device_id: &str) -> Result<DeviceDetails, AppError> {
@@ -407,6 +467,86 @@ impl BleManager {
     }
 }
 
+/// Read the Battery Level (0x2A19) of each peripheral. Each read is bounded by
+/// BATTERY_READ_TIMEOUT_MS; peripherals without a battery service, or that
+/// time out or error, are skipped.
+pub async fn read_battery_levels(peripherals: Vec<(String, Peripheral)>) -> Vec<(String, u8)> {
+    let timeout = std::time::Duration::from_millis(crate::config::BATTERY_READ_TIMEOUT_MS);
+    let mut levels = Vec::new();
+    for (id, peripheral) in peripherals {
+        let chars = peripheral.characteristics();
+        let Some(battery_char) = chars.iter().find(|c| c.uuid == BATTERY_LEVEL_CHAR) else {
+            continue;
+        };
+        match tokio::time::timeout(timeout, peripheral.read(battery_char)).await {
+            Ok(Ok(data)) => {
+                if let Some(&level) = data.first() {
+                    levels.push((id, level));
+                }
+            }
+            Ok(Err(e)) => log::debug!("[{}] Battery level read failed: {}", id, e),
+            Err(_) => log::debug!("[{}] Battery level read timed out", id),
+        }
+    }
+    levels
+}
+
+/// Run a zero-offset calibration on a connected power meter through the
+/// Cycling Power Control Point. Returns the offset the meter indicates. The
+/// whole exchange is bounded by POWER_ZERO_OFFSET_TIMEOUT_SECS.
+pub async fn set_power_zero_offset(device_id: &str, peripheral: Peripheral) -> Result<i16, AppError> {
+    let wait = std::time::Duration::from_secs(crate::config::POWER_ZERO_OFFSET_TIMEOUT_SECS);
+    let response = tokio::time::timeout(wait, async {
+        let control_point = peripheral
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid == CYCLING_POWER_CONTROL_POINT)
+            .ok_or_else(|| BleError::CharacteristicNotFound("Cycling Power Control Point".into()))?;
+
+        peripheral
+            .subscribe(&control_point)
+            .await
+            .map_err(|e| BleError::Btleplug(format!("Failed to subscribe to CP control point: {}", e)))?;
+        // Subscribe to the notification stream before writing so the response can't be missed
+        let mut stream = peripheral
+            .notifications()
+            .await
+            .map_err(|e| BleError::Btleplug(format!("Failed to get notification stream: {}", e)))?;
+        peripheral
+            .write(&control_point, &encode_offset_compensation(), WriteType::WithResponse)
+            .await
+            .map_err(|e| BleError::Btleplug(format!("Failed to write CP control point: {}", e)))?;
+
+        while let Some(notif) = stream.next().await {
+            if notif.uuid != CYCLING_POWER_CONTROL_POINT {
+                continue;
+            }
+            if let Some(result) = parse_offset_compensation_response(&notif.value) {
+                return Ok(Some(result));
+            }
+        }
+        Ok::<_, AppError>(None)
+    })
+    .await;
+
+    match response {
+        Ok(Err(e)) => Err(e),
+        Ok(Ok(Some(Ok(offset)))) => {
+            log::info!("[{}] Zero offset: {}", device_id, offset);
+            Ok(offset)
+        }
+        Ok(Ok(Some(Err(reason)))) => Err(BleError::Btleplug(format!(
+            "Power meter rejected zero offset: {}",
+            reason
+        ))
+        .into()),
+        Ok(Ok(None)) | Err(_) => Err(BleError::Btleplug(
+            "Power meter did not answer the zero offset request".into(),
+        )
+        .into()),
+    }
+}
+
 fn classify_device(services: &[BtUuid]) -> Option<DeviceType> {
     if services.contains(&FTMS_SERVICE) {
         Some(DeviceType::FitnessTrainer)
@@ -416,12 +556,24 @@ fn classify_device(services: &[BtUuid]) -> Option<DeviceType> {
         Some(DeviceType::HeartRate)
     } else if services.contains(&CSC_SERVICE) {
         Some(DeviceType::CadenceSpeed)
+    } else if services.contains(&SMO2_SERVICE) {
+        Some(DeviceType::MuscleOxygen)
+    } else if services.contains(&RADAR_SERVICE) {
+        Some(DeviceType::Radar)
+    } else if services.contains(&ENVIRONMENTAL_SENSING_SERVICE) {
+        Some(DeviceType::Environment)
     } else {
         None
     }
 }
 
 fn well_known_service_name(uuid: BtUuid) -> Option<String> {
+    if uuid == SMO2_SERVICE {
+        return Some("Muscle Oxygen".to_string());
+    }
+    if uuid == RADAR_SERVICE {
+        return Some("Radar".to_string());
+    }
     // Extract the 16-bit short UUID from the standard Bluetooth base
     let val = (uuid.as_u128() >> 96) as u16;
     let name = match val {
@@ -432,6 +584,7 @@ fn well_known_service_name(uuid: BtUuid) -> Option<String> {
         0x180F => "Battery Service",
         0x1816 => "Cycling Speed and Cadence",
         0x1818 => "Cycling Power",
+        0x181A => "Environmental Sensing",
         0x1826 => "Fitness Machine",
         _ => return None,
     };
@@ -439,6 +592,12 @@ fn well_known_service_name(uuid: BtUuid) -> Option<String> {
 }
 
 fn well_known_char_name(uuid: BtUuid) -> Option<String> {
+    if uuid == super::protocol::SMO2_SENSOR_DATA {
+        return Some("SmO2 Sensor Data".to_string());
+    }
+    if uuid == super::protocol::RADAR_THREATS {
+        return Some("Radar Threats".to_string());
+    }
     let val = (uuid.as_u128() >> 96) as u16;
     let name = match val {
         0x2A00 => "Device Name",
@@ -462,6 +621,7 @@ fn well_known_char_name(uuid: BtUuid) -> Option<String> {
         0x2A64 => "Cycling Power Vector",
         0x2A65 => "Cycling Power Feature",
         0x2A66 => "Cycling Power Control Point",
+        0x2A6E => "Temperature",
         0x2AD2 => "Indoor Bike Data",
         0x2AD3 => "Training Status",
         0x2AD6 => "Supported Resistance Level Range",
@@ -142,6 +142,7 @@ mod tests {
             model_number: None,
             serial_number: None,
             device_group: None,
+            alias: None,
             in_range: true,
         }
     }
@@ -160,6 +161,7 @@ mod tests {
             model_number: None,
             serial_number: None,
             device_group: None,
+            alias: None,
             in_range: true,
         }
     }
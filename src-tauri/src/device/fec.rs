@@ -1,7 +1,56 @@
+use serde::{Deserialize, Serialize};
+
 use super::ant::channel::send_acknowledged;
-use super::ant::usb::AntUsb;
+use super::ant::usb::AntLink;
 use crate::error::AppError;
 
+/// Outcome the trainer reports for its last control page (Page 0x47, byte 3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FecCommandResult {
+    Pass,
+    Fail,
+    NotSupported,
+    Rejected,
+    Pending,
+    /// No command received yet (0xFF) or a reserved value
+    Uninitialized,
+}
+
+/// Decoded FE-C Command Status page (0x47).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FecCommandStatus {
+    /// Page number of the last control command received (e.g. 0x31, 0x33)
+    pub last_command_id: u8,
+    /// Sequence number of that command, `None` when the trainer doesn't track it
+    pub sequence: Option<u8>,
+    pub result: FecCommandResult,
+    /// Command-specific echo of the accepted values (bytes 4-7)
+    pub data: [u8; 4],
+}
+
+/// Decode Command Status page (0x47). Returns `None` for other pages and when
+/// the trainer has not received any command yet.
+pub fn decode_command_status(data: &[u8; 8]) -> Option<FecCommandStatus> {
+    if data[0] != 0x47 || data[1] == 0xFF {
+        return None;
+    }
+    let result = match data[3] {
+        0 => FecCommandResult::Pass,
+        1 => FecCommandResult::Fail,
+        2 => FecCommandResult::NotSupported,
+        3 => FecCommandResult::Rejected,
+        4 => FecCommandResult::Pending,
+        _ => FecCommandResult::Uninitialized,
+    };
+    Some(FecCommandStatus {
+        last_command_id: data[1],
+        sequence: (data[2] != 0xFF).then_some(data[2]),
+        result,
+        data: [data[4], data[5], data[6], data[7]],
+    })
+}
+
 /// Encode target power page (0x31). Power in 0.25W resolution: watts * 4.
 fn encode_target_power(watts: u16) -> [u8; 8] {
     let power_raw = watts.saturating_mul(4);
@@ -48,14 +97,27 @@ fn encode_simulation(grade: f32, crr: f32, cw: f32) -> [u8; 8] {
     ]
 }
 
-/// FE-C trainer control via ANT+ acknowledged messages
+/// Encode a Request Data Page (0x46) asking the trainer to send `page` once.
+/// Trainers only broadcast Command Status (0x47) in reply to this request.
+fn encode_request_page(page: u8) -> [u8; 8] {
+    [
+        0x46, 0xFF, 0xFF, // reserved
+        0xFF, 0xFF, // descriptor bytes: none
+        0x01, // transmit once, as a broadcast
+        page, 0x01, // command type: request data page
+    ]
+}
+
+/// FE-C trainer control via ANT+ acknowledged messages. Each control page is
+/// followed by a request for Command Status, so the trainer reports whether
+/// it applied it.
 pub struct FecController<'a> {
-    usb: &'a AntUsb,
+    usb: &'a dyn AntLink,
     channel_number: u8,
 }
 
 impl<'a> FecController<'a> {
-    pub fn new(usb: &'a AntUsb, channel_number: u8) -> Self {
+    pub fn new(usb: &'a dyn AntLink, channel_number: u8) -> Self {
         Self {
             usb,
             channel_number,
@@ -64,21 +126,27 @@ impl<'a> FecController<'a> {
 
     /// Set target power (Page 0x31)
     pub fn set_target_power(&self, watts: u16) -> Result<(), AppError> {
-        send_acknowledged(self.usb, self.channel_number, &encode_target_power(watts))
+        self.send_control(&encode_target_power(watts))
     }
 
     /// Set basic resistance (Page 0x30)
     pub fn set_resistance(&self, level: u8) -> Result<(), AppError> {
-        send_acknowledged(self.usb, self.channel_number, &encode_resistance(level))
+        self.send_control(&encode_resistance(level))
     }
 
     /// Set track/simulation parameters (Page 0x33)
     pub fn set_simulation(&self, grade: f32, crr: f32, cw: f32) -> Result<(), AppError> {
-        send_acknowledged(
-            self.usb,
-            self.channel_number,
-            &encode_simulation(grade, crr, cw),
-        )
+        self.send_control(&encode_simulation(grade, crr, cw))
+    }
+
+    /// Send a control page, then ask for the Command Status reply. A failed
+    /// status request is only logged: the command itself went out.
+    fn send_control(&self, page: &[u8; 8]) -> Result<(), AppError> {
+        send_acknowledged(self.usb, self.channel_number, page)?;
+        if let Err(e) = send_acknowledged(self.usb, self.channel_number, &encode_request_page(0x47)) {
+            log::debug!("FE-C command status request failed: {}", e);
+        }
+        Ok(())
     }
 }
 
@@ -157,4 +225,47 @@ mod tests {
         // cw=0.5 → raw = 0.5 / 0.01 = 50
         assert_eq!(data[7], 50);
     }
+
+    // ---- Command Status (Page 0x47) ----
+
+    #[test]
+    fn decode_command_status_sim_pass() {
+        // Last command 0x33 (simulation), seq 7, pass, echo grade 5% (20500) and crr 100
+        let grade = 20500u16.to_le_bytes();
+        let data: [u8; 8] = [0x47, 0x33, 7, 0, 0xFF, grade[0], grade[1], 100];
+        let status = decode_command_status(&data).unwrap();
+        assert_eq!(status.last_command_id, 0x33);
+        assert_eq!(status.sequence, Some(7));
+        assert_eq!(status.result, FecCommandResult::Pass);
+        assert_eq!(u16::from_le_bytes([status.data[1], status.data[2]]), 20500);
+    }
+
+    #[test]
+    fn request_page_asks_for_command_status() {
+        let data = encode_request_page(0x47);
+        assert_eq!(data[0], 0x46);
+        assert_eq!(data[5], 0x01, "transmit once");
+        assert_eq!(data[6], 0x47);
+        assert_eq!(data[7], 0x01, "request data page");
+    }
+
+    #[test]
+    fn decode_command_status_target_power_reply() {
+        // Reply to a 200W target power request: echo of the 0.25W raw value
+        let sent = encode_target_power(200);
+        let data: [u8; 8] = [0x47, 0x31, 0xFF, 0, 0xFF, 0xFF, sent[6], sent[7]];
+        let status = decode_command_status(&data).unwrap();
+        assert_eq!(status.last_command_id, 0x31);
+        assert_eq!(status.sequence, None);
+        assert_eq!(status.result, FecCommandResult::Pass);
+        assert_eq!(u16::from_le_bytes([status.data[2], status.data[3]]) / 4, 200);
+    }
+
+    #[test]
+    fn decode_command_status_no_command_yet() {
+        let data: [u8; 8] = [0x47, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(decode_command_status(&data).is_none());
+        let other_page: [u8; 8] = [0x19, 0x33, 7, 0, 0, 0, 0, 0];
+        assert!(decode_command_status(&other_page).is_none());
+    }
 }
@@ -4,7 +4,10 @@ use futures::StreamExt;
 use log::{info, warn};
 use tokio::time::{sleep, timeout, Duration};
 
-use super::protocol::FTMS_CONTROL_POINT;
+use super::protocol::{
+    FTMS_CONTROL_POINT, FTMS_FEATURE, FTMS_SUPPORTED_POWER_RANGE, FTMS_SUPPORTED_RESISTANCE_RANGE,
+};
+use super::types::TrainerCapabilities;
 use crate::error::{AppError, BleError};
 
 const REQUEST_CONTROL: u8 = 0x00;
@@ -46,6 +49,42 @@ pub(crate) fn encode_simulation(grade: f32, crr: f32, cw: f32) -> Vec<u8> {
     ]
 }
 
+/// Target Setting Features bit: Indoor Bike Simulation Parameters Supported
+const FEATURE_INDOOR_BIKE_SIMULATION: u32 = 1 << 13;
+
+/// Parse Supported Power Range (0x2AD8): sint16 min, sint16 max, uint16 increment (1 W).
+pub(crate) fn parse_supported_power_range(data: &[u8]) -> Option<(i16, i16, u16)> {
+    if data.len() < 6 {
+        return None;
+    }
+    let min = i16::from_le_bytes([data[0], data[1]]);
+    let max = i16::from_le_bytes([data[2], data[3]]);
+    let increment = u16::from_le_bytes([data[4], data[5]]);
+    (min <= max).then_some((min, max, increment))
+}
+
+/// Parse Supported Resistance Level Range (0x2AD6): sint16 min, sint16 max,
+/// uint16 increment, all with 0.1 resolution.
+pub(crate) fn parse_supported_resistance_range(data: &[u8]) -> Option<(f32, f32, f32)> {
+    if data.len() < 6 {
+        return None;
+    }
+    let min = i16::from_le_bytes([data[0], data[1]]) as f32 / 10.0;
+    let max = i16::from_le_bytes([data[2], data[3]]) as f32 / 10.0;
+    let increment = u16::from_le_bytes([data[4], data[5]]) as f32 / 10.0;
+    (min <= max).then_some((min, max, increment))
+}
+
+/// Parse Fitness Machine Feature (0x2ACC) and report whether the trainer
+/// accepts Indoor Bike Simulation parameters.
+pub(crate) fn parse_supports_simulation(data: &[u8]) -> Option<bool> {
+    if data.len() < 8 {
+        return None;
+    }
+    let target_features = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    Some(target_features & FEATURE_INDOOR_BIKE_SIMULATION != 0)
+}
+
 /// FTMS Control Point response op code
 const RESPONSE_CODE: u8 = 0x80;
 
@@ -57,6 +96,8 @@ pub struct TrainerController {
     control_point: Characteristic,
     indications_enabled: bool,
     control_granted: bool,
+    /// Ranges read from the trainer on first use; they don't change while connected
+    capabilities: Option<TrainerCapabilities>,
 }
 
 impl TrainerController {
@@ -72,6 +113,7 @@ impl TrainerController {
             control_point,
             indications_enabled: false,
             control_granted: false,
+            capabilities: None,
         })
     }
 
@@ -133,6 +175,78 @@ impl TrainerController {
         self.write_control_and_wait(&[STOP_PAUSE, 0x01]).await
     }
 
+    /// Supported power/resistance ranges and simulation support, read from the
+    /// FTMS range and feature characteristics. Any that are missing or
+    /// unreadable fall back to `TrainerCapabilities::fallback()` values. The
+    /// result is cached only when every present characteristic was read, so a
+    /// failed or timed-out read is retried on the next call.
+    pub async fn capabilities(&mut self) -> TrainerCapabilities {
+        if let Some(ref caps) = self.capabilities {
+            return caps.clone();
+        }
+        let power = self.read_characteristic(FTMS_SUPPORTED_POWER_RANGE).await;
+        let resistance = self.read_characteristic(FTMS_SUPPORTED_RESISTANCE_RANGE).await;
+        let feature = self.read_characteristic(FTMS_FEATURE).await;
+        let complete = power.is_ok() && resistance.is_ok() && feature.is_ok();
+
+        let mut caps = TrainerCapabilities::fallback();
+        if let Some((min, max, inc)) = power
+            .ok()
+            .flatten()
+            .and_then(|d| parse_supported_power_range(&d))
+        {
+            caps.min_power_w = min;
+            caps.max_power_w = max;
+            caps.power_increment_w = inc;
+        }
+        if let Some((min, max, inc)) = resistance
+            .ok()
+            .flatten()
+            .and_then(|d| parse_supported_resistance_range(&d))
+        {
+            caps.min_resistance = min;
+            caps.max_resistance = max;
+            caps.resistance_increment = inc;
+        }
+        if let Some(sim) = feature
+            .ok()
+            .flatten()
+            .and_then(|d| parse_supports_simulation(&d))
+        {
+            caps.supports_sim = sim;
+        }
+        info!(
+            "FTMS: power {}-{}W, resistance {}-{}, sim={}",
+            caps.min_power_w, caps.max_power_w, caps.min_resistance, caps.max_resistance, caps.supports_sim
+        );
+        if complete {
+            self.capabilities = Some(caps.clone());
+        }
+        caps
+    }
+
+    /// Read a characteristic, bounded by FTMS_CAPABILITY_READ_TIMEOUT_MS.
+    /// `Ok(None)` means the trainer doesn't expose it; `Err` means the read
+    /// failed or timed out.
+    async fn read_characteristic(&self, uuid: uuid::Uuid) -> Result<Option<Vec<u8>>, ()> {
+        let characteristics = self.peripheral.characteristics();
+        let Some(c) = characteristics.iter().find(|c| c.uuid == uuid) else {
+            return Ok(None);
+        };
+        let limit = Duration::from_millis(crate::config::FTMS_CAPABILITY_READ_TIMEOUT_MS);
+        match timeout(limit, self.peripheral.read(c)).await {
+            Ok(Ok(data)) => Ok(Some(data)),
+            Ok(Err(e)) => {
+                warn!("FTMS: failed to read {}: {}", uuid, e);
+                Err(())
+            }
+            Err(_) => {
+                warn!("FTMS: timed out reading {}", uuid);
+                Err(())
+            }
+        }
+    }
+
     /// Reset control state (e.g. after a disconnection)
     #[allow(dead_code)]
     pub fn reset_control(&mut self) {
@@ -207,6 +321,44 @@ impl TrainerController {
 mod tests {
     use super::*;
 
+    // ---- Capability characteristics ----
+
+    #[test]
+    fn parse_power_range() {
+        // min 0 W, max 2000 W (0x07D0), increment 1 W
+        let data = [0x00, 0x00, 0xD0, 0x07, 0x01, 0x00];
+        assert_eq!(parse_supported_power_range(&data), Some((0, 2000, 1)));
+    }
+
+    #[test]
+    fn parse_power_range_negative_min() {
+        // min -10 W (0xFFF6), max 1500 W (0x05DC), increment 5 W
+        let data = [0xF6, 0xFF, 0xDC, 0x05, 0x05, 0x00];
+        assert_eq!(parse_supported_power_range(&data), Some((-10, 1500, 5)));
+    }
+
+    #[test]
+    fn parse_power_range_rejects_short_or_inverted() {
+        assert_eq!(parse_supported_power_range(&[0x00, 0x00, 0xD0, 0x07, 0x01]), None);
+        assert_eq!(parse_supported_power_range(&[0xD0, 0x07, 0x00, 0x00, 0x01, 0x00]), None);
+    }
+
+    #[test]
+    fn parse_resistance_range_tenths() {
+        // min 0.0, max 100.0 (raw 1000), increment 1.0 (raw 10)
+        let data = [0x00, 0x00, 0xE8, 0x03, 0x0A, 0x00];
+        assert_eq!(parse_supported_resistance_range(&data), Some((0.0, 100.0, 1.0)));
+    }
+
+    #[test]
+    fn parse_simulation_feature_bit() {
+        let mut data = [0u8; 8];
+        assert_eq!(parse_supports_simulation(&data), Some(false));
+        data[5] = 0x20; // bit 13 of target setting features
+        assert_eq!(parse_supports_simulation(&data), Some(true));
+        assert_eq!(parse_supports_simulation(&data[..7]), None);
+    }
+
     // ---- Target Power (0x05) ----
 
     #[test]
@@ -2,19 +2,17 @@ use btleplug::api::{Characteristic, Peripheral as _};
 use btleplug::platform::Peripheral;
 use futures::StreamExt;
 use log::{error, info, warn};
-use std::collections::HashMap;
-use std::sync::Arc;
 use tokio::sync::broadcast;
 
 use super::protocol::*;
-use super::types::{is_dominated, DeviceType, SensorReading};
+use super::types::{DeviceType, ReadingFilter, SensorReading};
 
 pub async fn listen_to_device(
     peripheral: Peripheral,
     device_type: DeviceType,
     tx: broadcast::Sender<SensorReading>,
     device_id: String,
-    primaries: Option<Arc<std::sync::RwLock<HashMap<DeviceType, String>>>>,
+    filter: Option<ReadingFilter>,
 ) {
     let characteristics = peripheral.characteristics();
     let target_chars: Vec<&Characteristic> = characteristics
@@ -23,7 +21,13 @@ pub async fn listen_to_device(
             DeviceType::HeartRate => c.uuid == HEART_RATE_MEASUREMENT,
             DeviceType::Power => c.uuid == CYCLING_POWER_MEASUREMENT,
             DeviceType::CadenceSpeed => c.uuid == CSC_MEASUREMENT,
-            DeviceType::FitnessTrainer => c.uuid == INDOOR_BIKE_DATA,
+            // Trainers often expose ambient temperature alongside FTMS
+            DeviceType::FitnessTrainer => {
+                c.uuid == INDOOR_BIKE_DATA || c.uuid == TEMPERATURE_MEASUREMENT
+            }
+            DeviceType::MuscleOxygen => c.uuid == SMO2_SENSOR_DATA,
+            DeviceType::Radar => c.uuid == RADAR_THREATS,
+            DeviceType::Environment => c.uuid == TEMPERATURE_MEASUREMENT,
         })
         .collect();
 
@@ -61,14 +65,13 @@ pub async fn listen_to_device(
     let mut prev_crank_time: u16 = 0;
 
     while let Some(notification) = notification_stream.next().await {
+        if let Some(ref f) = filter {
+            f.trace_packet(&device_id, notification.uuid, &notification.value);
+        }
         let readings: Vec<SensorReading> = if notification.uuid == HEART_RATE_MEASUREMENT {
             decode_heart_rate(&notification.value, &device_id)
-                .into_iter()
-                .collect()
         } else if notification.uuid == CYCLING_POWER_MEASUREMENT {
             decode_cycling_power(&notification.value, &device_id)
-                .into_iter()
-                .collect()
         } else if notification.uuid == CSC_MEASUREMENT {
             decode_csc(
                 &notification.value,
@@ -80,20 +83,24 @@ pub async fn listen_to_device(
             )
         } else if notification.uuid == INDOOR_BIKE_DATA {
             decode_indoor_bike_data(&notification.value, &device_id)
+        } else if notification.uuid == SMO2_SENSOR_DATA {
+            decode_smo2(&notification.value, &device_id).into_iter().collect()
+        } else if notification.uuid == RADAR_THREATS {
+            decode_radar(&notification.value, &device_id).into_iter().collect()
+        } else if notification.uuid == TEMPERATURE_MEASUREMENT {
+            decode_temperature(&notification.value, &device_id).into_iter().collect()
         } else {
             continue;
         };
 
         for reading in readings {
-            if let Some(ref p) = primaries {
-                let dominated = {
-                    let guard = p.read().unwrap_or_else(|e| e.into_inner());
-                    is_dominated(&guard, &reading)
-                };
-                if dominated {
-                    continue;
-                }
-            }
+            let reading = match &filter {
+                Some(f) => match f.forward(reading) {
+                    Some(reading) => reading,
+                    None => continue,
+                },
+                None => reading,
+            };
             if tx.send(reading).is_err() {
                 warn!("[{}] No receivers for sensor readings, stopping listener", device_id);
                 return;
@@ -1,30 +1,112 @@
 use btleplug::api::Peripheral as _;
 use log::{info, warn};
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::AtomicI64;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Instant;
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
 use super::ant::manager::AntManager;
-use super::ant::usb::AntUsb;
+use super::ant::protocol::DEFAULT_WHEEL_CIRCUMFERENCE_MM;
+use super::ant::usb::AntLink;
 use super::ble::BleManager;
 use super::dedup::compute_device_groups;
-use super::fec::FecController;
+use super::fec::{FecCommandStatus, FecController};
 use super::ftms::TrainerController;
 use super::listener::listen_to_device;
 use super::reconnect::ReconnectManager;
 use super::types::*;
 use crate::error::{AntError, AppError, BleError};
+use crate::session::hr_fusion::HrFusion;
 use crate::session::storage::Storage;
 
 enum TrainerBackend {
     Ftms(TrainerController),
-    Fec { usb: Arc<AntUsb>, channel: u8 },
+    Fec { usb: Arc<dyn AntLink>, channel: u8 },
+    /// Records each commanded target power, for driving control loops in tests
+    #[cfg(test)]
+    Mock(Arc<StdMutex<Vec<i16>>>),
 }
 
 use crate::config;
 
+/// Wheel circumference to use for a speed sensor: the stored per-device value,
+/// or the 700x25c default when none is configured.
+async fn wheel_circumference_for(storage: Option<&Storage>, device_id: &str) -> u32 {
+    let Some(storage) = storage else {
+        return DEFAULT_WHEEL_CIRCUMFERENCE_MM;
+    };
+    match storage.get_wheel_circumference(device_id).await {
+        Ok(Some(mm)) => mm,
+        Ok(None) => DEFAULT_WHEEL_CIRCUMFERENCE_MM,
+        Err(e) => {
+            warn!("[{}] Failed to load wheel circumference: {}", device_id, e);
+            DEFAULT_WHEEL_CIRCUMFERENCE_MM
+        }
+    }
+}
+
+/// Fit an ERG target to the trainer's supported power range. Targets below the
+/// floor are raised to it (the trainer can't go lower anyway, and callers such
+/// as the zone controller legitimately ask for "as easy as possible"); targets
+/// above the ceiling are rejected since silently capping them would hide a bug.
+fn fit_power_target(watts: i16, caps: &TrainerCapabilities) -> Result<i16, AppError> {
+    if watts > caps.max_power_w {
+        return Err(AppError::Session(format!(
+            "Target power {}W exceeds trainer maximum of {}W",
+            watts, caps.max_power_w
+        )));
+    }
+    Ok(watts.max(caps.min_power_w))
+}
+
+/// Whether a newly connected `candidate` should replace `current` as primary
+/// for their device type. When both report RSSI (BLE) the stronger signal
+/// wins; otherwise the more recently seen device wins (ANT+). Ties and pairs
+/// that can't be compared keep the current primary.
+fn prefers_as_primary(current: &DeviceInfo, candidate: &DeviceInfo) -> bool {
+    if let (Some(cur), Some(cand)) = (current.rssi, candidate.rssi) {
+        return cand > cur;
+    }
+    let seen = |info: &DeviceInfo| {
+        info.last_seen
+            .as_deref()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+    };
+    match (seen(current), seen(candidate)) {
+        (Some(cur), Some(cand)) => cand > cur,
+        (None, Some(_)) => true,
+        _ => false,
+    }
+}
+
+/// Insert a live device record over a stored one, carrying over the user-assigned
+/// alias (transports never report one).
+fn overlay_device(devices: &mut HashMap<String, DeviceInfo>, mut info: DeviceInfo) {
+    if info.alias.is_none() {
+        info.alias = devices.get(&info.id).and_then(|d| d.alias.clone());
+    }
+    devices.insert(info.id.clone(), info);
+}
+
+/// Run an ANT+ scan, repeating it up to `retries` more times while it finds
+/// nothing. Returns the last pass's result.
+fn scan_with_retries(
+    retries: u32,
+    mut scan: impl FnMut() -> Result<Vec<DeviceInfo>, AppError>,
+) -> Result<Vec<DeviceInfo>, AppError> {
+    let mut result = scan();
+    for attempt in 1..=retries {
+        if matches!(&result, Ok(devices) if !devices.is_empty()) {
+            break;
+        }
+        log::info!("[ant+] Scan found nothing, retrying ({}/{})", attempt, retries);
+        result = scan();
+    }
+    result
+}
+
 /// Unified device manager wrapping BLE and ANT+ transports
 pub struct DeviceManager {
     ble: Option<BleManager>,
@@ -34,6 +116,11 @@ pub struct DeviceManager {
     /// True after a failed ANT+ USB probe; prevents repeated USB enumeration.
     /// Reset on successful ANT+ init or on user-initiated scan.
     ant_probe_failed: bool,
+    /// False after a failed BLE init; scans and connects skip re-probing the
+    /// adapter until the user explicitly retries Bluetooth.
+    ble_available: bool,
+    /// True when the last BLE init was refused by the OS (macOS Bluetooth permission)
+    ble_permission_denied: bool,
     trainer_backends: HashMap<String, TrainerBackend>,
     /// Tracks currently connected devices so rescanning doesn't lose them
     connected_devices: HashMap<String, DeviceInfo>,
@@ -48,6 +135,25 @@ pub struct DeviceManager {
     reconnect: ReconnectManager,
     /// Shared primary-device map; listeners check this before sending readings
     primary_devices: Arc<std::sync::RwLock<HashMap<DeviceType, String>>>,
+    /// Shared device id → cross-transport group map, refreshed whenever groups are computed
+    device_groups: Arc<std::sync::RwLock<HashMap<String, String>>>,
+    /// Primaries chosen by the user; auto-selection never overrides these and
+    /// they are restored when the pinned device reconnects
+    pinned_primaries: HashMap<DeviceType, String>,
+    /// Session-owned dual-recording flag; listeners forward secondary power while set
+    dual_record: Arc<AtomicBool>,
+    /// Session-manager-owned HR fusion flag; listeners fuse every HR strap while set
+    hr_fusion: Arc<AtomicBool>,
+    /// Fusion state shared by all listeners
+    hr_fusion_state: Arc<StdMutex<HrFusion>>,
+    /// Raw packet logging toggle shared with every listener; off by default
+    packet_trace: Arc<AtomicBool>,
+    /// Announces trainers restored by the reconnect engine so active control can resume
+    trainer_reconnects: broadcast::Sender<String>,
+    /// When each currently connected device's connection was made
+    connected_since: HashMap<String, Instant>,
+    /// Watchdog-detected drops per device since its last explicit connect
+    drop_counts: HashMap<String, u32>,
 }
 
 impl DeviceManager {
@@ -57,6 +163,8 @@ impl DeviceManager {
             ant: None,
             ant_was_available: false,
             ant_probe_failed: false,
+            ble_available: true,
+            ble_permission_denied: false,
             trainer_backends: HashMap::new(),
             connected_devices: HashMap::new(),
             storage: None,
@@ -65,6 +173,17 @@ impl DeviceManager {
             listener_handles: HashMap::new(),
             reconnect: ReconnectManager::new(),
             primary_devices: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            device_groups: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            pinned_primaries: HashMap::new(),
+            dual_record: Arc::new(AtomicBool::new(false)),
+            hr_fusion: Arc::new(AtomicBool::new(false)),
+            hr_fusion_state: Arc::new(StdMutex::new(HrFusion::new(
+                config::READING_FRESHNESS_SECS * 1000,
+            ))),
+            packet_trace: Arc::new(AtomicBool::new(false)),
+            trainer_reconnects: broadcast::channel(8).0,
+            connected_since: HashMap::new(),
+            drop_counts: HashMap::new(),
         }
     }
 
@@ -72,19 +191,123 @@ impl DeviceManager {
         self.storage = Some(storage);
     }
 
+    /// Share the session manager's dual-recording flag with device listeners.
+    pub fn set_dual_record_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.dual_record = flag;
+    }
+
+    pub fn set_hr_fusion_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.hr_fusion = flag;
+    }
+
+    fn reading_filter(&self) -> ReadingFilter {
+        ReadingFilter {
+            primaries: self.primary_devices.clone(),
+            device_groups: self.device_groups.clone(),
+            dual_record: self.dual_record.clone(),
+            hr_fusion: self.hr_fusion.clone(),
+            hr_fusion_state: self.hr_fusion_state.clone(),
+            packet_trace: self.packet_trace.clone(),
+        }
+    }
+
+    /// Turn raw packet logging on or off for all current and future listeners.
+    pub fn set_packet_trace(&self, enabled: bool) {
+        self.packet_trace.store(enabled, Ordering::Relaxed);
+        info!("Raw packet trace {}", if enabled { "enabled" } else { "disabled" });
+    }
+
     /// Returns a clone of the shared primary-device Arc for external consumers.
     pub fn primaries_handle(&self) -> Arc<std::sync::RwLock<HashMap<DeviceType, String>>> {
         self.primary_devices.clone()
     }
 
-    /// Set device as primary for its type if no primary exists yet.
+    /// Returns a clone of the shared device-group map for external consumers.
+    pub fn groups_handle(&self) -> Arc<std::sync::RwLock<HashMap<String, String>>> {
+        self.device_groups.clone()
+    }
+
+    fn store_device_groups(&self, groups: &HashMap<String, String>) {
+        *self.device_groups.write().unwrap_or_else(|e| e.into_inner()) = groups.clone();
+    }
+
+    /// Set device as primary for its type if no primary exists yet, or if the
+    /// user pinned it as primary.
     fn auto_set_primary(&self, device_type: DeviceType, device_id: &str) {
         let mut p = self.primary_devices.write().unwrap_or_else(|e| e.into_inner());
-        p.entry(device_type).or_insert_with(|| device_id.to_owned());
+        if self.pinned_primaries.get(&device_type).map(String::as_str) == Some(device_id) {
+            p.insert(device_type, device_id.to_owned());
+        } else {
+            p.entry(device_type).or_insert_with(|| device_id.to_owned());
+        }
+    }
+
+    /// Make `device_id` the primary for `device_type` and keep it there:
+    /// signal-based auto-selection won't replace it.
+    pub fn set_primary_manual(&mut self, device_type: DeviceType, device_id: String) {
+        self.pinned_primaries.insert(device_type, device_id.clone());
+        let mut p = self.primary_devices.write().unwrap_or_else(|e| e.into_inner());
+        p.insert(device_type, device_id);
+    }
+
+    /// Drop any user pin on a device, e.g. when it is forgotten.
+    pub fn unpin_primary(&mut self, device_id: &str) {
+        self.pinned_primaries.retain(|_, v| v != device_id);
+    }
+
+    /// After `device_id` connects, re-evaluate the primary for its type by
+    /// signal quality (see `prefers_as_primary`). Returns the type and new
+    /// primary when it changed. User-pinned primaries are left alone.
+    pub async fn select_primary(&self, device_id: &str) -> Option<(DeviceType, String)> {
+        let candidate = self.connected_devices.get(device_id)?;
+        let device_type = candidate.device_type;
+        if self.pinned_primaries.contains_key(&device_type) {
+            return None;
+        }
+        let current_id = {
+            let p = self.primary_devices.read().unwrap_or_else(|e| e.into_inner());
+            p.get(&device_type).cloned()
+        }?;
+        if current_id == device_id {
+            return None;
+        }
+        let current = self.connected_devices.get(&current_id)?;
+
+        // Refresh with live signal data so the comparison isn't based on scan-time values
+        let rssi: HashMap<String, i16> = self.read_signal_strengths().await.into_iter().collect();
+        let refreshed = |info: &DeviceInfo| {
+            let mut info = info.clone();
+            if let Some(&r) = rssi.get(&info.id) {
+                info.rssi = Some(r);
+            }
+            if let Some(elapsed) = self.ant_elapsed_since_seen(&info.id) {
+                let seen = chrono::Utc::now() - chrono::Duration::from_std(elapsed).unwrap_or_default();
+                info.last_seen = Some(seen.to_rfc3339());
+            }
+            info
+        };
+        if !prefers_as_primary(&refreshed(current), &refreshed(candidate)) {
+            return None;
+        }
+
+        info!(
+            "[{}] Better signal than {}; selecting as primary {:?}",
+            device_id, current_id, device_type
+        );
+        let mut p = self.primary_devices.write().unwrap_or_else(|e| e.into_inner());
+        p.insert(device_type, device_id.to_owned());
+        Some((device_type, device_id.to_owned()))
+    }
+
+    /// Time since the last ANT+ message from `device_id`, if any has arrived.
+    fn ant_elapsed_since_seen(&self, device_id: &str) -> Option<std::time::Duration> {
+        let store = self.ant_last_seen.as_ref()?;
+        let last_seen = store.lock().unwrap_or_else(|e| e.into_inner());
+        super::ant::listener::atomic_elapsed(last_seen.get(device_id)?)
     }
 
     /// Remove all primary entries that reference the given device.
-    fn remove_primary(&self, device_id: &str) {
+    pub fn remove_primary(&self, device_id: &str) {
         let mut p = self.primary_devices.write().unwrap_or_else(|e| e.into_inner());
         p.retain(|_, v| v != device_id);
     }
@@ -122,6 +345,68 @@ impl DeviceManager {
         self.set_ant(ant);
     }
 
+    /// Whether a BLE adapter is usable (true until an init attempt fails).
+    pub fn ble_available(&self) -> bool {
+        self.ble_available
+    }
+
+    /// Whether the OS refused Bluetooth access on the last init attempt.
+    pub fn ble_permission_denied(&self) -> bool {
+        self.ble_permission_denied
+    }
+
+    /// Allow the next scan or connect to probe for a BLE adapter again.
+    pub fn reset_ble_probe(&mut self) {
+        self.ble_available = true;
+    }
+
+    /// Probe for a BLE adapter again at the user's request, e.g. after
+    /// plugging in a dongle. Returns whether BLE is now available.
+    pub async fn retry_ble(&mut self) -> bool {
+        self.reset_ble_probe();
+        self.ensure_ble().await.is_ok()
+    }
+
+    /// Ensure BLE is initialized, probing the adapter at most once until
+    /// `reset_ble_probe()` is called.
+    async fn ensure_ble(&mut self) -> Result<(), AppError> {
+        self.ensure_ble_with(BleManager::new).await
+    }
+
+    async fn ensure_ble_with<F, Fut>(&mut self, init: F) -> Result<(), AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<BleManager, AppError>>,
+    {
+        if self.ble.is_some() {
+            return Ok(());
+        }
+        if !self.ble_available {
+            return Err(self.ble_unavailable_error());
+        }
+        match init().await {
+            Ok(mgr) => {
+                self.ble = Some(mgr);
+                self.ble_permission_denied = false;
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("[ble] Not available: {}", e);
+                self.ble_available = false;
+                self.ble_permission_denied = matches!(e, AppError::Ble(BleError::PermissionDenied));
+                Err(self.ble_unavailable_error())
+            }
+        }
+    }
+
+    fn ble_unavailable_error(&self) -> AppError {
+        if self.ble_permission_denied {
+            BleError::PermissionDenied.into()
+        } else {
+            BleError::NoAdapter.into()
+        }
+    }
+
     /// Run a blocking closure with the AntManager, guaranteeing put-back even on panic.
     /// Returns Err if no AntManager is available or if spawn_blocking panics.
     async fn with_ant_blocking<F, R>(&mut self, f: F) -> Result<R, AppError>
@@ -165,8 +450,8 @@ impl DeviceManager {
                 }
             }
         }
-        for (id, info) in &self.connected_devices {
-            devices.insert(id.clone(), info.clone());
+        for info in self.connected_devices.values() {
+            overlay_device(&mut devices, info.clone());
         }
         // Annotate ANT+ devices with metadata from common data pages
         self.annotate_ant_metadata(&mut devices);
@@ -174,6 +459,7 @@ impl DeviceManager {
         // Compute cross-transport device groups
         let device_list: Vec<DeviceInfo> = devices.values().cloned().collect();
         let groups = compute_device_groups(&device_list);
+        self.store_device_groups(&groups);
         for (id, group_id) in &groups {
             if let Some(info) = devices.get_mut(id) {
                 info.device_group = Some(group_id.clone());
@@ -186,8 +472,29 @@ impl DeviceManager {
     /// Scan for devices on all available transports.
     /// Always includes currently-connected devices in the results.
     /// Loads known devices from storage as a base layer.
-    /// BLE and ANT+ scans run concurrently to minimize total scan time.
-    pub async fn scan_all(&mut self) -> Result<Vec<DeviceInfo>, AppError> {
+    /// BLE scans for `scan_duration_secs` while ANT+ scans concurrently to
+    /// minimize total scan time, with up to `ant_retries` extra ANT+ passes if
+    /// it finds nothing.
+    pub async fn scan_all(
+        &mut self,
+        scan_duration_secs: u64,
+        ant_retries: u32,
+    ) -> Result<Vec<DeviceInfo>, AppError> {
+        self.scan_all_with(scan_duration_secs, ant_retries, BleManager::new, AntManager::try_new)
+            .await
+    }
+
+    async fn scan_all_with<F, Fut>(
+        &mut self,
+        scan_duration_secs: u64,
+        ant_retries: u32,
+        ble_init: F,
+        ant_init: fn() -> Option<AntManager>,
+    ) -> Result<Vec<DeviceInfo>, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<BleManager, AppError>>,
+    {
         let mut discovered: HashMap<String, DeviceInfo> = HashMap::new();
         let mut scan_found: HashSet<String> = HashSet::new();
 
@@ -200,13 +507,8 @@ impl DeviceManager {
             }
         }
 
-        // Initialize BLE on first scan
-        if self.ble.is_none() {
-            match BleManager::new().await {
-                Ok(mgr) => self.ble = Some(mgr),
-                Err(e) => log::warn!("[ble] Not available: {}", e),
-            }
-        }
+        // Initialize BLE on first scan; without an adapter, scan ANT+ only
+        let _ = self.ensure_ble_with(ble_init).await;
 
         // Start BLE scan
         if let Some(ref ble) = self.ble {
@@ -220,9 +522,9 @@ impl DeviceManager {
         self.ant_probe_failed = false;
         let ant_taken = self.ant.take();
         let ant_task = tokio::task::spawn_blocking(move || {
-            let ant = ant_taken.or_else(AntManager::try_new);
+            let ant = ant_taken.or_else(ant_init);
             if let Some(mut ant_mgr) = ant {
-                let result = ant_mgr.scan();
+                let result = scan_with_retries(ant_retries, || ant_mgr.scan());
                 (Some(ant_mgr), result.ok())
             } else {
                 (None, None)
@@ -230,7 +532,7 @@ impl DeviceManager {
         });
 
         // Sleep during BLE scan (ANT+ runs concurrently on blocking thread)
-        tokio::time::sleep(tokio::time::Duration::from_secs(config::BLE_SCAN_DURATION_SECS)).await;
+        tokio::time::sleep(tokio::time::Duration::from_secs(scan_duration_secs)).await;
 
         // Collect BLE results
         if let Some(ref ble) = self.ble {
@@ -239,7 +541,7 @@ impl DeviceManager {
                 Ok(devices) => {
                     for d in devices {
                         scan_found.insert(d.id.clone());
-                        discovered.insert(d.id.clone(), d);
+                        overlay_device(&mut discovered, d);
                     }
                 }
                 Err(e) => log::warn!("[ble] Discovery failed: {}", e),
@@ -257,7 +559,7 @@ impl DeviceManager {
                 if let Some(devices) = ant_devices {
                     for d in devices {
                         scan_found.insert(d.id.clone());
-                        discovered.insert(d.id.clone(), d);
+                        overlay_device(&mut discovered, d);
                     }
                 }
             }
@@ -274,8 +576,8 @@ impl DeviceManager {
 
         // Merge: connected devices always appear (with Connected status),
         // plus any newly discovered devices not already connected
-        for (id, info) in &self.connected_devices {
-            discovered.insert(id.clone(), info.clone());
+        for info in self.connected_devices.values() {
+            overlay_device(&mut discovered, info.clone());
         }
 
         // Mark in_range based on whether the device was found in this scan
@@ -289,6 +591,7 @@ impl DeviceManager {
         // Compute cross-transport device groups
         let device_list: Vec<DeviceInfo> = discovered.values().cloned().collect();
         let groups = compute_device_groups(&device_list);
+        self.store_device_groups(&groups);
         for (id, group_id) in &groups {
             if let Some(info) = discovered.get_mut(id) {
                 info.device_group = Some(group_id.clone());
@@ -307,30 +610,67 @@ impl DeviceManager {
         Ok(result)
     }
 
-    /// Connect to a device by ID (routes to BLE or ANT+ based on ID prefix)
+    /// Connect to a device by ID (routes to BLE or ANT+ based on ID prefix).
+    /// Resets the device's drop count.
     pub async fn connect(
         &mut self,
         device_id: &str,
         tx: broadcast::Sender<SensorReading>,
     ) -> Result<DeviceInfo, AppError> {
-        if device_id.starts_with("ant:") {
-            self.connect_ant(device_id, tx).await
+        let info = self.open_connection(device_id, tx).await?;
+        self.drop_counts.remove(&info.id);
+        Ok(info)
+    }
+
+    /// Connect without touching the drop count; shared with the reconnect engine.
+    async fn open_connection(
+        &mut self,
+        device_id: &str,
+        tx: broadcast::Sender<SensorReading>,
+    ) -> Result<DeviceInfo, AppError> {
+        let info = if device_id.starts_with("ant:") {
+            self.connect_ant(device_id, tx).await?
         } else {
-            self.connect_ble(device_id, tx).await
+            self.connect_ble(device_id, tx).await?
+        };
+        self.connected_since.insert(info.id.clone(), Instant::now());
+        self.remember_connection(&info).await;
+        self.refresh_device_groups().await;
+        Ok(info)
+    }
+
+    /// Uptime of the current connection and watchdog drop count for a device.
+    pub fn get_device_health(&self, device_id: &str) -> DeviceHealth {
+        DeviceHealth {
+            uptime_secs: self
+                .connected_since
+                .get(device_id)
+                .map(|since| since.elapsed().as_secs()),
+            drop_count: self.drop_counts.get(device_id).copied().unwrap_or(0),
         }
     }
 
+    /// Stamp the device as last connected so it is picked for auto-connect on startup.
+    async fn remember_connection(&self, info: &DeviceInfo) {
+        if let Some(ref storage) = self.storage {
+            if let Err(e) = storage.mark_device_connected(info).await {
+                warn!("[{}] Failed to record connection: {}", info.id, e);
+            }
+        }
+    }
+
+    /// Regroup known and connected devices, so a device connected outside a
+    /// scan is deduplicated against its other-transport twin straight away.
+    async fn refresh_device_groups(&self) {
+        self.list_current().await;
+    }
+
     async fn connect_ble(
         &mut self,
         device_id: &str,
         tx: broadcast::Sender<SensorReading>,
     ) -> Result<DeviceInfo, AppError> {
-        if self.ble.is_none() {
-            match BleManager::new().await {
-                Ok(mgr) => self.ble = Some(mgr),
-                Err(e) => return Err(BleError::Btleplug(format!("BLE init failed: {}", e)).into()),
-            }
-        }
+        self.ensure_ble().await?;
         let ble = self.ble.as_ref().ok_or(BleError::NotInitialized)?;
         let mut info = ble.connect_device(device_id).await?;
 
@@ -364,11 +704,11 @@ impl DeviceManager {
                 let peripheral = peripheral.clone();
                 let device_type = info.device_type;
                 let did = device_id.to_string();
-                let primaries = Some(self.primary_devices.clone());
+                let filter = Some(self.reading_filter());
                 drop(connected_lock);
 
                 let handle = tokio::spawn(async move {
-                    listen_to_device(peripheral, device_type, tx, did, primaries).await;
+                    listen_to_device(peripheral, device_type, tx, did, filter).await;
                 });
                 self.listener_handles.insert(device_id.to_string(), handle);
             } else {
@@ -413,9 +753,10 @@ impl DeviceManager {
         }
 
         let id = device_id.to_string();
-        let primaries = Some(self.primary_devices.clone());
+        let filter = Some(self.reading_filter());
+        let wheel_mm = wheel_circumference_for(self.storage.as_deref(), device_id).await;
         let info = self
-            .with_ant_blocking(move |ant| ant.connect(&id, tx, primaries))
+            .with_ant_blocking(move |ant| ant.connect(&id, tx, filter, wheel_mm))
             .await??;
 
         // If it's a trainer, store FE-C backend
@@ -443,6 +784,24 @@ impl DeviceManager {
         Ok(info)
     }
 
+    /// Pair an ANT+ device by the device number printed on the sensor, skipping
+    /// the wildcard search that can miss devices in crowded RF environments.
+    pub async fn pair_ant(
+        &mut self,
+        device_type: DeviceType,
+        device_number: u16,
+        tx: broadcast::Sender<SensorReading>,
+    ) -> Result<DeviceInfo, AppError> {
+        self.ensure_ant().await;
+        if self.ant.is_none() {
+            return Err(AntError::NoUsbStick.into());
+        }
+        let device_id = self
+            .with_ant_blocking(move |ant| ant.register_manual(device_type, device_number))
+            .await??;
+        self.connect(&device_id, tx).await
+    }
+
     /// Disconnect a device
     pub async fn disconnect(&mut self, device_id: &str) -> Result<(), AppError> {
         self.remove_primary(device_id);
@@ -451,6 +810,7 @@ impl DeviceManager {
         }
         self.trainer_backends.remove(device_id);
         self.connected_devices.remove(device_id);
+        self.connected_since.remove(device_id);
 
         if device_id.starts_with("ant:") {
             if self.ant.is_some() {
@@ -537,6 +897,8 @@ impl DeviceManager {
             if let Some(handle) = self.listener_handles.remove(&info.id) {
                 handle.abort();
             }
+            self.connected_since.remove(&info.id);
+            *self.drop_counts.entry(info.id.clone()).or_insert(0) += 1;
         }
 
         // Register disconnected devices for auto-reconnect
@@ -559,11 +921,14 @@ impl DeviceManager {
 
         for info in due {
             let attempt = self.reconnect.attempt_count(&info.id);
-            match self.connect(&info.id, tx.clone()).await {
+            match self.open_connection(&info.id, tx.clone()).await {
                 Ok(new_info) => {
                     log::info!("[{}] Reconnected on attempt {}", info.id, attempt);
                     self.reconnect.remove(&info.id);
                     self.auto_set_primary(new_info.device_type, &new_info.id);
+                    if self.trainer_backends.contains_key(&new_info.id) {
+                        let _ = self.trainer_reconnects.send(new_info.id.clone());
+                    }
                     reconnected.push(new_info);
                 }
                 Err(e) => {
@@ -581,6 +946,46 @@ impl DeviceManager {
         (reconnected, still_trying)
     }
 
+    /// Receive the id of each trainer the reconnect engine restores.
+    pub fn subscribe_trainer_reconnects(&self) -> broadcast::Receiver<String> {
+        self.trainer_reconnects.subscribe()
+    }
+
+    /// Register a connected mock trainer. Returns the log of target powers it
+    /// is commanded to.
+    #[cfg(test)]
+    pub(crate) fn connect_mock_trainer(&mut self, device_id: &str) -> Arc<StdMutex<Vec<i16>>> {
+        let commands = Arc::new(StdMutex::new(Vec::new()));
+        self.connected_devices.insert(
+            device_id.to_string(),
+            DeviceInfo {
+                id: device_id.to_string(),
+                name: None,
+                device_type: DeviceType::FitnessTrainer,
+                status: ConnectionStatus::Connected,
+                transport: Transport::Ble,
+                rssi: None,
+                battery_level: None,
+                last_seen: None,
+                manufacturer: None,
+                model_number: None,
+                serial_number: None,
+                device_group: None,
+                alias: None,
+                in_range: true,
+            },
+        );
+        self.trainer_backends
+            .insert(device_id.to_string(), TrainerBackend::Mock(commands.clone()));
+        commands
+    }
+
+    /// Announce a restored trainer the way the reconnect engine does.
+    #[cfg(test)]
+    pub(crate) fn announce_trainer_reconnect(&self, device_id: &str) {
+        let _ = self.trainer_reconnects.send(device_id.to_string());
+    }
+
     pub fn clear_reconnect_target(&mut self, device_id: &str) {
         self.reconnect.remove(device_id);
     }
@@ -588,6 +993,17 @@ impl DeviceManager {
     // Trainer control methods -- C2: FE-C calls wrapped in spawn_blocking
 
     pub async fn set_target_power(&mut self, device_id: &str, watts: i16) -> Result<(), AppError> {
+        let caps = self.get_trainer_capabilities(device_id).await?;
+        let requested = watts;
+        let watts = fit_power_target(watts, &caps).inspect_err(|e| {
+            warn!("[{}] set_target_power({}W) rejected: {}", device_id, requested, e);
+        })?;
+        if watts != requested {
+            info!(
+                "[{}] Target power {}W below trainer minimum, clamped to {}W",
+                device_id, requested, watts
+            );
+        }
         let result = match self.trainer_backends.get_mut(device_id) {
             Some(TrainerBackend::Ftms(controller)) => {
                 controller.set_target_power(watts).await
@@ -597,12 +1013,17 @@ impl DeviceManager {
                 let ch = *channel;
                 let w = watts.max(0) as u16;
                 tokio::task::spawn_blocking(move || {
-                    let fec = FecController::new(&usb, ch);
+                    let fec = FecController::new(usb.as_ref(), ch);
                     fec.set_target_power(w)
                 })
                 .await
                 .map_err(|e| AppError::from(AntError::TaskPanicked(format!("FEC: {}", e))))?
             }
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(commands)) => {
+                commands.lock().unwrap().push(watts);
+                Ok(())
+            }
             None => Err(AppError::Session("No trainer connected".into())),
         };
         if let Err(ref e) = result {
@@ -621,12 +1042,14 @@ impl DeviceManager {
                 let ch = *channel;
                 let lvl = level;
                 tokio::task::spawn_blocking(move || {
-                    let fec = FecController::new(&usb, ch);
+                    let fec = FecController::new(usb.as_ref(), ch);
                     fec.set_resistance(lvl)
                 })
                 .await
                 .map_err(|e| AppError::from(AntError::TaskPanicked(format!("FEC: {}", e))))?
             }
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(_)) => Ok(()),
             None => Err(AppError::Session("No trainer connected".into())),
         };
         if let Err(ref e) = result {
@@ -650,12 +1073,14 @@ impl DeviceManager {
                 let usb = usb.clone();
                 let ch = *channel;
                 tokio::task::spawn_blocking(move || {
-                    let fec = FecController::new(&usb, ch);
+                    let fec = FecController::new(usb.as_ref(), ch);
                     fec.set_simulation(grade, crr, cw)
                 })
                 .await
                 .map_err(|e| AppError::from(AntError::TaskPanicked(format!("FEC: {}", e))))?
             }
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(_)) => Ok(()),
             None => Err(AppError::Session("No trainer connected".into())),
         };
         if let Err(ref e) = result {
@@ -664,12 +1089,29 @@ impl DeviceManager {
         result
     }
 
+    /// Supported control ranges of a connected trainer. FTMS trainers report
+    /// their own; FE-C has no equivalent page so it gets conservative defaults.
+    pub async fn get_trainer_capabilities(
+        &mut self,
+        device_id: &str,
+    ) -> Result<TrainerCapabilities, AppError> {
+        match self.trainer_backends.get_mut(device_id) {
+            Some(TrainerBackend::Ftms(controller)) => Ok(controller.capabilities().await),
+            Some(TrainerBackend::Fec { .. }) => Ok(TrainerCapabilities::fallback()),
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(_)) => Ok(TrainerCapabilities::fallback()),
+            None => Err(AppError::Session("No trainer connected".into())),
+        }
+    }
+
     pub async fn start_trainer(&mut self, device_id: &str) -> Result<(), AppError> {
         match self.trainer_backends.get_mut(device_id) {
             Some(TrainerBackend::Ftms(controller)) => controller.start().await,
             Some(TrainerBackend::Fec { .. }) => {
                 Err(AntError::NotSupported("start/stop for ANT+ trainers".into()).into())
             }
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(_)) => Ok(()),
             None => Err(AppError::Session("No trainer connected".into())),
         }
     }
@@ -680,10 +1122,27 @@ impl DeviceManager {
             Some(TrainerBackend::Fec { .. }) => {
                 Err(AntError::NotSupported("start/stop for ANT+ trainers".into()).into())
             }
+            #[cfg(test)]
+            Some(TrainerBackend::Mock(_)) => Ok(()),
             None => Err(AppError::Session("No trainer connected".into())),
         }
     }
 
+    /// The BLE power meter to zero-offset calibrate. The caller runs the
+    /// calibration with `ble::set_power_zero_offset` after releasing the manager.
+    pub async fn zero_offset_peripheral(
+        &self,
+        device_id: &str,
+    ) -> Result<btleplug::platform::Peripheral, AppError> {
+        if device_id.starts_with("ant:") {
+            return Err(AntError::NotSupported("zero offset for ANT+ power meters".into()).into());
+        }
+        match self.ble {
+            Some(ref ble) => ble.connected_peripheral(device_id).await,
+            None => Err(BleError::NotInitialized.into()),
+        }
+    }
+
     /// Get detailed information about a connected device
     pub async fn get_device_details(&self, device_id: &str) -> Result<DeviceDetails, AppError> {
         if device_id.starts_with("ant:") {
@@ -752,6 +1211,65 @@ impl DeviceManager {
         }
     }
 
+    /// Status the trainer last reported for an FE-C control command (Page 0x47),
+    /// for spotting grade or power commands it never acknowledged. ANT+ only.
+    pub fn last_command_status(&self, device_id: &str) -> Option<FecCommandStatus> {
+        let store = self.ant_metadata.as_ref()?;
+        let meta = store.lock().unwrap_or_else(|e| e.into_inner());
+        meta.get(device_id).and_then(|m| m.fec_command_status)
+    }
+
+    /// Battery levels of connected ANT+ devices (from common data page 82),
+    /// plus the connected BLE peripherals whose level must be read over GATT.
+    /// The caller reads those with `ble::read_battery_levels` after releasing
+    /// the manager.
+    pub async fn battery_level_sources(
+        &self,
+    ) -> (Vec<(String, u8)>, Vec<(String, btleplug::platform::Peripheral)>) {
+        let mut levels = Vec::new();
+        let mut peripherals = Vec::new();
+        for (id, info) in &self.connected_devices {
+            if info.status != ConnectionStatus::Connected {
+                continue;
+            }
+            if id.starts_with("ant:") {
+                let level = self
+                    .ant
+                    .as_ref()
+                    .and_then(|ant| ant.get_metadata(id))
+                    .and_then(|m| m.battery_level);
+                if let Some(level) = level {
+                    levels.push((id.clone(), level));
+                }
+            } else if let Some(ref ble) = self.ble {
+                if let Ok(peripheral) = ble.connected_peripheral(id).await {
+                    peripherals.push((id.clone(), peripheral));
+                }
+            }
+        }
+        (levels, peripherals)
+    }
+
+    /// Current RSSI of connected BLE devices (ANT+ has no per-device signal strength).
+    pub async fn read_signal_strengths(&self) -> Vec<(String, i16)> {
+        let Some(ref ble) = self.ble else {
+            return Vec::new();
+        };
+        ble.read_rssi_all()
+            .await
+            .into_iter()
+            .filter(|(id, _)| {
+                self.connected_devices
+                    .get(id)
+                    .is_some_and(|info| info.status == ConnectionStatus::Connected)
+            })
+            .collect()
+    }
+
+    pub fn is_connected(&self, device_id: &str) -> bool {
+        self.connected_devices.contains_key(device_id)
+    }
+
     /// Get the connected trainer device ID (for command routing).
     /// Cross-references trainer_backends with connected_devices to return
     /// only a trainer that is actually Connected, avoiding stale entries
@@ -872,6 +1390,317 @@ pub fn ant_manufacturer_name(id: u16) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::device::ant::listener::listen_ant_channel;
+    use crate::device::ant::usb::{MockAntLink, MSG_SET_CHANNEL_ID};
+    use std::sync::atomic::AtomicBool;
+
+    fn caps(min: i16, max: i16) -> TrainerCapabilities {
+        TrainerCapabilities {
+            min_power_w: min,
+            max_power_w: max,
+            ..TrainerCapabilities::fallback()
+        }
+    }
+
+    #[test]
+    fn power_target_below_min_is_clamped() {
+        assert_eq!(fit_power_target(5, &caps(50, 1500)).unwrap(), 50);
+        assert_eq!(fit_power_target(-20, &caps(0, 1500)).unwrap(), 0);
+    }
+
+    #[test]
+    fn power_target_above_max_is_rejected() {
+        let err = fit_power_target(1600, &caps(50, 1500)).unwrap_err();
+        assert!(err.to_string().contains("1500W"), "{}", err);
+    }
+
+    #[test]
+    fn power_target_in_range_is_unchanged() {
+        assert_eq!(fit_power_target(50, &caps(50, 1500)).unwrap(), 50);
+        assert_eq!(fit_power_target(250, &caps(50, 1500)).unwrap(), 250);
+        assert_eq!(fit_power_target(1500, &caps(50, 1500)).unwrap(), 1500);
+    }
+
+    #[test]
+    fn empty_ant_scan_is_retried_configured_times() {
+        let mut calls = 0;
+        let result = scan_with_retries(2, || {
+            calls += 1;
+            Ok(Vec::new())
+        });
+        assert!(result.unwrap().is_empty());
+        assert_eq!(calls, 3, "initial scan plus two retries");
+
+        let mut calls = 0;
+        let result = scan_with_retries(2, || {
+            calls += 1;
+            Ok(if calls == 2 { vec![device("ant:120:7", None)] } else { Vec::new() })
+        });
+        assert_eq!(result.unwrap().len(), 1);
+        assert_eq!(calls, 2, "stops once devices are found");
+    }
+
+    fn hr(id: &str, rssi: Option<i16>, last_seen: Option<&str>) -> DeviceInfo {
+        DeviceInfo {
+            device_type: DeviceType::HeartRate,
+            rssi,
+            last_seen: last_seen.map(str::to_string),
+            ..device(id, None)
+        }
+    }
+
+    #[test]
+    fn primary_prefers_stronger_rssi() {
+        let weak = hr("ble:a", Some(-80), None);
+        let strong = hr("ble:b", Some(-55), None);
+        assert!(prefers_as_primary(&weak, &strong));
+        assert!(!prefers_as_primary(&strong, &weak));
+        // Equal signal keeps the current primary
+        assert!(!prefers_as_primary(&strong, &strong.clone()));
+    }
+
+    #[test]
+    fn primary_prefers_more_recently_seen_ant_device() {
+        let stale = hr("ant:120:1", None, Some("2024-06-01T10:00:00Z"));
+        let fresh = hr("ant:120:2", None, Some("2024-06-01T10:00:05Z"));
+        assert!(prefers_as_primary(&stale, &fresh));
+        assert!(!prefers_as_primary(&fresh, &stale));
+        // A device that has never been heard from doesn't displace one that has
+        assert!(!prefers_as_primary(&fresh, &hr("ant:120:3", None, None)));
+    }
+
+    fn device(id: &str, alias: Option<&str>) -> DeviceInfo {
+        DeviceInfo {
+            id: id.to_string(),
+            name: Some("4F2A-91C3".to_string()),
+            device_type: DeviceType::Power,
+            status: ConnectionStatus::Connected,
+            transport: Transport::Ble,
+            rssi: None,
+            battery_level: None,
+            last_seen: None,
+            manufacturer: None,
+            model_number: None,
+            serial_number: None,
+            device_group: None,
+            alias: alias.map(String::from),
+            in_range: true,
+        }
+    }
+
+    #[test]
+    fn overlay_device_keeps_stored_alias() {
+        let mut devices = HashMap::from([("pm".to_string(), device("pm", Some("Assioma")))]);
+        overlay_device(&mut devices, device("pm", None));
+        assert_eq!(devices["pm"].alias.as_deref(), Some("Assioma"));
+        assert_eq!(devices["pm"].name.as_deref(), Some("4F2A-91C3"));
+    }
+
+    #[test]
+    fn packet_trace_toggle_reaches_running_listeners() {
+        let dm = DeviceManager::new();
+        let filter = dm.reading_filter();
+        assert!(!filter.packet_trace.load(Ordering::Relaxed));
+
+        dm.set_packet_trace(true);
+        assert!(filter.packet_trace.load(Ordering::Relaxed));
+        assert!(dm.reading_filter().packet_trace.load(Ordering::Relaxed));
+
+        dm.set_packet_trace(false);
+        assert!(!filter.packet_trace.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn watchdog_drop_increments_device_drop_count() {
+        let mut dm = DeviceManager::new();
+        let id = "ant:120:4242".to_string();
+        dm.connected_devices.insert(
+            id.clone(),
+            DeviceInfo {
+                id: id.clone(),
+                device_type: DeviceType::HeartRate,
+                transport: Transport::AntPlus,
+                ..device(&id, None)
+            },
+        );
+        dm.connected_since.insert(id.clone(), Instant::now());
+        // Last heard from well before the disconnect timeout
+        let stale = -((config::DEVICE_DISCONNECT_TIMEOUT_SECS as i64 + 60) * 1_000_000_000);
+        dm.ant_last_seen = Some(Arc::new(StdMutex::new(HashMap::from([(
+            id.clone(),
+            Arc::new(AtomicI64::new(stale)),
+        )]))));
+        assert_eq!(dm.get_device_health(&id).drop_count, 0);
+        assert!(dm.get_device_health(&id).uptime_secs.is_some());
+
+        let dropped = dm.check_connections().await;
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(
+            dm.get_device_health(&id),
+            DeviceHealth { uptime_secs: None, drop_count: 1 }
+        );
+        // Already removed, so a second sweep doesn't count it again
+        assert!(dm.check_connections().await.is_empty());
+        assert_eq!(dm.get_device_health(&id).drop_count, 1);
+        assert_eq!(dm.get_device_health("ant:120:1").drop_count, 0);
+    }
+
+    #[tokio::test]
+    async fn pairing_by_number_opens_channel_for_that_device() {
+        let mut dm = DeviceManager::new();
+        let link = Arc::new(MockAntLink::default());
+        dm.ant = Some(AntManager::with_link(link.clone()));
+        let (tx, _rx) = broadcast::channel(8);
+
+        let info = dm.pair_ant(DeviceType::Power, 12345, tx).await.unwrap();
+        assert_eq!(info.id, "ant:11:12345");
+        assert!(dm.connected_devices.contains_key("ant:11:12345"));
+
+        let sent = link.sent.lock().unwrap().clone();
+        let channel_id = sent
+            .iter()
+            .find(|m| m.msg_id == MSG_SET_CHANNEL_ID)
+            .expect("channel ID set");
+        // Device number 12345 (LE), power device type, wildcard transmission type
+        assert_eq!(channel_id.data[1..], [0x39, 0x30, 11, 0]);
+        // Paired directly: every command went to the one channel, no search scan
+        assert!(sent.iter().all(|m| m.data.first() == channel_id.data.first()));
+    }
+
+    #[tokio::test]
+    async fn refreshing_groups_pairs_connected_twins() {
+        let mut dm = DeviceManager::new();
+        for (id, name, transport) in [
+            ("ble-kickr", "KICKR 1234", Transport::Ble),
+            ("ant:17:1234", "FE-C 1234", Transport::AntPlus),
+        ] {
+            dm.connected_devices.insert(
+                id.to_string(),
+                DeviceInfo {
+                    name: Some(name.to_string()),
+                    device_type: DeviceType::FitnessTrainer,
+                    transport,
+                    ..device(id, None)
+                },
+            );
+        }
+        assert!(dm.groups_handle().read().unwrap().is_empty());
+
+        dm.refresh_device_groups().await;
+        let groups = dm.groups_handle().read().unwrap().clone();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["ble-kickr"], groups["ant:17:1234"]);
+    }
+
+    #[tokio::test]
+    async fn scans_do_not_reprobe_missing_ble_adapter() {
+        let mut dm = DeviceManager::new();
+        let attempts = std::cell::Cell::new(0);
+        let no_adapter = || async {
+            attempts.set(attempts.get() + 1);
+            Err::<BleManager, AppError>(BleError::NoAdapter.into())
+        };
+
+        for _ in 0..2 {
+            let devices = dm.scan_all_with(1, 0, no_adapter, || None).await.unwrap();
+            assert!(devices.is_empty());
+        }
+        assert_eq!(attempts.get(), 1);
+        assert!(!dm.ble_available());
+
+        // Only an explicit retry lets the next scan probe again
+        dm.reset_ble_probe();
+        dm.scan_all_with(1, 0, no_adapter, || None).await.unwrap();
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_ble_init_is_not_retried_until_reset() {
+        let mut dm = DeviceManager::new();
+        let attempts = std::cell::Cell::new(0);
+        let no_adapter = || async {
+            attempts.set(attempts.get() + 1);
+            Err::<BleManager, AppError>(BleError::NoAdapter.into())
+        };
+
+        for _ in 0..3 {
+            let err = dm.ensure_ble_with(no_adapter).await.unwrap_err();
+            assert!(matches!(err, AppError::Ble(BleError::NoAdapter)));
+        }
+        assert_eq!(attempts.get(), 1);
+        assert!(!dm.ble_available());
+
+        dm.reset_ble_probe();
+        assert!(dm.ensure_ble_with(no_adapter).await.is_err());
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn denied_ble_permission_is_reported_until_reprobed() {
+        let mut dm = DeviceManager::new();
+        let err = dm
+            .ensure_ble_with(|| async { Err(BleError::PermissionDenied.into()) })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Ble(BleError::PermissionDenied)));
+        assert!(dm.ble_permission_denied());
+
+        // Short-circuited calls keep reporting the permission problem
+        let err = dm.ensure_ble_with(|| async { unreachable!() }).await.unwrap_err();
+        assert!(matches!(err, AppError::Ble(BleError::PermissionDenied)));
+
+        dm.reset_ble_probe();
+        let err = dm
+            .ensure_ble_with(|| async { Err(BleError::NoAdapter.into()) })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Ble(BleError::NoAdapter)));
+        assert!(!dm.ble_permission_denied());
+    }
+
+    #[tokio::test]
+    async fn wheel_circumference_defaults_without_stored_value() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(&tmp.path().to_string_lossy()).await.unwrap();
+        assert_eq!(wheel_circumference_for(Some(&storage), "ant:123:1").await, 2105);
+        assert_eq!(wheel_circumference_for(None, "ant:123:1").await, 2105);
+    }
+
+    #[tokio::test]
+    async fn stored_wheel_circumference_reaches_speed_decoder() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(&tmp.path().to_string_lossy()).await.unwrap();
+        storage.set_wheel_circumference("ant:123:1", 2000).await.unwrap();
+        let wheel_mm = wheel_circumference_for(Some(&storage), "ant:123:1").await;
+        assert_eq!(wheel_mm, 2000);
+
+        let (page_tx, page_rx) = std::sync::mpsc::channel();
+        let (tx, mut rx) = broadcast::channel(8);
+        // Init page (time=0, revs=0), then 1 rev in 1024 ticks (1 second)
+        page_tx.send(vec![0, 0, 0, 0, 0x00, 0x00, 0x00, 0x00]).unwrap();
+        page_tx.send(vec![0, 0, 0, 0, 0x00, 0x04, 0x01, 0x00]).unwrap();
+        drop(page_tx);
+        listen_ant_channel(
+            page_rx,
+            DeviceType::CadenceSpeed,
+            tx,
+            Arc::new(AtomicBool::new(false)),
+            "ant:123:1".to_string(),
+            Arc::new(StdMutex::new(HashMap::new())),
+            123,
+            Arc::new(AtomicI64::new(0)),
+            None,
+            wheel_mm,
+        );
+
+        // 2.000m / 1.0s * 3.6 = 7.2 km/h (the 2105mm default would give 7.578)
+        match rx.try_recv().unwrap() {
+            SensorReading::Speed { kmh, .. } => {
+                assert!((kmh - 7.2).abs() < 0.01, "expected ~7.2, got {}", kmh)
+            }
+            other => panic!("expected Speed, got {:?}", other),
+        }
+    }
 
     #[test]
     fn manufacturer_garmin() {
@@ -1,10 +1,13 @@
 pub mod ant;
+pub mod battery;
 pub mod ble;
 pub mod dedup;
 pub mod fec;
 pub mod ftms;
 pub mod listener;
 pub mod manager;
+pub mod power_mismatch;
 pub mod protocol;
 pub mod reconnect;
+pub mod signal;
 pub mod types;
@@ -0,0 +1,97 @@
+/// Power (W) below which the two sources aren't compared: at low power a few
+/// watts of difference reads as a large percentage.
+const MIN_COMPARE_WATTS: f64 = 50.0;
+
+/// How far the trainer's power is from the power meter's, as a percentage of
+/// the meter. `None` when either source is below `MIN_COMPARE_WATTS`.
+pub fn mismatch_pct(meter_watts: f64, trainer_watts: f64) -> Option<f64> {
+    if meter_watts < MIN_COMPARE_WATTS || trainer_watts < MIN_COMPARE_WATTS {
+        return None;
+    }
+    Some((trainer_watts - meter_watts) / meter_watts * 100.0)
+}
+
+/// Detects a trainer and power meter disagreeing by more than a threshold for
+/// a run of consecutive samples, warning once per mismatched stretch rather
+/// than on every sample.
+pub struct PowerMismatchMonitor {
+    threshold_pct: f64,
+    required_samples: u32,
+    /// Consecutive samples over the threshold
+    run: u32,
+}
+
+impl PowerMismatchMonitor {
+    pub fn new(threshold_pct: f64, required_samples: u32) -> Self {
+        Self {
+            threshold_pct,
+            required_samples: required_samples.max(1),
+            run: 0,
+        }
+    }
+
+    /// Change how many consecutive samples trigger a warning, e.g. when the
+    /// sampling interval changes. A run in progress is kept.
+    pub fn set_required_samples(&mut self, required_samples: u32) {
+        self.required_samples = required_samples.max(1);
+    }
+
+    /// Record one pair of averaged powers. Returns the percentage difference
+    /// exactly once per stretch: on the sample that completes
+    /// `required_samples` consecutive mismatches. A sample within the
+    /// threshold, or too low to compare, resets the run.
+    pub fn observe(&mut self, meter_watts: f64, trainer_watts: f64) -> Option<f64> {
+        match mismatch_pct(meter_watts, trainer_watts) {
+            Some(pct) if pct.abs() > self.threshold_pct => {
+                self.run = self.run.saturating_add(1);
+                (self.run == self.required_samples).then_some(pct)
+            }
+            _ => {
+                self.run = 0;
+                None
+            }
+        }
+    }
+
+    /// Drop the current run, e.g. when one of the sources goes away.
+    pub fn reset(&mut self) {
+        self.run = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatch_is_relative_to_meter() {
+        assert_eq!(mismatch_pct(200.0, 230.0), Some(15.0));
+        assert_eq!(mismatch_pct(200.0, 180.0), Some(-10.0));
+        assert_eq!(mismatch_pct(40.0, 80.0), None);
+        assert_eq!(mismatch_pct(200.0, 0.0), None);
+    }
+
+    #[test]
+    fn fires_once_after_sustained_mismatch() {
+        let mut monitor = PowerMismatchMonitor::new(10.0, 3);
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+        assert_eq!(monitor.observe(200.0, 170.0), None);
+        assert_eq!(monitor.observe(200.0, 230.0), Some(15.0));
+        // Still mismatched: no repeat warning
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+    }
+
+    #[test]
+    fn agreement_or_low_power_resets_run() {
+        let mut monitor = PowerMismatchMonitor::new(10.0, 2);
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+        assert_eq!(monitor.observe(200.0, 210.0), None);
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+        assert_eq!(monitor.observe(30.0, 60.0), None);
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+        assert_eq!(monitor.observe(200.0, 230.0), Some(15.0));
+
+        monitor.reset();
+        assert_eq!(monitor.observe(200.0, 230.0), None);
+    }
+}
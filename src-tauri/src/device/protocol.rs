@@ -1,7 +1,8 @@
 use log::debug;
 use uuid::Uuid as BtUuid;
 
-use super::types::SensorReading;
+use super::types::{RadarThreat, SensorReading};
+use crate::session::metrics::kcal_to_work_kj;
 
 pub const HEART_RATE_MEASUREMENT: BtUuid =
     BtUuid::from_u128(0x00002A37_0000_1000_8000_00805f9b34fb);
@@ -10,6 +11,29 @@ pub const CYCLING_POWER_MEASUREMENT: BtUuid =
 pub const CSC_MEASUREMENT: BtUuid = BtUuid::from_u128(0x00002A5B_0000_1000_8000_00805f9b34fb);
 pub const INDOOR_BIKE_DATA: BtUuid = BtUuid::from_u128(0x00002AD2_0000_1000_8000_00805f9b34fb);
 pub const FTMS_CONTROL_POINT: BtUuid = BtUuid::from_u128(0x00002AD9_0000_1000_8000_00805f9b34fb);
+pub const FTMS_FEATURE: BtUuid = BtUuid::from_u128(0x00002ACC_0000_1000_8000_00805f9b34fb);
+pub const FTMS_SUPPORTED_RESISTANCE_RANGE: BtUuid = BtUuid::from_u128(0x00002AD6_0000_1000_8000_00805f9b34fb);
+pub const FTMS_SUPPORTED_POWER_RANGE: BtUuid = BtUuid::from_u128(0x00002AD8_0000_1000_8000_00805f9b34fb);
+pub const CYCLING_POWER_CONTROL_POINT: BtUuid =
+    BtUuid::from_u128(0x00002a66_0000_1000_8000_00805f9b34fb);
+pub const TEMPERATURE_MEASUREMENT: BtUuid =
+    BtUuid::from_u128(0x00002A6E_0000_1000_8000_00805f9b34fb);
+/// Moxy "SmO2 Sensor Data" characteristic (vendor 128-bit UUID).
+pub const SMO2_SENSOR_DATA: BtUuid = BtUuid::from_u128(0x6404d804_4cb9_11e8_b566_0800200c9a66);
+/// Garmin Varia radar threat characteristic (vendor 128-bit UUID).
+pub const RADAR_THREATS: BtUuid = BtUuid::from_u128(0x6a4e3203_667b_11e3_949a_0800200c9a66);
+
+/// SmO2 is reported in 0.1% units; anything above 100.0% is an invalid/warm-up sample.
+const SMO2_MAX_RAW: u16 = 1000;
+/// THb is reported in 0.01 g/dL units; 0x0FFF marks an invalid sample.
+const THB_INVALID_RAW: u16 = 0x0FFF;
+/// ESS Temperature "value is not known" sentinel.
+const TEMPERATURE_UNKNOWN_RAW: i16 = i16::MIN;
+
+/// Cycling Power Control Point op codes
+const CP_START_OFFSET_COMPENSATION: u8 = 0x0C;
+const CP_RESPONSE_CODE: u8 = 0x20;
+const CP_RESULT_SUCCESS: u8 = 0x01;
 
 fn now_epoch_ms() -> u64 {
     std::time::SystemTime::now()
@@ -18,65 +42,174 @@ fn now_epoch_ms() -> u64 {
         .as_millis() as u64
 }
 
-pub fn decode_heart_rate(data: &[u8], device_id: &str) -> Option<SensorReading> {
+/// Decode a Heart Rate Measurement notification.
+/// Returns the HR reading, followed by an `Hrv` reading when the packet carries
+/// RR-intervals (flag bit 4). Energy Expended (bit 3) is skipped over but not reported.
+pub fn decode_heart_rate(data: &[u8], device_id: &str) -> Vec<SensorReading> {
     if data.is_empty() {
-        return None;
+        return vec![];
     }
     let flags = data[0];
     let hr_format_16bit = flags & 0x01 != 0;
-    let bpm = if hr_format_16bit {
+    let energy_expended_present = flags & 0x08 != 0;
+    let rr_present = flags & 0x10 != 0;
+
+    let (bpm, mut offset) = if hr_format_16bit {
         if data.len() < 3 {
-            return None;
+            return vec![];
         }
-        u16::from_le_bytes([data[1], data[2]]) as u8
+        (u16::from_le_bytes([data[1], data[2]]) as u8, 3)
     } else {
         if data.len() < 2 {
-            return None;
+            return vec![];
         }
-        data[1]
+        (data[1], 2)
     };
-    Some(SensorReading::HeartRate {
+    let epoch_ms = now_epoch_ms();
+    let mut readings = vec![SensorReading::HeartRate {
         bpm,
         timestamp: Some(std::time::Instant::now()),
-        epoch_ms: now_epoch_ms(),
+        epoch_ms,
         device_id: device_id.to_string(),
-    })
+    }];
+
+    if energy_expended_present {
+        offset += 2;
+    }
+    if rr_present && data.len() > offset {
+        // RR-intervals are uint16 in 1/1024 s units; convert to rounded milliseconds
+        let rr_ms: Vec<u16> = data[offset..]
+            .chunks_exact(2)
+            .map(|c| {
+                let raw = u16::from_le_bytes([c[0], c[1]]) as u32;
+                ((raw * 1000 + 512) / 1024) as u16
+            })
+            .collect();
+        if !rr_ms.is_empty() {
+            readings.push(SensorReading::Hrv {
+                rr_ms,
+                epoch_ms,
+                device_id: device_id.to_string(),
+            });
+        }
+    }
+    readings
 }
 
-pub fn decode_cycling_power(data: &[u8], device_id: &str) -> Option<SensorReading> {
+/// Decode a Cycling Power Measurement notification.
+/// Returns the Power reading, followed by an `Energy` reading when the packet carries
+/// Accumulated Energy (flag bit 11) and a `Pedaling` reading when it carries extreme
+/// force/torque or dead spot angles. Optional fields are walked in spec order so later
+/// offsets stay correct even for the ones we skip.
+pub fn decode_cycling_power(data: &[u8], device_id: &str) -> Vec<SensorReading> {
     if data.len() < 4 {
-        return None;
+        return vec![];
     }
     let flags = u16::from_le_bytes([data[0], data[1]]);
     let watts = i16::from_le_bytes([data[2], data[3]]);
     if watts < 0 {
         debug!("BLE cycling power: negative watts {} from {}", watts, device_id);
-        return None;
+        return vec![];
     }
+    let mut offset = 4;
 
     // Pedal Power Balance: flag bit 0 = present, bit 1 = reference (1 = left pedal)
-    // Field is uint8 at offset 4, resolution 1/2 %
-    let pedal_balance = if flags & 0x01 != 0 && data.len() >= 5 {
-        let raw = data[4]; // percentage in 1/2% resolution
-        let pct = raw / 2; // approximate to whole percent
-        if flags & 0x02 != 0 {
-            // Reference is left pedal — invert to right pedal for consistency with ANT+
-            Some(100u8.saturating_sub(pct))
-        } else {
-            // Reference unknown — report as-is
-            Some(pct)
+    // Field is uint8, resolution 1/2 %
+    let mut pedal_balance = None;
+    if flags & 0x01 != 0 {
+        if data.len() > offset {
+            let raw = data[offset]; // percentage in 1/2% resolution
+            let pct = raw / 2; // approximate to whole percent
+            pedal_balance = if flags & 0x02 != 0 {
+                // Reference is left pedal — invert to right pedal for consistency with ANT+
+                Some(100u8.saturating_sub(pct))
+            } else {
+                // Reference unknown — report as-is
+                Some(pct)
+            };
         }
-    } else {
-        None
-    };
+        offset += 1;
+    }
+
+    // Accumulated Torque (skip)
+    if flags & 0x04 != 0 {
+        offset += 2;
+    }
+
+    // Wheel Revolution Data: cumulative revs (uint32) + last event time (uint16) (skip)
+    if flags & 0x10 != 0 {
+        offset += 6;
+    }
+
+    // Crank Revolution Data: cumulative revs (uint16) + last event time (uint16) (skip)
+    if flags & 0x20 != 0 {
+        offset += 4;
+    }
+
+    let read_u16 = |at: usize| data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+
+    // Extreme Force Magnitudes: max + min (sint16 each, N) (skip). The
+    // Cycling Power Service has no torque effectiveness or pedal smoothness
+    // field, and the extremes alone can't give them.
+    if flags & 0x40 != 0 {
+        offset += 4;
+    }
+
+    // Extreme Torque Magnitudes: max + min (sint16 each, 1/32 Nm) (skip)
+    if flags & 0x80 != 0 {
+        offset += 4;
+    }
+
+    // Extreme Angles: two packed uint12 values = 3 bytes (skip)
+    if flags & 0x100 != 0 {
+        offset += 3;
+    }
+
+    // Top Dead Spot Angle (uint16, degrees)
+    let mut top_dead_spot_deg = None;
+    if flags & 0x200 != 0 {
+        top_dead_spot_deg = read_u16(offset);
+        offset += 2;
+    }
+
+    // Bottom Dead Spot Angle (uint16, degrees)
+    let mut bottom_dead_spot_deg = None;
+    if flags & 0x400 != 0 {
+        bottom_dead_spot_deg = read_u16(offset);
+        offset += 2;
+    }
 
-    Some(SensorReading::Power {
+    let epoch_ms = now_epoch_ms();
+    let mut readings = vec![SensorReading::Power {
         watts: watts as u16,
         timestamp: Some(std::time::Instant::now()),
-        epoch_ms: now_epoch_ms(),
+        epoch_ms,
         device_id: device_id.to_string(),
         pedal_balance,
-    })
+    }];
+
+    // Accumulated Energy (uint16, 1 kJ resolution)
+    if flags & 0x800 != 0 && data.len() >= offset + 2 {
+        let raw_energy = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        readings.push(SensorReading::Energy {
+            kilojoules: raw_energy as f32,
+            epoch_ms,
+            device_id: device_id.to_string(),
+        });
+    }
+
+    if top_dead_spot_deg.is_some() || bottom_dead_spot_deg.is_some() {
+        readings.push(SensorReading::Pedaling {
+            torque_effectiveness: None,
+            smoothness: None,
+            top_dead_spot_deg,
+            bottom_dead_spot_deg,
+            epoch_ms,
+            device_id: device_id.to_string(),
+        });
+    }
+
+    readings
 }
 
 /// Default wheel circumference in mm (700x25c tire)
@@ -243,13 +376,38 @@ pub fn decode_indoor_bike_data(data: &[u8], device_id: &str) -> Vec<SensorReadin
         offset += 2;
     }
 
-    // Average Power (skip)
+    // Average Power (sint16) — the trainer's own running average. Reported
+    // separately: the session averages instantaneous power itself.
     if flags & 0x80 != 0 {
+        if data.len() >= offset + 2 {
+            let avg_power = i16::from_le_bytes([data[offset], data[offset + 1]]);
+            if avg_power >= 0 {
+                readings.push(SensorReading::AveragePower {
+                    watts: avg_power as u16,
+                    epoch_ms,
+                    device_id: did.clone(),
+                });
+            } else {
+                debug!("BLE indoor bike: negative average power {} from {}", avg_power, device_id);
+            }
+        }
         offset += 2;
     }
 
-    // Expended Energy: total (uint16) + per hour (uint16) + per minute (uint8) = 5 bytes (skip)
+    // Expended Energy: total (uint16, kcal) + per hour (uint16) + per minute (uint8) = 5 bytes.
+    // Only the total is reported, converted back to mechanical work; 0xFFFF
+    // means the trainer doesn't track it, and many report a constant 0.
     if flags & 0x100 != 0 {
+        if data.len() >= offset + 2 {
+            let total_kcal = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            if total_kcal != 0 && total_kcal != 0xFFFF {
+                readings.push(SensorReading::Energy {
+                    kilojoules: kcal_to_work_kj(total_kcal as f32),
+                    epoch_ms,
+                    device_id: did.clone(),
+                });
+            }
+        }
         offset += 5;
     }
 
@@ -271,10 +429,121 @@ pub fn decode_indoor_bike_data(data: &[u8], device_id: &str) -> Vec<SensorReadin
     readings
 }
 
+/// Decode a Moxy SmO2 Sensor Data notification:
+/// [count: u16][current SmO2: u16, 0.1%][previous SmO2: u16, 0.1%][THb: u16, 0.01 g/dL].
+/// Returns `None` for short packets and for samples the sensor flags as invalid.
+pub fn decode_smo2(data: &[u8], device_id: &str) -> Option<SensorReading> {
+    if data.len() < 8 {
+        return None;
+    }
+    let smo2_raw = u16::from_le_bytes([data[2], data[3]]);
+    let thb_raw = u16::from_le_bytes([data[6], data[7]]);
+    if smo2_raw > SMO2_MAX_RAW || thb_raw >= THB_INVALID_RAW {
+        debug!(
+            "[{}] Dropping invalid SmO2 sample (smo2={}, thb={})",
+            device_id, smo2_raw, thb_raw
+        );
+        return None;
+    }
+    Some(SensorReading::MuscleOxygen {
+        smo2: smo2_raw as f32 * 0.1,
+        thb: thb_raw as f32 * 0.01,
+        epoch_ms: now_epoch_ms(),
+        device_id: device_id.to_string(),
+    })
+}
+
+/// Decode a Varia radar notification: a one-byte packet header followed by
+/// one 3-byte record per tracked vehicle — [id][distance: m][speed: km/h].
+/// A header-only packet means no vehicles are in range. A trailing partial
+/// record is ignored.
+pub fn decode_radar(data: &[u8], device_id: &str) -> Option<SensorReading> {
+    if data.is_empty() {
+        return None;
+    }
+    let threats = data[1..]
+        .chunks_exact(3)
+        .map(|c| RadarThreat {
+            id: c[0],
+            distance_m: c[1],
+            speed_kmh: c[2],
+        })
+        .collect();
+    Some(SensorReading::RadarTargets {
+        threats,
+        epoch_ms: now_epoch_ms(),
+        device_id: device_id.to_string(),
+    })
+}
+
+/// Decode an ESS Temperature (0x2A6E) value: sint16 in 0.01 °C.
+/// Returns `None` for short packets and the "unknown" sentinel.
+pub fn decode_temperature(data: &[u8], device_id: &str) -> Option<SensorReading> {
+    if data.len() < 2 {
+        return None;
+    }
+    let raw = i16::from_le_bytes([data[0], data[1]]);
+    if raw == TEMPERATURE_UNKNOWN_RAW {
+        return None;
+    }
+    Some(SensorReading::Temperature {
+        celsius: raw as f32 * 0.01,
+        epoch_ms: now_epoch_ms(),
+        device_id: device_id.to_string(),
+    })
+}
+
+/// Cycling Power Control Point request that starts a zero-offset calibration.
+pub fn encode_offset_compensation() -> [u8; 1] {
+    [CP_START_OFFSET_COMPENSATION]
+}
+
+/// Parse a Cycling Power Control Point indication answering
+/// `encode_offset_compensation`. Returns `None` for indications that answer
+/// some other request, otherwise the new offset (raw force in N or torque in
+/// 1/32 Nm, as the meter defines it) or the reason the meter refused.
+pub fn parse_offset_compensation_response(data: &[u8]) -> Option<Result<i16, String>> {
+    if data.len() < 3 || data[0] != CP_RESPONSE_CODE || data[1] != CP_START_OFFSET_COMPENSATION {
+        return None;
+    }
+    Some(match data[2] {
+        CP_RESULT_SUCCESS if data.len() >= 5 => Ok(i16::from_le_bytes([data[3], data[4]])),
+        CP_RESULT_SUCCESS => Err("Offset compensation response has no offset value".into()),
+        0x02 => Err("Op code not supported".into()),
+        0x03 => Err("Invalid parameter".into()),
+        0x04 => Err("Operation failed (keep the cranks still and unweighted)".into()),
+        code => Err(format!("Unknown result code 0x{:02X}", code)),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn offset_compensation_request_is_single_opcode() {
+        assert_eq!(encode_offset_compensation(), [0x0C]);
+    }
+
+    #[test]
+    fn offset_compensation_response_carries_signed_offset() {
+        // Success with offset -12 (0xFFF4 little-endian)
+        assert_eq!(
+            parse_offset_compensation_response(&[0x20, 0x0C, 0x01, 0xF4, 0xFF]),
+            Some(Ok(-12))
+        );
+        assert_eq!(
+            parse_offset_compensation_response(&[0x20, 0x0C, 0x01, 0x2C, 0x01]),
+            Some(Ok(300))
+        );
+        assert!(matches!(
+            parse_offset_compensation_response(&[0x20, 0x0C, 0x04]),
+            Some(Err(_))
+        ));
+        // Responses to other op codes are not ours
+        assert_eq!(parse_offset_compensation_response(&[0x20, 0x01, 0x01]), None);
+    }
+
     fn assert_approx(actual: f32, expected: f32, epsilon: f32, msg: &str) {
         assert!(
             (actual - expected).abs() < epsilon,
@@ -288,14 +557,15 @@ mod tests {
 
     #[test]
     fn decode_hr_empty_data() {
-        assert!(decode_heart_rate(&[], DEV).is_none());
+        assert!(decode_heart_rate(&[], DEV).is_empty());
     }
 
     #[test]
     fn decode_hr_8bit_format() {
         let data = [0x00, 142]; // flags=0, 8-bit HR
-        let r = decode_heart_rate(&data, DEV).unwrap();
-        match r {
+        let r = decode_heart_rate(&data, DEV);
+        assert_eq!(r.len(), 1);
+        match r[0] {
             SensorReading::HeartRate { bpm, .. } => assert_eq!(bpm, 142),
             _ => panic!("expected HeartRate"),
         }
@@ -306,8 +576,9 @@ mod tests {
         let hr: u16 = 150;
         let hr_bytes = hr.to_le_bytes();
         let data = [0x01, hr_bytes[0], hr_bytes[1]]; // flags=1, 16-bit HR
-        let r = decode_heart_rate(&data, DEV).unwrap();
-        match r {
+        let r = decode_heart_rate(&data, DEV);
+        assert_eq!(r.len(), 1);
+        match r[0] {
             SensorReading::HeartRate { bpm, .. } => assert_eq!(bpm, 150),
             _ => panic!("expected HeartRate"),
         }
@@ -316,14 +587,55 @@ mod tests {
     #[test]
     fn decode_hr_16bit_too_short() {
         let data = [0x01, 0x96]; // flags=1 (16-bit), but only 2 bytes total
-        assert!(decode_heart_rate(&data, DEV).is_none());
+        assert!(decode_heart_rate(&data, DEV).is_empty());
+    }
+
+    #[test]
+    fn decode_hr_rr_intervals_converted_to_ms() {
+        // flags=0x10 (RR present), 8-bit HR=60, RR=1024 (1000ms) and 819 (~800ms)
+        let data = [0x10, 60, 0x00, 0x04, 0x33, 0x03];
+        let r = decode_heart_rate(&data, DEV);
+        assert_eq!(r.len(), 2);
+        match &r[0] {
+            SensorReading::HeartRate { bpm, .. } => assert_eq!(*bpm, 60),
+            _ => panic!("expected HeartRate"),
+        }
+        match &r[1] {
+            SensorReading::Hrv { rr_ms, device_id, .. } => {
+                // 819 * 1000 / 1024 = 799.8 → 800
+                assert_eq!(rr_ms, &vec![1000, 800]);
+                assert_eq!(device_id, DEV);
+            }
+            _ => panic!("expected Hrv"),
+        }
+    }
+
+    #[test]
+    fn decode_hr_rr_after_energy_expended() {
+        // flags=0x19: 16-bit HR, Energy Expended (2 bytes), RR present
+        let data = [0x19, 150, 0x00, 0xFF, 0xFF, 0x00, 0x02];
+        let r = decode_heart_rate(&data, DEV);
+        assert_eq!(r.len(), 2);
+        match &r[1] {
+            // 512 / 1024 s = 500ms; energy bytes must not be read as RR
+            SensorReading::Hrv { rr_ms, .. } => assert_eq!(rr_ms, &vec![500]),
+            _ => panic!("expected Hrv"),
+        }
+    }
+
+    #[test]
+    fn decode_hr_rr_flag_without_payload_emits_only_hr() {
+        let data = [0x10, 72];
+        let r = decode_heart_rate(&data, DEV);
+        assert_eq!(r.len(), 1);
+        assert!(matches!(r[0], SensorReading::HeartRate { bpm: 72, .. }));
     }
 
     // ── decode_cycling_power ───────────────────────────────────────
 
     #[test]
     fn decode_power_short_data() {
-        assert!(decode_cycling_power(&[0x00, 0x00, 0xFA], DEV).is_none());
+        assert!(decode_cycling_power(&[0x00, 0x00, 0xFA], DEV).is_empty());
     }
 
     #[test]
@@ -333,7 +645,7 @@ mod tests {
         let mut data = Vec::new();
         data.extend_from_slice(&flags.to_le_bytes());
         data.extend_from_slice(&watts.to_le_bytes());
-        let r = decode_cycling_power(&data, DEV).unwrap();
+        let r = decode_cycling_power(&data, DEV).remove(0);
         match r {
             SensorReading::Power {
                 watts: w,
@@ -354,7 +666,7 @@ mod tests {
         let mut data = Vec::new();
         data.extend_from_slice(&flags.to_le_bytes());
         data.extend_from_slice(&watts.to_le_bytes());
-        assert!(decode_cycling_power(&data, DEV).is_none());
+        assert!(decode_cycling_power(&data, DEV).is_empty());
     }
 
     #[test]
@@ -366,7 +678,7 @@ mod tests {
         data.extend_from_slice(&flags.to_le_bytes());
         data.extend_from_slice(&watts.to_le_bytes());
         data.push(raw_balance);
-        let r = decode_cycling_power(&data, DEV).unwrap();
+        let r = decode_cycling_power(&data, DEV).remove(0);
         match r {
             SensorReading::Power {
                 pedal_balance, ..
@@ -384,7 +696,7 @@ mod tests {
         data.extend_from_slice(&flags.to_le_bytes());
         data.extend_from_slice(&watts.to_le_bytes());
         data.push(raw_balance);
-        let r = decode_cycling_power(&data, DEV).unwrap();
+        let r = decode_cycling_power(&data, DEV).remove(0);
         match r {
             SensorReading::Power {
                 pedal_balance, ..
@@ -393,6 +705,117 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_power_accumulated_energy_only() {
+        let flags: u16 = 0x0800; // accumulated energy present, nothing else
+        let watts: i16 = 180;
+        let energy: u16 = 345;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&watts.to_le_bytes());
+        data.extend_from_slice(&energy.to_le_bytes()); // offset 4
+        let r = decode_cycling_power(&data, DEV);
+        assert_eq!(r.len(), 2);
+        assert!(matches!(r[0], SensorReading::Power { watts: 180, .. }));
+        match &r[1] {
+            SensorReading::Energy { kilojoules, .. } => assert_eq!(*kilojoules, 345.0),
+            _ => panic!("expected Energy"),
+        }
+    }
+
+    #[test]
+    fn decode_power_accumulated_energy_after_skipped_fields() {
+        // balance (1) + torque (2) + crank rev (4) + top/bottom dead spot (2+2) precede energy
+        let flags: u16 = 0x0001 | 0x0004 | 0x0020 | 0x0200 | 0x0400 | 0x0800;
+        let watts: i16 = 220;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&watts.to_le_bytes());
+        data.push(100); // balance
+        data.extend_from_slice(&[0xAA; 2]); // accumulated torque
+        data.extend_from_slice(&[0xBB; 4]); // crank revolution data
+        data.extend_from_slice(&[0xCC; 4]); // top + bottom dead spot angles
+        data.extend_from_slice(&1200u16.to_le_bytes()); // offset 4+1+2+4+4 = 15
+        let r = decode_cycling_power(&data, DEV);
+        // Power, Energy, then Pedaling for the dead spot angles
+        assert_eq!(r.len(), 3);
+        match &r[1] {
+            SensorReading::Energy { kilojoules, .. } => assert_eq!(*kilojoules, 1200.0),
+            _ => panic!("expected Energy"),
+        }
+    }
+
+    #[test]
+    fn decode_power_accumulated_energy_truncated_emits_only_power() {
+        let flags: u16 = 0x0800;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&150i16.to_le_bytes());
+        data.push(0x01); // only one of two energy bytes
+        let r = decode_cycling_power(&data, DEV);
+        assert_eq!(r.len(), 1);
+    }
+
+    #[test]
+    fn decode_power_pedaling_fields() {
+        // balance (1) + extreme torque (4) + extreme angles (3) + dead spots (2+2) + energy (2)
+        let flags: u16 = 0x0001 | 0x0080 | 0x0100 | 0x0200 | 0x0400 | 0x0800;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&250i16.to_le_bytes());
+        data.push(100); // balance
+        data.extend_from_slice(&(60i16 * 32).to_le_bytes()); // max torque 60 Nm
+        data.extend_from_slice(&(-4i16 * 32).to_le_bytes()); // min torque -4 Nm
+        data.extend_from_slice(&[0xDD; 3]); // extreme angles
+        data.extend_from_slice(&350u16.to_le_bytes()); // top dead spot
+        data.extend_from_slice(&170u16.to_le_bytes()); // bottom dead spot
+        data.extend_from_slice(&90u16.to_le_bytes()); // energy
+        let r = decode_cycling_power(&data, DEV);
+        assert_eq!(r.len(), 3);
+        assert!(matches!(r[1], SensorReading::Energy { kilojoules, .. } if kilojoules == 90.0));
+        match &r[2] {
+            SensorReading::Pedaling {
+                torque_effectiveness,
+                smoothness,
+                top_dead_spot_deg,
+                bottom_dead_spot_deg,
+                ..
+            } => {
+                // BLE reports no effectiveness or smoothness, only dead spots
+                assert!(torque_effectiveness.is_none());
+                assert!(smoothness.is_none());
+                assert_eq!(*top_dead_spot_deg, Some(350));
+                assert_eq!(*bottom_dead_spot_deg, Some(170));
+            }
+            _ => panic!("expected Pedaling"),
+        }
+    }
+
+    #[test]
+    fn decode_power_extreme_force_only_emits_no_pedaling() {
+        let flags: u16 = 0x0040 | 0x0800;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&200i16.to_le_bytes());
+        data.extend_from_slice(&400i16.to_le_bytes()); // max force N
+        data.extend_from_slice(&100i16.to_le_bytes()); // min force N
+        data.extend_from_slice(&42u16.to_le_bytes()); // energy, after the skipped extremes
+        let r = decode_cycling_power(&data, DEV);
+        assert_eq!(r.len(), 2);
+        assert!(matches!(r[1], SensorReading::Energy { kilojoules, .. } if kilojoules == 42.0));
+    }
+
+    #[test]
+    fn decode_power_without_pedaling_flags_emits_no_pedaling() {
+        let flags: u16 = 0x0001;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&200i16.to_le_bytes());
+        data.push(100);
+        let r = decode_cycling_power(&data, DEV);
+        assert_eq!(r.len(), 1);
+    }
+
     // ── decode_csc ─────────────────────────────────────────────────
 
     #[test]
@@ -636,6 +1059,7 @@ mod tests {
     fn decode_indoor_bike_skips_optional_fields() {
         // Enable all skip-only fields + HR to verify offset accumulation.
         // Speed is mandatory (+2), then skip fields: bit1(+2), bit3(+2), bit4(+3), bit5(+2), bit7(+2), bit8(+5) = 16 bytes
+        // (a zero bit8 total energy is not reported; a zero bit7 average power is)
         // bit9=1 (HR at offset 2+2+16=20)
         let flags: u16 = 0x0002 | 0x0008 | 0x0010 | 0x0020 | 0x0080 | 0x0100 | 0x0200;
         assert_eq!(flags, 0x03BA);
@@ -646,10 +1070,198 @@ mod tests {
         data.push(155); // HR bpm at offset 20
         assert_eq!(data.len(), 21);
         let readings = decode_indoor_bike_data(&data, DEV);
+        assert_eq!(readings.len(), 3); // speed + average power + HR
+        assert!(matches!(&readings[1], SensorReading::AveragePower { watts: 0, .. }));
+        match &readings[2] {
+            SensorReading::HeartRate { bpm, .. } => assert_eq!(*bpm, 155),
+            _ => panic!("expected HeartRate"),
+        }
+    }
+
+    #[test]
+    fn decode_indoor_bike_average_power_and_energy() {
+        // bit6 (power), bit7 (average power), bit8 (expended energy), bit9 (HR)
+        let flags: u16 = 0x0040 | 0x0080 | 0x0100 | 0x0200;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&3000u16.to_le_bytes()); // mandatory speed
+        data.extend_from_slice(&250i16.to_le_bytes()); // instantaneous power
+        data.extend_from_slice(&180i16.to_le_bytes()); // average power
+        data.extend_from_slice(&500u16.to_le_bytes()); // total energy, kcal
+        data.extend_from_slice(&900u16.to_le_bytes()); // energy per hour
+        data.push(15); // energy per minute
+        data.push(148); // HR
+        let readings = decode_indoor_bike_data(&data, DEV);
+        assert_eq!(readings.len(), 5); // speed, power, average power, energy, HR
+
+        // Average power comes out separately and never as a Power reading
+        let watts: Vec<u16> = readings
+            .iter()
+            .filter_map(|r| match r {
+                SensorReading::Power { watts, .. } => Some(*watts),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(watts, vec![250]);
+        assert!(matches!(
+            &readings[2],
+            SensorReading::AveragePower { watts: 180, device_id, .. } if device_id == DEV
+        ));
+        match &readings[3] {
+            // 500 kcal at 24% gross efficiency
+            SensorReading::Energy { kilojoules, .. } => {
+                assert_approx(*kilojoules, 502.1, 0.1, "energy")
+            }
+            _ => panic!("expected Energy"),
+        }
+        match &readings[4] {
+            SensorReading::HeartRate { bpm, .. } => assert_eq!(*bpm, 148),
+            _ => panic!("expected HeartRate"),
+        }
+    }
+
+    #[test]
+    fn decode_indoor_bike_unavailable_energy_not_reported() {
+        // bit8 (expended energy) with 0xFFFF totals, then HR
+        let flags: u16 = 0x0100 | 0x0200;
+        let mut data = Vec::new();
+        data.extend_from_slice(&flags.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // mandatory speed
+        data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        data.push(132);
+        let readings = decode_indoor_bike_data(&data, DEV);
         assert_eq!(readings.len(), 2); // speed + HR
         match &readings[1] {
-            SensorReading::HeartRate { bpm, .. } => assert_eq!(*bpm, 155),
+            SensorReading::HeartRate { bpm, .. } => assert_eq!(*bpm, 132),
             _ => panic!("expected HeartRate"),
         }
     }
+
+    #[test]
+    fn zero_ftms_energy_keeps_power_derived_work() {
+        use crate::session::metrics::MetricsCalculator;
+        // Power + expended energy with a trainer that never tracks energy (0 kcal)
+        let flags: u16 = 0x0040 | 0x0100;
+        let mut calc = MetricsCalculator::new(200);
+        for second in 0..61u64 {
+            let mut data = Vec::new();
+            data.extend_from_slice(&flags.to_le_bytes());
+            data.extend_from_slice(&3000u16.to_le_bytes()); // mandatory speed
+            data.extend_from_slice(&200i16.to_le_bytes()); // power
+            data.extend_from_slice(&[0, 0, 0, 0, 0]); // energy totals
+            for reading in decode_indoor_bike_data(&data, DEV) {
+                match reading {
+                    SensorReading::Power { watts, .. } => calc.record_power(watts, second * 1000),
+                    SensorReading::Energy {
+                        kilojoules,
+                        device_id,
+                        ..
+                    } => calc.record_energy(&device_id, kilojoules),
+                    _ => {}
+                }
+            }
+        }
+        // 60s at 200W
+        assert_approx(calc.work_kj().unwrap(), 12.0, 0.1, "power-integrated work");
+    }
+
+    // ── decode_smo2 ────────────────────────────────────────────────
+
+    fn smo2_packet(count: u16, smo2: u16, prev: u16, thb: u16) -> Vec<u8> {
+        [count, smo2, prev, thb]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn decode_smo2_sample_packet() {
+        // 64.3% SmO2, 12.57 g/dL THb
+        let data = smo2_packet(17, 643, 640, 1257);
+        match decode_smo2(&data, DEV) {
+            Some(SensorReading::MuscleOxygen {
+                smo2,
+                thb,
+                device_id,
+                ..
+            }) => {
+                assert_approx(smo2, 64.3, 0.001, "smo2");
+                assert_approx(thb, 12.57, 0.001, "thb");
+                assert_eq!(device_id, DEV);
+            }
+            other => panic!("expected MuscleOxygen, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_smo2_short_packet() {
+        assert!(decode_smo2(&[0x01, 0x00, 0x83, 0x02], DEV).is_none());
+    }
+
+    #[test]
+    fn decode_smo2_invalid_samples_dropped() {
+        assert!(decode_smo2(&smo2_packet(1, 1001, 640, 1257), DEV).is_none());
+        assert!(decode_smo2(&smo2_packet(1, 643, 640, 0x0FFF), DEV).is_none());
+        // 100.0% is still a valid reading
+        assert!(decode_smo2(&smo2_packet(1, 1000, 990, 1257), DEV).is_some());
+    }
+
+    // ── decode_radar ───────────────────────────────────────────────
+
+    #[test]
+    fn decode_radar_multi_target_packet() {
+        // header, then (id, distance, speed) × 3
+        let data = [0x30, 0x01, 0x2D, 0x18, 0x02, 0x5A, 0x0C, 0x03, 0x8C, 0x20];
+        match decode_radar(&data, DEV) {
+            Some(SensorReading::RadarTargets { threats, device_id, .. }) => {
+                assert_eq!(device_id, DEV);
+                assert_eq!(
+                    threats,
+                    vec![
+                        RadarThreat { id: 1, distance_m: 45, speed_kmh: 24 },
+                        RadarThreat { id: 2, distance_m: 90, speed_kmh: 12 },
+                        RadarThreat { id: 3, distance_m: 140, speed_kmh: 32 },
+                    ]
+                );
+            }
+            other => panic!("expected RadarTargets, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_radar_clear_road_and_partial_record() {
+        match decode_radar(&[0x31], DEV) {
+            Some(SensorReading::RadarTargets { threats, .. }) => assert!(threats.is_empty()),
+            other => panic!("expected RadarTargets, got {:?}", other),
+        }
+        match decode_radar(&[0x32, 0x04, 0x20, 0x10, 0x05], DEV) {
+            Some(SensorReading::RadarTargets { threats, .. }) => assert_eq!(threats.len(), 1),
+            other => panic!("expected RadarTargets, got {:?}", other),
+        }
+        assert!(decode_radar(&[], DEV).is_none());
+    }
+
+    // ── decode_temperature ─────────────────────────────────────────
+
+    #[test]
+    fn decode_temperature_positive_and_negative() {
+        match decode_temperature(&2153i16.to_le_bytes(), DEV) {
+            Some(SensorReading::Temperature { celsius, .. }) => {
+                assert_approx(celsius, 21.53, 0.001, "celsius")
+            }
+            other => panic!("expected Temperature, got {:?}", other),
+        }
+        match decode_temperature(&(-450i16).to_le_bytes(), DEV) {
+            Some(SensorReading::Temperature { celsius, .. }) => {
+                assert_approx(celsius, -4.5, 0.001, "celsius")
+            }
+            other => panic!("expected Temperature, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_temperature_unknown_and_short() {
+        assert!(decode_temperature(&[0x00, 0x80], DEV).is_none());
+        assert!(decode_temperature(&[0x10], DEV).is_none());
+    }
 }
@@ -89,6 +89,7 @@ mod tests {
             model_number: None,
             serial_number: None,
             device_group: None,
+            alias: None,
             in_range: true,
         }
     }
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Detects devices whose RSSI stays below a threshold for a stretch of time,
+/// warning once per weak stretch rather than on every sample.
+pub struct SignalMonitor {
+    threshold_dbm: i16,
+    duration: Duration,
+    /// When each device's current weak stretch began, and whether it has warned
+    weak_since: HashMap<String, (Instant, bool)>,
+}
+
+impl SignalMonitor {
+    pub fn new(threshold_dbm: i16, duration: Duration) -> Self {
+        Self {
+            threshold_dbm,
+            duration,
+            weak_since: HashMap::new(),
+        }
+    }
+
+    /// Record one sweep of RSSI samples taken at `now` and return the samples
+    /// that warn. A device warns exactly once per weak stretch: on the first
+    /// sample at least `duration` after its first weak sample. Measuring time
+    /// rather than counting samples keeps the window right whatever the
+    /// sampling interval. A sample at or above the threshold resets the
+    /// stretch, and so does a device missing from the sweep (its read timed
+    /// out or failed), since it can't be known to have stayed weak.
+    pub fn observe(&mut self, samples: &[(String, i16)], now: Instant) -> Vec<(String, i16)> {
+        self.weak_since.retain(|id, _| {
+            samples
+                .iter()
+                .any(|(sampled, rssi)| sampled == id && *rssi < self.threshold_dbm)
+        });
+        let mut warnings = Vec::new();
+        for (device_id, rssi) in samples {
+            if *rssi >= self.threshold_dbm {
+                continue;
+            }
+            let (since, warned) = self
+                .weak_since
+                .entry(device_id.clone())
+                .or_insert((now, false));
+            if !*warned && now.saturating_duration_since(*since) >= self.duration {
+                *warned = true;
+                warnings.push((device_id.clone(), *rssi));
+            }
+        }
+        warnings
+    }
+
+    /// Drop state for a device that disconnected.
+    pub fn forget(&mut self, device_id: &str) {
+        self.weak_since.remove(device_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, rssi: i16) -> Vec<(String, i16)> {
+        vec![(id.to_string(), rssi)]
+    }
+
+    #[test]
+    fn fires_once_the_weak_stretch_reaches_the_duration() {
+        let mut monitor = SignalMonitor::new(-85, Duration::from_secs(10));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        // Sampled every 5s: the second weak sample is only 5s into the stretch
+        assert!(monitor.observe(&sample("hr", -90), at(0)).is_empty());
+        assert!(monitor.observe(&sample("hr", -91), at(5)).is_empty());
+        assert!(monitor
+            .observe(&sample("hr", -92), t0 + Duration::from_millis(9_999))
+            .is_empty());
+        assert_eq!(
+            monitor.observe(&sample("hr", -92), at(10)),
+            sample("hr", -92)
+        );
+    }
+
+    #[test]
+    fn fires_once_per_weak_stretch() {
+        let mut monitor = SignalMonitor::new(-85, Duration::from_secs(5));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        assert!(monitor.observe(&sample("hr", -90), at(0)).is_empty());
+        assert_eq!(monitor.observe(&sample("hr", -90), at(5)).len(), 1);
+        assert!(monitor.observe(&sample("hr", -90), at(10)).is_empty());
+        assert!(monitor.observe(&sample("hr", -95), at(15)).is_empty());
+    }
+
+    #[test]
+    fn strong_sample_resets_stretch() {
+        let mut monitor = SignalMonitor::new(-85, Duration::from_secs(5));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        assert!(monitor.observe(&sample("hr", -90), at(0)).is_empty());
+        assert!(monitor.observe(&sample("hr", -85), at(5)).is_empty());
+        assert!(monitor.observe(&sample("hr", -90), at(10)).is_empty());
+        assert_eq!(monitor.observe(&sample("hr", -90), at(15)).len(), 1);
+    }
+
+    #[test]
+    fn missed_sample_resets_stretch() {
+        let mut monitor = SignalMonitor::new(-85, Duration::from_secs(5));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        assert!(monitor.observe(&sample("hr", -90), at(0)).is_empty());
+        // The RSSI read timed out, so the device is absent from this sweep
+        assert!(monitor.observe(&[], at(5)).is_empty());
+        assert!(monitor.observe(&sample("hr", -90), at(10)).is_empty());
+        assert_eq!(monitor.observe(&sample("hr", -90), at(15)).len(), 1);
+    }
+
+    #[test]
+    fn devices_are_tracked_independently() {
+        let mut monitor = SignalMonitor::new(-85, Duration::from_secs(5));
+        let t0 = Instant::now();
+        let both = vec![("hr".to_string(), -90), ("power".to_string(), -90)];
+        assert!(monitor.observe(&both, t0).is_empty());
+        assert_eq!(monitor.observe(&both, t0 + Duration::from_secs(5)), both);
+    }
+}
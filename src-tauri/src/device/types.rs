@@ -1,11 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Instant;
 
+use super::fec::FecCommandStatus;
+use crate::session::hr_fusion::HrFusion;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CommandSource {
     ZoneControl,
     Manual,
+    Workout,
+    RampTest,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -29,6 +36,9 @@ pub enum DeviceType {
     Power,
     CadenceSpeed,
     FitnessTrainer,
+    MuscleOxygen,
+    Radar,
+    Environment,
 }
 
 impl DeviceType {
@@ -38,6 +48,9 @@ impl DeviceType {
             Self::Power => "Power",
             Self::CadenceSpeed => "CadenceSpeed",
             Self::FitnessTrainer => "FitnessTrainer",
+            Self::MuscleOxygen => "MuscleOxygen",
+            Self::Radar => "Radar",
+            Self::Environment => "Environment",
         }
     }
 }
@@ -68,6 +81,9 @@ pub struct DeviceInfo {
     pub serial_number: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_group: Option<String>,
+    /// User-assigned display name. Shown instead of `name` without replacing it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
     /// Whether the device was found during the most recent scan.
     /// Defaults to `true` (optimistic before any scan has run).
     #[serde(default = "default_true")]
@@ -120,6 +136,142 @@ pub enum SensorReading {
         epoch_ms: u64,
         source: CommandSource,
     },
+    /// RR-intervals (ms) reported alongside a heart rate measurement.
+    /// Appended last so existing bincode sensor logs keep their variant indices.
+    Hrv {
+        rr_ms: Vec<u16>,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Accumulated energy (kJ) reported by a crank-based power meter.
+    Energy {
+        kilojoules: f32,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Muscle oxygen saturation (%) and total hemoglobin (g/dL) from an SmO2 sensor.
+    MuscleOxygen {
+        smo2: f32,
+        thb: f32,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Vehicles currently tracked by a rear-facing bike radar. An empty list
+    /// means the road is clear. Live-only: never written to the sensor log.
+    RadarTargets {
+        threats: Vec<RadarThreat>,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Ambient temperature from an Environmental Sensing service.
+    Temperature {
+        celsius: f32,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Virtual gear selection reported by a trainer emulating a drivetrain.
+    /// Indices are 1-based: `front` = chainring, `rear` = cog.
+    Gear {
+        front: u8,
+        rear: u8,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Power from a non-primary meter, recorded only while dual recording is on.
+    /// Kept separate from `Power` so metrics and analysis use the primary alone.
+    SecondaryPower {
+        watts: u16,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Road grade (%) sent to the trainer in simulation mode.
+    Grade {
+        percent: f32,
+        epoch_ms: u64,
+    },
+    /// Pedal stroke quality from a power meter: torque effectiveness and pedal
+    /// smoothness from ANT+ power page 0x13 (left/right averaged), dead spot
+    /// angles from the extended BLE Cycling Power fields.
+    Pedaling {
+        torque_effectiveness: Option<f32>,
+        smoothness: Option<f32>,
+        /// Crank angles (degrees) where the stroke stops/starts producing force
+        top_dead_spot_deg: Option<u16>,
+        bottom_dead_spot_deg: Option<u16>,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+    /// Lap marker recorded when the rider starts a new lap. Only written to
+    /// the sensor log so exports can split the ride at the same points.
+    Lap {
+        epoch_ms: u64,
+    },
+    /// A trainer's own running average power (FTMS Indoor Bike Data). Kept
+    /// apart from `Power` so the session's averages use instantaneous power only.
+    AveragePower {
+        watts: u16,
+        epoch_ms: u64,
+        #[serde(default)]
+        device_id: String,
+    },
+}
+
+/// A single vehicle approaching from behind, as reported by the radar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RadarThreat {
+    /// Radar-assigned track id, stable while the vehicle stays in range
+    pub id: u8,
+    /// Distance behind the rider (m)
+    pub distance_m: u8,
+    /// Closing speed relative to the rider (km/h)
+    pub speed_kmh: u8,
+}
+
+/// Control ranges a trainer accepts, so callers can validate targets before commanding it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainerCapabilities {
+    pub min_power_w: i16,
+    pub max_power_w: i16,
+    pub power_increment_w: u16,
+    /// Resistance level bounds in the trainer's own units (percent for FE-C)
+    pub min_resistance: f32,
+    pub max_resistance: f32,
+    pub resistance_increment: f32,
+    pub supports_sim: bool,
+}
+
+impl TrainerCapabilities {
+    /// Conservative ranges for trainers that don't advertise their own: FE-C
+    /// has no capability page for power limits, and some FTMS trainers omit
+    /// the optional range characteristics.
+    pub fn fallback() -> Self {
+        Self {
+            min_power_w: 0,
+            max_power_w: 2000,
+            power_increment_w: 1,
+            min_resistance: 0.0,
+            max_resistance: 100.0,
+            resistance_increment: 0.5,
+            supports_sim: true,
+        }
+    }
+}
+
+/// Connection reliability of one device since the app started.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceHealth {
+    /// Seconds since the current connection was made; `None` while disconnected
+    pub uptime_secs: Option<u64>,
+    /// Disconnects the connection watchdog has detected since the last explicit connect
+    pub drop_count: u32,
 }
 
 /// Detailed information about a connected device, including GATT services and characteristics.
@@ -164,6 +316,106 @@ pub struct AntDeviceMetadata {
     pub serial_number: Option<u32>,
     pub battery_level: Option<u8>,
     pub battery_voltage: Option<f32>,
+    /// Last FE-C Command Status page (0x47), trainers only
+    pub fec_command_status: Option<FecCommandStatus>,
+}
+
+/// Shared state listeners consult before forwarding a reading to the broadcast channel.
+#[derive(Debug, Clone)]
+pub struct ReadingFilter {
+    pub primaries: Arc<RwLock<HashMap<DeviceType, String>>>,
+    /// Device id → cross-transport group id, from the last scan
+    pub device_groups: Arc<RwLock<HashMap<String, String>>>,
+    /// While set, power readings from non-primary meters are forwarded too
+    /// so the session can record both sources side by side.
+    pub dual_record: Arc<AtomicBool>,
+    /// While set, heart rate from every strap is blended into one fused
+    /// reading before it is broadcast.
+    pub hr_fusion: Arc<AtomicBool>,
+    /// Blending state shared by all listeners while fusing
+    pub hr_fusion_state: Arc<Mutex<HrFusion>>,
+    /// While set, listeners log every raw notification/page as hex at debug level.
+    pub packet_trace: Arc<AtomicBool>,
+}
+
+impl ReadingFilter {
+    /// Log a raw packet when packet tracing is on; a single atomic load otherwise.
+    pub fn trace_packet(&self, device_id: &str, source: impl std::fmt::Display, data: &[u8]) {
+        if !self.packet_trace.load(Ordering::Relaxed) {
+            return;
+        }
+        let hex: Vec<String> = data.iter().map(|b| format!("{:02x}", b)).collect();
+        log::debug!("[{}] {} raw: {}", device_id, source, hex.join(" "));
+    }
+
+    /// True when the reading should not reach the broadcast channel.
+    pub fn should_drop(&self, reading: &SensorReading) -> bool {
+        let guard = self.primaries.read().unwrap_or_else(|e| e.into_inner());
+        let groups = self.device_groups.read().unwrap_or_else(|e| e.into_inner());
+        // The other transport of the primary device is dropped even while dual
+        // recording or fusing, since it would only count the same sensor twice.
+        if is_group_duplicate(&guard, &groups, reading) {
+            return true;
+        }
+        is_dominated(&guard, reading)
+            && !(matches!(reading, SensorReading::Power { .. })
+                && self.dual_record.load(Ordering::Relaxed))
+            && !(matches!(reading, SensorReading::HeartRate { .. })
+                && self.hr_fusion.load(Ordering::Relaxed))
+    }
+
+    /// The reading as it should enter the broadcast channel, or `None` to drop
+    /// it. Non-primary power kept for dual recording goes out as
+    /// `SecondaryPower`, and with HR fusion on every strap's reading is
+    /// replaced by the blended one, so no subscriber sees raw secondary values.
+    pub fn forward(&self, reading: SensorReading) -> Option<SensorReading> {
+        if self.should_drop(&reading) {
+            return None;
+        }
+        let reading = {
+            let guard = self.primaries.read().unwrap_or_else(|e| e.into_inner());
+            tag_secondary_power(&guard, reading)
+        };
+        let fusing = self.hr_fusion.load(Ordering::Relaxed);
+        let mut fusion = self.hr_fusion_state.lock().unwrap_or_else(|e| e.into_inner());
+        if !fusing {
+            fusion.clear();
+        }
+        Some(match reading {
+            SensorReading::HeartRate {
+                bpm,
+                epoch_ms,
+                ref device_id,
+                ..
+            } if fusing => fusion.fuse(bpm, epoch_ms, device_id),
+            other => other,
+        })
+    }
+}
+
+/// Re-tag power from a non-primary meter as `SecondaryPower`. Such readings only
+/// get past `ReadingFilter::should_drop` while dual recording is on. Other
+/// readings pass through.
+pub fn tag_secondary_power(
+    primaries: &HashMap<DeviceType, String>,
+    reading: SensorReading,
+) -> SensorReading {
+    if !is_dominated(primaries, &reading) {
+        return reading;
+    }
+    match reading {
+        SensorReading::Power {
+            watts,
+            epoch_ms,
+            device_id,
+            ..
+        } => SensorReading::SecondaryPower {
+            watts,
+            epoch_ms,
+            device_id,
+        },
+        other => other,
+    }
 }
 
 /// Returns true when the reading comes from a non-primary device for its type.
@@ -179,6 +431,27 @@ pub fn is_dominated(
     }
 }
 
+/// Returns true when the reading comes from a non-primary connection of the same
+/// physical device as the primary for its type, e.g. pedals paired over both BLE
+/// and ANT+. `groups` maps device ids to their `device_group`.
+pub fn is_group_duplicate(
+    primaries: &HashMap<DeviceType, String>,
+    groups: &HashMap<String, String>,
+    reading: &SensorReading,
+) -> bool {
+    let device_id = reading.device_id();
+    let Some(primary_id) = primaries.get(&reading.device_type()) else {
+        return false;
+    };
+    if device_id.is_empty() || device_id == primary_id {
+        return false;
+    }
+    match (groups.get(device_id), groups.get(primary_id)) {
+        (Some(group), Some(primary_group)) => group == primary_group,
+        _ => false,
+    }
+}
+
 impl SensorReading {
     #[allow(dead_code)]
     pub fn epoch_ms(&self) -> u64 {
@@ -188,6 +461,38 @@ impl SensorReading {
             SensorReading::Cadence { epoch_ms, .. } => *epoch_ms,
             SensorReading::Speed { epoch_ms, .. } => *epoch_ms,
             SensorReading::TrainerCommand { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Hrv { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Energy { epoch_ms, .. } => *epoch_ms,
+            SensorReading::MuscleOxygen { epoch_ms, .. } => *epoch_ms,
+            SensorReading::RadarTargets { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Temperature { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Gear { epoch_ms, .. } => *epoch_ms,
+            SensorReading::SecondaryPower { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Grade { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Pedaling { epoch_ms, .. } => *epoch_ms,
+            SensorReading::Lap { epoch_ms } => *epoch_ms,
+            SensorReading::AveragePower { epoch_ms, .. } => *epoch_ms,
+        }
+    }
+
+    pub fn set_epoch_ms(&mut self, ms: u64) {
+        match self {
+            SensorReading::Power { epoch_ms, .. }
+            | SensorReading::HeartRate { epoch_ms, .. }
+            | SensorReading::Cadence { epoch_ms, .. }
+            | SensorReading::Speed { epoch_ms, .. }
+            | SensorReading::TrainerCommand { epoch_ms, .. }
+            | SensorReading::Hrv { epoch_ms, .. }
+            | SensorReading::Energy { epoch_ms, .. }
+            | SensorReading::MuscleOxygen { epoch_ms, .. }
+            | SensorReading::RadarTargets { epoch_ms, .. }
+            | SensorReading::Temperature { epoch_ms, .. }
+            | SensorReading::Gear { epoch_ms, .. }
+            | SensorReading::SecondaryPower { epoch_ms, .. }
+            | SensorReading::Grade { epoch_ms, .. }
+            | SensorReading::Pedaling { epoch_ms, .. }
+            | SensorReading::Lap { epoch_ms }
+            | SensorReading::AveragePower { epoch_ms, .. } => *epoch_ms = ms,
         }
     }
 
@@ -198,6 +503,17 @@ impl SensorReading {
             SensorReading::Cadence { device_id, .. } => device_id,
             SensorReading::Speed { device_id, .. } => device_id,
             SensorReading::TrainerCommand { .. } => "",
+            SensorReading::Hrv { device_id, .. } => device_id,
+            SensorReading::Energy { device_id, .. } => device_id,
+            SensorReading::MuscleOxygen { device_id, .. } => device_id,
+            SensorReading::RadarTargets { device_id, .. } => device_id,
+            SensorReading::Temperature { device_id, .. } => device_id,
+            SensorReading::Gear { device_id, .. } => device_id,
+            SensorReading::SecondaryPower { device_id, .. } => device_id,
+            SensorReading::Grade { .. } => "",
+            SensorReading::Pedaling { device_id, .. } => device_id,
+            SensorReading::Lap { .. } => "",
+            SensorReading::AveragePower { device_id, .. } => device_id,
         }
     }
 
@@ -208,6 +524,17 @@ impl SensorReading {
             SensorReading::Cadence { .. } => DeviceType::CadenceSpeed,
             SensorReading::Speed { .. } => DeviceType::CadenceSpeed,
             SensorReading::TrainerCommand { .. } => DeviceType::FitnessTrainer,
+            SensorReading::Hrv { .. } => DeviceType::HeartRate,
+            SensorReading::Energy { .. } => DeviceType::Power,
+            SensorReading::MuscleOxygen { .. } => DeviceType::MuscleOxygen,
+            SensorReading::RadarTargets { .. } => DeviceType::Radar,
+            SensorReading::Temperature { .. } => DeviceType::Environment,
+            SensorReading::Gear { .. } => DeviceType::FitnessTrainer,
+            SensorReading::SecondaryPower { .. } => DeviceType::Power,
+            SensorReading::Grade { .. } => DeviceType::FitnessTrainer,
+            SensorReading::Pedaling { .. } => DeviceType::Power,
+            SensorReading::Lap { .. } => DeviceType::FitnessTrainer,
+            SensorReading::AveragePower { .. } => DeviceType::FitnessTrainer,
         }
     }
 }
@@ -288,4 +615,125 @@ mod tests {
         // Power reading from primary power device is not
         assert!(!is_dominated(&primaries, &power_reading("pm-1")));
     }
+
+    #[test]
+    fn grouped_power_streams_keep_only_primary() {
+        // Same pedals over BLE and ANT+, plus an unrelated second meter
+        let primaries = HashMap::from([(DeviceType::Power, "ble-pedals".to_string())]);
+        let groups = HashMap::from([
+            ("ble-pedals".to_string(), "group-1".to_string()),
+            ("ant-pedals".to_string(), "group-1".to_string()),
+        ]);
+        let filter = ReadingFilter {
+            primaries: Arc::new(RwLock::new(primaries)),
+            device_groups: Arc::new(RwLock::new(groups)),
+            dual_record: Arc::new(AtomicBool::new(true)),
+            hr_fusion: Arc::new(AtomicBool::new(false)),
+            hr_fusion_state: Arc::new(Mutex::new(HrFusion::new(5000))),
+            packet_trace: Arc::new(AtomicBool::new(false)),
+        };
+
+        let stream = ["ble-pedals", "ant-pedals", "ble-pedals", "ant-pedals", "hub-pm"]
+            .map(power_reading);
+        let reached: Vec<SensorReading> =
+            stream.into_iter().filter_map(|r| filter.forward(r)).collect();
+
+        let primary: Vec<&str> = reached
+            .iter()
+            .filter(|r| matches!(r, SensorReading::Power { .. }))
+            .map(|r| r.device_id())
+            .collect();
+        assert_eq!(primary, vec!["ble-pedals", "ble-pedals"]);
+        // Dual recording still keeps the ungrouped meter as a secondary source
+        assert!(reached.iter().all(|r| r.device_id() != "ant-pedals"));
+        assert!(matches!(reached.last(), Some(SensorReading::SecondaryPower { .. })));
+    }
+
+    #[test]
+    fn reading_filter_keeps_secondary_power_only_while_dual_recording() {
+        let filter = ReadingFilter {
+            primaries: Arc::new(RwLock::new(HashMap::from([
+                (DeviceType::Power, "pm-1".to_string()),
+                (DeviceType::HeartRate, "hr-1".to_string()),
+            ]))),
+            device_groups: Arc::new(RwLock::new(HashMap::new())),
+            dual_record: Arc::new(AtomicBool::new(false)),
+            hr_fusion: Arc::new(AtomicBool::new(false)),
+            hr_fusion_state: Arc::new(Mutex::new(HrFusion::new(5000))),
+            packet_trace: Arc::new(AtomicBool::new(false)),
+        };
+        assert!(filter.should_drop(&power_reading("pm-2")));
+        filter.dual_record.store(true, Ordering::Relaxed);
+        assert!(!filter.should_drop(&power_reading("pm-2")));
+        assert!(!filter.should_drop(&power_reading("pm-1")));
+        assert!(filter.should_drop(&hr_reading("hr-2")));
+        filter.hr_fusion.store(true, Ordering::Relaxed);
+        assert!(!filter.should_drop(&hr_reading("hr-2")));
+    }
+
+    #[test]
+    fn forward_tags_secondary_power_before_broadcast() {
+        let filter = ReadingFilter {
+            primaries: Arc::new(RwLock::new(HashMap::from([(DeviceType::Power, "pm-1".to_string())]))),
+            device_groups: Arc::new(RwLock::new(HashMap::new())),
+            dual_record: Arc::new(AtomicBool::new(true)),
+            hr_fusion: Arc::new(AtomicBool::new(false)),
+            hr_fusion_state: Arc::new(Mutex::new(HrFusion::new(5000))),
+            packet_trace: Arc::new(AtomicBool::new(false)),
+        };
+        assert!(matches!(filter.forward(power_reading("pm-1")), Some(SensorReading::Power { .. })));
+        assert!(matches!(
+            filter.forward(power_reading("pm-2")),
+            Some(SensorReading::SecondaryPower { ref device_id, .. }) if device_id == "pm-2"
+        ));
+        filter.dual_record.store(false, Ordering::Relaxed);
+        assert!(filter.forward(power_reading("pm-2")).is_none());
+    }
+
+    #[test]
+    fn forward_fuses_heart_rate_before_broadcast() {
+        let filter = ReadingFilter {
+            primaries: Arc::new(RwLock::new(HashMap::from([(DeviceType::HeartRate, "hr-1".to_string())]))),
+            device_groups: Arc::new(RwLock::new(HashMap::new())),
+            dual_record: Arc::new(AtomicBool::new(false)),
+            hr_fusion: Arc::new(AtomicBool::new(true)),
+            hr_fusion_state: Arc::new(Mutex::new(HrFusion::new(5000))),
+            packet_trace: Arc::new(AtomicBool::new(false)),
+        };
+        let strap = |device_id: &str, bpm: u8| SensorReading::HeartRate {
+            bpm,
+            timestamp: None,
+            epoch_ms: 1000,
+            device_id: device_id.to_string(),
+        };
+        filter.forward(strap("hr-1", 140));
+        match filter.forward(strap("hr-2", 150)) {
+            Some(SensorReading::HeartRate { bpm, device_id, .. }) => {
+                assert_eq!(bpm, 145);
+                assert_eq!(device_id, crate::session::hr_fusion::FUSION_DEVICE_ID);
+            }
+            other => panic!("expected fused HeartRate, got {:?}", other),
+        }
+
+        // Fusion off: the primary strap passes through untouched
+        filter.hr_fusion.store(false, Ordering::Relaxed);
+        assert!(matches!(
+            filter.forward(strap("hr-1", 141)),
+            Some(SensorReading::HeartRate { bpm: 141, .. })
+        ));
+        assert!(filter.forward(strap("hr-2", 151)).is_none());
+    }
+
+    #[test]
+    fn tag_secondary_power_only_retags_non_primary_power() {
+        let primaries = HashMap::from([(DeviceType::Power, "pm-1".to_string())]);
+        assert!(matches!(
+            tag_secondary_power(&primaries, power_reading("pm-2")),
+            SensorReading::SecondaryPower { watts: 200, ref device_id, .. } if device_id == "pm-2"
+        ));
+        assert!(matches!(
+            tag_secondary_power(&primaries, power_reading("pm-1")),
+            SensorReading::Power { .. }
+        ));
+    }
 }
@@ -4,8 +4,10 @@ use thiserror::Error;
 pub enum BleError {
     #[error("BLE not initialized")]
     NotInitialized,
-    #[error("No BLE adapter found")]
+    #[error("No Bluetooth adapter")]
     NoAdapter,
+    #[error("Bluetooth permission denied")]
+    PermissionDenied,
     #[error("No recognized services on device {0}")]
     UnrecognizedDevice(String),
     #[error("Characteristic not found: {0}")]
@@ -14,7 +14,9 @@ use flexi_logger::{
 };
 use log::Record;
 use session::manager::SessionManager;
-use session::storage::Storage;
+use session::storage::{SharedStorage, Storage, DEFAULT_PROFILE};
+use session::ramp_test::RampTestRunner;
+use session::workout::WorkoutRunner;
 use session::zone_control::controller::ZoneController;
 use std::io::Write;
 use std::sync::Arc;
@@ -127,9 +129,18 @@ pub fn run() {
             let app_handle = app.handle().clone();
 
             let state = tauri::async_runtime::block_on(async {
-                let storage = Storage::new(&data_dir)
-                    .await
-                    .expect("Failed to initialize storage");
+                let profile = Storage::load_active_profile(&data_dir);
+                let (profile, storage) = match Storage::open_profile(&data_dir, &profile).await {
+                    Ok(storage) => (profile, storage),
+                    Err(e) if profile != DEFAULT_PROFILE => {
+                        log::warn!("Failed to open profile {}, using default: {}", profile, e);
+                        let storage = Storage::new(&data_dir)
+                            .await
+                            .expect("Failed to initialize storage");
+                        (DEFAULT_PROFILE.to_string(), storage)
+                    }
+                    Err(e) => panic!("Failed to initialize storage: {}", e),
+                };
 
                 // Recover any sessions from autosave files (crash recovery)
                 match storage.recover_autosaved_sessions().await {
@@ -137,23 +148,86 @@ pub fn run() {
                     Ok(n) => log::info!("Recovered {} autosaved session(s)", n),
                     Err(e) => log::warn!("Autosave recovery failed: {}", e),
                 }
+                match storage.prune_stale_autosaves(config::STALE_AUTOSAVE_MAX_AGE_HOURS).await {
+                    Ok(0) => {}
+                    Ok(n) => log::info!("Pruned {} stale autosave file(s)", n),
+                    Err(e) => log::warn!("Autosave pruning failed: {}", e),
+                }
 
                 let session_manager = Arc::new(SessionManager::new());
 
+                let initial_intervals = match storage.get_user_config().await {
+                    Ok(c) => c.task_intervals(),
+                    Err(e) => {
+                        log::warn!("Failed to load task intervals, using defaults: {}", e);
+                        session::types::TaskIntervals::default()
+                    }
+                };
+                let (task_intervals, _) = tokio::sync::watch::channel(initial_intervals);
+
+                let storage = Arc::new(storage);
+                let shared_storage = SharedStorage::new(&profile, storage.clone());
+                let mut device_manager = DeviceManager::new();
+                device_manager.set_storage(storage.clone());
+                device_manager.set_dual_record_flag(session_manager.dual_record_handle());
+                device_manager.set_hr_fusion_flag(session_manager.hr_fusion_handle());
+                let primary_devices = device_manager.primaries_handle();
+                let device_groups = device_manager.groups_handle();
+
                 // I6: Spawn a single global processor task that handles ALL sensor readings.
                 // This replaces the per-device processor tasks that caused duplicate processing.
                 // Non-primary readings are filtered at source (BLE/ANT+ listeners), so every
-                // reading that arrives here is from a primary device.
+                // reading that arrives here is from a primary device — except secondary
+                // power while dual recording, which the listeners re-tag as SecondaryPower
+                // and which is kept out of the live view. With HR fusion on, listeners
+                // send the fused reading in place of each strap's.
+                // Readings from the primary's twin on the other transport (same
+                // `device_group`) are dropped here too, whatever the dual-record state.
                 let session_mgr_clone = session_manager.clone();
+                let processor_primaries = primary_devices.clone();
                 let sensor_rx: broadcast::Receiver<crate::device::types::SensorReading> = sensor_tx.subscribe();
                 let handle = app_handle.clone();
                 tokio::spawn(async move {
                     let mut rx = sensor_rx;
                     loop {
                         match rx.recv().await {
+                            Ok(crate::device::types::SensorReading::RadarTargets {
+                                threats,
+                                epoch_ms,
+                                device_id,
+                            }) => {
+                                // Radar is live-only: surface it on its own event and keep
+                                // it out of the session and the sensor_reading stream.
+                                let _ = handle.emit("radar_update", &serde_json::json!({
+                                    "device_id": device_id,
+                                    "epoch_ms": epoch_ms,
+                                    "threats": threats,
+                                }));
+                            }
                             Ok(reading) => {
-                                session_mgr_clone.process_reading(reading.clone()).await;
-                                let _ = handle.emit("sensor_reading", &reading);
+                                {
+                                    let p = processor_primaries.read().unwrap_or_else(|e| e.into_inner());
+                                    let g = device_groups.read().unwrap_or_else(|e| e.into_inner());
+                                    if crate::device::types::is_group_duplicate(&p, &g, &reading) {
+                                        continue;
+                                    }
+                                }
+                                let outcome = session_mgr_clone.process_reading(reading.clone()).await;
+                                match outcome.auto_pause {
+                                    Some(crate::session::types::AutoPauseEvent::Paused) => {
+                                        let _ = handle.emit("session_autopaused", ());
+                                    }
+                                    Some(crate::session::types::AutoPauseEvent::Resumed) => {
+                                        let _ = handle.emit("session_resumed", ());
+                                    }
+                                    None => {}
+                                }
+                                if let Some(speed) = outcome.virtual_speed {
+                                    let _ = handle.emit("sensor_reading", &speed);
+                                }
+                                if !matches!(reading, crate::device::types::SensorReading::SecondaryPower { .. }) {
+                                    let _ = handle.emit("sensor_reading", &reading);
+                                }
                             }
                             Err(broadcast::error::RecvError::Lagged(n)) => {
                                 log::warn!("Dropped {} sensor readings", n);
@@ -163,25 +237,45 @@ pub fn run() {
                     }
                 });
 
-                let storage = Arc::new(storage);
-                let mut device_manager = DeviceManager::new();
-                device_manager.set_storage(storage.clone());
-                let primary_devices = device_manager.primaries_handle();
-
                 let device_manager = Arc::new(tokio::sync::Mutex::new(device_manager));
 
-                // Connection watchdog: every 5s, check for silently-disconnected devices
-                // and attempt reconnects. DeviceManager.check_connections() handles all
+                // Connection watchdog: every `connection_check_interval_secs` (5s by default),
+                // check for silently-disconnected devices and attempt reconnects. DeviceManager.check_connections() handles all
                 // internal cleanup (listener handles, trainer backends, connected_devices).
-                // This watchdog cleans up primaries, emits frontend events, and drives
-                // the auto-reconnect engine.
+                // This watchdog cleans up primaries, emits frontend events, drives
+                // the auto-reconnect engine, and samples RSSI and battery levels for
+                // weak-signal and low-battery warnings. While a trainer and a power
+                // meter are both recording it also compares their averaged power.
                 {
                     let dm = device_manager.clone();
+                    let session_mgr = session_manager.clone();
                     let handle = app_handle.clone();
                     let sensor_tx_clone = sensor_tx.clone();
+                    let storage_clone = shared_storage.clone();
+                    let mut intervals = task_intervals.subscribe();
                     tokio::spawn(async move {
+                        let mut battery_monitor = device::battery::BatteryMonitor::new();
+                        let mut signal_monitor = device::signal::SignalMonitor::new(
+                            config::WEAK_SIGNAL_RSSI_DBM,
+                            std::time::Duration::from_secs(config::WEAK_SIGNAL_DURATION_SECS),
+                        );
+                        let mut mismatch_monitor = device::power_mismatch::PowerMismatchMonitor::new(
+                            config::POWER_MISMATCH_PCT,
+                            (config::POWER_MISMATCH_DURATION_SECS / config::CONNECTION_CHECK_INTERVAL_SECS) as u32,
+                        );
+                        let mut last_battery_check: Option<std::time::Instant> = None;
                         loop {
-                            tokio::time::sleep(tokio::time::Duration::from_secs(config::CONNECTION_CHECK_INTERVAL_SECS)).await;
+                            let period = intervals.borrow_and_update().connection_check_secs;
+                            // Keep the mismatch window the same length in seconds, but at
+                            // least one sample when the period outlasts the window
+                            mismatch_monitor.set_required_samples(
+                                ((config::POWER_MISMATCH_DURATION_SECS / period) as u32).max(1),
+                            );
+                            tokio::select! {
+                                _ = tokio::time::sleep(tokio::time::Duration::from_secs(period)) => {}
+                                // Interval changed: restart the wait with the new period
+                                Ok(()) = intervals.changed() => continue,
+                            }
 
                             let disconnected = {
                                 let mut dm = dm.lock().await;
@@ -191,6 +285,8 @@ pub fn run() {
                             if !disconnected.is_empty() {
                                 // Emit disconnect events to frontend
                                 for info in &disconnected {
+                                    battery_monitor.forget(&info.id);
+                                    signal_monitor.forget(&info.id);
                                     let _ = handle.emit("device_disconnected", &info.id);
                                 }
 
@@ -226,19 +322,99 @@ pub fn run() {
                                         "attempt": attempt,
                                     }));
                             }
+
+                            let signal = {
+                                let dm = dm.lock().await;
+                                dm.read_signal_strengths().await
+                            };
+                            for (device_id, rssi) in signal_monitor.observe(&signal, std::time::Instant::now()) {
+                                log::warn!("[{}] Weak signal: {} dBm", device_id, rssi);
+                                let _ = handle.emit("device_signal_weak", &serde_json::json!({
+                                    "device_id": device_id,
+                                    "rssi": rssi,
+                                }));
+                            }
+
+                            // The trainer's power only reaches the session log (as
+                            // SecondaryPower) while dual recording with a separate meter.
+                            let trainer_id = dm.lock().await.connected_trainer_id();
+                            let averages = match &trainer_id {
+                                Some(_) => session_mgr
+                                    .recent_power_by_device(config::POWER_MISMATCH_WINDOW_SECS * 1000)
+                                    .await,
+                                None => Default::default(),
+                            };
+                            let pair = trainer_id.as_ref().and_then(|trainer_id| {
+                                let trainer_watts = *averages.get(trainer_id)?;
+                                let (meter_id, meter_watts) =
+                                    averages.iter().find(|(id, _)| *id != trainer_id)?;
+                                Some((trainer_id, trainer_watts, meter_id, *meter_watts))
+                            });
+                            match pair {
+                                Some((trainer_id, trainer_watts, meter_id, meter_watts)) => {
+                                    if let Some(diff_pct) = mismatch_monitor.observe(meter_watts, trainer_watts) {
+                                        log::warn!(
+                                            "Trainer {} reads {:.0}W vs power meter {} at {:.0}W ({:+.1}%)",
+                                            trainer_id, trainer_watts, meter_id, meter_watts, diff_pct
+                                        );
+                                        let _ = handle.emit("power_mismatch", &serde_json::json!({
+                                            "trainer_id": trainer_id,
+                                            "meter_id": meter_id,
+                                            "trainer_watts": trainer_watts.round(),
+                                            "meter_watts": meter_watts.round(),
+                                            "diff_pct": diff_pct,
+                                        }));
+                                    }
+                                }
+                                None => mismatch_monitor.reset(),
+                            }
+
+                            let battery_due = last_battery_check.map_or(true, |t| {
+                                t.elapsed().as_secs() >= config::BATTERY_CHECK_INTERVAL_SECS
+                            });
+                            if battery_due {
+                                last_battery_check = Some(std::time::Instant::now());
+                                let threshold = match storage_clone.current().get_user_config().await {
+                                    Ok(c) => c.battery_low_threshold,
+                                    Err(e) => {
+                                        log::warn!("Failed to load battery threshold: {}", e);
+                                        continue;
+                                    }
+                                };
+                                // GATT reads happen after the manager lock is released
+                                let (mut levels, peripherals) =
+                                    dm.lock().await.battery_level_sources().await;
+                                levels.extend(crate::device::ble::read_battery_levels(peripherals).await);
+                                for (device_id, level) in levels {
+                                    if battery_monitor.observe(&device_id, level, threshold) {
+                                        log::warn!("[{}] Battery low: {}%", device_id, level);
+                                        let _ = handle.emit("device_battery_low", &serde_json::json!({
+                                            "device_id": device_id,
+                                            "level": level,
+                                        }));
+                                    }
+                                }
+                            }
                         }
                     });
                 }
 
-                // Autosave task: every 30s, snapshot the active session to disk
+                // Autosave task: every `autosave_interval_secs` (30s by default),
+                // snapshot the active session to disk
                 {
                     let session_mgr = session_manager.clone();
-                    let storage_clone = storage.clone();
+                    let shared = shared_storage.clone();
+                    let mut intervals = task_intervals.subscribe();
                     tokio::spawn(async move {
                         let mut accumulated_log: Vec<crate::device::types::SensorReading> = Vec::new();
                         let mut current_session_id: Option<String> = None;
                         loop {
-                            tokio::time::sleep(tokio::time::Duration::from_secs(config::AUTOSAVE_INTERVAL_SECS)).await;
+                            let period = intervals.borrow_and_update().autosave_secs;
+                            tokio::select! {
+                                _ = tokio::time::sleep(tokio::time::Duration::from_secs(period)) => {}
+                                // Interval changed: restart the wait with the new period
+                                Ok(()) = intervals.changed() => continue,
+                            }
                             if let Some((session_id, summary, delta)) =
                                 session_mgr.snapshot_for_autosave().await
                             {
@@ -248,11 +424,17 @@ pub fn run() {
                                     current_session_id = Some(session_id.clone());
                                 }
                                 accumulated_log.extend(delta);
+                                let storage_clone = shared.current();
                                 if let Err(e) =
                                     storage_clone.write_autosave(&session_id, &summary, &accumulated_log).await
                                 {
                                     log::warn!("Autosave failed: {}", e);
                                 }
+                                // The session may have been stopped or discarded while
+                                // writing; don't leave a checkpoint behind to be recovered.
+                                if session_mgr.active_session_id().await.as_deref() != Some(&session_id) {
+                                    storage_clone.remove_autosave(&session_id);
+                                }
                             } else {
                                 // No active session — reset accumulator
                                 accumulated_log.clear();
@@ -273,22 +455,90 @@ pub fn run() {
                         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
                         loop {
                             interval.tick().await;
-                            if let Some(metrics) = session_mgr.get_live_metrics().await {
+                            if let Some(metrics) = session_mgr.live_metrics_event().await {
                                 let _ = handle.emit("live_metrics", &metrics);
                             }
                         }
                     });
                 }
 
+                // Startup auto-connect: reconnect the most recently used sensor of
+                // each type in the background, so startup never waits on a sensor
+                // that is switched off. Each attempt holds the device manager lock,
+                // so it is bounded by a timeout and the lock is released between
+                // attempts; the whole pass has its own deadline.
+                {
+                    let dm = device_manager.clone();
+                    let handle = app_handle.clone();
+                    let storage = shared_storage.current();
+                    let tx = sensor_tx.clone();
+                    tokio::spawn(async move {
+                        let enabled = storage
+                            .get_user_config()
+                            .await
+                            .map(|c| c.auto_connect_on_start)
+                            .unwrap_or(false);
+                        if !enabled {
+                            return;
+                        }
+                        let ids = match storage.auto_connect_device_ids().await {
+                            Ok(ids) => ids,
+                            Err(e) => {
+                                log::warn!("Auto-connect: failed to load last devices: {}", e);
+                                return;
+                            }
+                        };
+                        let deadline = tokio::time::Instant::now()
+                            + tokio::time::Duration::from_secs(config::AUTO_CONNECT_TOTAL_TIMEOUT_SECS);
+                        for id in ids {
+                            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                            if remaining.is_zero() {
+                                log::warn!("Auto-connect: out of time, skipping {}", id);
+                                continue;
+                            }
+                            let mut dm = dm.lock().await;
+                            let attempt = tokio::time::timeout(
+                                remaining.min(tokio::time::Duration::from_secs(
+                                    config::AUTO_CONNECT_TIMEOUT_SECS,
+                                )),
+                                dm.connect(&id, tx.clone()),
+                            )
+                            .await;
+                            match attempt {
+                                Ok(Ok(info)) => {
+                                    log::info!("Auto-connected {}", info.id);
+                                    commands::emit_primary_change(&handle, &dm, &info.id).await;
+                                    let all = dm.list_current().await;
+                                    let _ = handle.emit("device_list_updated", &all);
+                                }
+                                Ok(Err(e)) => log::warn!("Auto-connect {} failed: {}", id, e),
+                                Err(_) => {
+                                    log::warn!("Auto-connect {} timed out", id);
+                                    // Drop whatever the cancelled connect left half set up
+                                    if let Err(e) = dm.disconnect(&id).await {
+                                        log::debug!("Auto-connect cleanup for {} failed: {}", id, e);
+                                    }
+                                }
+                            }
+                            drop(dm);
+                        }
+                    });
+                }
+
                 let zone_controller = Arc::new(tokio::sync::Mutex::new(ZoneController::new()));
+                let workout_runner = Arc::new(tokio::sync::Mutex::new(WorkoutRunner::new()));
+                let ramp_test = Arc::new(tokio::sync::Mutex::new(RampTestRunner::new()));
 
                 AppState {
                     device_manager,
                     session_manager,
-                    storage,
+                    storage: shared_storage,
                     sensor_tx,
                     primary_devices,
                     zone_controller,
+                    workout_runner,
+                    ramp_test,
+                    task_intervals,
                     #[cfg(not(feature = "production"))]
                     simulator: Arc::new(tokio::sync::Mutex::new(simulator::Simulator::new())),
                 }
@@ -302,7 +552,7 @@ pub fn run() {
             if let WindowEvent::CloseRequested { .. } = event {
                 let state = window.state::<AppState>();
                 let session_mgr = state.session_manager.clone();
-                let storage = state.storage.clone();
+                let storage = state.storage.current();
                 tauri::async_runtime::block_on(async {
                     // Save active session before shutdown
                     if let Some((summary, sensor_log)) = session_mgr.stop_session_with_log().await {
@@ -323,40 +573,105 @@ pub fn run() {
         let builder = builder.invoke_handler(tauri::generate_handler![
             commands::scan_devices,
             commands::connect_device,
+            commands::pair_ant_device,
             commands::disconnect_device,
+            commands::forget_device,
             commands::get_known_devices,
             commands::get_device_details,
+            commands::get_device_health,
+            commands::get_trainer_command_status,
+            commands::set_power_zero_offset,
             commands::start_session,
             commands::stop_session,
+            commands::discard_session,
+            commands::set_dual_record,
+            commands::set_hr_fusion,
+            commands::get_hr_fusion,
+            commands::set_packet_trace,
             commands::pause_session,
+            commands::new_lap,
             commands::resume_session,
             commands::list_sessions,
+            commands::search_sessions,
             commands::get_session,
             commands::get_session_analysis,
+            commands::add_session_segment,
+            commands::delete_session_segment,
+            commands::get_session_segments,
+            commands::estimate_ftp,
+            commands::accept_ftp_estimate,
+            commands::get_ftp_history,
+            commands::recompute_session_metrics,
+            commands::recompute_all_after,
+            commands::record_calibration,
+            commands::get_calibration_history,
+            commands::get_session_intervals,
+            commands::get_power_source_comparison,
             commands::get_user_config,
             commands::save_user_config,
             commands::set_trainer_power,
             commands::set_trainer_resistance,
             commands::set_trainer_simulation,
+            commands::get_trainer_capabilities,
             commands::start_trainer,
             commands::stop_trainer,
             commands::export_session_fit,
+            commands::export_session_raw_csv,
+            commands::get_strava_authorize_url,
+            commands::connect_strava,
+            commands::disconnect_strava,
+            commands::get_strava_connected,
+            commands::upload_session_strava,
+            commands::import_fit,
             commands::update_session_metadata,
+            commands::get_structured_rpe,
+            commands::set_session_favorite,
             commands::delete_session,
+            commands::find_duplicate_sessions,
+            commands::merge_sessions,
+            commands::split_session,
+            commands::concat_sessions,
             commands::set_primary_device,
             commands::get_primary_devices,
+            commands::set_wheel_circumference,
             commands::unlink_devices,
+            commands::set_device_alias,
             commands::start_zone_control,
+            commands::get_zone_control_config,
+            commands::save_zone_control_config,
+            commands::set_power_bias,
             commands::stop_zone_control,
             commands::pause_zone_control,
             commands::resume_zone_control,
+            commands::set_zone_control_debug,
             commands::get_zone_control_status,
+            commands::start_workout,
+            commands::import_workout,
+            commands::stop_workout,
+            commands::pause_workout,
+            commands::resume_workout,
+            commands::skip_workout_step,
+            commands::get_workout_status,
+            commands::start_ramp_test,
+            commands::stop_ramp_test,
+            commands::get_ramp_test_status,
             commands::estimate_initial_power,
             commands::save_zone_ride_config,
             commands::get_zone_ride_config,
             commands::get_best_power_curve,
+            commands::get_power_curve_with_best,
+            commands::get_best_efforts,
+            commands::get_training_load,
+            commands::get_period_summary,
+            commands::export_power_curve_csv,
+            commands::export_backup,
+            commands::import_backup,
+            commands::list_profiles,
+            commands::get_active_profile,
+            commands::switch_profile,
             commands::backfill_power_curves,
             commands::check_prerequisites,
+            commands::retry_bluetooth,
             commands::fix_prerequisites,
             commands::sim_start,
             commands::sim_stop,
@@ -367,40 +682,105 @@ pub fn run() {
         let builder = builder.invoke_handler(tauri::generate_handler![
             commands::scan_devices,
             commands::connect_device,
+            commands::pair_ant_device,
             commands::disconnect_device,
+            commands::forget_device,
             commands::get_known_devices,
             commands::get_device_details,
+            commands::get_device_health,
+            commands::get_trainer_command_status,
+            commands::set_power_zero_offset,
             commands::start_session,
             commands::stop_session,
+            commands::discard_session,
+            commands::set_dual_record,
+            commands::set_hr_fusion,
+            commands::get_hr_fusion,
+            commands::set_packet_trace,
             commands::pause_session,
+            commands::new_lap,
             commands::resume_session,
             commands::list_sessions,
+            commands::search_sessions,
             commands::get_session,
             commands::get_session_analysis,
+            commands::add_session_segment,
+            commands::delete_session_segment,
+            commands::get_session_segments,
+            commands::estimate_ftp,
+            commands::accept_ftp_estimate,
+            commands::get_ftp_history,
+            commands::recompute_session_metrics,
+            commands::recompute_all_after,
+            commands::record_calibration,
+            commands::get_calibration_history,
+            commands::get_session_intervals,
+            commands::get_power_source_comparison,
             commands::get_user_config,
             commands::save_user_config,
             commands::set_trainer_power,
             commands::set_trainer_resistance,
             commands::set_trainer_simulation,
+            commands::get_trainer_capabilities,
             commands::start_trainer,
             commands::stop_trainer,
             commands::export_session_fit,
+            commands::export_session_raw_csv,
+            commands::get_strava_authorize_url,
+            commands::connect_strava,
+            commands::disconnect_strava,
+            commands::get_strava_connected,
+            commands::upload_session_strava,
+            commands::import_fit,
             commands::update_session_metadata,
+            commands::get_structured_rpe,
+            commands::set_session_favorite,
             commands::delete_session,
+            commands::find_duplicate_sessions,
+            commands::merge_sessions,
+            commands::split_session,
+            commands::concat_sessions,
             commands::set_primary_device,
             commands::get_primary_devices,
+            commands::set_wheel_circumference,
             commands::unlink_devices,
+            commands::set_device_alias,
             commands::start_zone_control,
+            commands::get_zone_control_config,
+            commands::save_zone_control_config,
+            commands::set_power_bias,
             commands::stop_zone_control,
             commands::pause_zone_control,
             commands::resume_zone_control,
+            commands::set_zone_control_debug,
             commands::get_zone_control_status,
+            commands::start_workout,
+            commands::import_workout,
+            commands::stop_workout,
+            commands::pause_workout,
+            commands::resume_workout,
+            commands::skip_workout_step,
+            commands::get_workout_status,
+            commands::start_ramp_test,
+            commands::stop_ramp_test,
+            commands::get_ramp_test_status,
             commands::estimate_initial_power,
             commands::save_zone_ride_config,
             commands::get_zone_ride_config,
             commands::get_best_power_curve,
+            commands::get_power_curve_with_best,
+            commands::get_best_efforts,
+            commands::get_training_load,
+            commands::get_period_summary,
+            commands::export_power_curve_csv,
+            commands::export_backup,
+            commands::import_backup,
+            commands::list_profiles,
+            commands::get_active_profile,
+            commands::switch_profile,
             commands::backfill_power_curves,
             commands::check_prerequisites,
+            commands::retry_bluetooth,
             commands::fix_prerequisites,
         ]);
 
@@ -7,6 +7,10 @@ pub struct PrereqStatus {
     pub bluetooth_service: bool,
     pub all_met: bool,
     pub pkexec_available: bool,
+    /// False once BLE init has failed (e.g. no adapter on a headless machine)
+    pub ble_available: bool,
+    /// False when the OS has refused this app Bluetooth access (macOS)
+    pub bluetooth_permission: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -16,8 +20,18 @@ pub struct FixResult {
     pub status: PrereqStatus,
 }
 
+/// Fold the device manager's last BLE init outcome into a status. A denied
+/// Bluetooth permission can be fixed from System Settings, so it counts against
+/// `all_met`; a missing adapter cannot be fixed from here and does not.
+pub fn apply_ble_state(status: &mut PrereqStatus, ble_available: bool, permission_denied: bool) {
+    status.ble_available = ble_available;
+    status.bluetooth_permission = !permission_denied;
+    status.all_met &= !permission_denied;
+}
+
 // macOS and Windows handle BLE natively (Core Bluetooth / WinRT) and don't
-// need udev rules, BlueZ, or systemd. Return "all met" on non-Linux platforms.
+// need udev rules, BlueZ, or systemd. Return "all met" on non-Linux platforms;
+// the Bluetooth permission is applied from the BLE init result.
 #[cfg(not(target_os = "linux"))]
 pub fn check() -> PrereqStatus {
     PrereqStatus {
@@ -26,10 +40,36 @@ pub fn check() -> PrereqStatus {
         bluetooth_service: true,
         all_met: true,
         pkexec_available: false,
+        ble_available: true,
+        bluetooth_permission: true,
     }
 }
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(target_os = "macos")]
+const BLUETOOTH_SETTINGS_URL: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_Bluetooth";
+
+/// The only fixable prerequisite on macOS is the Bluetooth permission, which
+/// the user has to grant in System Settings.
+#[cfg(target_os = "macos")]
+pub fn fix(_udev_rules_source: &str) -> FixResult {
+    let opened = std::process::Command::new("open")
+        .arg(BLUETOOTH_SETTINGS_URL)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    FixResult {
+        success: opened,
+        message: if opened {
+            "Allow Bluetooth access for this app in System Settings, then scan again.".into()
+        } else {
+            "Could not open System Settings. Allow Bluetooth access under Privacy & Security > Bluetooth.".into()
+        },
+        status: check(),
+    }
+}
+
+#[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
 pub fn fix(_udev_rules_source: &str) -> FixResult {
     FixResult {
         success: true,
@@ -93,6 +133,8 @@ pub fn check() -> PrereqStatus {
         bluetooth_service,
         all_met: udev_rules && bluez_installed && bluetooth_service,
         pkexec_available,
+        ble_available: true,
+        bluetooth_permission: true,
     }
 }
 
@@ -233,6 +275,8 @@ mod tests {
             bluetooth_service: false,
             all_met: false,
             pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
         };
         let cmds = build_fix_commands(&status, "/tmp/99-ant-usb.rules");
         // udev: cp, udevadm control, udevadm trigger (3 commands)
@@ -257,6 +301,8 @@ mod tests {
             bluetooth_service: true,
             all_met: false,
             pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
         };
         let cmds = build_fix_commands(&status, "/opt/rules/99-ant-usb.rules");
         assert_eq!(cmds.len(), 3);
@@ -273,6 +319,8 @@ mod tests {
             bluetooth_service: true,
             all_met: true,
             pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
         };
         let cmds = build_fix_commands(&status, "/tmp/rules");
         assert!(cmds.is_empty());
@@ -286,6 +334,8 @@ mod tests {
             bluetooth_service: true,
             all_met: false,
             pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
         };
         let cmds = build_fix_commands(&status, "/tmp/rules");
         // No udev or systemctl commands
@@ -315,10 +365,42 @@ mod tests {
             bluetooth_service: true,
             all_met: false,
             pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
         };
         let evil_path = "/tmp/it's a \"test\" && rm -rf /";
         let cmds = build_fix_commands(&status, evil_path);
         // The path must appear as a single, unmodified argument
         assert_eq!(cmds[0][1], evil_path);
     }
+
+    #[test]
+    fn denied_ble_permission_is_an_unmet_prerequisite() {
+        let mut status = all_met_status();
+        apply_ble_state(&mut status, false, true);
+        assert!(!status.bluetooth_permission);
+        assert!(!status.ble_available);
+        assert!(!status.all_met);
+    }
+
+    #[test]
+    fn missing_ble_adapter_keeps_prerequisites_met() {
+        let mut status = all_met_status();
+        apply_ble_state(&mut status, false, false);
+        assert!(status.bluetooth_permission);
+        assert!(!status.ble_available);
+        assert!(status.all_met);
+    }
+
+    fn all_met_status() -> PrereqStatus {
+        PrereqStatus {
+            udev_rules: true,
+            bluez_installed: true,
+            bluetooth_service: true,
+            all_met: true,
+            pkexec_available: true,
+            ble_available: true,
+            bluetooth_permission: true,
+        }
+    }
 }
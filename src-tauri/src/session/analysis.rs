@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::device::types::SensorReading;
-use crate::session::types::{SessionConfig, SessionSummary};
+use crate::session::types::{SessionConfig, SessionSegment, SessionSummary};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionAnalysis {
@@ -9,7 +10,161 @@ pub struct SessionAnalysis {
     pub power_curve: Vec<PowerCurvePoint>,
     pub power_zone_distribution: Vec<ZoneBucket>,
     pub hr_zone_distribution: Vec<ZoneBucket>,
+    pub cadence_zone_distribution: Vec<ZoneBucket>,
+    /// Time per running pace zone. Only populated for runs.
+    pub pace_zone_distribution: Vec<ZoneBucket>,
     pub pwc: Option<PwcMarkers>,
+    pub decoupling_pct: Option<f64>,
+    /// W′ balance (joules) per second from the first power reading.
+    /// Only populated when critical power and W′ are configured.
+    pub wbal: Option<Vec<f64>>,
+    /// Force vs pedal velocity scatter, one point per paired power/cadence second.
+    pub quadrants: Vec<QuadrantPoint>,
+    /// SmO2/THb samples from a muscle oxygen sensor, on the timeseries clock.
+    pub muscle_oxygen: Vec<MuscleOxygenPoint>,
+    /// One-minute heart rate recovery (bpm dropped 60s after the last hard effort).
+    pub hr_recovery_bpm: Option<u8>,
+    /// Ride averages of pedal stroke metrics (%), when an ANT+ power meter reports them.
+    pub avg_torque_effectiveness: Option<f32>,
+    pub avg_pedal_smoothness: Option<f32>,
+    /// Sensor dropouts per channel, on the timeseries clock, for shading.
+    pub gaps: Vec<Gap>,
+    /// Heart rate stuck on one value for over `FLATLINE_MIN_SECS`, usually a
+    /// stalled strap rather than a real reading.
+    pub flatlines: Vec<Flatline>,
+    /// User-labelled time ranges. Filled from storage by `get_session_analysis`.
+    pub segments: Vec<SessionSegment>,
+}
+
+/// Sensor channels checked for dropouts by `detect_gaps` and stuck values by
+/// `detect_flatline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapChannel {
+    Power,
+    HeartRate,
+    Cadence,
+    Speed,
+}
+
+impl GapChannel {
+    /// Reading times on this channel, in recording order.
+    fn times(self, samples: &AnalysisSamples) -> Vec<u64> {
+        match self {
+            GapChannel::Power => samples.power.iter().map(|(ms, _)| *ms).collect(),
+            GapChannel::HeartRate => samples.heart_rate.iter().map(|(ms, _)| *ms).collect(),
+            GapChannel::Cadence => samples.cadence.iter().map(|(ms, _)| *ms).collect(),
+            GapChannel::Speed => samples.speed.iter().map(|(ms, _)| *ms).collect(),
+        }
+    }
+
+    /// Readings on this channel as `(epoch_ms, value)`, in recording order.
+    fn values(self, samples: &AnalysisSamples) -> Vec<(u64, f64)> {
+        match self {
+            GapChannel::Power => samples.power.iter().map(|&(ms, w)| (ms, w as f64)).collect(),
+            GapChannel::HeartRate => {
+                samples.heart_rate.iter().map(|&(ms, bpm)| (ms, bpm as f64)).collect()
+            }
+            GapChannel::Cadence => samples.cadence.iter().map(|&(ms, rpm)| (ms, rpm as f64)).collect(),
+            GapChannel::Speed => samples.speed.iter().map(|&(ms, kmh)| (ms, kmh as f64)).collect(),
+        }
+    }
+}
+
+/// The parts of a sensor log that `compute_analysis` reads, kept as compact
+/// `(epoch_ms, value)` samples per channel in recording order. Built from a
+/// loaded log, or folded chunk by chunk while streaming a long one from disk
+/// so the full `Vec<SensorReading>` is never held in memory.
+#[derive(Debug, Default)]
+pub struct AnalysisSamples {
+    /// Earliest timestamp of any reading; the timeseries clock starts here.
+    t0: Option<u64>,
+    /// Latest timestamp of any reading, where the recording ends.
+    t_end: Option<u64>,
+    power: Vec<(u64, u16)>,
+    heart_rate: Vec<(u64, u8)>,
+    cadence: Vec<(u64, f32)>,
+    speed: Vec<(u64, f32)>,
+    /// (epoch_ms, SmO2, THb)
+    muscle_oxygen: Vec<(u64, f32, f32)>,
+    /// Running (sum, count) of the pedal stroke percentages
+    torque_effectiveness: (f32, usize),
+    pedal_smoothness: (f32, usize),
+}
+
+impl AnalysisSamples {
+    pub fn from_readings(readings: &[SensorReading]) -> Self {
+        let mut samples = Self::default();
+        samples.push(readings);
+        samples
+    }
+
+    /// Fold the next chunk of the log, in recording order.
+    pub fn push(&mut self, chunk: &[SensorReading]) {
+        for reading in chunk {
+            let ms = reading.epoch_ms();
+            self.t0 = Some(self.t0.map_or(ms, |t0| t0.min(ms)));
+            self.t_end = Some(self.t_end.map_or(ms, |end| end.max(ms)));
+            match reading {
+                SensorReading::Power { watts, epoch_ms, .. } => {
+                    self.power.push((*epoch_ms, *watts))
+                }
+                SensorReading::HeartRate { bpm, epoch_ms, .. } => {
+                    self.heart_rate.push((*epoch_ms, *bpm))
+                }
+                SensorReading::Cadence { rpm, epoch_ms, .. } => {
+                    self.cadence.push((*epoch_ms, *rpm))
+                }
+                SensorReading::Speed { kmh, epoch_ms, .. } => self.speed.push((*epoch_ms, *kmh)),
+                SensorReading::MuscleOxygen {
+                    smo2, thb, epoch_ms, ..
+                } => self.muscle_oxygen.push((*epoch_ms, *smo2, *thb)),
+                SensorReading::Pedaling {
+                    torque_effectiveness,
+                    smoothness,
+                    ..
+                } => {
+                    for (acc, value) in [
+                        (&mut self.torque_effectiveness, torque_effectiveness),
+                        (&mut self.pedal_smoothness, smoothness),
+                    ] {
+                        if let Some(v) = value {
+                            acc.0 += v;
+                            acc.1 += 1;
+                        }
+                    }
+                }
+                SensorReading::TrainerCommand { .. }
+                | SensorReading::Hrv { .. }
+                | SensorReading::Energy { .. }
+                | SensorReading::RadarTargets { .. }
+                | SensorReading::Temperature { .. }
+                | SensorReading::Gear { .. }
+                | SensorReading::SecondaryPower { .. }
+                | SensorReading::Grade { .. }
+                | SensorReading::Lap { .. }
+                | SensorReading::AveragePower { .. } => {}
+            }
+        }
+    }
+}
+
+/// A stretch with no readings on one channel, in seconds since the first reading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gap {
+    pub channel: GapChannel,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub duration_secs: f64,
+}
+
+/// A stretch where one channel repeated the exact same value, in seconds since
+/// the first reading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flatline {
+    pub channel: GapChannel,
+    pub start_secs: f64,
+    pub end_secs: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +188,98 @@ pub struct TimeseriesPoint {
 pub struct PowerCurvePoint {
     pub duration_secs: u32,
     pub watts: u16,
+    /// `watts` as % of the session's effective FTP. Only filled when the
+    /// caller asks for it (see `annotate_ftp_pct`).
+    #[serde(default)]
+    pub pct_ftp: Option<f32>,
+}
+
+/// Best average power for one duration across stored sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BestEffort {
+    pub duration_secs: u32,
+    pub watts: u16,
+    /// Session the best effort came from
+    pub session_id: String,
+}
+
+/// One duration on the session-vs-all-time power curve chart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerCurveComparisonPoint {
+    pub duration_secs: u32,
+    pub session_watts: Option<u16>,
+    /// All-time best including this session
+    pub best_watts: Option<u16>,
+    /// The session beat every other session at this duration
+    pub new_best: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerCurveComparison {
+    pub points: Vec<PowerCurveComparisonPoint>,
+}
+
+/// A duration where a session beat the previous all-time best.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub duration_secs: u32,
+    pub previous_watts: u16,
+    pub watts: u16,
+}
+
+/// Payload of the `new_personal_best` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPersonalBests {
+    pub session_id: String,
+    pub records: Vec<PersonalBest>,
+}
+
+/// A detected work interval. Times are seconds from the first power reading;
+/// `end_secs` is exclusive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interval {
+    pub start_secs: u32,
+    pub end_secs: u32,
+    pub avg_power: u16,
+    pub duration_secs: u32,
+}
+
+/// One second of quadrant analysis. `quadrant` is 1–4 relative to the
+/// FTP force/velocity split: I high force + high velocity, II high force +
+/// low velocity, III low + low, IV low force + high velocity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuadrantPoint {
+    /// Seconds from the first paired sample
+    pub elapsed_secs: u32,
+    /// Circumferential pedal velocity (m/s)
+    pub cpv: f64,
+    /// Average effective pedal force (N)
+    pub aepf: f64,
+    pub quadrant: u8,
+}
+
+/// One muscle oxygen sample. `elapsed_secs` shares the timeseries origin
+/// (the earliest reading of any kind) so the two can be overlaid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuscleOxygenPoint {
+    pub elapsed_secs: f64,
+    /// Muscle oxygen saturation (%)
+    pub smo2: f32,
+    /// Total hemoglobin (g/dL)
+    pub thb: f32,
+}
+
+/// Per-second agreement between two power meters recorded side by side.
+/// Deltas are `compared − reference`; percentages are relative to the reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSourceComparison {
+    pub reference_device_id: String,
+    pub compared_device_id: String,
+    /// Seconds where both sources reported and the reference was non-zero
+    pub paired_secs: u32,
+    pub mean_delta_watts: f64,
+    pub mean_pct_diff: f64,
+    pub max_pct_diff: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,34 +287,345 @@ pub struct ZoneBucket {
     pub zone: u8,
     pub duration_secs: f64,
     pub percentage: f64,
+    /// Time-weighted average watts (power zones) or bpm (HR zones) while in
+    /// the zone. `None` for empty zones and for other zone types.
+    #[serde(default)]
+    pub avg_value: Option<f64>,
 }
 
-const MAX_READING_GAP_MS: u64 = 5000;
+/// Heart rate unchanged for longer than this is flagged as a stuck strap.
+const FLATLINE_MIN_SECS: u64 = 60;
+
+/// Below this speed a runner is treated as stopped and not counted in any pace zone.
+const MIN_RUN_SPEED_KMH: f32 = 1.0;
+
+/// Activity types recorded as runs, from the history editor or FIT import.
+fn is_run(activity_type: Option<&str>) -> bool {
+    matches!(activity_type, Some("run" | "running"))
+}
+
+/// Minimum paired power/HR seconds required in each half for decoupling.
+const MIN_DECOUPLING_SAMPLES_PER_HALF: usize = 30;
+
+/// Power (% FTP) that opens a work interval.
+const INTERVAL_THRESHOLD_PCT: f64 = 90.0;
+/// Minimum sustained duration for a work interval.
+const INTERVAL_MIN_SECS: u32 = 20;
+/// Dips below threshold shorter than this don't end an interval (hysteresis).
+const INTERVAL_MAX_DIP_SECS: usize = 5;
+
+/// Variability index above which a ride with repeated efforts is "intervals".
+const CLASSIFY_INTERVALS_MIN_VI: f32 = 1.10;
+/// Work intervals needed, alongside a high VI, to classify as "intervals".
+const CLASSIFY_INTERVALS_MIN_COUNT: usize = 3;
+/// Intensity factor at or above which a short ride is a race or test.
+const CLASSIFY_HARD_MIN_IF: f32 = 0.95;
+/// Longest hard ride classified as a test; up to `CLASSIFY_RACE_MAX_SECS` is a race.
+const CLASSIFY_TEST_MAX_SECS: u64 = 30 * 60;
+const CLASSIFY_RACE_MAX_SECS: u64 = 60 * 60;
+/// Endurance rides are long, easy and steady.
+const CLASSIFY_ENDURANCE_MAX_IF: f32 = 0.75;
+const CLASSIFY_ENDURANCE_MAX_VI: f32 = 1.05;
+const CLASSIFY_ENDURANCE_MIN_SECS: u64 = 60 * 60;
+
+/// Cadence at which the quadrant split crosses the FTP power line.
+const QUADRANT_REFERENCE_RPM: f64 = 80.0;
+
+/// Power (% of best 1-minute power) that counts as hard effort for HR recovery.
+const HRR_EFFORT_PCT: f64 = 70.0;
+/// HR recovery is measured this long after the effort ends.
+const HRR_RECOVERY_SECS: u64 = 60;
+/// Peak HR is taken from this many seconds either side of the effort end.
+const HRR_PEAK_WINDOW_SECS: u64 = 30;
+
+/// km/h → mph for imperial output.
+const KMH_TO_MPH: f32 = 0.621_371;
 
 const POWER_CURVE_DURATIONS: &[u32] = &[
     1, 2, 3, 5, 10, 15, 20, 30, 45, 60, 120, 300, 600, 1200, 1800, 3600,
 ];
 
+/// Storage is metric; convert speed to mph for `"imperial"` output.
+/// Power, HR and cadence are unit-independent.
+fn convert_timeseries_units(
+    mut timeseries: Vec<TimeseriesPoint>,
+    units: &str,
+) -> Vec<TimeseriesPoint> {
+    if units == "imperial" {
+        for point in &mut timeseries {
+            point.speed = point.speed.map(|kmh| kmh * KMH_TO_MPH);
+        }
+    }
+    timeseries
+}
+
 pub fn compute_analysis(
-    readings: &[SensorReading],
+    samples: &AnalysisSamples,
     session: &SessionSummary,
     config: &SessionConfig,
+    smoothing_secs: Option<u32>,
+    units: &str,
 ) -> SessionAnalysis {
-    let timeseries = build_timeseries(readings, session.duration_secs);
-    let power_curve = compute_power_curve(readings);
-    let ftp = session.ftp.unwrap_or(config.ftp);
-    let (power_zone_distribution, hr_zone_distribution) =
-        compute_zone_distribution(readings, ftp, &config.power_zones, &config.hr_zones);
-    let pwc = compute_pwc(&timeseries);
+    let timeseries = build_timeseries(samples, session.duration_secs, smoothing_secs);
+    let power_curve = power_curve_from_1s(&resample_power(&samples.power));
+    // A per-sport threshold wins over the (cycling) FTP stamped on the session
+    let ftp = config
+        .sport_ftp(session.activity_type.as_deref())
+        .or(session.ftp)
+        .unwrap_or(config.ftp);
+    let max_gap_ms = config.max_gap_ms();
+    let (mut power_zone_distribution, hr_zone_distribution) =
+        compute_zone_distribution(
+            samples,
+            ftp,
+            config.power_zone_model.boundaries(&config.power_zones),
+            &config.hr_zones,
+            max_gap_ms,
+        );
+    let cadence_zone_distribution =
+        compute_cadence_zone_distribution(samples, &config.cadence_zones, max_gap_ms);
+    // Runs are zoned by pace; power zones are dropped when no running power was recorded.
+    let pace_zone_distribution = if is_run(session.activity_type.as_deref()) {
+        if samples.power.is_empty() {
+            power_zone_distribution.clear();
+        }
+        compute_pace_zone_distribution(samples, &config.pace_zones, max_gap_ms)
+    } else {
+        Vec::new()
+    };
+    // PWC regression always runs on raw per-second values so the display
+    // smoothing choice doesn't change the markers.
+    let pwc = if smoothing_secs.is_some() {
+        compute_pwc(&build_timeseries(samples, session.duration_secs, None))
+    } else {
+        compute_pwc(&timeseries)
+    };
+    let decoupling_pct = compute_decoupling(samples);
+    let wbal = match (config.critical_power, config.w_prime) {
+        (Some(cp), Some(w_prime)) if cp > 0 && w_prime > 0 => Some(
+            compute_wbal(samples, cp, w_prime)
+                .into_iter()
+                .map(|(_, w)| w)
+                .collect(),
+        ),
+        _ => None,
+    };
+    let (avg_torque_effectiveness, avg_pedal_smoothness) = compute_pedaling_averages(samples);
     SessionAnalysis {
-        timeseries,
+        timeseries: convert_timeseries_units(timeseries, units),
         power_curve,
         power_zone_distribution,
         hr_zone_distribution,
+        cadence_zone_distribution,
+        pace_zone_distribution,
         pwc,
+        decoupling_pct,
+        wbal,
+        quadrants: compute_quadrant_analysis(samples, config.crank_length_mm as f64 / 1000.0, ftp),
+        muscle_oxygen: build_muscle_oxygen_series(samples),
+        hr_recovery_bpm: compute_hr_recovery(samples, max_gap_ms),
+        avg_torque_effectiveness,
+        avg_pedal_smoothness,
+        gaps: [
+            GapChannel::Power,
+            GapChannel::HeartRate,
+            GapChannel::Cadence,
+            GapChannel::Speed,
+        ]
+        .into_iter()
+        .flat_map(|channel| detect_gaps(samples, channel, max_gap_ms))
+        .collect(),
+        // Power and cadence legitimately hold steady in ERG mode, so only HR is checked
+        flatlines: detect_flatline(samples, GapChannel::HeartRate, FLATLINE_MIN_SECS)
+            .into_iter()
+            .map(|(start_secs, end_secs)| Flatline {
+                channel: GapChannel::HeartRate,
+                start_secs,
+                end_secs,
+            })
+            .collect(),
+        segments: Vec::new(),
     }
 }
 
+/// Find dropouts on `channel`: stretches longer than `max_gap_ms` between
+/// consecutive readings, including from the start of the recording to the
+/// channel's first reading and from its last reading to the end. A channel
+/// with no readings has no gaps.
+pub fn detect_gaps(samples: &AnalysisSamples, channel: GapChannel, max_gap_ms: u64) -> Vec<Gap> {
+    let (Some(t0), Some(t_end)) = (samples.t0, samples.t_end) else {
+        return Vec::new();
+    };
+    let mut times = channel.times(samples);
+    if times.is_empty() {
+        return Vec::new();
+    }
+    times.sort_unstable();
+    times.insert(0, t0);
+    times.push(t_end);
+
+    times
+        .windows(2)
+        .filter(|w| w[1] - w[0] > max_gap_ms)
+        .map(|w| {
+            let start_secs = (w[0] - t0) as f64 / 1000.0;
+            let end_secs = (w[1] - t0) as f64 / 1000.0;
+            Gap {
+                channel,
+                start_secs,
+                end_secs,
+                duration_secs: end_secs - start_secs,
+            }
+        })
+        .collect()
+}
+
+/// Find runs where `channel` repeats the exact same value for longer than
+/// `min_secs`, as `(start_secs, end_secs)` from the first to the last reading
+/// of the run. The caller picks the channel, since steady power in ERG mode
+/// is expected while a frozen heart rate is not.
+pub fn detect_flatline(samples: &AnalysisSamples, channel: GapChannel, min_secs: u64) -> Vec<(f64, f64)> {
+    let Some(t0) = samples.t0 else {
+        return Vec::new();
+    };
+    let mut values = channel.values(samples);
+    values.sort_by_key(|(ms, _)| *ms);
+
+    let to_secs = |ms: u64| (ms - t0) as f64 / 1000.0;
+    let mut flatlines = Vec::new();
+    let mut run_start = 0;
+    for i in 1..=values.len() {
+        if i < values.len() && values[i].1 == values[run_start].1 {
+            continue;
+        }
+        let (start_ms, end_ms) = (values[run_start].0, values[i - 1].0);
+        if end_ms - start_ms > min_secs * 1000 {
+            flatlines.push((to_secs(start_ms), to_secs(end_ms)));
+        }
+        run_start = i;
+    }
+    flatlines
+}
+
+/// Average torque effectiveness and pedal smoothness over every `Pedaling`
+/// reading that carries them.
+fn compute_pedaling_averages(samples: &AnalysisSamples) -> (Option<f32>, Option<f32>) {
+    let mean = |(sum, count): (f32, usize)| (count > 0).then(|| sum / count as f32);
+    (
+        mean(samples.torque_effectiveness),
+        mean(samples.pedal_smoothness),
+    )
+}
+
+/// Compute one-minute heart rate recovery: the drop from peak HR around the
+/// end of the last hard effort to HR 60 seconds later.
+///
+/// The effort ends at the last power reading at or above `HRR_EFFORT_PCT` of
+/// the ride's best 1-minute power. Returns `None` without a 1-minute effort,
+/// without an HR reading within `max_gap_ms` of the 60s mark, or if HR did
+/// not drop.
+pub fn compute_hr_recovery(samples: &AnalysisSamples, max_gap_ms: u64) -> Option<u8> {
+    let power_1s = resample_power(&samples.power);
+    let best_minute = power_1s
+        .windows(60)
+        .map(|w| w.iter().sum::<u32>() as f64 / 60.0)
+        .fold(None, |best: Option<f64>, avg| Some(best.map_or(avg, |b| b.max(avg))))?;
+    if best_minute <= 0.0 {
+        return None;
+    }
+    let threshold = best_minute * HRR_EFFORT_PCT / 100.0;
+
+    let effort_end_ms = samples
+        .power
+        .iter()
+        .filter(|(_, watts)| *watts as f64 >= threshold)
+        .map(|(ms, _)| *ms)
+        .max()?;
+
+    let mut hr: Vec<(u64, u8)> = samples
+        .heart_rate
+        .iter()
+        .filter(|(_, bpm)| *bpm > 0)
+        .copied()
+        .collect();
+    hr.sort_by_key(|(ms, _)| *ms);
+
+    let window_ms = HRR_PEAK_WINDOW_SECS * 1000;
+    let peak = hr
+        .iter()
+        .filter(|(ms, _)| ms.abs_diff(effort_end_ms) <= window_ms)
+        .map(|(_, bpm)| *bpm)
+        .max()?;
+
+    // First HR at or after the 60s mark, as long as it isn't stale-gap late
+    let target_ms = effort_end_ms + HRR_RECOVERY_SECS * 1000;
+    let (_, recovered) = hr
+        .iter()
+        .find(|(ms, _)| *ms >= target_ms)
+        .filter(|(ms, _)| ms - target_ms <= max_gap_ms)?;
+
+    peak.checked_sub(*recovered).filter(|drop| *drop > 0)
+}
+
+/// Compute aerobic decoupling (Pw:Hr) as the percentage drop in the
+/// power-to-HR ratio from the first half of the ride to the second.
+///
+/// Power and HR are averaged into shared epoch-second buckets so sensors
+/// sampling at different rates contribute one paired sample per second.
+/// Returns `None` if either half has fewer than 30 paired seconds.
+fn compute_decoupling(samples: &AnalysisSamples) -> Option<f64> {
+    // sec -> (power_sum, power_count, hr_sum, hr_count)
+    let mut buckets: BTreeMap<u64, (u64, u32, u64, u32)> = BTreeMap::new();
+    for (epoch_ms, watts) in &samples.power {
+        let b = buckets.entry(epoch_ms / 1000).or_default();
+        b.0 += *watts as u64;
+        b.1 += 1;
+    }
+    for (epoch_ms, bpm) in &samples.heart_rate {
+        let b = buckets.entry(epoch_ms / 1000).or_default();
+        b.2 += *bpm as u64;
+        b.3 += 1;
+    }
+
+    let paired: Vec<(u64, f64, f64)> = buckets
+        .into_iter()
+        .filter(|(_, (_, pc, _, hc))| *pc > 0 && *hc > 0)
+        .map(|(sec, (ps, pc, hs, hc))| (sec, ps as f64 / pc as f64, hs as f64 / hc as f64))
+        .collect();
+
+    let first_sec = paired.first()?.0;
+    let last_sec = paired.last()?.0;
+    // Split at the elapsed-time midpoint, not the sample midpoint
+    let midpoint = first_sec as f64 + (last_sec - first_sec) as f64 / 2.0;
+    let split = paired.partition_point(|(sec, _, _)| (*sec as f64) < midpoint);
+    let (first, second) = paired.split_at(split);
+
+    if first.len() < MIN_DECOUPLING_SAMPLES_PER_HALF
+        || second.len() < MIN_DECOUPLING_SAMPLES_PER_HALF
+    {
+        return None;
+    }
+
+    let ratio = |half: &[(u64, f64, f64)]| -> Option<f64> {
+        let n = half.len() as f64;
+        let avg_power = half.iter().map(|(_, p, _)| p).sum::<f64>() / n;
+        let avg_hr = half.iter().map(|(_, _, h)| h).sum::<f64>() / n;
+        if avg_hr <= 0.0 {
+            None
+        } else {
+            Some(avg_power / avg_hr)
+        }
+    };
+
+    let first_ratio = ratio(first)?;
+    let second_ratio = ratio(second)?;
+    if first_ratio <= 0.0 {
+        return None;
+    }
+
+    Some((first_ratio - second_ratio) / first_ratio * 100.0)
+}
+
 /// Compute Physical Working Capacity at HR 150 and 170 by inverting the
 /// HR-power regression line: power = (target_HR - intercept) / slope.
 pub fn compute_pwc(timeseries: &[TimeseriesPoint]) -> Option<PwcMarkers> {
@@ -100,21 +658,96 @@ pub fn compute_power_curve_from_readings(readings: &[SensorReading]) -> Vec<Powe
     compute_power_curve(readings)
 }
 
+/// Align a session's power curve with the best of all other sessions on
+/// `POWER_CURVE_DURATIONS`. Durations neither curve covers are omitted.
+pub fn compare_power_curves(
+    session: &[PowerCurvePoint],
+    previous_best: &[PowerCurvePoint],
+) -> PowerCurveComparison {
+    let watts_at = |curve: &[PowerCurvePoint], d: u32| {
+        curve.iter().find(|p| p.duration_secs == d).map(|p| p.watts)
+    };
+    let points = POWER_CURVE_DURATIONS
+        .iter()
+        .filter_map(|&d| {
+            let session_watts = watts_at(session, d);
+            let previous = watts_at(previous_best, d);
+            if session_watts.is_none() && previous.is_none() {
+                return None;
+            }
+            Some(PowerCurveComparisonPoint {
+                duration_secs: d,
+                session_watts,
+                best_watts: session_watts.max(previous),
+                new_best: session_watts.is_some_and(|w| w > 0 && previous.map_or(true, |p| w > p)),
+            })
+        })
+        .collect();
+    PowerCurveComparison { points }
+}
+
+/// Durations where `session` beat an existing best in `previous_best`.
+/// Durations with no earlier record don't count, so a first ride isn't a
+/// wall of PBs.
+pub fn find_personal_bests(
+    session: &[PowerCurvePoint],
+    previous_best: &[PowerCurvePoint],
+) -> Vec<PersonalBest> {
+    session
+        .iter()
+        .filter_map(|point| {
+            let previous = previous_best
+                .iter()
+                .find(|p| p.duration_secs == point.duration_secs)?;
+            (point.watts > previous.watts).then_some(PersonalBest {
+                duration_secs: point.duration_secs,
+                previous_watts: previous.watts,
+                watts: point.watts,
+            })
+        })
+        .collect()
+}
+
+/// Estimate FTP as 95% of the best 20-minute power on a power curve.
+/// Returns `None` when the curve has no 20-minute entry (ride too short).
+pub fn estimate_ftp(curve: &[PowerCurvePoint]) -> Option<u16> {
+    curve
+        .iter()
+        .find(|p| p.duration_secs == 1200)
+        .filter(|p| p.watts > 0)
+        .map(|p| (p.watts as f32 * 0.95).round() as u16)
+}
+
+/// Fill `pct_ftp` on each point against `ftp`. Leaves it unset when FTP is zero.
+pub fn annotate_ftp_pct(curve: &mut [PowerCurvePoint], ftp: u16) {
+    if ftp == 0 {
+        return;
+    }
+    for point in curve {
+        point.pct_ftp = Some(point.watts as f32 / ftp as f32 * 100.0);
+    }
+}
+
 /// Build a 1-second timeseries from raw sensor readings.
 /// Public wrapper for use by zone control history estimation.
 pub fn build_timeseries_from_readings(
     readings: &[SensorReading],
     duration_secs: u64,
 ) -> Vec<TimeseriesPoint> {
-    build_timeseries(readings, duration_secs)
+    build_timeseries(&AnalysisSamples::from_readings(readings), duration_secs, None)
 }
 
-fn build_timeseries(readings: &[SensorReading], duration_secs: u64) -> Vec<TimeseriesPoint> {
-    if readings.is_empty() {
+/// Build a 1-second timeseries, optionally applying a centered moving average
+/// of `smoothing_secs` to each channel. Windows never average across empty
+/// seconds: they shrink at gaps, and empty seconds stay empty.
+fn build_timeseries(
+    samples: &AnalysisSamples,
+    duration_secs: u64,
+    smoothing_secs: Option<u32>,
+) -> Vec<TimeseriesPoint> {
+    let Some(t0) = samples.t0 else {
         return Vec::new();
-    }
-
-    let t0 = readings.iter().map(|r| r.epoch_ms()).min().unwrap();
+    };
     let num_slots = duration_secs as usize;
 
     // Each slot holds the last-seen value for each channel.
@@ -134,19 +767,54 @@ fn build_timeseries(readings: &[SensorReading], duration_secs: u64) -> Vec<Times
         })
         .collect();
 
-    for reading in readings {
-        let elapsed_ms = reading.epoch_ms().saturating_sub(t0);
-        let sec = (elapsed_ms / 1000) as usize;
-        if sec >= num_slots {
-            continue;
+    let slot_index = |epoch_ms: u64| {
+        let sec = (epoch_ms.saturating_sub(t0) / 1000) as usize;
+        (sec < num_slots).then_some(sec)
+    };
+    for &(ms, watts) in &samples.power {
+        if let Some(sec) = slot_index(ms) {
+            slots[sec].power = Some(watts);
         }
-        let slot = &mut slots[sec];
-        match reading {
-            SensorReading::Power { watts, .. } => slot.power = Some(*watts),
-            SensorReading::HeartRate { bpm, .. } => slot.heart_rate = Some(*bpm),
-            SensorReading::Cadence { rpm, .. } => slot.cadence = Some(*rpm),
-            SensorReading::Speed { kmh, .. } => slot.speed = Some(*kmh),
-            SensorReading::TrainerCommand { .. } => {}
+    }
+    for &(ms, bpm) in &samples.heart_rate {
+        if let Some(sec) = slot_index(ms) {
+            slots[sec].heart_rate = Some(bpm);
+        }
+    }
+    for &(ms, rpm) in &samples.cadence {
+        if let Some(sec) = slot_index(ms) {
+            slots[sec].cadence = Some(rpm);
+        }
+    }
+    for &(ms, kmh) in &samples.speed {
+        if let Some(sec) = slot_index(ms) {
+            slots[sec].speed = Some(kmh);
+        }
+    }
+
+    if let Some(window) = smoothing_secs.filter(|&w| w > 1) {
+        let window = window as usize;
+        let power = centered_moving_average(
+            &slots.iter().map(|s| s.power.map(|v| v as f64)).collect::<Vec<_>>(),
+            window,
+        );
+        let heart_rate = centered_moving_average(
+            &slots.iter().map(|s| s.heart_rate.map(|v| v as f64)).collect::<Vec<_>>(),
+            window,
+        );
+        let cadence = centered_moving_average(
+            &slots.iter().map(|s| s.cadence.map(|v| v as f64)).collect::<Vec<_>>(),
+            window,
+        );
+        let speed = centered_moving_average(
+            &slots.iter().map(|s| s.speed.map(|v| v as f64)).collect::<Vec<_>>(),
+            window,
+        );
+        for (i, slot) in slots.iter_mut().enumerate() {
+            slot.power = power[i].map(|v| v.round() as u16);
+            slot.heart_rate = heart_rate[i].map(|v| v.round() as u8);
+            slot.cadence = cadence[i].map(|v| v as f32);
+            slot.speed = speed[i].map(|v| v as f32);
         }
     }
 
@@ -170,23 +838,46 @@ fn build_timeseries(readings: &[SensorReading], duration_secs: u64) -> Vec<Times
         .collect()
 }
 
-fn compute_power_curve(readings: &[SensorReading]) -> Vec<PowerCurvePoint> {
-    // Extract power readings sorted by time.
-    let mut power_data: Vec<(u64, u16)> = readings
-        .iter()
-        .filter_map(|r| match r {
-            SensorReading::Power { watts, epoch_ms, .. } => Some((*epoch_ms, *watts)),
-            _ => None,
+/// Centered moving average over per-second values. For even windows the
+/// extra sample is taken from the past (e.g. 10s → 5 before, 4 after).
+/// The window stops at the first `None` on either side instead of spanning it.
+fn centered_moving_average(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    let before = window / 2;
+    let after = window.saturating_sub(1) - before;
+
+    (0..values.len())
+        .map(|i| {
+            values[i]?;
+            let mut lo = i;
+            while lo > 0 && i - lo < before && values[lo - 1].is_some() {
+                lo -= 1;
+            }
+            let mut hi = i;
+            while hi + 1 < values.len() && hi - i < after && values[hi + 1].is_some() {
+                hi += 1;
+            }
+            let sum: f64 = values[lo..=hi].iter().flatten().sum();
+            Some(sum / (hi - lo + 1) as f64)
         })
-        .collect();
+        .collect()
+}
 
+/// Resample power readings to a 1-second array: average where data exists,
+/// hold-last-value otherwise. Leading empty seconds are skipped, so index 0
+/// is the first second with a power reading. Empty if there is no power data.
+pub(crate) fn resample_power_1s(readings: &[SensorReading]) -> Vec<u32> {
+    resample_power(&AnalysisSamples::from_readings(readings).power)
+}
+
+/// `resample_power_1s` over `(epoch_ms, watts)` samples.
+fn resample_power(power: &[(u64, u16)]) -> Vec<u32> {
+    let mut power_data = power.to_vec();
     if power_data.is_empty() {
         return Vec::new();
     }
 
     power_data.sort_by_key(|(ms, _)| *ms);
 
-    // Resample to 1-second array with hold-last-value.
     let min_sec = power_data[0].0 / 1000;
     let max_sec = power_data.last().unwrap().0 / 1000;
     let len = (max_sec - min_sec + 1) as usize;
@@ -217,41 +908,389 @@ fn compute_power_curve(readings: &[SensorReading]) -> Vec<PowerCurvePoint> {
         arr[i] = last_val;
     }
 
-    // Sliding window for each target duration.
-    let mut result = Vec::new();
-    for &d in POWER_CURVE_DURATIONS {
-        let d_usize = d as usize;
-        if d_usize > arr.len() {
+    arr
+}
+
+/// Compute W′ balance using the Skiba differential model over the 1-second
+/// power array. Returns `(elapsed_secs, wbal_joules)` pairs where elapsed is
+/// measured from the first power reading.
+///
+/// Above CP, W′ depletes by `(P - CP)` joules per second. At or below CP it
+/// recharges by `(CP - P) * (W′ - W′bal) / W′`, so recovery slows as the
+/// tank refills. The balance is capped at `w_prime` but may go negative
+/// when the athlete exceeds the modelled capacity.
+pub fn compute_wbal(samples: &AnalysisSamples, cp: u16, w_prime: u32) -> Vec<(f64, f64)> {
+    let arr = resample_power(&samples.power);
+    if arr.is_empty() || w_prime == 0 {
+        return Vec::new();
+    }
+
+    let cp = cp as f64;
+    let w_prime = w_prime as f64;
+    let mut balance = w_prime;
+
+    arr.iter()
+        .enumerate()
+        .map(|(i, &watts)| {
+            let p = watts as f64;
+            if p > cp {
+                balance -= p - cp;
+            } else {
+                balance += (cp - p) * (w_prime - balance) / w_prime;
+            }
+            balance = balance.min(w_prime);
+            (i as f64, balance)
+        })
+        .collect()
+}
+
+/// Circumferential pedal velocity (m/s) for a cadence and crank length.
+fn pedal_velocity(rpm: f64, crank_length_m: f64) -> f64 {
+    rpm * crank_length_m * 2.0 * std::f64::consts::PI / 60.0
+}
+
+/// Quadrant analysis: pair per-second power and cadence into circumferential
+/// pedal velocity (CPV) and average effective pedal force (AEPF = P / CPV).
+/// Seconds without both a power and a non-zero cadence reading are skipped.
+/// Quadrants split at FTP pedalled at `QUADRANT_REFERENCE_RPM`.
+pub fn compute_quadrant_analysis(
+    samples: &AnalysisSamples,
+    crank_length_m: f64,
+    ftp: u16,
+) -> Vec<QuadrantPoint> {
+    if crank_length_m <= 0.0 {
+        return Vec::new();
+    }
+    // sec -> (power_sum, power_count, cadence_sum, cadence_count)
+    let mut buckets: BTreeMap<u64, (f64, u32, f64, u32)> = BTreeMap::new();
+    for (epoch_ms, watts) in &samples.power {
+        let b = buckets.entry(epoch_ms / 1000).or_default();
+        b.0 += *watts as f64;
+        b.1 += 1;
+    }
+    for (epoch_ms, rpm) in &samples.cadence {
+        let b = buckets.entry(epoch_ms / 1000).or_default();
+        b.2 += *rpm as f64;
+        b.3 += 1;
+    }
+    let cpv_split = pedal_velocity(QUADRANT_REFERENCE_RPM, crank_length_m);
+    let aepf_split = ftp.max(1) as f64 / cpv_split;
+    let mut first_sec = None;
+    buckets
+        .into_iter()
+        .filter(|(_, (_, pc, cs, cc))| *pc > 0 && *cc > 0 && *cs > 0.0)
+        .map(|(sec, (ps, pc, cs, cc))| {
+            let start = *first_sec.get_or_insert(sec);
+            let cpv = pedal_velocity(cs / cc as f64, crank_length_m);
+            let aepf = (ps / pc as f64) / cpv;
+            let quadrant = match (aepf >= aepf_split, cpv >= cpv_split) {
+                (true, true) => 1,
+                (true, false) => 2,
+                (false, false) => 3,
+                (false, true) => 4,
+            };
+            QuadrantPoint {
+                elapsed_secs: (sec - start) as u32,
+                cpv,
+                aepf,
+                quadrant,
+            }
+        })
+        .collect()
+}
+
+/// Extract the SmO2/THb samples in recording order, timed from the earliest
+/// reading so they line up with `build_timeseries`.
+fn build_muscle_oxygen_series(samples: &AnalysisSamples) -> Vec<MuscleOxygenPoint> {
+    let Some(t0) = samples.t0 else {
+        return Vec::new();
+    };
+    samples
+        .muscle_oxygen
+        .iter()
+        .map(|&(epoch_ms, smo2, thb)| MuscleOxygenPoint {
+            elapsed_secs: epoch_ms.saturating_sub(t0) as f64 / 1000.0,
+            smo2,
+            thb,
+        })
+        .collect()
+}
+
+/// Total climbing (m) from simulation-mode grade commands. Integrates
+/// speed × sin(atan(grade)) between consecutive speed readings the same way
+/// distance is integrated (previous speed held over the interval), with gaps
+/// capped at `max_gap_ms`. Only uphill grades add to the total.
+/// Returns `None` when the session has no grade readings.
+pub fn compute_elevation_gain(readings: &[SensorReading], max_gap_ms: u64) -> Option<f32> {
+    let mut events: Vec<&SensorReading> = readings
+        .iter()
+        .filter(|r| matches!(r, SensorReading::Speed { .. } | SensorReading::Grade { .. }))
+        .collect();
+    if !events.iter().any(|r| matches!(r, SensorReading::Grade { .. })) {
+        return None;
+    }
+    events.sort_by_key(|r| r.epoch_ms());
+
+    let mut grade_pct = 0.0_f64;
+    let mut last_speed: Option<(u64, f64)> = None;
+    let mut ascent_m = 0.0_f64;
+    for reading in events {
+        match reading {
+            SensorReading::Grade { percent, .. } => grade_pct = *percent as f64,
+            SensorReading::Speed { kmh, epoch_ms, .. } => {
+                if let Some((prev_epoch, prev_mps)) = last_speed {
+                    let delta_ms = epoch_ms.saturating_sub(prev_epoch).min(max_gap_ms);
+                    if grade_pct > 0.0 {
+                        let climb_ratio = (grade_pct / 100.0).atan().sin();
+                        ascent_m += prev_mps * (delta_ms as f64 / 1000.0) * climb_ratio;
+                    }
+                }
+                last_speed = Some((*epoch_ms, *kmh as f64 / 3.6));
+            }
+            _ => {}
+        }
+    }
+    Some(ascent_m as f32)
+}
+
+/// Mean right-pedal share (%) over power readings that carry a balance.
+/// `None` when no reading does, e.g. trainers and single-sided meters.
+pub fn compute_avg_pedal_balance(readings: &[SensorReading]) -> Option<u8> {
+    let (sum, count) = readings
+        .iter()
+        .filter_map(|r| match r {
+            SensorReading::Power {
+                pedal_balance: Some(pct),
+                ..
+            } => Some(*pct as u64),
+            _ => None,
+        })
+        .fold((0u64, 0u64), |(sum, count), pct| (sum + pct, count + 1));
+    (count > 0).then(|| (sum as f64 / count as f64).round() as u8)
+}
+
+/// Compare two power sources recorded in the same session. The primary meter
+/// (`Power` readings) is the reference when present; otherwise the source with
+/// the most samples is. Readings are averaged per epoch-second and only seconds
+/// where both sources report, with non-zero reference power, are paired.
+/// Returns `None` with fewer than two sources or no overlapping seconds.
+pub fn compute_power_source_comparison(
+    readings: &[SensorReading],
+) -> Option<PowerSourceComparison> {
+    // sec -> (watts_sum, count)
+    type PerSecond = BTreeMap<u64, (f64, u32)>;
+    // device_id -> (is_primary, per-second power)
+    let mut sources: BTreeMap<&str, (bool, PerSecond)> = BTreeMap::new();
+    for reading in readings {
+        let (watts, epoch_ms, device_id, is_primary) = match reading {
+            SensorReading::Power {
+                watts,
+                epoch_ms,
+                device_id,
+                ..
+            } => (*watts, *epoch_ms, device_id, true),
+            SensorReading::SecondaryPower {
+                watts,
+                epoch_ms,
+                device_id,
+            } => (*watts, *epoch_ms, device_id, false),
+            _ => continue,
+        };
+        let source = sources.entry(device_id.as_str()).or_default();
+        source.0 |= is_primary;
+        let bucket = source.1.entry(epoch_ms / 1000).or_default();
+        bucket.0 += watts as f64;
+        bucket.1 += 1;
+    }
+    if sources.len() < 2 {
+        return None;
+    }
+    let mut ranked: Vec<_> = sources.into_iter().collect();
+    ranked.sort_by_key(|(_, (is_primary, secs))| (!*is_primary, std::cmp::Reverse(secs.len())));
+    let (reference_id, (_, reference)) = &ranked[0];
+    let (compared_id, (_, compared)) = &ranked[1];
+
+    let mut paired_secs = 0u32;
+    let mut delta_sum = 0.0;
+    let mut pct_sum = 0.0;
+    let mut max_pct_diff: f64 = 0.0;
+    for (sec, (ref_sum, ref_count)) in reference {
+        let Some((cmp_sum, cmp_count)) = compared.get(sec) else {
+            continue;
+        };
+        let ref_watts = ref_sum / *ref_count as f64;
+        if ref_watts <= 0.0 {
             continue;
         }
+        let delta = cmp_sum / *cmp_count as f64 - ref_watts;
+        let pct = delta.abs() / ref_watts * 100.0;
+        paired_secs += 1;
+        delta_sum += delta;
+        pct_sum += pct;
+        max_pct_diff = max_pct_diff.max(pct);
+    }
+    if paired_secs == 0 {
+        return None;
+    }
+    Some(PowerSourceComparison {
+        reference_device_id: reference_id.to_string(),
+        compared_device_id: compared_id.to_string(),
+        paired_secs,
+        mean_delta_watts: delta_sum / paired_secs as f64,
+        mean_pct_diff: pct_sum / paired_secs as f64,
+        max_pct_diff,
+    })
+}
 
-        let mut window_sum: u64 = arr[..d_usize].iter().map(|&v| v as u64).sum();
-        let mut max_sum = window_sum;
+/// Detect work intervals: stretches of power ≥90% FTP lasting at least 20s.
+/// Dips below threshold of up to 5s are absorbed into the surrounding
+/// interval so a missed pedal stroke doesn't split one effort into two.
+/// Trailing dip seconds are trimmed from the reported interval.
+pub fn detect_intervals(readings: &[SensorReading], ftp: u16) -> Vec<Interval> {
+    let arr = resample_power_1s(readings);
+    let threshold = ftp.max(1) as f64 * INTERVAL_THRESHOLD_PCT / 100.0;
+
+    let mut intervals = Vec::new();
+    let mut push_interval = |start: usize, end: usize| {
+        let duration = (end - start) as u32;
+        if duration < INTERVAL_MIN_SECS {
+            return;
+        }
+        let sum: u64 = arr[start..end].iter().map(|&w| w as u64).sum();
+        intervals.push(Interval {
+            start_secs: start as u32,
+            end_secs: end as u32,
+            avg_power: (sum as f64 / duration as f64).round() as u16,
+            duration_secs: duration,
+        });
+    };
 
-        for i in 1..=(arr.len() - d_usize) {
-            window_sum = window_sum - arr[i - 1] as u64 + arr[i + d_usize - 1] as u64;
-            if window_sum > max_sum {
-                max_sum = window_sum;
+    // (start, end of last above-threshold second + 1)
+    let mut current: Option<(usize, usize)> = None;
+    for (i, &watts) in arr.iter().enumerate() {
+        let above = watts as f64 >= threshold;
+        current = match current {
+            None if above => Some((i, i + 1)),
+            None => None,
+            Some((start, _)) if above => Some((start, i + 1)),
+            Some((start, last_end)) => {
+                if i + 1 - last_end > INTERVAL_MAX_DIP_SECS {
+                    push_interval(start, last_end);
+                    None
+                } else {
+                    Some((start, last_end))
+                }
             }
+        };
+    }
+    if let Some((start, last_end)) = current {
+        push_interval(start, last_end);
+    }
+
+    intervals
+}
+
+/// Guess an `activity_type` for a ride from its summary and sensor log, used
+/// to pre-fill the type on save. Any simulated grade makes it "outdoor_sim";
+/// otherwise the call is made on intensity factor, variability index and the
+/// number of detected work intervals. Rides without power are "other".
+pub fn classify_activity(summary: &SessionSummary, readings: &[SensorReading]) -> &'static str {
+    if readings
+        .iter()
+        .any(|r| matches!(r, SensorReading::Grade { .. }))
+    {
+        return "outdoor_sim";
+    }
+    let Some(intensity) = summary.intensity_factor else {
+        return "other";
+    };
+    let vi = summary.variability_index.unwrap_or(1.0);
+    let duration = summary.duration_secs;
+
+    if intensity >= CLASSIFY_HARD_MIN_IF && duration <= CLASSIFY_RACE_MAX_SECS {
+        return if duration <= CLASSIFY_TEST_MAX_SECS {
+            "test"
+        } else {
+            "race"
+        };
+    }
+    if vi >= CLASSIFY_INTERVALS_MIN_VI {
+        let ftp = summary.ftp.unwrap_or(0);
+        if ftp > 0 && detect_intervals(readings, ftp).len() >= CLASSIFY_INTERVALS_MIN_COUNT {
+            return "intervals";
         }
+    }
+    if intensity < CLASSIFY_ENDURANCE_MAX_IF
+        && vi < CLASSIFY_ENDURANCE_MAX_VI
+        && duration >= CLASSIFY_ENDURANCE_MIN_SECS
+    {
+        return "endurance";
+    }
+    "free_ride"
+}
 
-        result.push(PowerCurvePoint {
-            duration_secs: d,
-            watts: (max_sum as f64 / d as f64).round() as u16,
-        });
+/// Prefix sums of 1-second power: `prefix[i]` is the sum of `arr[..i]`, so any
+/// window sum is a single subtraction.
+fn power_prefix_sums(arr: &[u32]) -> Vec<u64> {
+    let mut prefix = Vec::with_capacity(arr.len() + 1);
+    prefix.push(0u64);
+    let mut running = 0u64;
+    for &v in arr {
+        running += v as u64;
+        prefix.push(running);
+    }
+    prefix
+}
+
+/// Best average power over `duration_secs` from `power_prefix_sums` output.
+/// `None` when the duration is zero or longer than the data.
+fn best_window_watts(prefix: &[u64], duration_secs: u32) -> Option<u16> {
+    let d = duration_secs as usize;
+    if d == 0 || d >= prefix.len() {
+        return None;
     }
+    let max_sum = prefix
+        .iter()
+        .zip(&prefix[d..])
+        .map(|(start, end)| end - start)
+        .max()?;
+    Some((max_sum as f64 / d as f64).round() as u16)
+}
+
+/// Best average power over any `duration_secs`, not just `POWER_CURVE_DURATIONS`.
+/// `None` when the ride has less power data than the duration.
+pub fn best_power_for(readings: &[SensorReading], duration_secs: u32) -> Option<u16> {
+    best_window_watts(&power_prefix_sums(&resample_power_1s(readings)), duration_secs)
+}
+
+fn compute_power_curve(readings: &[SensorReading]) -> Vec<PowerCurvePoint> {
+    power_curve_from_1s(&resample_power_1s(readings))
+}
 
-    result
+fn power_curve_from_1s(arr: &[u32]) -> Vec<PowerCurvePoint> {
+    let prefix = power_prefix_sums(arr);
+    POWER_CURVE_DURATIONS
+        .iter()
+        .filter_map(|&d| {
+            best_window_watts(&prefix, d).map(|watts| PowerCurvePoint {
+                duration_secs: d,
+                watts,
+                pct_ftp: None,
+            })
+        })
+        .collect()
 }
 
-fn classify_power_zone(watts: u16, ftp: u16, zones: &[u16; 6]) -> u8 {
+/// `zones` are upper boundaries (% FTP); power above the last one is the top
+/// zone, so N boundaries give N + 1 zones.
+fn classify_power_zone(watts: u16, ftp: u16, zones: &[u16]) -> u8 {
     let pct = (watts as f32 / ftp.max(1) as f32) * 100.0;
     for (i, &upper) in zones.iter().enumerate() {
         if pct <= upper as f32 {
             return (i + 1) as u8;
         }
     }
-    7
+    zones.len() as u8 + 1
 }
 
 fn classify_hr_zone(bpm: u8, zones: &[u8; 5]) -> u8 {
@@ -263,27 +1302,38 @@ fn classify_hr_zone(bpm: u8, zones: &[u8; 5]) -> u8 {
     5
 }
 
+/// Cadence bands are lower-inclusive: with edges [60, 75, 90, 105],
+/// 59.9 rpm is zone 1 and 60.0 rpm is zone 2.
+fn classify_cadence_zone(rpm: f32, zones: &[u16; 4]) -> u8 {
+    for (i, &edge) in zones.iter().enumerate() {
+        if rpm < edge as f32 {
+            return (i + 1) as u8;
+        }
+    }
+    5
+}
+
 fn compute_zone_distribution(
-    readings: &[SensorReading],
+    samples: &AnalysisSamples,
     ftp: u16,
-    power_zones: &[u16; 6],
+    power_zones: &[u16],
     hr_zones: &[u8; 5],
+    max_gap_ms: u64,
 ) -> (Vec<ZoneBucket>, Vec<ZoneBucket>) {
-    // Power zones (7 zones)
-    let mut power_data: Vec<(u64, u16)> = readings
-        .iter()
-        .filter_map(|r| match r {
-            SensorReading::Power { watts, epoch_ms, .. } => Some((*epoch_ms, *watts)),
-            _ => None,
-        })
-        .collect();
+    // Power zones: one more than there are boundaries
+    let zone_count = power_zones.len() + 1;
+    let mut power_data = samples.power.clone();
     power_data.sort_by_key(|(ms, _)| *ms);
 
-    let mut power_zone_time = [0.0f64; 7];
+    let mut power_zone_time = vec![0.0f64; zone_count];
+    // Watt-seconds per zone, for the time-weighted zone average
+    let mut power_zone_sum = vec![0.0f64; zone_count];
     for pair in power_data.windows(2) {
-        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(MAX_READING_GAP_MS);
+        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(max_gap_ms);
         let zone = classify_power_zone(pair[0].1, ftp, power_zones);
-        power_zone_time[(zone - 1) as usize] += delta_ms as f64 / 1000.0;
+        let secs = delta_ms as f64 / 1000.0;
+        power_zone_time[(zone - 1) as usize] += secs;
+        power_zone_sum[(zone - 1) as usize] += pair[0].1 as f64 * secs;
     }
 
     let power_total: f64 = power_zone_time.iter().sum();
@@ -298,24 +1348,22 @@ fn compute_zone_distribution(
             } else {
                 0.0
             },
+            avg_value: (secs > 0.0).then(|| power_zone_sum[i] / secs),
         })
         .collect();
 
     // HR zones (5 zones)
-    let mut hr_data: Vec<(u64, u8)> = readings
-        .iter()
-        .filter_map(|r| match r {
-            SensorReading::HeartRate { bpm, epoch_ms, .. } => Some((*epoch_ms, *bpm)),
-            _ => None,
-        })
-        .collect();
+    let mut hr_data = samples.heart_rate.clone();
     hr_data.sort_by_key(|(ms, _)| *ms);
 
     let mut hr_zone_time = [0.0f64; 5];
+    let mut hr_zone_sum = [0.0f64; 5];
     for pair in hr_data.windows(2) {
-        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(MAX_READING_GAP_MS);
+        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(max_gap_ms);
         let zone = classify_hr_zone(pair[0].1, hr_zones);
-        hr_zone_time[(zone - 1) as usize] += delta_ms as f64 / 1000.0;
+        let secs = delta_ms as f64 / 1000.0;
+        hr_zone_time[(zone - 1) as usize] += secs;
+        hr_zone_sum[(zone - 1) as usize] += pair[0].1 as f64 * secs;
     }
 
     let hr_total: f64 = hr_zone_time.iter().sum();
@@ -330,12 +1378,92 @@ fn compute_zone_distribution(
             } else {
                 0.0
             },
+            avg_value: (secs > 0.0).then(|| hr_zone_sum[i] / secs),
         })
         .collect();
 
     (power_zone_dist, hr_zone_dist)
 }
 
+fn compute_cadence_zone_distribution(
+    samples: &AnalysisSamples,
+    cadence_zones: &[u16; 4],
+    max_gap_ms: u64,
+) -> Vec<ZoneBucket> {
+    let mut cadence_data = samples.cadence.clone();
+    cadence_data.sort_by_key(|(ms, _)| *ms);
+
+    let mut cadence_zone_time = [0.0f64; 5];
+    for pair in cadence_data.windows(2) {
+        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(max_gap_ms);
+        let zone = classify_cadence_zone(pair[0].1, cadence_zones);
+        cadence_zone_time[(zone - 1) as usize] += delta_ms as f64 / 1000.0;
+    }
+
+    let cadence_total: f64 = cadence_zone_time.iter().sum();
+    cadence_zone_time
+        .iter()
+        .enumerate()
+        .map(|(i, &secs)| ZoneBucket {
+            zone: (i + 1) as u8,
+            duration_secs: secs,
+            percentage: if cadence_total > 0.0 {
+                secs / cadence_total * 100.0
+            } else {
+                0.0
+            },
+            avg_value: None,
+        })
+        .collect()
+}
+
+/// Zone for a running pace. Edges are seconds per km, slowest first; a pace
+/// equal to an edge belongs to the faster zone.
+fn classify_pace_zone(secs_per_km: f32, zones: &[u16; 4]) -> u8 {
+    for (i, &edge) in zones.iter().enumerate() {
+        if secs_per_km > edge as f32 {
+            return (i + 1) as u8;
+        }
+    }
+    5
+}
+
+fn compute_pace_zone_distribution(
+    samples: &AnalysisSamples,
+    pace_zones: &[u16; 4],
+    max_gap_ms: u64,
+) -> Vec<ZoneBucket> {
+    let mut speed_data = samples.speed.clone();
+    speed_data.sort_by_key(|(ms, _)| *ms);
+
+    let mut pace_zone_time = [0.0f64; 5];
+    for pair in speed_data.windows(2) {
+        let kmh = pair[0].1;
+        if kmh < MIN_RUN_SPEED_KMH {
+            continue;
+        }
+        let delta_ms = pair[1].0.saturating_sub(pair[0].0).min(max_gap_ms);
+        let zone = classify_pace_zone(3600.0 / kmh, pace_zones);
+        pace_zone_time[(zone - 1) as usize] += delta_ms as f64 / 1000.0;
+    }
+
+    let pace_total: f64 = pace_zone_time.iter().sum();
+    pace_zone_time
+        .iter()
+        .enumerate()
+        .map(|(i, &secs)| ZoneBucket {
+            zone: (i + 1) as u8,
+            duration_secs: secs,
+            percentage: if pace_total > 0.0 {
+                secs / pace_total * 100.0
+            } else {
+                0.0
+            },
+            avg_value: None,
+        })
+        .collect()
+}
+
 /// Linear regression model: HR = slope * power + intercept.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HrPowerModel {
@@ -411,6 +1539,9 @@ pub fn compute_hr_power_regression(timeseries: &[TimeseriesPoint]) -> Option<HrP
 mod tests {
     use super::*;
     use chrono::Utc;
+    use crate::session::types::PowerZoneModel;
+
+    const MAX_READING_GAP_MS: u64 = crate::config::DEFAULT_MAX_GAP_SECS * 1000;
 
     fn power_reading(watts: u16, epoch_ms: u64) -> SensorReading {
         SensorReading::Power {
@@ -475,82 +1606,571 @@ mod tests {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: None,
+            calories: None,
+            avg_cadence_active: None,
+            avg_temp_c: None,
+            total_ascent_m: None,
+            is_favorite: false,
+            xpower: None,
+            data_quality_score: None,
+            avg_pedal_balance: None,
         }
     }
 
-    fn assert_approx(actual: f64, expected: f64, epsilon: f64, msg: &str) {
-        assert!(
-            (actual - expected).abs() <= epsilon,
-            "{msg}: expected {expected} ± {epsilon}, got {actual}"
-        );
+    fn samples(readings: &[SensorReading]) -> AnalysisSamples {
+        AnalysisSamples::from_readings(readings)
+    }
+
+    fn assert_approx(actual: f64, expected: f64, epsilon: f64, msg: &str) {
+        assert!(
+            (actual - expected).abs() <= epsilon,
+            "{msg}: expected {expected} ± {epsilon}, got {actual}"
+        );
+    }
+
+    // --- Power curve tests ---
+
+    #[test]
+    fn power_curve_constant_power() {
+        // 60 readings at 200W, 1Hz starting at t=0
+        let readings: Vec<SensorReading> =
+            (0..60).map(|i| power_reading(200, i * 1000)).collect();
+
+        let curve = compute_power_curve(&readings);
+
+        // All durations ≤ 60s should be 200W
+        for pt in &curve {
+            assert!(pt.duration_secs <= 60, "should not exceed session length");
+            assert_eq!(pt.watts, 200, "constant 200W for {}s duration", pt.duration_secs);
+        }
+        // Should have entries for 1,2,3,5,10,15,20,30,45,60
+        assert_eq!(curve.len(), 10);
+    }
+
+    #[test]
+    fn power_curve_best_interval() {
+        // 10s @ 100W, then 10s @ 300W (t=0..9s @ 100W, t=10..19s @ 300W)
+        let mut readings: Vec<SensorReading> = Vec::new();
+        for i in 0..10 {
+            readings.push(power_reading(100, i * 1000));
+        }
+        for i in 10..20 {
+            readings.push(power_reading(300, i * 1000));
+        }
+
+        let curve = compute_power_curve(&readings);
+
+        // 1s best = 300W (best single second in the 300W block)
+        let p1 = curve.iter().find(|p| p.duration_secs == 1).unwrap();
+        assert_eq!(p1.watts, 300);
+
+        // 10s best = 300W (the 10s block of 300W)
+        let p10 = curve.iter().find(|p| p.duration_secs == 10).unwrap();
+        assert_eq!(p10.watts, 300);
+
+        // 20s best = average of 100*10 + 300*10 = 4000/20 = 200W
+        let p20 = curve.iter().find(|p| p.duration_secs == 20).unwrap();
+        assert_eq!(p20.watts, 200);
+    }
+
+    #[test]
+    fn power_curve_six_hour_ride_matches_naive_windows() {
+        // 6h at a varying endurance pace with a 20-minute 300W block at 3h
+        let secs = 6 * 3600u64;
+        let readings: Vec<SensorReading> = (0..secs)
+            .map(|i| {
+                let watts = if (10_800..12_000).contains(&i) {
+                    300
+                } else {
+                    140 + (i % 23) as u16
+                };
+                power_reading(watts, i * 1000)
+            })
+            .collect();
+
+        let curve = compute_power_curve(&readings);
+        assert_eq!(curve.len(), POWER_CURVE_DURATIONS.len());
+        assert_eq!(curve.iter().find(|p| p.duration_secs == 1200).unwrap().watts, 300);
+
+        // Reference: classic rolling-sum scan per duration
+        let arr = resample_power_1s(&readings);
+        for pt in &curve {
+            let d = pt.duration_secs as usize;
+            let mut window: u64 = arr[..d].iter().map(|&v| v as u64).sum();
+            let mut best = window;
+            for i in d..arr.len() {
+                window = window + arr[i] as u64 - arr[i - d] as u64;
+                best = best.max(window);
+            }
+            assert_eq!(pt.watts, (best as f64 / d as f64).round() as u16, "{}s", d);
+        }
+    }
+
+    #[test]
+    fn best_power_for_arbitrary_durations() {
+        // 10 min at 200W with a 7s 900W sprint at 120s and 90s at 400W from 300s
+        let readings: Vec<SensorReading> = (0..600u64)
+            .map(|i| {
+                let watts = match i {
+                    120..=126 => 900,
+                    300..=389 => 400,
+                    _ => 200,
+                };
+                power_reading(watts, i * 1000)
+            })
+            .collect();
+
+        assert_eq!(best_power_for(&readings, 7), Some(900));
+        assert_eq!(best_power_for(&readings, 90), Some(400));
+        // 8s either side of the sprint picks up one 200W second
+        assert_eq!(best_power_for(&readings, 8), Some(813));
+        assert_eq!(best_power_for(&readings, 600), Some(238));
+        assert_eq!(best_power_for(&readings, 601), None);
+        assert_eq!(best_power_for(&readings, 0), None);
+    }
+
+    #[test]
+    fn compare_power_curves_marks_new_bests() {
+        let session = vec![
+            PowerCurvePoint { duration_secs: 5, watts: 620, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 380, pct_ftp: None },
+        ];
+        let previous = vec![
+            PowerCurvePoint { duration_secs: 5, watts: 590, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 410, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 300, watts: 330, pct_ftp: None },
+        ];
+        let cmp = compare_power_curves(&session, &previous);
+        assert_eq!(cmp.points.len(), 3);
+
+        let p5 = &cmp.points[0];
+        assert_eq!((p5.duration_secs, p5.session_watts, p5.best_watts), (5, Some(620), Some(620)));
+        assert!(p5.new_best);
+
+        let p60 = &cmp.points[1];
+        assert_eq!((p60.session_watts, p60.best_watts), (Some(380), Some(410)));
+        assert!(!p60.new_best);
+
+        // Longer than the session: only the stored best is shown
+        let p300 = &cmp.points[2];
+        assert_eq!((p300.session_watts, p300.best_watts), (None, Some(330)));
+        assert!(!p300.new_best);
+    }
+
+    #[test]
+    fn power_curve_capped_at_session_length() {
+        // 30 readings → no entry with duration > 30
+        let readings: Vec<SensorReading> =
+            (0..30).map(|i| power_reading(150, i * 1000)).collect();
+
+        let curve = compute_power_curve(&readings);
+        for pt in &curve {
+            assert!(pt.duration_secs <= 30);
+        }
+    }
+
+    #[test]
+    fn estimate_ftp_from_steady_20_min_effort() {
+        let readings: Vec<SensorReading> =
+            (0..1200).map(|i| power_reading(210, i * 1000)).collect();
+        let curve = compute_power_curve(&readings);
+        // 95% of 210W = 199.5W
+        assert_eq!(estimate_ftp(&curve), Some(200));
+    }
+
+    #[test]
+    fn estimate_ftp_needs_20_minutes() {
+        let readings: Vec<SensorReading> =
+            (0..1199).map(|i| power_reading(300, i * 1000)).collect();
+        assert_eq!(estimate_ftp(&compute_power_curve(&readings)), None);
+    }
+
+    #[test]
+    fn power_curve_pct_of_ftp() {
+        let mut curve = vec![
+            PowerCurvePoint { duration_secs: 60, watts: 300, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 1200, watts: 200, pct_ftp: None },
+        ];
+        annotate_ftp_pct(&mut curve, 250);
+        assert_approx(curve[0].pct_ftp.unwrap() as f64, 120.0, 0.001, "300W at FTP 250");
+        assert_approx(curve[1].pct_ftp.unwrap() as f64, 80.0, 0.001, "200W at FTP 250");
+        assert_eq!(curve[0].watts, 300, "raw watts untouched");
+
+        let mut no_ftp = vec![PowerCurvePoint { duration_secs: 60, watts: 300, pct_ftp: None }];
+        annotate_ftp_pct(&mut no_ftp, 0);
+        assert!(no_ftp[0].pct_ftp.is_none());
+    }
+
+    #[test]
+    fn power_curve_empty_readings() {
+        let curve = compute_power_curve(&[]);
+        assert!(curve.is_empty());
+
+        // Also: only HR readings, no power
+        let readings = vec![hr_reading(140, 1000)];
+        let curve = compute_power_curve(&readings);
+        assert!(curve.is_empty());
+    }
+
+    // --- Quadrant analysis ---
+
+    #[test]
+    fn quadrant_point_for_250w_at_90rpm() {
+        let readings = vec![power_reading(250, 1000), cadence_reading(90.0, 1200)];
+        let points = compute_quadrant_analysis(&samples(&readings), 0.1725, 250);
+        assert_eq!(points.len(), 1);
+        // CPV = 90 * 0.1725 * 2π / 60 ≈ 1.6258 m/s; AEPF = 250 / CPV ≈ 153.77 N
+        assert_approx(points[0].cpv, 1.6258, 0.0001, "cpv");
+        assert_approx(points[0].aepf, 153.77, 0.01, "aepf");
+        // FTP at 90 rpm: above the 80 rpm velocity split, below its force split
+        assert_eq!(points[0].quadrant, 4);
+    }
+
+    #[test]
+    fn quadrant_skips_unpaired_and_coasting_seconds() {
+        let readings = vec![
+            power_reading(200, 0),
+            cadence_reading(85.0, 0),
+            power_reading(210, 1000), // no cadence this second
+            cadence_reading(90.0, 2000), // no power this second
+            power_reading(0, 3000),
+            cadence_reading(0.0, 3000), // coasting
+            power_reading(400, 4000),
+            cadence_reading(60.0, 4000),
+        ];
+        let points = compute_quadrant_analysis(&samples(&readings), 0.1725, 250);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].elapsed_secs, 0);
+        assert_eq!(points[1].elapsed_secs, 4);
+        // 400W at 60 rpm: high force, low velocity
+        assert_eq!(points[1].quadrant, 2);
+    }
+
+    // --- Elevation ---
+
+    fn grade_reading(percent: f32, epoch_ms: u64) -> SensorReading {
+        SensorReading::Grade { percent, epoch_ms }
+    }
+
+    #[test]
+    fn elevation_gain_constant_5pct_grade() {
+        // 10 min at 36 km/h (10 m/s) on 5%: 6000 m × sin(atan(0.05)) ≈ 299.6 m
+        let mut readings = vec![grade_reading(5.0, 0)];
+        readings.extend((0..=600).map(|i| speed_reading(36.0, i * 1000)));
+        let gain = compute_elevation_gain(&readings, MAX_READING_GAP_MS).unwrap() as f64;
+        let expected = 6000.0 * 0.05_f64.atan().sin();
+        assert_approx(gain, expected, 0.01, "ascent");
+    }
+
+    #[test]
+    fn elevation_gain_caps_gaps_and_ignores_descents() {
+        let readings = vec![
+            grade_reading(-4.0, 0),
+            speed_reading(36.0, 0),
+            speed_reading(36.0, 10_000), // downhill: no gain
+            grade_reading(10.0, 10_000),
+            speed_reading(36.0, 30_000), // 20s gap capped to 5s
+        ];
+        let gain = compute_elevation_gain(&readings, MAX_READING_GAP_MS).unwrap() as f64;
+        assert_approx(gain, 50.0 * 0.1_f64.atan().sin(), 0.001, "capped ascent");
+        // A wider max gap counts the whole 20s
+        let gain = compute_elevation_gain(&readings, 30_000).unwrap() as f64;
+        assert_approx(gain, 200.0 * 0.1_f64.atan().sin(), 0.001, "uncapped ascent");
+        assert!(compute_elevation_gain(&[speed_reading(30.0, 0)], MAX_READING_GAP_MS).is_none());
+    }
+
+    #[test]
+    fn avg_pedal_balance_skips_readings_without_balance() {
+        // 48/52 L/R ride: right pedal mostly 52%, a few 51/53 readings either side
+        let mut readings: Vec<_> = (0..60u64)
+            .map(|i| SensorReading::Power {
+                watts: 200,
+                timestamp: None,
+                epoch_ms: i * 1000,
+                device_id: String::new(),
+                pedal_balance: Some(match i % 4 {
+                    0 => 51,
+                    2 => 53,
+                    _ => 52,
+                }),
+            })
+            .collect();
+        // Dropped balance field on some packets must not drag the mean down
+        readings.extend((60..70u64).map(|i| power_reading(200, i * 1000)));
+
+        assert_eq!(compute_avg_pedal_balance(&readings), Some(52));
+        let no_balance: Vec<_> = (0..10u64).map(|i| power_reading(200, i * 1000)).collect();
+        assert_eq!(compute_avg_pedal_balance(&no_balance), None);
+    }
+
+    // --- Power source comparison ---
+
+    fn primary_power(watts: u16, epoch_ms: u64) -> SensorReading {
+        SensorReading::Power {
+            watts,
+            timestamp: None,
+            epoch_ms,
+            device_id: "pm-a".to_string(),
+            pedal_balance: None,
+        }
+    }
+
+    fn secondary_power(watts: u16, epoch_ms: u64) -> SensorReading {
+        SensorReading::SecondaryPower {
+            watts,
+            epoch_ms,
+            device_id: "pm-b".to_string(),
+        }
+    }
+
+    #[test]
+    fn power_source_comparison_streams_5w_apart() {
+        let mut readings = Vec::new();
+        for i in 0..60u64 {
+            let base = 200 + (i % 10) as u16 * 5;
+            readings.push(primary_power(base, i * 1000));
+            readings.push(secondary_power(base + 5, i * 1000 + 300));
+        }
+        let cmp = compute_power_source_comparison(&readings).unwrap();
+        assert_eq!(cmp.reference_device_id, "pm-a");
+        assert_eq!(cmp.compared_device_id, "pm-b");
+        assert_eq!(cmp.paired_secs, 60);
+        assert_approx(cmp.mean_delta_watts, 5.0, 1e-9, "mean delta");
+        // 5W on 200W is the largest relative gap
+        assert_approx(cmp.max_pct_diff, 2.5, 1e-9, "max pct");
+        assert!(cmp.mean_pct_diff < 2.5 && cmp.mean_pct_diff > 2.0);
+    }
+
+    #[test]
+    fn power_source_comparison_needs_two_sources() {
+        let readings: Vec<SensorReading> = (0..10).map(|i| primary_power(200, i * 1000)).collect();
+        assert!(compute_power_source_comparison(&readings).is_none());
+        // Two sources that never overlap
+        let readings = vec![primary_power(200, 0), secondary_power(205, 5000)];
+        assert!(compute_power_source_comparison(&readings).is_none());
+    }
+
+    // --- Muscle oxygen ---
+
+    #[test]
+    fn muscle_oxygen_series_shares_timeseries_origin() {
+        let readings = vec![
+            power_reading(200, 10_000),
+            SensorReading::MuscleOxygen {
+                smo2: 62.5,
+                thb: 12.4,
+                epoch_ms: 12_500,
+                device_id: "moxy".to_string(),
+            },
+            hr_reading(140, 13_000),
+        ];
+        let points = build_muscle_oxygen_series(&samples(&readings));
+        assert_eq!(points.len(), 1);
+        assert_approx(points[0].elapsed_secs, 2.5, 1e-9, "elapsed");
+        assert_eq!(points[0].smo2, 62.5);
+        assert_eq!(points[0].thb, 12.4);
+        assert!(build_muscle_oxygen_series(&samples(&[power_reading(200, 0)])).is_empty());
+    }
+
+    // --- W′ balance tests ---
+
+    #[test]
+    fn wbal_depletes_linearly_above_cp() {
+        // CP=250, W′=20000. 60s @ 350W → 100 J/s above CP → 20000 - 6000 = 14000
+        let readings: Vec<SensorReading> =
+            (0..60).map(|i| power_reading(350, i * 1000)).collect();
+
+        let wbal = compute_wbal(&samples(&readings), 250, 20_000);
+
+        assert_eq!(wbal.len(), 60);
+        assert_approx(wbal[0].1, 19_900.0, 0.1, "first second");
+        assert_approx(wbal[59].1, 14_000.0, 0.1, "after 60s above CP");
     }
 
-    // --- Power curve tests ---
+    #[test]
+    fn wbal_recharges_during_recovery() {
+        // 60s @ 350W (→14000J), then 1s @ 150W:
+        // recharge = (250 - 150) * (20000 - 14000) / 20000 = 30J → 14030
+        let mut readings: Vec<SensorReading> =
+            (0..60).map(|i| power_reading(350, i * 1000)).collect();
+        readings.extend((60..180).map(|i| power_reading(150, i * 1000)));
+
+        let wbal = compute_wbal(&samples(&readings), 250, 20_000);
+
+        assert_approx(wbal[60].1, 14_030.0, 0.1, "first recovery second");
+        // Recovery continues to climb but never exceeds W′
+        assert!(wbal[179].1 > wbal[60].1, "W′bal should keep recharging");
+        assert!(wbal[179].1 < 20_000.0, "exponential recharge should not be complete");
+    }
 
     #[test]
-    fn power_curve_constant_power() {
-        // 60 readings at 200W, 1Hz starting at t=0
+    fn wbal_capped_at_w_prime_and_can_go_negative() {
+        // Below CP from the start → stays at the cap
+        let easy: Vec<SensorReading> =
+            (0..30).map(|i| power_reading(100, i * 1000)).collect();
+        let wbal = compute_wbal(&samples(&easy), 250, 20_000);
+        assert!(wbal.iter().all(|&(_, w)| (w - 20_000.0).abs() < 1e-9));
+
+        // 100s @ 500W → 250 J/s → 20000 - 25000 = -5000
+        let hard: Vec<SensorReading> =
+            (0..100).map(|i| power_reading(500, i * 1000)).collect();
+        let wbal = compute_wbal(&samples(&hard), 250, 20_000);
+        assert_approx(wbal[99].1, -5_000.0, 0.1, "over-exertion goes negative");
+    }
+
+    #[test]
+    fn analysis_wbal_only_with_cp_model_configured() {
         let readings: Vec<SensorReading> =
-            (0..60).map(|i| power_reading(200, i * 1000)).collect();
+            (0..10).map(|i| power_reading(300, i * 1000)).collect();
+        let session = test_session(10, 250);
+        let mut config = test_config();
+
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
+        assert!(analysis.wbal.is_none());
+
+        config.critical_power = Some(250);
+        config.w_prime = Some(20_000);
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
+        let wbal = analysis.wbal.expect("W′bal with CP model set");
+        assert_eq!(wbal.len(), 10);
+        assert_approx(wbal[9], 19_500.0, 0.1, "10s at 50W over CP");
+    }
 
-        let curve = compute_power_curve(&readings);
+    #[test]
+    fn analysis_speed_in_mph_when_imperial() {
+        let readings: Vec<SensorReading> = (0..5)
+            .flat_map(|i| [power_reading(200, i * 1000), speed_reading(30.0, i * 1000)])
+            .collect();
+        let session = test_session(5, 200);
+        let config = test_config();
 
-        // All durations ≤ 60s should be 200W
-        for pt in &curve {
-            assert!(pt.duration_secs <= 60, "should not exceed session length");
-            assert_eq!(pt.watts, 200, "constant 200W for {}s duration", pt.duration_secs);
+        let metric = compute_analysis(&samples(&readings), &session, &config, None, "metric");
+        let imperial = compute_analysis(&samples(&readings), &session, &config, None, "imperial");
+        let metric_speed = metric.timeseries[0].speed.unwrap();
+        let imperial_speed = imperial.timeseries[0].speed.unwrap();
+        assert!((metric_speed - 30.0).abs() < 1e-4);
+        assert!((imperial_speed - 18.64).abs() < 0.01, "got {}", imperial_speed);
+        // Power is unit-independent
+        assert_eq!(metric.timeseries[0].power, imperial.timeseries[0].power);
+    }
+
+    // --- Interval detection tests ---
+
+    fn push_block(readings: &mut Vec<SensorReading>, start_sec: u64, secs: u64, watts: u16) {
+        for i in start_sec..start_sec + secs {
+            readings.push(power_reading(watts, i * 1000));
         }
-        // Should have entries for 1,2,3,5,10,15,20,30,45,60
-        assert_eq!(curve.len(), 10);
     }
 
     #[test]
-    fn power_curve_best_interval() {
-        // 10s @ 100W, then 10s @ 300W (t=0..9s @ 100W, t=10..19s @ 300W)
-        let mut readings: Vec<SensorReading> = Vec::new();
-        for i in 0..10 {
-            readings.push(power_reading(100, i * 1000));
+    fn intervals_4x4min_vo2_workout() {
+        // FTP=250, threshold 225W. 10min warmup @ 150W, then 4 × (4min @ 300W, 3min @ 120W),
+        // 10min cooldown @ 130W.
+        let mut readings = Vec::new();
+        push_block(&mut readings, 0, 600, 150);
+        let mut t = 600;
+        for _ in 0..4 {
+            push_block(&mut readings, t, 240, 300);
+            push_block(&mut readings, t + 240, 180, 120);
+            t += 420;
         }
-        for i in 10..20 {
-            readings.push(power_reading(300, i * 1000));
+        push_block(&mut readings, t, 600, 130);
+
+        let intervals = detect_intervals(&readings, 250);
+
+        assert_eq!(intervals.len(), 4, "should find exactly four work intervals");
+        for (k, iv) in intervals.iter().enumerate() {
+            assert_eq!(iv.start_secs, 600 + k as u32 * 420);
+            assert_eq!(iv.duration_secs, 240);
+            assert_eq!(iv.end_secs, iv.start_secs + 240);
+            assert_eq!(iv.avg_power, 300);
         }
+    }
 
-        let curve = compute_power_curve(&readings);
+    #[test]
+    fn intervals_brief_dip_does_not_split() {
+        // 60s @ 300W with a 3s dip to 100W in the middle → one 60s interval
+        let mut readings = Vec::new();
+        push_block(&mut readings, 0, 30, 300);
+        push_block(&mut readings, 30, 3, 100);
+        push_block(&mut readings, 33, 27, 300);
+        push_block(&mut readings, 60, 30, 100);
+
+        let intervals = detect_intervals(&readings, 250);
+
+        assert_eq!(intervals.len(), 1);
+        assert_eq!(intervals[0].start_secs, 0);
+        assert_eq!(intervals[0].end_secs, 60);
+        // (57 × 300 + 3 × 100) / 60 = 290
+        assert_eq!(intervals[0].avg_power, 290);
+    }
 
-        // 1s best = 300W (best single second in the 300W block)
-        let p1 = curve.iter().find(|p| p.duration_secs == 1).unwrap();
-        assert_eq!(p1.watts, 300);
+    #[test]
+    fn intervals_long_dip_splits_and_short_efforts_ignored() {
+        // 30s @ 300W, 10s @ 100W (longer than hysteresis), 30s @ 300W,
+        // 60s easy, then a 15s sprint (too short to count)
+        let mut readings = Vec::new();
+        push_block(&mut readings, 0, 30, 300);
+        push_block(&mut readings, 30, 10, 100);
+        push_block(&mut readings, 40, 30, 300);
+        push_block(&mut readings, 70, 60, 100);
+        push_block(&mut readings, 130, 15, 400);
+        push_block(&mut readings, 145, 30, 100);
+
+        let intervals = detect_intervals(&readings, 250);
+
+        assert_eq!(intervals.len(), 2);
+        assert_eq!((intervals[0].start_secs, intervals[0].end_secs), (0, 30));
+        assert_eq!((intervals[1].start_secs, intervals[1].end_secs), (40, 70));
+    }
 
-        // 10s best = 300W (the 10s block of 300W)
-        let p10 = curve.iter().find(|p| p.duration_secs == 10).unwrap();
-        assert_eq!(p10.watts, 300);
+    #[test]
+    fn intervals_empty_without_power() {
+        assert!(detect_intervals(&[], 250).is_empty());
+        assert!(detect_intervals(&[hr_reading(150, 0)], 250).is_empty());
+    }
 
-        // 20s best = average of 100*10 + 300*10 = 4000/20 = 200W
-        let p20 = curve.iter().find(|p| p.duration_secs == 20).unwrap();
-        assert_eq!(p20.watts, 200);
+    // --- Activity classification tests ---
+
+    fn classified_session(duration_secs: u64, intensity: f32, vi: f32) -> SessionSummary {
+        let mut session = test_session(duration_secs, 200);
+        session.intensity_factor = Some(intensity);
+        session.variability_index = Some(vi);
+        session
     }
 
     #[test]
-    fn power_curve_capped_at_session_length() {
-        // 30 readings → no entry with duration > 30
-        let readings: Vec<SensorReading> =
-            (0..30).map(|i| power_reading(150, i * 1000)).collect();
+    fn classify_interval_session() {
+        // 5 × (60s at 300W, 120s at 100W) with FTP 200
+        let readings: Vec<SensorReading> = (0..900u64)
+            .map(|i| power_reading(if i % 180 < 60 { 300 } else { 100 }, i * 1000))
+            .collect();
+        let session = classified_session(900, 0.85, 1.25);
+        assert_eq!(classify_activity(&session, &readings), "intervals");
 
-        let curve = compute_power_curve(&readings);
-        for pt in &curve {
-            assert!(pt.duration_secs <= 30);
-        }
+        // Same variability without repeated efforts isn't an interval session
+        let steady: Vec<SensorReading> = (0..900).map(|i| power_reading(150, i * 1000)).collect();
+        assert_eq!(classify_activity(&session, &steady), "free_ride");
     }
 
     #[test]
-    fn power_curve_empty_readings() {
-        let curve = compute_power_curve(&[]);
-        assert!(curve.is_empty());
+    fn classify_steady_and_hard_rides() {
+        assert_eq!(classify_activity(&classified_session(7200, 0.68, 1.02), &[]), "endurance");
+        // Easy but short is not endurance
+        assert_eq!(classify_activity(&classified_session(1800, 0.68, 1.02), &[]), "free_ride");
+        assert_eq!(classify_activity(&classified_session(1200, 1.02, 1.03), &[]), "test");
+        assert_eq!(classify_activity(&classified_session(3000, 0.97, 1.08), &[]), "race");
+    }
 
-        // Also: only HR readings, no power
-        let readings = vec![hr_reading(140, 1000)];
-        let curve = compute_power_curve(&readings);
-        assert!(curve.is_empty());
+    #[test]
+    fn classify_sim_and_powerless_rides() {
+        let readings = vec![power_reading(200, 0), grade_reading(4.0, 500)];
+        assert_eq!(
+            classify_activity(&classified_session(3600, 0.7, 1.02), &readings),
+            "outdoor_sim"
+        );
+        assert_eq!(classify_activity(&test_session(3600, 200), &[]), "other");
     }
 
     // --- Zone distribution tests ---
@@ -564,7 +2184,7 @@ mod tests {
         let config = test_config();
 
         let (power_zones, _) =
-            compute_zone_distribution(&readings, 200, &config.power_zones, &config.hr_zones);
+            compute_zone_distribution(&samples(&readings), 200, &config.power_zones, &config.hr_zones, config.max_gap_ms());
 
         // 9 seconds of zone time total (9 gaps between 10 readings)
         let total: f64 = power_zones.iter().map(|z| z.duration_secs).sum();
@@ -589,7 +2209,7 @@ mod tests {
         let config = test_config();
 
         let (power_zones, _) =
-            compute_zone_distribution(&readings, 200, &config.power_zones, &config.hr_zones);
+            compute_zone_distribution(&samples(&readings), 200, &config.power_zones, &config.hr_zones, config.max_gap_ms());
 
         // Gaps: 0→1, 1→2, 2→3, 3→4 at 100W (Z1) = 4s
         //        4→5 at 100W (Z1) = 1s  (reading at t=4 is 100W, gap to t=5)
@@ -604,6 +2224,57 @@ mod tests {
         assert_approx(power_zones[5].duration_secs, 4.0, 0.01, "zone 6 duration");
     }
 
+    #[test]
+    fn three_zone_model_gives_three_buckets() {
+        // Boundaries 75% / 100% at FTP 200: 150W and 200W
+        let mut readings: Vec<SensorReading> =
+            (0..10).map(|i| power_reading(120, i * 1000)).collect();
+        readings.extend((10..20).map(|i| power_reading(180, i * 1000)));
+        readings.extend((20..31).map(|i| power_reading(320, i * 1000)));
+        let config = SessionConfig {
+            power_zones: [75, 100, 105, 120, 130, 150],
+            power_zone_model: PowerZoneModel::Three,
+            ..test_config()
+        };
+
+        let analysis = compute_analysis(
+            &samples(&readings),
+            &test_session(31, 200),
+            &config,
+            None,
+            "metric",
+        );
+        let zones = &analysis.power_zone_distribution;
+
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones.iter().map(|z| z.zone).collect::<Vec<_>>(), vec![1, 2, 3]);
+        for (zone, secs) in zones.iter().zip([10.0, 10.0, 10.0]) {
+            assert_approx(zone.duration_secs, secs, 0.01, "three-zone duration");
+        }
+        // 320W is far above 100% but still the top zone, not Z7
+        assert_approx(zones[2].avg_value.unwrap(), 320.0, 0.01, "zone 3 average");
+    }
+
+    #[test]
+    fn zone_average_power_is_time_weighted() {
+        // FTP 200 → Z2 is 111-150W. 10s at 120W (1Hz), then 140W sampled every
+        // 2s for 20s, then 10s at 250W (Z6).
+        let mut readings: Vec<SensorReading> =
+            (0..10).map(|i| power_reading(120, i * 1000)).collect();
+        readings.extend((0..10).map(|i| power_reading(140, 10_000 + i * 2000)));
+        readings.extend((30..40).map(|i| power_reading(250, i * 1000)));
+        let config = test_config();
+
+        let (power_zones, _) =
+            compute_zone_distribution(&samples(&readings), 200, &config.power_zones, &config.hr_zones, config.max_gap_ms());
+
+        assert_approx(power_zones[1].duration_secs, 30.0, 0.01, "zone 2 duration");
+        // (120W × 10s + 140W × 20s) / 30s, not the 130W reading mean
+        assert_approx(power_zones[1].avg_value.unwrap(), 133.33, 0.01, "zone 2 average");
+        assert_approx(power_zones[5].avg_value.unwrap(), 250.0, 0.01, "zone 6 average");
+        assert!(power_zones[0].avg_value.is_none(), "empty zone has no average");
+    }
+
     #[test]
     fn zone_gap_capped_at_5s() {
         // Two power readings 10s apart → only 5s counted (MAX_READING_GAP_MS cap)
@@ -611,12 +2282,41 @@ mod tests {
         let config = test_config();
 
         let (power_zones, _) =
-            compute_zone_distribution(&readings, 200, &config.power_zones, &config.hr_zones);
+            compute_zone_distribution(&samples(&readings), 200, &config.power_zones, &config.hr_zones, config.max_gap_ms());
 
         let total: f64 = power_zones.iter().map(|z| z.duration_secs).sum();
         assert_approx(total, 5.0, 0.01, "gap capped at 5s");
     }
 
+    #[test]
+    fn sparse_hr_counts_full_time_with_raised_gap_cap() {
+        // An older strap reporting every 10s over one minute
+        let readings: Vec<_> = (0..7).map(|i| hr_reading(130, i * 10_000)).collect();
+        let config = SessionConfig {
+            max_gap_secs: 12,
+            ..test_config()
+        };
+
+        let (_, raised) = compute_zone_distribution(
+            &samples(&readings),
+            200,
+            &config.power_zones,
+            &config.hr_zones,
+            config.max_gap_ms(),
+        );
+        let (_, default) = compute_zone_distribution(
+            &samples(&readings),
+            200,
+            &config.power_zones,
+            &config.hr_zones,
+            test_config().max_gap_ms(),
+        );
+
+        let total = |zones: &[ZoneBucket]| zones.iter().map(|z| z.duration_secs).sum::<f64>();
+        assert_approx(total(&raised), 60.0, 0.01, "12s cap keeps every interval");
+        assert_approx(total(&default), 30.0, 0.01, "default cap clips each interval to 5s");
+    }
+
     #[test]
     fn hr_zone_distribution() {
         // Default hr_zones: [120, 140, 160, 175, 190]
@@ -632,7 +2332,7 @@ mod tests {
         let config = test_config();
 
         let (_, hr_zones) =
-            compute_zone_distribution(&readings, 200, &config.power_zones, &config.hr_zones);
+            compute_zone_distribution(&samples(&readings), 200, &config.power_zones, &config.hr_zones, config.max_gap_ms());
 
         let total: f64 = hr_zones.iter().map(|z| z.duration_secs).sum();
         assert_approx(total, 9.0, 0.01, "total HR zone time");
@@ -641,6 +2341,95 @@ mod tests {
         assert_approx(hr_zones[0].duration_secs, 5.0, 0.01, "HR zone 1 duration");
         // Z3 (141-160): pairs at 150bpm → 4s (pairs 5-6,6-7,7-8,8-9)
         assert_approx(hr_zones[2].duration_secs, 4.0, 0.01, "HR zone 3 duration");
+        assert_approx(hr_zones[0].avg_value.unwrap(), 100.0, 0.01, "HR zone 1 average");
+        assert_approx(hr_zones[2].avg_value.unwrap(), 150.0, 0.01, "HR zone 3 average");
+        assert!(hr_zones[1].avg_value.is_none());
+    }
+
+    #[test]
+    fn cadence_zone_split_time() {
+        // Default cadence_zones: [60, 75, 90, 105]
+        // 5 readings at 55rpm (Z1: <60), then 5 at 95rpm (Z4: 90–105), 1Hz
+        let mut readings = Vec::new();
+        for i in 0..5 {
+            readings.push(cadence_reading(55.0, i * 1000));
+        }
+        for i in 5..10 {
+            readings.push(cadence_reading(95.0, i * 1000));
+        }
+        let config = test_config();
+
+        let cadence_zones = compute_cadence_zone_distribution(&samples(&readings), &config.cadence_zones, config.max_gap_ms());
+
+        assert_eq!(cadence_zones.len(), 5);
+        let total: f64 = cadence_zones.iter().map(|z| z.duration_secs).sum();
+        assert_approx(total, 9.0, 0.01, "total cadence zone time");
+        // Pairs 0-1..4-5 have prev=55rpm → Z1 = 5s; pairs 5-6..8-9 prev=95rpm → Z4 = 4s
+        assert_approx(cadence_zones[0].duration_secs, 5.0, 0.01, "cadence zone 1 duration");
+        assert_approx(cadence_zones[3].duration_secs, 4.0, 0.01, "cadence zone 4 duration");
+        assert_approx(cadence_zones[3].percentage, 44.44, 0.01, "cadence zone 4 percentage");
+    }
+
+    #[test]
+    fn cadence_zone_band_edges_are_lower_inclusive() {
+        let zones = [60, 75, 90, 105];
+        assert_eq!(classify_cadence_zone(0.0, &zones), 1);
+        assert_eq!(classify_cadence_zone(59.9, &zones), 1);
+        assert_eq!(classify_cadence_zone(60.0, &zones), 2);
+        assert_eq!(classify_cadence_zone(104.9, &zones), 4);
+        assert_eq!(classify_cadence_zone(105.0, &zones), 5);
+    }
+
+    #[test]
+    fn cadence_zone_gap_capped_and_custom_edges() {
+        // Custom edges [70, 80, 90, 100]: 85rpm → Z3. Readings 20s apart → capped at 5s.
+        let readings = vec![cadence_reading(85.0, 0), cadence_reading(85.0, 20_000)];
+
+        let cadence_zones = compute_cadence_zone_distribution(&samples(&readings), &[70, 80, 90, 100], MAX_READING_GAP_MS);
+
+        assert_approx(cadence_zones[2].duration_secs, 5.0, 0.01, "gap capped at 5s in Z3");
+        assert_approx(cadence_zones[2].percentage, 100.0, 0.01, "all time in Z3");
+    }
+
+    #[test]
+    fn run_speed_stream_fills_pace_zones() {
+        // 60s each at 8 km/h (7:30/km, Z1), 11 km/h (~5:27/km, Z3) and 14 km/h
+        // (~4:17/km, Z5), then 10s standing still
+        let mut readings = Vec::new();
+        for (block, kmh) in [8.0, 11.0, 14.0].into_iter().enumerate() {
+            for i in 0..60 {
+                readings.push(speed_reading(kmh, (block as u64 * 60 + i) * 1000));
+            }
+        }
+        for i in 180..190 {
+            readings.push(speed_reading(0.0, i * 1000));
+        }
+        let mut session = test_session(190, 250);
+        session.activity_type = Some("run".into());
+        let config = test_config();
+
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
+
+        let pace = &analysis.pace_zone_distribution;
+        assert_eq!(pace.len(), 5);
+        assert_approx(pace[0].duration_secs, 60.0, 0.01, "pace zone 1");
+        assert_approx(pace[1].duration_secs, 0.0, 0.01, "pace zone 2");
+        assert_approx(pace[2].duration_secs, 60.0, 0.01, "pace zone 3");
+        assert_approx(pace[4].duration_secs, 60.0, 0.01, "pace zone 5 excludes standing");
+        assert!(analysis.power_zone_distribution.is_empty(), "no power → no power zones");
+
+        session.activity_type = Some("endurance".into());
+        let ride = compute_analysis(&samples(&readings), &session, &config, None, "metric");
+        assert!(ride.pace_zone_distribution.is_empty());
+    }
+
+    #[test]
+    fn pace_zone_edges_belong_to_faster_zone() {
+        let zones = [390, 330, 300, 270];
+        assert_eq!(classify_pace_zone(450.0, &zones), 1);
+        assert_eq!(classify_pace_zone(390.0, &zones), 2);
+        assert_eq!(classify_pace_zone(300.0, &zones), 4);
+        assert_eq!(classify_pace_zone(269.0, &zones), 5);
     }
 
     // --- Timeseries tests ---
@@ -657,7 +2446,7 @@ mod tests {
             }
         }
 
-        let ts = build_timeseries(&readings, 3);
+        let ts = build_timeseries(&samples(&readings), 3, None);
 
         assert_eq!(ts.len(), 3, "should have 3 second-slots");
         // Last value in each second is the one at sub=3, so watts = 203
@@ -674,7 +2463,7 @@ mod tests {
             hr_reading(145, 1500),
         ];
 
-        let ts = build_timeseries(&readings, 5);
+        let ts = build_timeseries(&samples(&readings), 5, None);
 
         assert_eq!(ts.len(), 1, "one slot has data");
         let pt = &ts[0];
@@ -686,9 +2475,71 @@ mod tests {
         assert_eq!(pt.speed, None);
     }
 
+    /// Square wave: 5s @ 100W, 5s @ 300W, repeated for 40s.
+    fn square_wave_readings() -> Vec<SensorReading> {
+        (0..40u64)
+            .map(|i| power_reading(if (i / 5) % 2 == 0 { 100 } else { 300 }, i * 1000))
+            .collect()
+    }
+
+    #[test]
+    fn timeseries_smoothing_3s_softens_square_wave_edges() {
+        let ts = build_timeseries(&samples(&square_wave_readings()), 40, Some(3));
+
+        assert_eq!(ts.len(), 40);
+        // Slot 2: window 1..=3, all 100W → unchanged
+        assert_eq!(ts[2].power, Some(100));
+        // Slot 4: window 3..=5 = 100, 100, 300 → 166.67 → 167
+        assert_eq!(ts[4].power, Some(167));
+        // Slot 5: window 4..=6 = 100, 300, 300 → 233.33 → 233
+        assert_eq!(ts[5].power, Some(233));
+        // Slot 0: window shrinks at the start → 0..=1 = 100, 100
+        assert_eq!(ts[0].power, Some(100));
+    }
+
+    #[test]
+    fn timeseries_smoothing_10s_flattens_square_wave() {
+        let ts = build_timeseries(&samples(&square_wave_readings()), 40, Some(10));
+
+        // 10s window (5 before, 4 after) spans exactly one full period away
+        // from the edges: 5×100 + 5×300 = 2000 / 10 = 200W
+        for pt in &ts[5..=35] {
+            assert_eq!(pt.power, Some(200), "slot {} should average one full period", pt.elapsed_secs);
+        }
+        // Slot 0: window 0..=4, all 100W
+        assert_eq!(ts[0].power, Some(100));
+    }
+
+    #[test]
+    fn timeseries_smoothing_window_shrinks_at_gap() {
+        // 200W for 0..=4, nothing at 5, 400W for 6..=10
+        let mut readings: Vec<SensorReading> =
+            (0..5).map(|i| power_reading(200, i * 1000)).collect();
+        readings.extend((6..11).map(|i| power_reading(400, i * 1000)));
+
+        let ts = build_timeseries(&samples(&readings), 11, Some(3));
+
+        // Gap second is not fabricated
+        assert_eq!(ts.len(), 10);
+        assert!(ts.iter().all(|pt| pt.elapsed_secs != 5.0));
+        // Slots either side of the gap only average their own side
+        let at = |sec: f64| ts.iter().find(|pt| pt.elapsed_secs == sec).unwrap().power;
+        assert_eq!(at(4.0), Some(200));
+        assert_eq!(at(6.0), Some(400));
+    }
+
+    #[test]
+    fn timeseries_smoothing_none_matches_raw() {
+        let raw = build_timeseries(&samples(&square_wave_readings()), 40, None);
+        let one = build_timeseries(&samples(&square_wave_readings()), 40, Some(1));
+        for (a, b) in raw.iter().zip(one.iter()) {
+            assert_eq!(a.power, b.power);
+        }
+    }
+
     #[test]
     fn timeseries_empty() {
-        let ts = build_timeseries(&[], 60);
+        let ts = build_timeseries(&samples(&[]), 60, None);
         assert!(ts.is_empty());
     }
 
@@ -704,7 +2555,7 @@ mod tests {
         let session = test_session(2, 250);
         let config = test_config();
 
-        let analysis = compute_analysis(&readings, &session, &config);
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
 
         // 250W at FTP=250 → 100% FTP → zone 4 (threshold: 90-105%)
         // Power zones [55, 75, 90, 105, 120, 150] → Z4 is 90-105% FTP
@@ -724,7 +2575,7 @@ mod tests {
         session.ftp = None;
         let config = test_config(); // default FTP=200
 
-        let analysis = compute_analysis(&readings, &session, &config);
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
 
         // 200W at FTP=200 → 100% FTP → zone 4
         let z4 = analysis.power_zone_distribution.iter().find(|z| z.zone == 4);
@@ -742,7 +2593,7 @@ mod tests {
         let session = test_session(2, 100);
         let config = test_config(); // FTP=200
 
-        let analysis = compute_analysis(&readings, &session, &config);
+        let analysis = compute_analysis(&samples(&readings), &session, &config, None, "metric");
 
         // 200W at FTP=100 → 200% FTP → zone 7 (>150%)
         let z7 = analysis.power_zone_distribution.iter().find(|z| z.zone == 7);
@@ -750,6 +2601,279 @@ mod tests {
         assert!(z7.unwrap().percentage > 0.0, "200W at FTP=100 should be zone 7");
     }
 
+    /// Percentage of time in power `zone` for `session` analysed under `config`.
+    fn power_zone_pct(session: &SessionSummary, config: &SessionConfig, zone: u8) -> f64 {
+        let readings = vec![power_reading(200, 1000), power_reading(200, 2000)];
+        let analysis = compute_analysis(&samples(&readings), session, config, None, "metric");
+        analysis
+            .power_zone_distribution
+            .iter()
+            .find(|z| z.zone == zone)
+            .map_or(0.0, |z| z.percentage)
+    }
+
+    #[test]
+    fn run_session_is_zoned_against_running_threshold() {
+        // Session stamped with cycling FTP=200; running threshold 250
+        let mut session = test_session(2, 200);
+        session.activity_type = Some("run".into());
+        let config = SessionConfig {
+            ftp_running: Some(250),
+            ..test_config()
+        };
+
+        // 200W at 250W → 80% → zone 3, not zone 4 as at the cycling FTP
+        assert!(power_zone_pct(&session, &config, 3) > 0.0, "run should use running FTP");
+        assert_eq!(power_zone_pct(&session, &config, 4), 0.0);
+    }
+
+    #[test]
+    fn ride_and_unset_sport_threshold_keep_cycling_ftp() {
+        let config = SessionConfig {
+            ftp_running: Some(250),
+            ..test_config()
+        };
+        let mut ride = test_session(2, 200);
+        ride.activity_type = Some("cycling".into());
+        assert!(power_zone_pct(&ride, &config, 4) > 0.0, "ride ignores running FTP");
+
+        // A row with no rowing threshold falls back to the session's FTP
+        let mut row = test_session(2, 200);
+        row.activity_type = Some("rowing".into());
+        assert!(power_zone_pct(&row, &config, 4) > 0.0, "row falls back to ftp");
+    }
+
+    // --- Gap detection tests ---
+
+    #[test]
+    fn detects_power_dropout_mid_ride() {
+        // 60s ride with HR throughout; power drops out from t=30 to t=40
+        let mut readings = Vec::new();
+        for i in 0..60u64 {
+            if !(31..40).contains(&i) {
+                readings.push(power_reading(200, i * 1000));
+            }
+            readings.push(hr_reading(140, i * 1000));
+        }
+
+        let gaps = detect_gaps(&samples(&readings), GapChannel::Power, MAX_READING_GAP_MS);
+        assert_eq!(
+            gaps,
+            vec![Gap {
+                channel: GapChannel::Power,
+                start_secs: 30.0,
+                end_secs: 40.0,
+                duration_secs: 10.0,
+            }]
+        );
+        assert!(detect_gaps(&samples(&readings), GapChannel::HeartRate, MAX_READING_GAP_MS).is_empty());
+        assert!(detect_gaps(&samples(&readings), GapChannel::Cadence, MAX_READING_GAP_MS).is_empty());
+    }
+
+    #[test]
+    fn late_start_and_early_end_are_gaps() {
+        // HR for 60s; power only from t=20 to t=45
+        let mut readings = Vec::new();
+        for i in 0..60u64 {
+            if (20..=45).contains(&i) {
+                readings.push(power_reading(200, i * 1000));
+            }
+            readings.push(hr_reading(140, i * 1000));
+        }
+
+        let gaps = detect_gaps(&samples(&readings), GapChannel::Power, MAX_READING_GAP_MS);
+        let spans: Vec<(f64, f64)> = gaps.iter().map(|g| (g.start_secs, g.end_secs)).collect();
+        assert_eq!(spans, vec![(0.0, 20.0), (45.0, 59.0)]);
+        assert!(detect_gaps(&samples(&readings), GapChannel::HeartRate, MAX_READING_GAP_MS).is_empty());
+    }
+
+    #[test]
+    fn short_dropout_is_not_a_gap() {
+        // A 5s hole is within MAX_READING_GAP_MS
+        let readings: Vec<SensorReading> = (0..30u64)
+            .filter(|i| !(11..15).contains(i))
+            .map(|i| power_reading(200, i * 1000))
+            .collect();
+        assert!(detect_gaps(&samples(&readings), GapChannel::Power, MAX_READING_GAP_MS).is_empty());
+    }
+
+    // --- Flatline detection tests ---
+
+    #[test]
+    fn flatlined_hr_segment_is_flagged_but_steady_erg_power_is_not_checked() {
+        // HR varies for 60s, freezes at 152 bpm from 60s to 150s, then recovers.
+        // Power holds 200W throughout, as in ERG mode.
+        let mut readings = Vec::new();
+        for i in 0..200u64 {
+            let bpm = if (60..=150).contains(&i) { 152 } else { 130 + (i % 7) as u8 };
+            readings.push(hr_reading(bpm, i * 1000));
+            readings.push(power_reading(200, i * 1000));
+        }
+        let samples = samples(&readings);
+
+        assert_eq!(detect_flatline(&samples, GapChannel::HeartRate, 60), vec![(60.0, 150.0)]);
+        // Shorter than the threshold: not flagged
+        assert!(detect_flatline(&samples, GapChannel::HeartRate, 120).is_empty());
+
+        let session = test_session(200, 200);
+        let analysis = compute_analysis(&samples, &session, &test_config(), None, "metric");
+        assert_eq!(
+            analysis.flatlines,
+            vec![Flatline {
+                channel: GapChannel::HeartRate,
+                start_secs: 60.0,
+                end_secs: 150.0,
+            }]
+        );
+    }
+
+    // --- Pedaling tests ---
+
+    #[test]
+    fn pedaling_averages_skip_missing_values() {
+        let pedaling = |te: Option<f32>, ps: Option<f32>, epoch_ms: u64| SensorReading::Pedaling {
+            torque_effectiveness: te,
+            smoothness: ps,
+            top_dead_spot_deg: None,
+            bottom_dead_spot_deg: None,
+            epoch_ms,
+            device_id: String::new(),
+        };
+        let readings = vec![
+            pedaling(Some(90.0), Some(20.0), 0),
+            pedaling(Some(80.0), Some(30.0), 1000),
+            pedaling(None, None, 2000),
+            power_reading(200, 2000),
+        ];
+        let (te, ps) = compute_pedaling_averages(&samples(&readings));
+        assert_approx(te.unwrap() as f64, 85.0, 0.01, "torque effectiveness");
+        assert_approx(ps.unwrap() as f64, 25.0, 0.01, "smoothness");
+        assert_eq!(compute_pedaling_averages(&samples(&[power_reading(200, 0)])), (None, None));
+    }
+
+    // --- HR recovery tests ---
+
+    #[test]
+    fn hr_recovery_after_hard_effort() {
+        // 120s easy at 120W (HR 120), 180s hard at 300W with HR climbing to
+        // 175, then 90s of soft-pedalling at 80W with HR falling 0.5 bpm/s.
+        let mut readings = Vec::new();
+        for i in 0..120u64 {
+            readings.push(power_reading(120, i * 1000));
+            readings.push(hr_reading(120, i * 1000));
+        }
+        for i in 120..300u64 {
+            readings.push(power_reading(300, i * 1000));
+            readings.push(hr_reading((130 + (i - 120) / 4).min(175) as u8, i * 1000));
+        }
+        for i in 300..390u64 {
+            readings.push(power_reading(80, i * 1000));
+            readings.push(hr_reading((175 - (i - 299) / 2) as u8, i * 1000));
+        }
+
+        // Effort ends at t=299; HR peaks at 175 just after, 145 at t=359
+        assert_eq!(compute_hr_recovery(&samples(&readings), MAX_READING_GAP_MS), Some(30));
+    }
+
+    #[test]
+    fn hr_recovery_none_without_recovery_tail() {
+        // Hard effort right up to the end: no HR 60s after it
+        let mut readings = Vec::new();
+        for i in 0..200u64 {
+            readings.push(power_reading(if i < 100 { 120 } else { 300 }, i * 1000));
+            readings.push(hr_reading(150, i * 1000));
+        }
+        readings.push(power_reading(50, 230_000));
+        readings.push(hr_reading(130, 230_000));
+        assert!(compute_hr_recovery(&samples(&readings), MAX_READING_GAP_MS).is_none());
+    }
+
+    #[test]
+    fn hr_recovery_late_reading_within_max_gap() {
+        // Effort ends at t=199; the only later HR arrives 31s past the 60s mark
+        let mut readings = Vec::new();
+        for i in 0..200u64 {
+            readings.push(power_reading(if i < 100 { 120 } else { 300 }, i * 1000));
+            readings.push(hr_reading(150, i * 1000));
+        }
+        readings.push(hr_reading(130, 290_000));
+        assert!(compute_hr_recovery(&samples(&readings), MAX_READING_GAP_MS).is_none());
+        assert_eq!(compute_hr_recovery(&samples(&readings), 40_000), Some(20));
+    }
+
+    // --- Aerobic decoupling tests ---
+
+    #[test]
+    fn decoupling_positive_when_hr_drifts_up_at_flat_power() {
+        // 120s at 200W. HR 125 for first 60s, 150 for last 60s.
+        // Midpoint of 0..119 = 59.5 → first half 0..59 (60 samples), second 60..119 (60).
+        // Ratios: 200/125 = 1.6, 200/150 = 1.3333
+        // Decoupling = (1.6 - 1.3333) / 1.6 * 100 = 16.67%
+        let mut readings = Vec::new();
+        for i in 0..120u64 {
+            readings.push(power_reading(200, i * 1000));
+            readings.push(hr_reading(if i < 60 { 125 } else { 150 }, i * 1000 + 500));
+        }
+
+        let pct = compute_decoupling(&samples(&readings)).expect("should compute decoupling");
+        assert_approx(pct, 16.67, 0.01, "decoupling");
+    }
+
+    #[test]
+    fn decoupling_zero_for_constant_ratio() {
+        let mut readings = Vec::new();
+        for i in 0..100u64 {
+            readings.push(power_reading(180, i * 1000));
+            readings.push(hr_reading(140, i * 1000));
+        }
+
+        let pct = compute_decoupling(&samples(&readings)).expect("should compute decoupling");
+        assert_approx(pct, 0.0, 0.01, "no drift");
+    }
+
+    #[test]
+    fn decoupling_buckets_by_second_across_sample_rates() {
+        // Power at 4Hz alternating 100/300 within each second (avg 200),
+        // HR at 1Hz. Raw sample counts would weight power 4x; bucketing
+        // must still give 200W per second → ratios 200/125 and 200/150.
+        let mut readings = Vec::new();
+        for i in 0..120u64 {
+            for sub in 0..4u64 {
+                let w = if sub % 2 == 0 { 100 } else { 300 };
+                readings.push(power_reading(w, i * 1000 + sub * 250));
+            }
+            readings.push(hr_reading(if i < 60 { 125 } else { 150 }, i * 1000));
+        }
+
+        let pct = compute_decoupling(&samples(&readings)).expect("should compute decoupling");
+        assert_approx(pct, 16.67, 0.01, "decoupling with mixed sample rates");
+    }
+
+    #[test]
+    fn decoupling_none_when_half_has_too_few_pairs() {
+        // 50 paired seconds total → 25 per half, below the 30 threshold
+        let mut readings = Vec::new();
+        for i in 0..50u64 {
+            readings.push(power_reading(200, i * 1000));
+            readings.push(hr_reading(140, i * 1000));
+        }
+        assert!(compute_decoupling(&samples(&readings)).is_none());
+    }
+
+    #[test]
+    fn decoupling_ignores_unpaired_seconds() {
+        // HR only present for the first 40s; power for 120s → second half
+        // has zero paired seconds.
+        let mut readings = Vec::new();
+        for i in 0..120u64 {
+            readings.push(power_reading(200, i * 1000));
+            if i < 40 {
+                readings.push(hr_reading(140, i * 1000));
+            }
+        }
+        assert!(compute_decoupling(&samples(&readings)).is_none());
+    }
+
     // --- HR-Power regression tests ---
 
     fn make_timeseries(pairs: &[(u16, u8)]) -> Vec<TimeseriesPoint> {
@@ -0,0 +1,122 @@
+use std::fmt::Display;
+
+use crate::device::types::SensorReading;
+use crate::error::AppError;
+use crate::session::analysis::PowerCurvePoint;
+
+/// Render a mean-maximal power curve as CSV with columns
+/// `duration_secs,watts,watts_per_kg`. Points are written in the order given
+/// (storage returns them ascending by duration). An empty curve is an error
+/// so callers never produce a header-only file.
+pub fn power_curve_csv(curve: &[PowerCurvePoint], weight_kg: f32) -> Result<String, AppError> {
+    if curve.is_empty() {
+        return Err(AppError::Session("No power curve data to export".into()));
+    }
+
+    let mut out = String::from("duration_secs,watts,watts_per_kg\n");
+    for point in curve {
+        let wkg = if weight_kg > 0.0 {
+            format!("{:.2}", point.watts as f32 / weight_kg)
+        } else {
+            String::new()
+        };
+        out.push_str(&format!("{},{},{}\n", point.duration_secs, point.watts, wkg));
+    }
+    Ok(out)
+}
+
+/// Render a raw sensor log as CSV with columns
+/// `epoch_ms,type,device_id,value1,value2`, one row per reading in log order.
+/// `value2` carries the secondary field where a variant has one (pedal
+/// balance, total hemoglobin, rear gear, ...) and is blank otherwise.
+pub fn raw_sensor_csv(readings: &[SensorReading]) -> String {
+    let mut out = String::from("epoch_ms,type,device_id,value1,value2\n");
+    for reading in readings {
+        let (kind, value1, value2) = match reading {
+            SensorReading::Power {
+                watts, pedal_balance, ..
+            } => ("Power", watts.to_string(), opt(pedal_balance)),
+            SensorReading::HeartRate { bpm, .. } => ("HeartRate", bpm.to_string(), String::new()),
+            SensorReading::Cadence { rpm, .. } => ("Cadence", rpm.to_string(), String::new()),
+            SensorReading::Speed { kmh, .. } => ("Speed", kmh.to_string(), String::new()),
+            SensorReading::TrainerCommand {
+                target_watts, source, ..
+            } => ("TrainerCommand", target_watts.to_string(), format!("{:?}", source)),
+            // RR intervals of one measurement share a row, separated by ';'
+            SensorReading::Hrv { rr_ms, .. } => {
+                let rr: Vec<String> = rr_ms.iter().map(|rr| rr.to_string()).collect();
+                ("Hrv", rr.join(";"), String::new())
+            }
+            SensorReading::Energy { kilojoules, .. } => {
+                ("Energy", kilojoules.to_string(), String::new())
+            }
+            SensorReading::MuscleOxygen { smo2, thb, .. } => {
+                ("MuscleOxygen", smo2.to_string(), thb.to_string())
+            }
+            SensorReading::RadarTargets { threats, .. } => {
+                ("RadarTargets", threats.len().to_string(), String::new())
+            }
+            SensorReading::Temperature { celsius, .. } => {
+                ("Temperature", celsius.to_string(), String::new())
+            }
+            SensorReading::Gear { front, rear, .. } => ("Gear", front.to_string(), rear.to_string()),
+            SensorReading::SecondaryPower { watts, .. } => {
+                ("SecondaryPower", watts.to_string(), String::new())
+            }
+            SensorReading::Grade { percent, .. } => ("Grade", percent.to_string(), String::new()),
+            SensorReading::Pedaling {
+                torque_effectiveness,
+                smoothness,
+                ..
+            } => ("Pedaling", opt(torque_effectiveness), opt(smoothness)),
+            SensorReading::Lap { .. } => ("Lap", String::new(), String::new()),
+            SensorReading::AveragePower { watts, .. } => {
+                ("AveragePower", watts.to_string(), String::new())
+            }
+        };
+        out.push_str(&format!(
+            "{},{},{},{},{}\n",
+            reading.epoch_ms(),
+            kind,
+            reading.device_id(),
+            value1,
+            value2
+        ));
+    }
+    out
+}
+
+fn opt<T: Display>(value: &Option<T>) -> String {
+    value.as_ref().map_or_else(String::new, |v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_curve_csv_empty_is_error() {
+        assert!(power_curve_csv(&[], 75.0).is_err());
+    }
+
+    #[test]
+    fn power_curve_csv_computes_watts_per_kg() {
+        let curve = vec![
+            PowerCurvePoint { duration_secs: 5, watts: 600, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 1200, watts: 270, pct_ftp: None },
+        ];
+        let csv = power_curve_csv(&curve, 75.0).unwrap();
+        // 600/75 = 8.00, 270/75 = 3.60
+        assert_eq!(
+            csv,
+            "duration_secs,watts,watts_per_kg\n5,600,8.00\n1200,270,3.60\n"
+        );
+    }
+
+    #[test]
+    fn power_curve_csv_blank_wkg_without_weight() {
+        let curve = vec![PowerCurvePoint { duration_secs: 1, watts: 500, pct_ftp: None }];
+        let csv = power_curve_csv(&curve, 0.0).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("1,500,"));
+    }
+}
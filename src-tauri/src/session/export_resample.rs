@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use crate::device::types::SensorReading;
+
+/// Longest run of missing seconds that `fill_gaps` bridges by interpolation.
+/// Longer dropouts stay missing rather than inventing data.
+pub const MAX_INTERPOLATED_GAP_SECS: usize = 10;
+
+/// One second of exported data. Channels are `None` where the sensor had no reading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportSample {
+    pub epoch_secs: u64,
+    pub power: Option<u16>,
+    pub heart_rate: Option<u8>,
+    pub cadence: Option<f32>,
+    pub speed_kmh: Option<f32>,
+}
+
+/// Resample readings to one sample per epoch-second for file exports, averaging
+/// readings that share a second. With `fill_gaps`, each channel is linearly
+/// interpolated across runs of up to `MAX_INTERPOLATED_GAP_SECS` missing seconds.
+/// Seconds with no data on any channel are left out.
+pub fn resample_per_second(readings: &[SensorReading], fill_gaps: bool) -> Vec<ExportSample> {
+    // sec -> per-channel (sum, count): power, hr, cadence, speed
+    let mut buckets: BTreeMap<u64, [(f64, u32); 4]> = BTreeMap::new();
+    for reading in readings {
+        let (channel, value) = match reading {
+            SensorReading::Power { watts, .. } => (0, *watts as f64),
+            SensorReading::HeartRate { bpm, .. } => (1, *bpm as f64),
+            SensorReading::Cadence { rpm, .. } => (2, *rpm as f64),
+            SensorReading::Speed { kmh, .. } => (3, *kmh as f64),
+            _ => continue,
+        };
+        let slot = &mut buckets.entry(reading.epoch_ms() / 1000).or_default()[channel];
+        slot.0 += value;
+        slot.1 += 1;
+    }
+
+    let (Some(&first), Some(&last)) = (buckets.keys().next(), buckets.keys().next_back()) else {
+        return Vec::new();
+    };
+    let len = (last - first + 1) as usize;
+    let mut channels = vec![vec![None; len]; 4];
+    for (sec, slots) in &buckets {
+        for (channel, (sum, count)) in slots.iter().enumerate() {
+            if *count > 0 {
+                channels[channel][(sec - first) as usize] = Some(sum / *count as f64);
+            }
+        }
+    }
+    if fill_gaps {
+        for values in &mut channels {
+            interpolate_short_gaps(values);
+        }
+    }
+
+    (0..len)
+        .filter(|&i| channels.iter().any(|values| values[i].is_some()))
+        .map(|i| ExportSample {
+            epoch_secs: first + i as u64,
+            power: channels[0][i].map(|v| v.round() as u16),
+            heart_rate: channels[1][i].map(|v| v.round() as u8),
+            cadence: channels[2][i].map(|v| v as f32),
+            speed_kmh: channels[3][i].map(|v| v as f32),
+        })
+        .collect()
+}
+
+/// Linearly fill runs of up to `MAX_INTERPOLATED_GAP_SECS` `None`s that have a
+/// value on both sides.
+fn interpolate_short_gaps(values: &mut [Option<f64>]) {
+    let mut prev: Option<usize> = None;
+    for i in 0..values.len() {
+        let Some(end) = values[i] else {
+            continue;
+        };
+        if let Some(p) = prev {
+            let missing = i - p - 1;
+            if missing > 0 && missing <= MAX_INTERPOLATED_GAP_SECS {
+                let start = values[p].unwrap_or(end);
+                let span = (i - p) as f64;
+                for (k, value) in values.iter_mut().enumerate().take(i).skip(p + 1) {
+                    *value = Some(start + (end - start) * (k - p) as f64 / span);
+                }
+            }
+        }
+        prev = Some(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn power(watts: u16, sec: u64) -> SensorReading {
+        SensorReading::Power {
+            watts,
+            timestamp: None,
+            epoch_ms: sec * 1000,
+            device_id: String::new(),
+            pedal_balance: None,
+        }
+    }
+
+    fn hr(bpm: u8, sec: u64) -> SensorReading {
+        SensorReading::HeartRate {
+            bpm,
+            timestamp: None,
+            epoch_ms: sec * 1000,
+            device_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn interpolates_across_short_gap() {
+        // Power at t=0..=10 except a 3s hole at t=4..=6, ramping 100 → 200 across it
+        let mut readings: Vec<SensorReading> = (0..=3).map(|s| power(100, s)).collect();
+        readings.extend((7..=10).map(|s| power(200, s)));
+
+        let raw = resample_per_second(&readings, false);
+        assert_eq!(raw.len(), 8, "holes are skipped without fill_gaps");
+
+        let filled = resample_per_second(&readings, true);
+        assert_eq!(filled.len(), 11);
+        let gap: Vec<Option<u16>> = filled[4..=6].iter().map(|s| s.power).collect();
+        assert_eq!(gap, vec![Some(125), Some(150), Some(175)]);
+    }
+
+    #[test]
+    fn leaves_long_gap_missing() {
+        // HR throughout; power drops out for 30s
+        let mut readings: Vec<SensorReading> = (0..=40).map(|s| hr(140, s)).collect();
+        readings.extend((0..=5).map(|s| power(200, s)));
+        readings.extend((36..=40).map(|s| power(220, s)));
+
+        let filled = resample_per_second(&readings, true);
+        assert_eq!(filled.len(), 41);
+        assert!(filled[6..36].iter().all(|s| s.power.is_none()));
+        assert!(filled.iter().all(|s| s.heart_rate == Some(140)));
+    }
+
+    #[test]
+    fn averages_readings_within_a_second() {
+        let readings = vec![
+            SensorReading::Power {
+                watts: 100,
+                timestamp: None,
+                epoch_ms: 5_100,
+                device_id: String::new(),
+                pedal_balance: None,
+            },
+            SensorReading::Power {
+                watts: 300,
+                timestamp: None,
+                epoch_ms: 5_600,
+                device_id: String::new(),
+                pedal_balance: None,
+            },
+        ];
+        let samples = resample_per_second(&readings, false);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].epoch_secs, 5);
+        assert_eq!(samples[0].power, Some(200));
+        assert!(samples[0].heart_rate.is_none());
+    }
+}
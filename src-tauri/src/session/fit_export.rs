@@ -1,12 +1,13 @@
+use super::export_resample::resample_per_second;
 use super::types::SessionSummary;
 use crate::device::types::SensorReading;
 use crate::error::AppError;
 
 /// FIT epoch offset: seconds between Unix epoch (1970-01-01) and FIT epoch (1989-12-31 00:00:00 UTC)
-const FIT_EPOCH_OFFSET: i64 = 631065600;
+pub(crate) const FIT_EPOCH_OFFSET: i64 = 631065600;
 
 /// CRC-16/ARC lookup table (polynomial 0xA001, reflected)
-fn fit_crc16(data: &[u8]) -> u16 {
+pub(crate) fn fit_crc16(data: &[u8]) -> u16 {
     let mut crc: u16 = 0;
     for &byte in data {
         for bit in 0..8 {
@@ -89,10 +90,78 @@ impl FitWriter {
     }
 }
 
-/// Export a session as a FIT file.
-pub fn export_fit(summary: &SessionSummary, readings: &[SensorReading]) -> Result<Vec<u8>, AppError> {
+/// Split `[start_ts, end_ts]` at the recorded lap markers. Consecutive
+/// entries bound one lap each, so laps cover the ride without gaps or overlap.
+fn lap_bounds(readings: &[SensorReading], start_ts: u32, end_ts: u32) -> Vec<u32> {
+    let mut bounds = vec![start_ts];
+    let mut markers: Vec<u32> = readings
+        .iter()
+        .filter_map(|r| match r {
+            SensorReading::Lap { epoch_ms } => Some(unix_to_fit_timestamp(*epoch_ms)),
+            _ => None,
+        })
+        .filter(|&ts| ts > start_ts && ts < end_ts)
+        .collect();
+    markers.sort_unstable();
+    markers.dedup();
+    bounds.extend(markers);
+    bounds.push(end_ts);
+    bounds
+}
+
+/// Per-lap totals accumulated from the per-second records.
+#[derive(Debug, Clone, Default)]
+struct LapStats {
+    power_sum: u64,
+    power_count: u64,
+    max_power: Option<u16>,
+    hr_sum: u64,
+    hr_count: u64,
+    /// Cumulative distance (m * 100) when the lap started and at its last record
+    distance_start: u32,
+    distance_end: u32,
+}
+
+impl LapStats {
+    fn record(&mut self, power: Option<u16>, heart_rate: Option<u8>, distance_m100: u32) {
+        if let Some(w) = power {
+            self.power_sum += w as u64;
+            self.power_count += 1;
+            self.max_power = Some(self.max_power.map_or(w, |m| m.max(w)));
+        }
+        if let Some(bpm) = heart_rate {
+            self.hr_sum += bpm as u64;
+            self.hr_count += 1;
+        }
+        self.distance_end = distance_m100;
+    }
+
+    fn avg_power(&self) -> Option<u16> {
+        (self.power_count > 0).then(|| (self.power_sum / self.power_count) as u16)
+    }
+
+    fn avg_hr(&self) -> Option<u8> {
+        (self.hr_count > 0).then(|| (self.hr_sum / self.hr_count) as u8)
+    }
+
+    fn distance(&self) -> u32 {
+        self.distance_end.saturating_sub(self.distance_start)
+    }
+}
+
+/// Encode a session as a FIT activity. With `fill_gaps`, short sensor dropouts
+/// are interpolated in the per-second records (see `resample_per_second`).
+pub fn export_fit(
+    summary: &SessionSummary,
+    readings: &[SensorReading],
+    fill_gaps: bool,
+) -> Result<Vec<u8>, AppError> {
     let mut w = FitWriter::new();
     let start_ts = datetime_to_fit_timestamp(&summary.start_time);
+    let end_ts = start_ts + summary.duration_secs as u32;
+    let bounds = lap_bounds(readings, start_ts, end_ts);
+    let mut laps = vec![LapStats::default(); bounds.len() - 1];
+    let mut lap_idx = 0;
 
     // --- file_id message (local 0, global 0) ---
     w.write_definition(0, 0, &[
@@ -156,51 +225,44 @@ pub fn export_fit(summary: &SessionSummary, readings: &[SensorReading]) -> Resul
         (5, 4, 134),   // distance: uint32 (m * 100)
     ]);
 
-    let mut last_hr: u8 = 0xFF;
-    let mut last_cadence: u8 = 0xFF;
-    let mut last_speed: u16 = 0xFFFF;
     let mut cumulative_distance_m100: u32 = 0;
     let mut last_speed_ms: f64 = 0.0;
-    let mut last_speed_epoch_ms: Option<u64> = None;
+    let mut last_speed_secs: Option<u64> = None;
     let mut max_speed_ms1000: u16 = 0;
 
-    for reading in readings {
-        match reading {
-            SensorReading::HeartRate { bpm, .. } => {
-                last_hr = *bpm;
-            }
-            SensorReading::Cadence { rpm, .. } => {
-                last_cadence = (*rpm).min(254.0) as u8;
-            }
-            SensorReading::Speed { kmh, epoch_ms, .. } => {
-                if let Some(prev) = last_speed_epoch_ms {
-                    let dt_s = (*epoch_ms as f64 - prev as f64) / 1000.0;
+    // One record per second; missing channels are written as FIT invalid values
+    for sample in resample_per_second(readings, fill_gaps) {
+        let ts = unix_to_fit_timestamp(sample.epoch_secs * 1000);
+        while lap_idx + 1 < laps.len() && ts >= bounds[lap_idx + 1] {
+            lap_idx += 1;
+            laps[lap_idx].distance_start = cumulative_distance_m100;
+            laps[lap_idx].distance_end = cumulative_distance_m100;
+        }
+        let speed_ms1000 = match sample.speed_kmh {
+            Some(kmh) => {
+                if let Some(prev) = last_speed_secs {
+                    let dt_s = (sample.epoch_secs - prev) as f64;
                     cumulative_distance_m100 += (last_speed_ms * dt_s * 100.0) as u32;
                 }
-                last_speed_ms = *kmh as f64 / 3.6;
-                last_speed_epoch_ms = Some(*epoch_ms);
-                let ms_1000 = (*kmh / 3.6 * 1000.0) as u16;
-                last_speed = ms_1000;
+                last_speed_ms = kmh as f64 / 3.6;
+                last_speed_secs = Some(sample.epoch_secs);
+                let ms_1000 = (kmh / 3.6 * 1000.0) as u16;
                 max_speed_ms1000 = max_speed_ms1000.max(ms_1000);
+                ms_1000
             }
-            SensorReading::Power {
-                watts, epoch_ms, ..
-            } => {
-                let ts = unix_to_fit_timestamp(*epoch_ms);
-                let mut rec = Vec::with_capacity(14);
-                rec.extend_from_slice(&ts.to_le_bytes());
-                rec.extend_from_slice(&watts.to_le_bytes());
-                rec.push(last_hr);
-                rec.push(last_cadence);
-                rec.extend_from_slice(&last_speed.to_le_bytes());
-                rec.extend_from_slice(&cumulative_distance_m100.to_le_bytes());
-                w.write_data(3, &rec);
-            }
-            SensorReading::TrainerCommand { .. } => {}
-        }
+            None => 0xFFFF,
+        };
+        laps[lap_idx].record(sample.power, sample.heart_rate, cumulative_distance_m100);
+        let mut rec = Vec::with_capacity(14);
+        rec.extend_from_slice(&ts.to_le_bytes());
+        rec.extend_from_slice(&sample.power.unwrap_or(0xFFFF).to_le_bytes());
+        rec.push(sample.heart_rate.unwrap_or(0xFF));
+        rec.push(sample.cadence.map_or(0xFF, |rpm| rpm.min(254.0) as u8));
+        rec.extend_from_slice(&speed_ms1000.to_le_bytes());
+        rec.extend_from_slice(&cumulative_distance_m100.to_le_bytes());
+        w.write_data(3, &rec);
     }
 
-    let end_ts = start_ts + summary.duration_secs as u32;
     let elapsed_ms = (summary.duration_secs * 1000) as u32;
 
     // Stop event (timer stop_all)
@@ -210,23 +272,40 @@ pub fn export_fit(summary: &SessionSummary, readings: &[SensorReading]) -> Resul
     stop_evt.push(4); // event_type = stop_all
     w.write_data(2, &stop_evt);
 
-    // --- lap message (local 4, global 19) ---
+    // --- lap messages (local 4, global 19), one per lap marker span ---
     w.write_definition(4, 19, &[
         (253, 4, 134), // timestamp
+        (254, 2, 132), // message_index: uint16
+        (0, 1, 0),     // event: enum
+        (1, 1, 0),     // event_type: enum
         (2, 4, 134),   // start_time
         (7, 4, 134),   // total_elapsed_time (s * 1000)
         (8, 4, 134),   // total_timer_time (s * 1000)
+        (9, 4, 134),   // total_distance: uint32 (m * 100)
+        (15, 1, 2),    // avg_heart_rate: uint8
+        (19, 2, 132),  // avg_power: uint16
+        (20, 2, 132),  // max_power: uint16
         (25, 1, 0),    // sport: enum
         (39, 1, 0),    // sub_sport: enum
     ]);
-    let mut lap_data = Vec::new();
-    lap_data.extend_from_slice(&end_ts.to_le_bytes());
-    lap_data.extend_from_slice(&start_ts.to_le_bytes());
-    lap_data.extend_from_slice(&elapsed_ms.to_le_bytes());
-    lap_data.extend_from_slice(&elapsed_ms.to_le_bytes());
-    lap_data.push(2); // sport = cycling
-    lap_data.push(6); // sub_sport = indoor_cycling
-    w.write_data(4, &lap_data);
+    for (i, (lap, span)) in laps.iter().zip(bounds.windows(2)).enumerate() {
+        let lap_ms = (span[1] - span[0]) * 1000;
+        let mut lap_data = Vec::with_capacity(32);
+        lap_data.extend_from_slice(&span[1].to_le_bytes());
+        lap_data.extend_from_slice(&(i as u16).to_le_bytes());
+        lap_data.push(9); // event = lap
+        lap_data.push(1); // event_type = stop
+        lap_data.extend_from_slice(&span[0].to_le_bytes());
+        lap_data.extend_from_slice(&lap_ms.to_le_bytes());
+        lap_data.extend_from_slice(&lap_ms.to_le_bytes());
+        lap_data.extend_from_slice(&lap.distance().to_le_bytes());
+        lap_data.push(lap.avg_hr().unwrap_or(0xFF));
+        lap_data.extend_from_slice(&lap.avg_power().unwrap_or(0xFFFF).to_le_bytes());
+        lap_data.extend_from_slice(&lap.max_power.unwrap_or(0xFFFF).to_le_bytes());
+        lap_data.push(2); // sport = cycling
+        lap_data.push(6); // sub_sport = indoor_cycling
+        w.write_data(4, &lap_data);
+    }
 
     // --- session message (local 5, global 18) ---
     let total_distance = summary
@@ -272,6 +351,7 @@ pub fn export_fit(summary: &SessionSummary, readings: &[SensorReading]) -> Resul
         (35, 2, 132),  // tss: uint16 (* 10)
         (36, 2, 132),  // intensity_factor: uint16 (* 1000)
         (38, 2, 132),  // threshold_power: uint16
+        (26, 2, 132),  // num_laps: uint16
     ]);
     let mut sess_data = Vec::new();
     sess_data.extend_from_slice(&end_ts.to_le_bytes());
@@ -298,6 +378,7 @@ pub fn export_fit(summary: &SessionSummary, readings: &[SensorReading]) -> Resul
     sess_data.extend_from_slice(&tss_x10.to_le_bytes());
     sess_data.extend_from_slice(&if_x1000.to_le_bytes());
     sess_data.extend_from_slice(&summary.ftp.unwrap_or(0xFFFF).to_le_bytes());
+    sess_data.extend_from_slice(&(laps.len() as u16).to_le_bytes());
     w.write_data(5, &sess_data);
 
     // --- activity message (local 6, global 34) ---
@@ -349,13 +430,22 @@ mod tests {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: None,
+            calories: None,
+            avg_cadence_active: None,
+            avg_temp_c: None,
+            total_ascent_m: None,
+            is_favorite: false,
+            xpower: None,
+            data_quality_score: None,
+            avg_pedal_balance: None,
         }
     }
 
     #[test]
     fn fit_file_starts_with_header() {
         let summary = make_summary();
-        let data = export_fit(&summary, &[]).unwrap();
+        let data = export_fit(&summary, &[], false).unwrap();
         assert!(data.len() >= 14);
         assert_eq!(data[0], 14); // header size
         assert_eq!(&data[8..12], b".FIT");
@@ -393,7 +483,7 @@ mod tests {
 
     #[test]
     fn fit_header_crc_matches_recomputed() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let stored_crc = u16::from_le_bytes([data[12], data[13]]);
         let recomputed = fit_crc16(&data[0..12]);
         assert_eq!(stored_crc, recomputed);
@@ -401,7 +491,7 @@ mod tests {
 
     #[test]
     fn fit_file_crc_matches_recomputed() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let len = data.len();
         let stored_crc = u16::from_le_bytes([data[len - 2], data[len - 1]]);
         let recomputed = fit_crc16(&data[..len - 2]);
@@ -411,7 +501,7 @@ mod tests {
     #[test]
     fn fit_file_crc_self_check_yields_zero() {
         // CRC over entire file including appended CRC should be 0
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         assert_eq!(fit_crc16(&data), 0);
     }
 
@@ -440,7 +530,7 @@ mod tests {
                 pedal_balance: None,
             },
         ];
-        let data = export_fit(&summary, &readings).unwrap();
+        let data = export_fit(&summary, &readings, false).unwrap();
         // Should be larger than just header (14) + CRC (2)
         assert!(data.len() > 16, "FIT file too small: {} bytes", data.len());
     }
@@ -448,7 +538,7 @@ mod tests {
     #[test]
     fn fit_export_empty_readings() {
         let summary = make_summary();
-        let data = export_fit(&summary, &[]).unwrap();
+        let data = export_fit(&summary, &[], false).unwrap();
         // Still valid: file_id + session + lap, just no records
         assert!(data.len() > 16, "FIT file too small: {} bytes", data.len());
         // Check header magic
@@ -548,9 +638,44 @@ mod tests {
         msgs
     }
 
+    #[test]
+    fn fit_export_splits_laps_at_markers() {
+        let mut summary = make_summary();
+        summary.duration_secs = 60;
+        let t0: u64 = 1_718_445_600_000;
+        let mut readings: Vec<SensorReading> = (0..60)
+            .map(|s| SensorReading::Power {
+                watts: if s < 20 { 150 } else { 250 },
+                timestamp: None,
+                epoch_ms: t0 + s * 1000,
+                device_id: "test".to_string(),
+                pedal_balance: None,
+            })
+            .collect();
+        readings.insert(20, SensorReading::Lap { epoch_ms: t0 + 20_000 });
+
+        let data = export_fit(&summary, &readings, false).unwrap();
+        let msgs = parse_fit_messages(&data);
+        let laps: Vec<_> = msgs.iter().filter(|m| m.global_msg == 19).collect();
+        assert_eq!(laps.len(), 2);
+
+        let start = unix_to_fit_timestamp(t0);
+        assert_eq!(laps[0].field_u32(2), Some(start));
+        assert_eq!(laps[0].field_u32(253), Some(start + 20));
+        assert_eq!(laps[1].field_u32(2), Some(start + 20));
+        assert_eq!(laps[1].field_u32(253), Some(start + 60));
+        assert_eq!(laps[0].field_u32(7), Some(20_000));
+        assert_eq!(laps[1].field_u32(7), Some(40_000));
+        assert_eq!(laps[0].field_u16(19), Some(150));
+        assert_eq!(laps[1].field_u16(20), Some(250));
+
+        let session = msgs.iter().find(|m| m.global_msg == 18).expect("no session");
+        assert_eq!(session.field_u16(26), Some(2));
+    }
+
     #[test]
     fn fit_export_includes_device_info() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let msgs = parse_fit_messages(&data);
         let dev = msgs.iter().find(|m| m.global_msg == 23).expect("no device_info message");
         assert_eq!(dev.field_u16(3), Some(255), "manufacturer should be 255 (development)");
@@ -563,7 +688,7 @@ mod tests {
 
     #[test]
     fn fit_export_includes_sport_cycling() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let msgs = parse_fit_messages(&data);
         let session = msgs.iter().find(|m| m.global_msg == 18).expect("no session message");
         assert_eq!(session.field_u8(5), Some(2), "sport should be 2 (cycling)");
@@ -572,7 +697,7 @@ mod tests {
 
     #[test]
     fn fit_export_includes_activity_message() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let msgs = parse_fit_messages(&data);
         let act = msgs.iter().find(|m| m.global_msg == 34).expect("no activity message");
         assert_eq!(act.field_u16(1), Some(1), "num_sessions should be 1");
@@ -601,7 +726,7 @@ mod tests {
                 pedal_balance: None,
             });
         }
-        let data = export_fit(&summary, &readings).unwrap();
+        let data = export_fit(&summary, &readings, false).unwrap();
         let msgs = parse_fit_messages(&data);
         let records: Vec<_> = msgs.iter().filter(|m| m.global_msg == 20).collect();
         let last_record = records.last().expect("no record messages");
@@ -621,7 +746,7 @@ mod tests {
         summary.work_kj = Some(648.0);
         summary.distance_km = Some(30.0);
 
-        let data = export_fit(&summary, &[]).unwrap();
+        let data = export_fit(&summary, &[], false).unwrap();
         let msgs = parse_fit_messages(&data);
         let session = msgs.iter().find(|m| m.global_msg == 18).expect("no session");
 
@@ -637,7 +762,7 @@ mod tests {
 
     #[test]
     fn fit_export_event_start_and_stop() {
-        let data = export_fit(&make_summary(), &[]).unwrap();
+        let data = export_fit(&make_summary(), &[], false).unwrap();
         let msgs = parse_fit_messages(&data);
         let events: Vec<_> = msgs.iter().filter(|m| m.global_msg == 21).collect();
         assert_eq!(events.len(), 2, "expected exactly 2 event messages");
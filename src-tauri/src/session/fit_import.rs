@@ -0,0 +1,535 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use super::fit_export::{fit_crc16, FIT_EPOCH_OFFSET};
+use super::metrics::{self, MetricsCalculator};
+use super::types::{SessionConfig, SessionSummary};
+use crate::device::types::SensorReading;
+use crate::error::AppError;
+
+/// Device id attached to readings decoded from an imported file.
+const IMPORT_DEVICE_ID: &str = "fit-import";
+
+// Global message numbers
+const MSG_SESSION: u16 = 18;
+const MSG_RECORD: u16 = 20;
+const MSG_SPORT: u16 = 12;
+
+/// A decoded FIT activity: the sensor stream plus session-level metadata.
+#[derive(Debug, Default)]
+pub struct FitActivity {
+    pub readings: Vec<SensorReading>,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Session `total_timer_time`, when the file carries a session message.
+    pub timer_secs: Option<u64>,
+    pub sport: Option<u8>,
+    pub sub_sport: Option<u8>,
+    /// Session `total_ascent` (m), when the device recorded one.
+    pub total_ascent_m: Option<f32>,
+}
+
+struct FieldDef {
+    num: u8,
+    size: usize,
+    base_type: u8,
+}
+
+struct MsgDef {
+    global_msg: u16,
+    big_endian: bool,
+    fields: Vec<FieldDef>,
+    /// Total size of developer fields, which are skipped.
+    dev_size: usize,
+}
+
+/// Decoded field values of one data message; invalid values are already dropped.
+struct Fields(Vec<(u8, u64)>);
+
+impl Fields {
+    fn get(&self, num: u8) -> Option<u64> {
+        self.0.iter().find(|(n, _)| *n == num).map(|(_, v)| *v)
+    }
+}
+
+fn fit_to_epoch_ms(fit_ts: u32) -> u64 {
+    ((fit_ts as i64 + FIT_EPOCH_OFFSET) * 1000) as u64
+}
+
+fn truncated(pos: usize) -> AppError {
+    AppError::Serialization(format!("FIT file truncated at byte {}", pos))
+}
+
+/// Decode a single-value field, returning `None` for arrays, strings and the
+/// base type's invalid sentinel.
+fn decode_value(bytes: &[u8], base_type: u8, big_endian: bool) -> Option<u64> {
+    let (size, invalid) = match base_type & 0x1F {
+        0x00 | 0x02 | 0x0D => (1, 0xFF),
+        0x01 => (1, 0x7F),
+        0x0A => (1, 0),
+        0x03 => (2, 0x7FFF),
+        0x04 => (2, 0xFFFF),
+        0x0B => (2, 0),
+        0x05 => (4, 0x7FFF_FFFF),
+        0x06 => (4, 0xFFFF_FFFF),
+        0x0C => (4, 0),
+        _ => return None,
+    };
+    if bytes.len() != size {
+        return None;
+    }
+    let value = if big_endian {
+        bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+    } else {
+        bytes.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+    };
+    (value != invalid).then_some(value)
+}
+
+/// Parse a FIT activity file into sensor readings and session metadata.
+/// Record fields that are absent or hold the invalid sentinel produce no
+/// reading for that channel.
+pub fn parse_fit(data: &[u8]) -> Result<FitActivity, AppError> {
+    if data.len() < 12 || &data[8..12] != b".FIT" {
+        return Err(AppError::Serialization("Not a FIT file".into()));
+    }
+    let header_size = data[0] as usize;
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let end = header_size + data_size;
+    if header_size < 12 || end > data.len() {
+        return Err(truncated(data.len()));
+    }
+    if data.len() >= end + 2 && fit_crc16(&data[..end + 2]) != 0 {
+        return Err(AppError::Serialization("FIT file CRC mismatch".into()));
+    }
+
+    let mut defs: HashMap<u8, MsgDef> = HashMap::new();
+    let mut activity = FitActivity::default();
+    let mut session_start: Option<u32> = None;
+    let mut seen_session = false;
+    let mut last_timestamp: u32 = 0;
+    let mut pos = header_size;
+
+    while pos < end {
+        let header = data[pos];
+        pos += 1;
+
+        // Compressed timestamp header: 5-bit offset from the last full timestamp
+        let (local_msg, compressed_ts) = if header & 0x80 != 0 {
+            let offset = (header & 0x1F) as u32;
+            let mut ts = (last_timestamp & !0x1F) + offset;
+            if offset < (last_timestamp & 0x1F) {
+                ts += 0x20;
+            }
+            ((header >> 5) & 0x03, Some(ts))
+        } else {
+            (header & 0x0F, None)
+        };
+
+        if compressed_ts.is_none() && header & 0x40 != 0 {
+            let has_dev_fields = header & 0x20 != 0;
+            if pos + 5 > end {
+                return Err(truncated(pos));
+            }
+            let big_endian = data[pos + 1] == 1;
+            let global_bytes = [data[pos + 2], data[pos + 3]];
+            let global_msg = if big_endian {
+                u16::from_be_bytes(global_bytes)
+            } else {
+                u16::from_le_bytes(global_bytes)
+            };
+            let num_fields = data[pos + 4] as usize;
+            pos += 5;
+            if pos + num_fields * 3 > end {
+                return Err(truncated(pos));
+            }
+            let fields = data[pos..pos + num_fields * 3]
+                .chunks_exact(3)
+                .map(|f| FieldDef {
+                    num: f[0],
+                    size: f[1] as usize,
+                    base_type: f[2],
+                })
+                .collect();
+            pos += num_fields * 3;
+            let mut dev_size = 0;
+            if has_dev_fields {
+                let num_dev = *data.get(pos).ok_or_else(|| truncated(pos))? as usize;
+                pos += 1;
+                if pos + num_dev * 3 > end {
+                    return Err(truncated(pos));
+                }
+                dev_size = data[pos..pos + num_dev * 3]
+                    .chunks_exact(3)
+                    .map(|f| f[1] as usize)
+                    .sum();
+                pos += num_dev * 3;
+            }
+            defs.insert(
+                local_msg,
+                MsgDef {
+                    global_msg,
+                    big_endian,
+                    fields,
+                    dev_size,
+                },
+            );
+            continue;
+        }
+
+        let def = defs.get(&local_msg).ok_or_else(|| {
+            AppError::Serialization(format!(
+                "FIT data message for undefined local type {} at byte {}",
+                local_msg,
+                pos - 1
+            ))
+        })?;
+        let mut values = Vec::with_capacity(def.fields.len());
+        for f in &def.fields {
+            if pos + f.size > end {
+                return Err(truncated(pos));
+            }
+            if let Some(v) = decode_value(&data[pos..pos + f.size], f.base_type, def.big_endian) {
+                values.push((f.num, v));
+            }
+            pos += f.size;
+        }
+        pos += def.dev_size;
+        if pos > end {
+            return Err(truncated(pos));
+        }
+        let fields = Fields(values);
+
+        let timestamp = compressed_ts.or(fields.get(253).map(|t| t as u32));
+        if let Some(ts) = timestamp {
+            last_timestamp = ts;
+        }
+
+        match def.global_msg {
+            MSG_RECORD => {
+                if let Some(ts) = timestamp {
+                    push_record(&mut activity.readings, &fields, fit_to_epoch_ms(ts));
+                }
+            }
+            // Only the first session is imported
+            MSG_SESSION if !seen_session => {
+                seen_session = true;
+                session_start = fields.get(2).map(|t| t as u32);
+                activity.timer_secs = fields.get(8).map(|ms| ms / 1000);
+                activity.sport = fields.get(5).map(|s| s as u8).or(activity.sport);
+                activity.sub_sport = fields.get(6).map(|s| s as u8).or(activity.sub_sport);
+                activity.total_ascent_m = fields.get(22).map(|m| m as f32);
+            }
+            MSG_SPORT => {
+                activity.sport = activity.sport.or(fields.get(0).map(|s| s as u8));
+                activity.sub_sport = activity.sub_sport.or(fields.get(1).map(|s| s as u8));
+            }
+            _ => {}
+        }
+    }
+
+    activity.start_time = session_start
+        .map(fit_to_epoch_ms)
+        .or_else(|| activity.readings.first().map(|r| r.epoch_ms()))
+        .and_then(|ms| DateTime::from_timestamp_millis(ms as i64));
+    Ok(activity)
+}
+
+/// Convert one record message into readings, one per channel that is present.
+fn push_record(readings: &mut Vec<SensorReading>, fields: &Fields, epoch_ms: u64) {
+    let device_id = IMPORT_DEVICE_ID.to_string();
+    if let Some(bpm) = fields.get(3) {
+        readings.push(SensorReading::HeartRate {
+            bpm: bpm as u8,
+            timestamp: None,
+            epoch_ms,
+            device_id: device_id.clone(),
+        });
+    }
+    if let Some(rpm) = fields.get(4) {
+        readings.push(SensorReading::Cadence {
+            rpm: rpm as f32,
+            timestamp: None,
+            epoch_ms,
+            device_id: device_id.clone(),
+        });
+    }
+    // enhanced_speed (73) supersedes speed (6); both are m/s * 1000
+    if let Some(speed) = fields.get(73).or(fields.get(6)) {
+        readings.push(SensorReading::Speed {
+            kmh: (speed as f64 / 1000.0 * 3.6) as f32,
+            timestamp: None,
+            epoch_ms,
+            device_id: device_id.clone(),
+        });
+    }
+    if let Some(watts) = fields.get(7) {
+        readings.push(SensorReading::Power {
+            watts: watts as u16,
+            timestamp: None,
+            epoch_ms,
+            device_id,
+            pedal_balance: None,
+        });
+    }
+}
+
+/// Map a FIT sport/sub-sport pair to an `(activity_type, title)` pair.
+fn sport_labels(sport: u8, sub_sport: Option<u8>) -> Option<(&'static str, &'static str)> {
+    let labels = match (sport, sub_sport) {
+        (2, Some(6)) => ("cycling", "Indoor Cycling"),
+        (2, Some(7)) => ("cycling", "Road Cycling"),
+        (2, Some(8)) => ("cycling", "Mountain Biking"),
+        (2, Some(11)) => ("cycling", "Cyclocross"),
+        (2, Some(13)) => ("cycling", "Track Cycling"),
+        (2, Some(46)) => ("cycling", "Gravel Cycling"),
+        (2, Some(58)) => ("cycling", "Virtual Cycling"),
+        (2, _) => ("cycling", "Cycling"),
+        (1, Some(1)) => ("running", "Treadmill Run"),
+        (1, _) => ("running", "Running"),
+        (5, _) => ("swimming", "Swimming"),
+        (11, _) => ("walking", "Walking"),
+        (15, _) => ("rowing", "Rowing"),
+        (17, _) => ("hiking", "Hiking"),
+        _ => return None,
+    };
+    Some(labels)
+}
+
+/// Build a session summary for an imported activity, computing metrics the same
+/// way a live session does.
+pub fn summarize(
+    id: String,
+    activity: &FitActivity,
+    config: &SessionConfig,
+) -> Result<SessionSummary, AppError> {
+    let ftp = config.ftp;
+    let start_time = activity
+        .start_time
+        .ok_or_else(|| AppError::Serialization("FIT file contains no activity data".into()))?;
+
+    let mut metrics = MetricsCalculator::new(ftp).with_max_gap_ms(config.max_gap_ms());
+    for reading in &activity.readings {
+        match reading {
+            SensorReading::Power {
+                watts, epoch_ms, ..
+            } => metrics.record_power(*watts, *epoch_ms),
+            SensorReading::HeartRate { bpm, .. } => metrics.record_hr(*bpm),
+            SensorReading::Cadence { rpm, .. } => metrics.record_cadence(*rpm),
+            SensorReading::Speed { kmh, epoch_ms, .. } => metrics.record_speed(*kmh, *epoch_ms),
+            SensorReading::Energy {
+                kilojoules,
+                device_id,
+                ..
+            } => metrics.record_energy(device_id, *kilojoules),
+            SensorReading::Temperature { celsius, .. } => metrics.record_temperature(*celsius),
+            SensorReading::TrainerCommand { .. }
+            | SensorReading::Hrv { .. }
+            | SensorReading::MuscleOxygen { .. }
+            | SensorReading::RadarTargets { .. }
+            | SensorReading::Gear { .. }
+            | SensorReading::SecondaryPower { .. }
+            | SensorReading::Grade { .. }
+            | SensorReading::Pedaling { .. }
+            | SensorReading::Lap { .. }
+            | SensorReading::AveragePower { .. } => {}
+        }
+    }
+
+    let duration_secs = activity.timer_secs.unwrap_or_else(|| {
+        match (activity.readings.first(), activity.readings.last()) {
+            (Some(first), Some(last)) => last.epoch_ms().saturating_sub(first.epoch_ms()) / 1000,
+            _ => 0,
+        }
+    });
+    let age = config
+        .date_of_birth
+        .as_deref()
+        .and_then(|dob| metrics::age_on(dob, start_time.date_naive()));
+    let labels = activity
+        .sport
+        .and_then(|sport| sport_labels(sport, activity.sub_sport));
+
+    Ok(SessionSummary {
+        id,
+        start_time,
+        duration_secs,
+        ftp: Some(ftp),
+        avg_power: metrics.avg_power(usize::MAX).map(|v| v as u16),
+        max_power: metrics.max_power(),
+        normalized_power: metrics.normalized_power().map(|v| v as u16),
+        tss: metrics.tss(duration_secs),
+        intensity_factor: metrics.intensity_factor(),
+        avg_hr: metrics.avg_hr(),
+        max_hr: metrics.max_hr(),
+        avg_cadence: metrics.avg_cadence(),
+        avg_speed: metrics.avg_speed(),
+        work_kj: metrics.work_kj(),
+        variability_index: metrics.variability_index(),
+        distance_km: metrics.distance_km(),
+        title: labels.map(|(_, title)| title.to_string()),
+        activity_type: labels.map(|(kind, _)| kind.to_string()),
+        rpe: None,
+        notes: None,
+        efficiency_factor: metrics.efficiency_factor(),
+        calories: metrics::estimate_calories(
+            &activity.readings,
+            config.weight_kg,
+            metrics.avg_hr(),
+            config.sex.as_deref(),
+            age,
+        ),
+        avg_cadence_active: metrics.avg_cadence_active(),
+        avg_temp_c: metrics.avg_temp_c(),
+        total_ascent_m: activity.total_ascent_m,
+        is_favorite: false,
+        xpower: metrics::compute_xpower(&activity.readings),
+        data_quality_score: metrics::validate_work(&activity.readings).map(|v| v.quality_score),
+        avg_pedal_balance: super::analysis::compute_avg_pedal_balance(&activity.readings),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::fit_export::export_fit;
+
+    const BASE_MS: u64 = 1_718_445_600_000;
+
+    fn ride(secs: u64, with_hr: bool) -> Vec<SensorReading> {
+        let mut readings = Vec::new();
+        for i in 0..secs {
+            let epoch_ms = BASE_MS + i * 1000;
+            if with_hr {
+                readings.push(SensorReading::HeartRate {
+                    bpm: 130 + (i % 20) as u8,
+                    timestamp: None,
+                    epoch_ms,
+                    device_id: "hr".to_string(),
+                });
+            }
+            readings.push(SensorReading::Cadence {
+                rpm: 90.0,
+                timestamp: None,
+                epoch_ms,
+                device_id: "cad".to_string(),
+            });
+            readings.push(SensorReading::Speed {
+                kmh: 30.0,
+                timestamp: None,
+                epoch_ms,
+                device_id: "spd".to_string(),
+            });
+            readings.push(SensorReading::Power {
+                watts: 150 + ((i / 30) % 4) as u16 * 50,
+                timestamp: None,
+                epoch_ms,
+                device_id: "pwr".to_string(),
+                pedal_balance: None,
+            });
+        }
+        readings
+    }
+
+    fn activity_from(readings: Vec<SensorReading>) -> FitActivity {
+        FitActivity {
+            start_time: DateTime::from_timestamp_millis(BASE_MS as i64),
+            timer_secs: Some(readings.last().unwrap().epoch_ms().saturating_sub(BASE_MS) / 1000),
+            readings,
+            sport: None,
+            sub_sport: None,
+            total_ascent_m: None,
+        }
+    }
+
+    fn config() -> SessionConfig {
+        SessionConfig {
+            ftp: 250,
+            ..SessionConfig::default()
+        }
+    }
+
+    fn assert_close(a: Option<f32>, b: Option<f32>, eps: f32, label: &str) {
+        match (a, b) {
+            (Some(a), Some(b)) => assert!((a - b).abs() <= eps, "{}: {} vs {}", label, a, b),
+            _ => assert_eq!(a, b, "{}", label),
+        }
+    }
+
+    #[test]
+    fn export_then_import_round_trips_summary() {
+        let original = summarize("orig".into(), &activity_from(ride(600, true)), &config()).unwrap();
+        let fit = export_fit(&original, &ride(600, true), false).unwrap();
+
+        let imported = parse_fit(&fit).unwrap();
+        let summary = summarize("copy".into(), &imported, &config()).unwrap();
+
+        assert_eq!(summary.start_time, original.start_time);
+        assert_eq!(summary.duration_secs, original.duration_secs);
+        assert_eq!(summary.avg_power, original.avg_power);
+        assert_eq!(summary.max_power, original.max_power);
+        assert_eq!(summary.normalized_power, original.normalized_power);
+        assert_eq!(summary.avg_hr, original.avg_hr);
+        assert_eq!(summary.max_hr, original.max_hr);
+        assert_close(summary.tss, original.tss, 0.01, "tss");
+        assert_close(summary.intensity_factor, original.intensity_factor, 0.001, "if");
+        assert_close(summary.avg_cadence, original.avg_cadence, 0.5, "cadence");
+        assert_close(summary.avg_speed, original.avg_speed, 0.01, "speed");
+        assert_close(summary.distance_km, original.distance_km, 0.01, "distance");
+        assert_close(summary.work_kj, original.work_kj, 0.1, "work");
+        assert_eq!(summary.title.as_deref(), Some("Indoor Cycling"));
+        assert_eq!(summary.activity_type.as_deref(), Some("cycling"));
+    }
+
+    #[test]
+    fn missing_hr_maps_to_no_readings() {
+        let original = summarize("orig".into(), &activity_from(ride(60, false)), &config()).unwrap();
+        let fit = export_fit(&original, &ride(60, false), false).unwrap();
+
+        let imported = parse_fit(&fit).unwrap();
+        assert!(!imported
+            .readings
+            .iter()
+            .any(|r| matches!(r, SensorReading::HeartRate { .. })));
+        let summary = summarize("copy".into(), &imported, &config()).unwrap();
+        assert_eq!(summary.avg_hr, None);
+        assert!(summary.avg_power.is_some());
+    }
+
+    #[test]
+    fn compressed_timestamp_headers_are_decoded() {
+        let mut body = Vec::new();
+        // Definition: local 0 = record with timestamp + power
+        body.extend_from_slice(&[0x40, 0, 0, 20, 0, 2, 253, 4, 134, 7, 2, 132]);
+        body.push(0x00);
+        body.extend_from_slice(&1000u32.to_le_bytes());
+        body.extend_from_slice(&200u16.to_le_bytes());
+        // Definition: local 1 = record with power only, used via compressed header
+        body.extend_from_slice(&[0x41, 0, 0, 20, 0, 1, 7, 2, 132]);
+        // Compressed header: local 1, offset 10 → timestamp 1002 (1000 has low bits 8)
+        body.push(0x80 | (1 << 5) | 10);
+        body.extend_from_slice(&0xFFFFu16.to_le_bytes()); // invalid power → no reading
+        body.push(0x80 | (1 << 5) | 11);
+        body.extend_from_slice(&210u16.to_le_bytes());
+
+        let mut data = vec![12, 0x10, 0, 0];
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(b".FIT");
+        data.extend_from_slice(&body);
+
+        let activity = parse_fit(&data).unwrap();
+        let epochs: Vec<u64> = activity.readings.iter().map(|r| r.epoch_ms()).collect();
+        assert_eq!(epochs, vec![fit_to_epoch_ms(1000), fit_to_epoch_ms(1003)]);
+        assert_eq!(activity.sport, None);
+        assert_eq!(activity.start_time.unwrap().timestamp_millis() as u64, fit_to_epoch_ms(1000));
+    }
+
+    #[test]
+    fn rejects_non_fit_data() {
+        assert!(parse_fit(b"definitely not a fit file").is_err());
+    }
+
+    #[test]
+    fn summarize_without_records_errors() {
+        assert!(summarize("x".into(), &FitActivity::default(), &config()).is_err());
+    }
+}
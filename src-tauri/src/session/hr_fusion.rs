@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::device::types::SensorReading;
+
+/// Reserved device id carried by fused heart-rate readings.
+pub const FUSION_DEVICE_ID: &str = "fusion";
+
+/// Blends heart rate from several straps (e.g. chest strap + optical armband)
+/// into one stream: each incoming reading updates that strap's latest value and
+/// yields a synthetic reading averaging every strap heard from recently.
+#[derive(Debug)]
+pub struct HrFusion {
+    /// Latest (bpm, epoch_ms) per source device
+    latest: HashMap<String, (u8, u64)>,
+    /// Straps silent for longer than this drop out of the average
+    max_age_ms: u64,
+}
+
+impl HrFusion {
+    pub fn new(max_age_ms: u64) -> Self {
+        Self {
+            latest: HashMap::new(),
+            max_age_ms,
+        }
+    }
+
+    /// Record one strap's reading and return the fused reading.
+    pub fn fuse(&mut self, bpm: u8, epoch_ms: u64, device_id: &str) -> SensorReading {
+        self.latest.insert(device_id.to_string(), (bpm, epoch_ms));
+        let max_age_ms = self.max_age_ms;
+        self.latest
+            .retain(|_, (_, seen_ms)| epoch_ms.saturating_sub(*seen_ms) <= max_age_ms);
+
+        let sum: u32 = self.latest.values().map(|(b, _)| *b as u32).sum();
+        let avg = (sum as f64 / self.latest.len() as f64).round() as u8;
+        SensorReading::HeartRate {
+            bpm: avg,
+            timestamp: Some(std::time::Instant::now()),
+            epoch_ms,
+            device_id: FUSION_DEVICE_ID.to_string(),
+        }
+    }
+
+    /// Forget all straps, e.g. when fusion is switched off.
+    pub fn clear(&mut self) {
+        self.latest.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fused_bpm(reading: SensorReading) -> u8 {
+        match reading {
+            SensorReading::HeartRate { bpm, device_id, .. } => {
+                assert_eq!(device_id, FUSION_DEVICE_ID);
+                bpm
+            }
+            other => panic!("expected HeartRate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fused_stream_is_average_of_latest_values() {
+        let mut fusion = HrFusion::new(5000);
+        let chest = [140u8, 142, 145, 150, 152];
+        let arm = [136u8, 140, 141, 146, 150];
+        for i in 0..chest.len() {
+            let t = i as u64 * 1000;
+            let after_chest = fused_bpm(fusion.fuse(chest[i], t, "hr-chest"));
+            if i > 0 {
+                // Chest updated, armband still at its previous value
+                let expected = (chest[i] as f64 + arm[i - 1] as f64) / 2.0;
+                assert_eq!(after_chest, expected.round() as u8);
+            } else {
+                assert_eq!(after_chest, chest[0]);
+            }
+            let after_arm = fused_bpm(fusion.fuse(arm[i], t + 500, "hr-arm"));
+            let expected = (chest[i] as f64 + arm[i] as f64) / 2.0;
+            assert_eq!(after_arm, expected.round() as u8);
+        }
+    }
+
+    #[test]
+    fn silent_strap_drops_out_of_average() {
+        let mut fusion = HrFusion::new(5000);
+        fusion.fuse(150, 0, "hr-chest");
+        assert_eq!(fused_bpm(fusion.fuse(130, 1000, "hr-arm")), 140);
+        // Chest strap silent for 6s: only the armband remains
+        assert_eq!(fused_bpm(fusion.fuse(132, 6000, "hr-arm")), 132);
+    }
+}
@@ -1,11 +1,14 @@
 use chrono::Utc;
 use log::info;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-use super::metrics::MetricsCalculator;
+use super::metrics::{self, LapMetrics, MetricsCalculator};
+use super::physics::VirtualSpeed;
 use super::types::*;
 use crate::device::types::SensorReading;
 
@@ -13,19 +16,67 @@ use crate::config;
 
 pub struct SessionManager {
     current_session: Arc<Mutex<Option<ActiveSession>>>,
+    /// Opt-in A/B power recording for the active session. Shared with device
+    /// listeners so non-primary power readings reach the processor while set.
+    dual_record: Arc<AtomicBool>,
+    /// Blend all connected HR straps into one averaged stream. Shared with
+    /// device listeners so non-primary HR readings get through while set.
+    hr_fusion: Arc<AtomicBool>,
 }
 
 /// Maximum gap between readings before we stop counting elapsed time.
 /// Prevents pauses, sensor drops, or reconnects from inflating duration.
 const MAX_READING_GAP_SECS: u64 = 5;
 
+/// Maps monotonic capture times onto the session's wall-clock start, so a
+/// system clock jump (NTP sync, sleep/wake) mid-ride can't reorder readings
+/// or distort the durations analysis derives from `epoch_ms`.
+struct SessionClock {
+    start: Instant,
+    start_epoch_ms: u64,
+}
+
+impl SessionClock {
+    fn new(start_time: chrono::DateTime<Utc>) -> Self {
+        Self {
+            start: Instant::now(),
+            start_epoch_ms: start_time.timestamp_millis().max(0) as u64,
+        }
+    }
+
+    fn epoch_ms_at(&self, at: Instant) -> u64 {
+        self.start_epoch_ms + at.saturating_duration_since(self.start).as_millis() as u64
+    }
+
+    /// Monotonic-derived `epoch_ms` for a live reading. Readings that carry a
+    /// capture `Instant` use it; variants without one were just produced, so
+    /// use now. Readings of the former built without an `Instant` (replayed
+    /// or imported data) keep their own timestamp.
+    fn restamp(&self, reading: &mut SensorReading) {
+        let at = match reading {
+            SensorReading::Power { timestamp, .. }
+            | SensorReading::HeartRate { timestamp, .. }
+            | SensorReading::Cadence { timestamp, .. }
+            | SensorReading::Speed { timestamp, .. } => match timestamp {
+                Some(at) => *at,
+                None => return,
+            },
+            _ => Instant::now(),
+        };
+        reading.set_epoch_ms(self.epoch_ms_at(at));
+    }
+}
+
 struct ActiveSession {
     id: String,
     config: SessionConfig,
     status: SessionStatus,
     metrics: MetricsCalculator,
+    /// Current-lap power metrics; whole-ride totals stay in `metrics`
+    lap: LapMetrics,
     sensor_log: Vec<SensorReading>,
     start_time: chrono::DateTime<chrono::Utc>,
+    clock: SessionClock,
     /// Accumulated active riding time (excludes pauses and gaps > MAX_READING_GAP_SECS)
     active_elapsed_ms: u64,
     /// Wall-clock time of last processed reading (for computing deltas)
@@ -36,28 +87,95 @@ struct ActiveSession {
     last_speed: Option<Instant>,
     /// Index up to which sensor_log has been snapshotted for autosave
     autosave_cursor: usize,
+    /// Stopped by `config.auto_pause_secs` rather than the user
+    auto_paused: bool,
+    /// Last power or cadence reading showing the rider pedalling
+    last_pedaling: Option<Instant>,
+    /// `active_elapsed_ms` as of `last_pedaling`, restored when auto-pausing
+    /// so the coast before the pause isn't counted
+    active_ms_at_last_pedaling: u64,
+    /// Speed from power, when `config.virtual_speed` is on
+    virtual_speed: Option<VirtualSpeed>,
+}
+
+impl ActiveSession {
+    fn calories(&self) -> Option<u32> {
+        let age = self
+            .config
+            .date_of_birth
+            .as_deref()
+            .and_then(|dob| metrics::age_on(dob, self.start_time.date_naive()));
+        metrics::estimate_calories(
+            &self.sensor_log,
+            self.config.weight_kg,
+            self.metrics.avg_hr(),
+            self.config.sex.as_deref(),
+            age,
+        )
+    }
 }
 
 impl SessionManager {
     pub fn new() -> Self {
         Self {
             current_session: Arc::new(Mutex::new(None)),
+            dual_record: Arc::new(AtomicBool::new(false)),
+            hr_fusion: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    pub fn dual_record_handle(&self) -> Arc<AtomicBool> {
+        self.dual_record.clone()
+    }
+
+    pub fn hr_fusion_handle(&self) -> Arc<AtomicBool> {
+        self.hr_fusion.clone()
+    }
+
+    /// Average every connected HR strap instead of using only the primary.
+    /// Unlike dual recording this is not tied to a session.
+    pub fn set_hr_fusion(&self, enabled: bool) {
+        info!("HR fusion {}", if enabled { "enabled" } else { "disabled" });
+        self.hr_fusion.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn hr_fusion_enabled(&self) -> bool {
+        self.hr_fusion.load(Ordering::Relaxed)
+    }
+
+    /// Record power from every connected meter for the rest of the active
+    /// session. Cleared automatically when the session stops or is discarded.
+    pub async fn set_dual_record(&self, enabled: bool) -> Result<(), crate::error::AppError> {
+        let lock = self.current_session.lock().await;
+        let Some(session) = lock.as_ref() else {
+            return Err(crate::error::AppError::Session("No active session".into()));
+        };
+        info!(
+            "Dual power recording {} for session {}",
+            if enabled { "enabled" } else { "disabled" },
+            session.id
+        );
+        self.dual_record.store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
     pub async fn start_session(&self, config: SessionConfig) -> Result<String, crate::error::AppError> {
         let mut lock = self.current_session.lock().await;
         if lock.is_some() {
             return Err(crate::error::AppError::Session("Session already active".into()));
         }
         let id = Uuid::new_v4().to_string();
+        let start_time = Utc::now();
+        let virtual_speed = VirtualSpeed::from_config(&config);
         let session = ActiveSession {
             id: id.clone(),
-            metrics: MetricsCalculator::new(config.ftp),
+            metrics: MetricsCalculator::new(config.ftp).with_max_gap_ms(config.max_gap_ms()),
+            lap: LapMetrics::new(config.ftp),
             config,
             status: SessionStatus::Running,
             sensor_log: Vec::new(),
-            start_time: Utc::now(),
+            start_time,
+            clock: SessionClock::new(start_time),
             active_elapsed_ms: 0,
             last_reading_time: None,
             last_power: None,
@@ -65,6 +183,10 @@ impl SessionManager {
             last_cadence: None,
             last_speed: None,
             autosave_cursor: 0,
+            auto_paused: false,
+            last_pedaling: None,
+            active_ms_at_last_pedaling: 0,
+            virtual_speed,
         };
         *lock = Some(session);
         info!("Session started: {}", id);
@@ -81,9 +203,11 @@ impl SessionManager {
     ) -> Option<(SessionSummary, Vec<SensorReading>)> {
         let mut lock = self.current_session.lock().await;
         let session = lock.take()?;
+        self.dual_record.store(false, Ordering::Relaxed);
         info!("Session stopped: {}", session.id);
         let active_secs = session.active_elapsed_ms / 1000;
-        let summary = SessionSummary {
+        let calories = session.calories();
+        let mut summary = SessionSummary {
             id: session.id,
             start_time: session.start_time,
             duration_secs: active_secs,
@@ -104,10 +228,37 @@ impl SessionManager {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: session.metrics.efficiency_factor(),
+            calories,
+            avg_cadence_active: session.metrics.avg_cadence_active(),
+            avg_temp_c: session.metrics.avg_temp_c(),
+            total_ascent_m: super::analysis::compute_elevation_gain(
+                &session.sensor_log,
+                session.config.max_gap_ms(),
+            ),
+            is_favorite: false,
+            xpower: metrics::compute_xpower(&session.sensor_log),
+            data_quality_score: metrics::validate_work(&session.sensor_log).map(|v| v.quality_score),
+            avg_pedal_balance: super::analysis::compute_avg_pedal_balance(&session.sensor_log),
         };
+        summary.activity_type =
+            Some(super::analysis::classify_activity(&summary, &session.sensor_log).to_string());
         Some((summary, session.sensor_log))
     }
 
+    /// End the active session without producing a summary. Returns its id so
+    /// the caller can clean up any autosave checkpoint.
+    pub async fn discard_session(&self) -> Option<String> {
+        let session = self.current_session.lock().await.take()?;
+        self.dual_record.store(false, Ordering::Relaxed);
+        info!("Session discarded: {}", session.id);
+        Some(session.id)
+    }
+
+    pub async fn active_session_id(&self) -> Option<String> {
+        self.current_session.lock().await.as_ref().map(|s| s.id.clone())
+    }
+
     pub async fn pause_session(&self) {
         if let Some(session) = self.current_session.lock().await.as_mut() {
             info!("Session paused: {}", session.id);
@@ -117,37 +268,116 @@ impl SessionManager {
         }
     }
 
+    /// Start a new lap. Returns the new lap number, or `None` without a session.
+    pub async fn new_lap(&self) -> Option<u32> {
+        let mut lock = self.current_session.lock().await;
+        let session = lock.as_mut()?;
+        session.lap.new_lap(session.active_elapsed_ms);
+        let epoch_ms = session.clock.epoch_ms_at(Instant::now());
+        session.sensor_log.push(SensorReading::Lap { epoch_ms });
+        info!("Session {}: lap {}", session.id, session.lap.number());
+        Some(session.lap.number())
+    }
+
     pub async fn resume_session(&self) {
+        self.resume_session_at(Instant::now()).await
+    }
+
+    async fn resume_session_at(&self, now: Instant) {
         if let Some(session) = self.current_session.lock().await.as_mut() {
             info!("Session resumed: {}", session.id);
             session.status = SessionStatus::Running;
+            session.auto_paused = false;
+            // Restart the auto-pause window so time spent paused doesn't count as coasting
+            session.last_pedaling = Some(now);
+            session.active_ms_at_last_pedaling = session.active_elapsed_ms;
         }
     }
 
-    pub async fn process_reading(&self, reading: SensorReading) {
+    /// Record a reading into the active session. The outcome reports an
+    /// auto-pause or auto-resume and any virtual speed derived from it.
+    pub async fn process_reading(&self, reading: SensorReading) -> ReadingOutcome {
+        self.process_reading_at(reading, Instant::now()).await
+    }
+
+    async fn process_reading_at(&self, mut reading: SensorReading, now: Instant) -> ReadingOutcome {
         let mut lock = self.current_session.lock().await;
         let Some(session) = lock.as_mut() else {
-            return;
+            return ReadingOutcome::default();
         };
         if session.status != SessionStatus::Running {
-            return;
+            return ReadingOutcome::default();
+        }
+        // Radar updates are live-only: they don't count as activity and are
+        // too frequent to be worth keeping in the sensor log.
+        if matches!(reading, SensorReading::RadarTargets { .. }) {
+            return ReadingOutcome::default();
+        }
+
+        let mut event = None;
+        let auto_pause = session.config.auto_pause_secs;
+        let pedaling = match &reading {
+            SensorReading::Power { watts, .. } => *watts > 0,
+            SensorReading::Cadence { rpm, .. } => *rpm >= 1.0,
+            _ => false,
+        };
+        if let Some(limit) = auto_pause {
+            if pedaling {
+                if session.auto_paused {
+                    info!("Session auto-resumed: {}", session.id);
+                    session.auto_paused = false;
+                    event = Some(AutoPauseEvent::Resumed);
+                }
+                session.last_pedaling = Some(now);
+            } else if session.auto_paused {
+                return ReadingOutcome::default();
+            } else if let Some(last) = session.last_pedaling {
+                if now.saturating_duration_since(last) >= Duration::from_secs(limit as u64) {
+                    info!("Session auto-paused: {}", session.id);
+                    session.active_elapsed_ms = session.active_ms_at_last_pedaling;
+                    session.auto_paused = true;
+                    session.last_reading_time = None;
+                    return ReadingOutcome {
+                        auto_pause: Some(AutoPauseEvent::Paused),
+                        ..Default::default()
+                    };
+                }
+            }
         }
 
         // Accumulate active elapsed time (any reading type counts)
-        let now = Instant::now();
         if let Some(prev) = session.last_reading_time {
-            let delta_ms = prev.elapsed().as_millis() as u64;
+            let delta_ms = now.saturating_duration_since(prev).as_millis() as u64;
             // Cap gap to avoid counting sensor dropouts or reconnects
             let capped = delta_ms.min(MAX_READING_GAP_SECS * 1000);
             session.active_elapsed_ms += capped;
         }
         session.last_reading_time = Some(now);
-
+        if pedaling && auto_pause.is_some() {
+            session.active_ms_at_last_pedaling = session.active_elapsed_ms;
+        }
+        session.clock.restamp(&mut reading);
+
+        let virtual_speed = session
+            .virtual_speed
+            .as_mut()
+            .and_then(|model| model.observe(&reading));
+        if let Some(SensorReading::Speed { kmh, epoch_ms, .. }) = &virtual_speed {
+            session.metrics.record_speed(*kmh, *epoch_ms);
+            session.last_speed = Some(now);
+        }
         match &reading {
             SensorReading::Power {
-                watts, epoch_ms, ..
+                watts,
+                epoch_ms,
+                pedal_balance,
+                ..
             } => {
                 session.metrics.record_power(*watts, *epoch_ms);
+                if let Some(balance) = pedal_balance {
+                    session.metrics.record_balance(*balance, *epoch_ms);
+                }
+                session.lap.record_power(*watts, *epoch_ms);
                 session.last_power = Some(now);
             }
             SensorReading::HeartRate { bpm, .. } => {
@@ -162,11 +392,48 @@ impl SessionManager {
                 session.metrics.record_speed(*kmh, *epoch_ms);
                 session.last_speed = Some(now);
             }
-            SensorReading::TrainerCommand { .. } => {
+            SensorReading::Energy {
+                kilojoules,
+                device_id,
+                ..
+            } => {
+                session.metrics.record_energy(device_id, *kilojoules);
+            }
+            SensorReading::Temperature { celsius, .. } => {
+                session.metrics.record_temperature(*celsius);
+            }
+            SensorReading::TrainerCommand { .. }
+            | SensorReading::Hrv { .. }
+            | SensorReading::MuscleOxygen { .. }
+            | SensorReading::RadarTargets { .. }
+            | SensorReading::Gear { .. }
+            | SensorReading::SecondaryPower { .. }
+            | SensorReading::Grade { .. }
+            | SensorReading::Pedaling { .. }
+            | SensorReading::Lap { .. }
+            | SensorReading::AveragePower { .. } => {
                 // No metrics to record — logged to sensor_log below
             }
         }
         session.sensor_log.push(reading);
+        session.sensor_log.extend(virtual_speed.clone());
+        ReadingOutcome {
+            auto_pause: event,
+            virtual_speed,
+        }
+    }
+
+    /// Payload of the `live_metrics` event the push task emits, `None` when no
+    /// session is running. Serialized the same way as `get_live_metrics`.
+    pub async fn live_metrics_event(&self) -> Option<serde_json::Value> {
+        let metrics = self.get_live_metrics().await?;
+        match serde_json::to_value(metrics) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::warn!("Failed to serialize live metrics: {}", e);
+                None
+            }
+        }
     }
 
     pub async fn get_live_metrics(&self) -> Option<LiveMetrics> {
@@ -181,20 +448,33 @@ impl SessionManager {
             elapsed_secs: active_secs,
             current_power: session.metrics.current_power(),
             avg_power_3s: session.metrics.avg_power(3),
+            smoothed_power_3s: session.metrics.smoothed_power_3s(),
             avg_power_10s: session.metrics.avg_power(10),
             avg_power_30s: session.metrics.avg_power(30),
-            normalized_power: session.metrics.normalized_power(),
+            normalized_power: session.metrics.current_np(),
             tss: session.metrics.tss(active_secs),
-            intensity_factor: session.metrics.intensity_factor(),
+            intensity_factor: session.metrics.current_if(session.config.ftp),
             current_hr: session.metrics.current_hr(),
             current_cadence: session.metrics.current_cadence(),
             current_speed: session.metrics.current_speed(),
             hr_zone: session.metrics.hr_zone(&session.config.hr_zones),
-            power_zone: session.metrics.power_zone(session.config.ftp, &session.config.power_zones),
+            power_zone: session.metrics.power_zone(
+                session.config.ftp,
+                session
+                    .config
+                    .power_zone_model
+                    .boundaries(&session.config.power_zones),
+            ),
             stale_power: is_stale(session.last_power),
             stale_hr: is_stale(session.last_hr),
             stale_cadence: is_stale(session.last_cadence),
             stale_speed: is_stale(session.last_speed),
+            lap_number: session.lap.number(),
+            lap_elapsed_secs: session.lap.elapsed_secs(session.active_elapsed_ms),
+            lap_avg_power: session.lap.avg_power(),
+            lap_normalized_power: session.lap.normalized_power(),
+            pedal_balance: session.metrics.current_balance(),
+            avg_pedal_balance_30s: session.metrics.avg_balance_30s(),
         })
     }
 
@@ -226,13 +506,49 @@ impl SessionManager {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: session.metrics.efficiency_factor(),
+            calories: session.calories(),
+            avg_cadence_active: session.metrics.avg_cadence_active(),
+            avg_temp_c: session.metrics.avg_temp_c(),
+            total_ascent_m: super::analysis::compute_elevation_gain(
+                &session.sensor_log,
+                session.config.max_gap_ms(),
+            ),
+            is_favorite: false,
+            xpower: metrics::compute_xpower(&session.sensor_log),
+            data_quality_score: metrics::validate_work(&session.sensor_log).map(|v| v.quality_score),
+            avg_pedal_balance: super::analysis::compute_avg_pedal_balance(&session.sensor_log),
         };
         let delta = session.sensor_log[session.autosave_cursor..].to_vec();
         session.autosave_cursor = session.sensor_log.len();
         Some((session.id.clone(), summary, delta))
     }
 
-    #[allow(dead_code)]
+    /// Average power per device over the last `window_ms` of the active
+    /// session's log. Counts dual-recorded `SecondaryPower` too, so a trainer
+    /// and power meter both appear while dual recording. Empty when idle.
+    pub async fn recent_power_by_device(&self, window_ms: u64) -> HashMap<String, f64> {
+        let lock = self.current_session.lock().await;
+        let Some(session) = lock.as_ref() else {
+            return HashMap::new();
+        };
+        // Logged readings carry session-clock stamps, so measure the window on that clock
+        let cutoff = session.clock.epoch_ms_at(Instant::now()).saturating_sub(window_ms);
+        let mut sums: HashMap<&str, (u64, u32)> = HashMap::new();
+        for reading in session.sensor_log.iter().rev().take_while(|r| r.epoch_ms() >= cutoff) {
+            if let SensorReading::Power { watts, device_id, .. }
+            | SensorReading::SecondaryPower { watts, device_id, .. } = reading
+            {
+                let entry = sums.entry(device_id.as_str()).or_default();
+                entry.0 += *watts as u64;
+                entry.1 += 1;
+            }
+        }
+        sums.into_iter()
+            .map(|(id, (sum, count))| (id.to_string(), sum as f64 / count as f64))
+            .collect()
+    }
+
     pub async fn is_active(&self) -> bool {
         self.current_session.lock().await.is_some()
     }
@@ -303,6 +619,133 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn discard_leaves_no_session_or_autosave() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = crate::session::storage::Storage::new(&tmp.path().to_string_lossy())
+            .await
+            .unwrap();
+        let mgr = SessionManager::new();
+        let id = mgr.start_session(default_config()).await.unwrap();
+        mgr.process_reading(power_reading(200)).await;
+        mgr.process_reading(hr_reading(140)).await;
+
+        // Autosave checkpoint as the background task would write it
+        let (snap_id, summary, log) = mgr.snapshot_for_autosave().await.unwrap();
+        storage.write_autosave(&snap_id, &summary, &log).await.unwrap();
+
+        assert_eq!(mgr.discard_session().await, Some(id.clone()));
+        storage.remove_autosave(&id);
+
+        assert!(!mgr.is_active().await);
+        assert!(mgr.active_session_id().await.is_none());
+        assert!(mgr.stop_session().await.is_none());
+        // Readings after discard are not routed anywhere
+        mgr.process_reading(power_reading(300)).await;
+        assert!(mgr.get_sensor_log().await.is_empty());
+
+        assert!(storage.list_sessions().await.unwrap().is_empty());
+        let autosave = tmp.path().join("sessions").join(format!(".autosave_{}.bin", id));
+        assert!(!autosave.exists());
+        assert_eq!(storage.recover_autosaved_sessions().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn discard_without_start_returns_none() {
+        let mgr = SessionManager::new();
+        assert!(mgr.discard_session().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn radar_targets_are_not_logged() {
+        let mgr = SessionManager::new();
+        mgr.start_session(default_config()).await.unwrap();
+        mgr.process_reading(SensorReading::RadarTargets {
+            threats: Vec::new(),
+            epoch_ms: 0,
+            device_id: "radar-1".to_string(),
+        })
+        .await;
+        assert!(mgr.get_sensor_log().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dual_record_requires_session_and_clears_on_stop() {
+        let mgr = SessionManager::new();
+        assert!(mgr.set_dual_record(true).await.is_err());
+        mgr.start_session(default_config()).await.unwrap();
+        mgr.set_dual_record(true).await.unwrap();
+        assert!(mgr.dual_record_handle().load(Ordering::Relaxed));
+
+        mgr.process_reading(power_reading(200)).await;
+        mgr.process_reading(SensorReading::SecondaryPower {
+            watts: 400,
+            epoch_ms: 0,
+            device_id: "pm-2".to_string(),
+        })
+        .await;
+        let (summary, log) = mgr.stop_session_with_log().await.unwrap();
+        // Secondary power is logged but never reaches the session metrics
+        assert_eq!(summary.max_power, Some(200));
+        assert_eq!(log.len(), 2);
+        assert!(!mgr.dual_record_handle().load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn backward_clock_jump_keeps_timeline_monotonic() {
+        let mgr = SessionManager::new();
+        mgr.start_session(default_config()).await.unwrap();
+        let base = Instant::now();
+        // 20s of 1Hz power; the wall clock jumps back an hour after 10s
+        let wall_start: u64 = 1_700_000_000_000;
+        for i in 0..20u64 {
+            let wall = if i < 10 {
+                wall_start + i * 1000
+            } else {
+                wall_start - 3_600_000 + i * 1000
+            };
+            mgr.process_reading(SensorReading::Power {
+                watts: 200,
+                timestamp: Some(base + std::time::Duration::from_secs(i)),
+                epoch_ms: wall,
+                device_id: "test".to_string(),
+                pedal_balance: None,
+            })
+            .await;
+        }
+        let (_, log) = mgr.stop_session_with_log().await.unwrap();
+
+        let stamps: Vec<u64> = log.iter().map(|r| r.epoch_ms()).collect();
+        assert!(stamps.windows(2).all(|w| w[1] > w[0]), "epoch_ms went backwards: {stamps:?}");
+        let span = stamps.last().unwrap() - stamps.first().unwrap();
+        assert!((18_900..=19_100).contains(&span), "span {span}ms should be ~19s");
+
+        let timeseries = crate::session::analysis::build_timeseries_from_readings(&log, 20);
+        assert!(timeseries.windows(2).all(|w| w[1].elapsed_secs > w[0].elapsed_secs));
+        assert!(timeseries.iter().all(|p| p.power == Some(200)), "every second should have power");
+    }
+
+    #[tokio::test]
+    async fn recent_power_uses_session_clock_when_wall_clock_skews() {
+        let mgr = SessionManager::new();
+        mgr.start_session(default_config()).await.unwrap();
+        // The wall clock jumped forward an hour since the session started
+        mgr.current_session.lock().await.as_mut().unwrap().clock.start_epoch_ms -= 3_600_000;
+        for watts in [190, 210] {
+            mgr.process_reading(SensorReading::Power {
+                watts,
+                timestamp: Some(Instant::now()),
+                epoch_ms: 0,
+                device_id: "trainer".to_string(),
+                pedal_balance: None,
+            })
+            .await;
+        }
+        let recent = mgr.recent_power_by_device(5_000).await;
+        assert_eq!(recent.len(), 1);
+        assert!((recent["trainer"] - 200.0).abs() < 1e-9);
+    }
+
     #[tokio::test]
     async fn stop_without_start_returns_none() {
         let mgr = SessionManager::new();
@@ -468,6 +911,72 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn virtual_speed_gives_trainer_rides_a_distance() {
+        let mgr = SessionManager::new();
+        let config = SessionConfig {
+            virtual_speed: true,
+            ..default_config()
+        };
+        mgr.start_session(config).await.unwrap();
+        feed_constant_power(&mgr, 200, 121, 0).await;
+
+        let kmh = crate::session::physics::virtual_speed(
+            200,
+            0.0,
+            crate::config::DEFAULT_VIRTUAL_CRR,
+            crate::config::DEFAULT_VIRTUAL_CDA_M2,
+            75.0 + crate::config::DEFAULT_BIKE_WEIGHT_KG,
+        );
+        let speeds = mgr
+            .get_sensor_log()
+            .await
+            .iter()
+            .filter(|r| matches!(r, SensorReading::Speed { .. }))
+            .count();
+        assert_eq!(speeds, 121);
+        let summary = mgr.stop_session().await.unwrap();
+        let km = summary.distance_km.unwrap();
+        assert!((km - kmh * 120.0 / 3600.0).abs() < 0.01, "got {km} km");
+
+        // Off by default: power alone gives no distance
+        mgr.start_session(default_config()).await.unwrap();
+        feed_constant_power(&mgr, 200, 121, 0).await;
+        assert!(mgr.stop_session().await.unwrap().distance_km.is_none());
+    }
+
+    #[tokio::test]
+    async fn speed_sensor_takes_over_from_virtual_speed() {
+        let mgr = SessionManager::new();
+        let config = SessionConfig {
+            virtual_speed: true,
+            ..default_config()
+        };
+        mgr.start_session(config).await.unwrap();
+        let outcome = mgr.process_reading(power_reading_at(200, 0)).await;
+        let Some(SensorReading::Speed { kmh: virtual_kmh, .. }) = outcome.virtual_speed else {
+            panic!("expected a virtual speed reading");
+        };
+        mgr.process_reading(SensorReading::Speed {
+            kmh: 30.0,
+            timestamp: None,
+            epoch_ms: 500,
+            device_id: "spd".to_string(),
+        })
+        .await;
+        assert!(mgr.process_reading(power_reading_at(200, 1000)).await.virtual_speed.is_none());
+
+        let log = mgr.get_sensor_log().await;
+        let speeds: Vec<f32> = log
+            .iter()
+            .filter_map(|r| match r {
+                SensorReading::Speed { kmh, .. } => Some(*kmh),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(speeds, vec![virtual_kmh, 30.0]);
+    }
+
     #[tokio::test]
     async fn stop_summary_includes_np_tss_if() {
         let mgr = SessionManager::new();
@@ -504,6 +1013,26 @@ mod tests {
         assert!(live.tss.is_some(), "live TSS should be computed when NP is available");
     }
 
+    #[tokio::test]
+    async fn pushed_live_metrics_match_pulled_snapshot() {
+        let mgr = SessionManager::new();
+        // No session: the push task emits nothing
+        assert!(mgr.live_metrics_event().await.is_none());
+        mgr.start_session(default_config()).await.unwrap();
+        feed_constant_power(&mgr, 200, 35, 0).await;
+        mgr.process_reading(hr_reading(140)).await;
+
+        // The push task emits the same snapshot get_live_metrics returns, serialized
+        let pushed = mgr.live_metrics_event().await.unwrap();
+        let pulled = mgr.get_live_metrics().await.unwrap();
+        assert_eq!(pushed, serde_json::to_value(&pulled).unwrap());
+        assert_eq!(pushed["current_power"], 200);
+        assert_eq!(pushed["current_hr"], 140);
+
+        mgr.stop_session().await;
+        assert!(mgr.live_metrics_event().await.is_none());
+    }
+
     #[tokio::test]
     async fn snapshot_includes_np_tss_if() {
         let mgr = SessionManager::new();
@@ -532,4 +1061,91 @@ mod tests {
         assert!(summary.intensity_factor.is_none());
         assert!(summary.tss.is_none());
     }
+
+    #[tokio::test]
+    async fn auto_pause_excludes_coast_from_duration() {
+        let mgr = SessionManager::new();
+        let config = SessionConfig {
+            auto_pause_secs: Some(5),
+            ..default_config()
+        };
+        mgr.start_session(config).await.unwrap();
+        let base = Instant::now();
+        let at = |secs: u64| base + Duration::from_secs(secs);
+        let cadence = |rpm: f32| SensorReading::Cadence {
+            rpm,
+            timestamp: None,
+            epoch_ms: 0,
+            device_id: "test".to_string(),
+        };
+
+        // 60s of pedalling
+        for t in 0..=60 {
+            assert_eq!(mgr.process_reading_at(power_reading(200), at(t)).await.auto_pause, None);
+        }
+        // Coast: zero power and cadence for two minutes
+        let mut events = Vec::new();
+        for t in 61..=180 {
+            events.extend(mgr.process_reading_at(power_reading(0), at(t)).await.auto_pause);
+            events.extend(mgr.process_reading_at(cadence(0.0), at(t)).await.auto_pause);
+            events.extend(mgr.process_reading_at(hr_reading(120), at(t)).await.auto_pause);
+        }
+        assert_eq!(events, vec![AutoPauseEvent::Paused]);
+        // Next nonzero reading resumes, then another 60s of pedalling
+        assert_eq!(
+            mgr.process_reading_at(cadence(85.0), at(181)).await.auto_pause,
+            Some(AutoPauseEvent::Resumed)
+        );
+        for t in 182..=241 {
+            assert_eq!(mgr.process_reading_at(power_reading(200), at(t)).await.auto_pause, None);
+        }
+
+        let summary = mgr.stop_session().await.unwrap();
+        assert_eq!(summary.duration_secs, 120);
+    }
+
+    #[tokio::test]
+    async fn coasting_without_auto_pause_counts_toward_duration() {
+        let mgr = SessionManager::new();
+        mgr.start_session(default_config()).await.unwrap();
+        let base = Instant::now();
+        for t in 0..=60 {
+            let watts = if t < 30 { 200 } else { 0 };
+            let outcome = mgr
+                .process_reading_at(power_reading(watts), base + Duration::from_secs(t))
+                .await;
+            assert_eq!(outcome.auto_pause, None);
+        }
+        let summary = mgr.stop_session().await.unwrap();
+        assert_eq!(summary.duration_secs, 60);
+    }
+
+    #[tokio::test]
+    async fn manual_resume_restarts_auto_pause_window() {
+        let mgr = SessionManager::new();
+        let config = SessionConfig {
+            auto_pause_secs: Some(5),
+            ..default_config()
+        };
+        mgr.start_session(config).await.unwrap();
+        let base = Instant::now();
+        let at = |secs: u64| base + Duration::from_secs(secs);
+
+        for t in 0..=30 {
+            mgr.process_reading_at(power_reading(200), at(t)).await;
+        }
+        // Paused by hand for a minute, then resumed while not yet pedalling
+        mgr.pause_session().await;
+        mgr.resume_session_at(at(90)).await;
+        for t in 91..=94 {
+            assert_eq!(mgr.process_reading_at(power_reading(0), at(t)).await.auto_pause, None);
+        }
+        assert_eq!(
+            mgr.process_reading_at(power_reading(0), at(95)).await.auto_pause,
+            Some(AutoPauseEvent::Paused)
+        );
+
+        let summary = mgr.stop_session().await.unwrap();
+        assert_eq!(summary.duration_secs, 30);
+    }
 }
@@ -1,4 +1,56 @@
-use std::collections::VecDeque;
+use chrono::NaiveDate;
+use std::collections::{HashMap, VecDeque};
+
+use crate::device::types::SensorReading;
+
+/// Gross efficiency assumed when converting pedalling work to energy expenditure.
+const GROSS_EFFICIENCY: f64 = 0.24;
+const KJ_PER_KCAL: f64 = 4.184;
+/// Time constant of the exponential average used for xPower.
+const XPOWER_TIME_CONSTANT_SECS: f64 = 25.0;
+/// Window for the smoothed live power display.
+const SMOOTHED_POWER_WINDOW_MS: u64 = 3000;
+/// Window for the rolling pedal balance average.
+const BALANCE_WINDOW_MS: u64 = 30_000;
+/// Integrated work this far (percent) from average power × duration is flagged.
+const WORK_DISCREPANCY_FLAG_PCT: f32 = 10.0;
+/// Range of the BLE Accumulated Energy uint16 counter (kJ)
+const ENERGY_COUNTER_MAX: f32 = 65536.0;
+/// A drop counts as a rollover only if it crosses the top within this many kJ;
+/// far more than any device accumulates between two readings.
+const ENERGY_ROLLOVER_WINDOW_KJ: f32 = 1000.0;
+
+/// One device's accumulated energy counter.
+#[derive(Default)]
+struct EnergyCounter {
+    last_reading: Option<f32>,
+    total_kj: f64,
+    intervals: u32,
+}
+
+impl EnergyCounter {
+    /// The BLE field is a uint16 counter: a drop from near the top to near zero
+    /// is a rollover, any other drop is a reset (reconnect, device switch) and
+    /// counting restarts from the new value.
+    fn record(&mut self, kilojoules: f32) {
+        if let Some(prev) = self.last_reading {
+            let delta = if kilojoules >= prev {
+                Some(kilojoules - prev)
+            } else if prev > ENERGY_COUNTER_MAX - ENERGY_ROLLOVER_WINDOW_KJ
+                && kilojoules < ENERGY_ROLLOVER_WINDOW_KJ
+            {
+                Some(kilojoules + ENERGY_COUNTER_MAX - prev)
+            } else {
+                None
+            };
+            if let Some(delta) = delta {
+                self.total_kj += delta as f64;
+                self.intervals += 1;
+            }
+        }
+        self.last_reading = Some(kilojoules);
+    }
+}
 
 pub struct MetricsCalculator {
     ftp: u16,
@@ -17,6 +69,18 @@ pub struct MetricsCalculator {
     cadence_readings: Vec<f32>,
     speed_history: Vec<(u64, f32)>,
     last_speed_ms: Option<u64>,
+    /// Longest gap between speed samples counted toward distance; the user's
+    /// `max_gap_secs`, as in analysis
+    max_gap_ms: u64,
+    /// Distance integrated from speed samples as they arrive
+    distance_km: f64,
+    /// Accumulated energy counters, one per reporting device (power meter,
+    /// FTMS trainer), so one device's counter never offsets another's
+    energy_counters: HashMap<String, EnergyCounter>,
+    temperature_sum: f64,
+    temperature_count: u32,
+    /// Right-pedal % from dual-sided meters, pruned to the last 30s
+    balance_history: VecDeque<(u64, u8)>,
 }
 
 impl MetricsCalculator {
@@ -34,9 +98,21 @@ impl MetricsCalculator {
             cadence_readings: Vec::new(),
             speed_history: Vec::new(),
             last_speed_ms: None,
+            max_gap_ms: crate::config::DEFAULT_MAX_GAP_SECS * 1000,
+            distance_km: 0.0,
+            energy_counters: HashMap::new(),
+            temperature_sum: 0.0,
+            temperature_count: 0,
+            balance_history: VecDeque::new(),
         }
     }
 
+    /// Cap gaps between speed samples at `max_gap_ms` instead of the default.
+    pub fn with_max_gap_ms(mut self, max_gap_ms: u64) -> Self {
+        self.max_gap_ms = max_gap_ms;
+        self
+    }
+
     pub fn record_power(&mut self, watts: u16, epoch_ms: u64) {
         // Enforce monotonicity: clamp backward jumps from clock adjustments
         // or device switches so windowed averages stay consistent.
@@ -90,6 +166,19 @@ impl MetricsCalculator {
         }
     }
 
+    /// Record a device-reported accumulated energy value (kJ) from `device_id`.
+    pub fn record_energy(&mut self, device_id: &str, kilojoules: f32) {
+        self.energy_counters
+            .entry(device_id.to_string())
+            .or_default()
+            .record(kilojoules);
+    }
+
+    pub fn record_temperature(&mut self, celsius: f32) {
+        self.temperature_sum += celsius as f64;
+        self.temperature_count += 1;
+    }
+
     pub fn record_hr(&mut self, bpm: u8) {
         self.hr_readings.push(bpm);
     }
@@ -98,15 +187,55 @@ impl MetricsCalculator {
         self.cadence_readings.push(rpm);
     }
 
+    /// Record a speed sample and add the distance covered since the previous
+    /// one at that sample's speed. Gaps over `max_gap_ms` (a dropout or pause)
+    /// only count up to the cap.
     pub fn record_speed(&mut self, kmh: f32, epoch_ms: u64) {
         let ts = match self.last_speed_ms {
             Some(prev) if epoch_ms < prev => prev,
             _ => epoch_ms,
         };
+        if let (Some(prev_ms), Some(&(_, prev_kmh))) = (self.last_speed_ms, self.speed_history.last()) {
+            let dt_ms = (ts - prev_ms).min(self.max_gap_ms);
+            self.distance_km += prev_kmh as f64 * dt_ms as f64 / 3_600_000.0;
+        }
         self.last_speed_ms = Some(ts);
         self.speed_history.push((ts, kmh));
     }
 
+    /// Record the right-pedal share reported alongside a power reading. Kept
+    /// apart from power so trainers and single-sided meters leave it empty.
+    pub fn record_balance(&mut self, right_pct: u8, epoch_ms: u64) {
+        self.balance_history.push_back((epoch_ms, right_pct));
+        let cutoff = epoch_ms.saturating_sub(BALANCE_WINDOW_MS);
+        while self.balance_history.front().is_some_and(|(ts, _)| *ts < cutoff) {
+            self.balance_history.pop_front();
+        }
+    }
+
+    /// Balance readings within 30s of the latest power reading, so balance
+    /// drops out once power comes from a source that doesn't report it.
+    fn recent_balance(&self) -> impl Iterator<Item = u8> + '_ {
+        let cutoff = self
+            .last_epoch_ms
+            .map_or(0, |ts| ts.saturating_sub(BALANCE_WINDOW_MS));
+        self.balance_history
+            .iter()
+            .filter(move |(ts, _)| *ts >= cutoff)
+            .map(|(_, pct)| *pct)
+    }
+
+    pub fn current_balance(&self) -> Option<u8> {
+        self.recent_balance().last()
+    }
+
+    pub fn avg_balance_30s(&self) -> Option<f32> {
+        let (sum, count) = self
+            .recent_balance()
+            .fold((0u32, 0u32), |(sum, count), pct| (sum + pct as u32, count + 1));
+        (count > 0).then(|| sum as f32 / count as f32)
+    }
+
     pub fn current_power(&self) -> Option<u16> {
         self.power_history.last().map(|(_, w)| *w)
     }
@@ -133,6 +262,40 @@ impl MetricsCalculator {
         Some(slice.iter().map(|&w| w as f32).sum::<f32>() / slice.len() as f32)
     }
 
+    /// Time-weighted power over the last 3 seconds, for a steadier live display.
+    ///
+    /// Each reading stands for the power since the previous one, so a burst of
+    /// fast samples doesn't outweigh a slower stretch of the window. Falls back to
+    /// the plain mean when the window holds no measurable time.
+    pub fn smoothed_power_3s(&self) -> Option<f32> {
+        let last_ms = self.last_epoch_ms?;
+        let cutoff = last_ms.saturating_sub(SMOOTHED_POWER_WINDOW_MS);
+        let start = self.power_history.partition_point(|(ts, _)| *ts < cutoff);
+        let window = &self.power_history[start..];
+        if window.is_empty() {
+            return None;
+        }
+
+        let mut weighted_sum = 0.0f64;
+        let mut total_ms = 0u64;
+        for (i, &(ts, watts)) in window.iter().enumerate() {
+            let prev_ts = if i > 0 {
+                window[i - 1].0
+            } else {
+                // The first sample's interval reaches back past the window start
+                start.checked_sub(1).map_or(ts, |p| self.power_history[p].0)
+            };
+            let span = ts - prev_ts.max(cutoff);
+            weighted_sum += watts as f64 * span as f64;
+            total_ms += span;
+        }
+        if total_ms == 0 {
+            let sum: f64 = window.iter().map(|(_, w)| *w as f64).sum();
+            return Some((sum / window.len() as f64) as f32);
+        }
+        Some((weighted_sum / total_ms as f64) as f32)
+    }
+
     pub fn normalized_power(&self) -> Option<f32> {
         if self.fourth_power_count == 0 {
             return None;
@@ -140,6 +303,16 @@ impl MetricsCalculator {
         Some((self.fourth_power_sum / self.fourth_power_count as f64).powf(0.25) as f32)
     }
 
+    /// Live NP for the ride so far; `None` until a full 30-second window exists.
+    pub fn current_np(&self) -> Option<f32> {
+        self.normalized_power()
+    }
+
+    /// Live IF for the ride so far against `ftp`.
+    pub fn current_if(&self, ftp: u16) -> Option<f32> {
+        self.current_np().map(|np| np / ftp.max(1) as f32)
+    }
+
     pub fn intensity_factor(&self) -> Option<f32> {
         self.normalized_power().map(|np| np / self.ftp as f32)
     }
@@ -186,6 +359,30 @@ impl MetricsCalculator {
         Some(nonzero.iter().sum::<f32>() / nonzero.len() as f32)
     }
 
+    /// Average cadence over pedalling samples only (≥ 1 RPM). Unlike
+    /// `avg_cadence`, this also drops the sub-1 RPM residue some sensors
+    /// report while the crank winds down during coasting.
+    pub fn avg_cadence_active(&self) -> Option<f32> {
+        let active: Vec<f32> = self
+            .cadence_readings
+            .iter()
+            .copied()
+            .filter(|&v| v >= 1.0)
+            .collect();
+        if active.is_empty() {
+            return None;
+        }
+        Some(active.iter().sum::<f32>() / active.len() as f32)
+    }
+
+    /// Mean ambient temperature (°C) over all readings.
+    pub fn avg_temp_c(&self) -> Option<f32> {
+        if self.temperature_count == 0 {
+            return None;
+        }
+        Some((self.temperature_sum / self.temperature_count as f64) as f32)
+    }
+
     pub fn avg_speed(&self) -> Option<f32> {
         let nonzero: Vec<f32> = self.speed_history.iter().map(|(_, v)| *v).filter(|&v| v > 0.0).collect();
         if nonzero.is_empty() { return None; }
@@ -196,15 +393,21 @@ impl MetricsCalculator {
         if self.speed_history.len() < 2 {
             return None;
         }
-        let mut total_km: f64 = 0.0;
-        for pair in self.speed_history.windows(2) {
-            let dt_hours = (pair[1].0 as f64 - pair[0].0 as f64) / 1000.0 / 3600.0;
-            total_km += pair[0].1 as f64 * dt_hours;
-        }
-        Some(total_km as f32)
+        Some(self.distance_km as f32)
     }
 
+    /// Total work. Prefers the power meter's accumulated energy counter when it
+    /// reported at least twice; otherwise integrates instantaneous power.
     pub fn work_kj(&self) -> Option<f32> {
+        // The device that reported longest, if any reported twice
+        if let Some(counter) = self
+            .energy_counters
+            .values()
+            .filter(|c| c.intervals > 0)
+            .max_by_key(|c| c.intervals)
+        {
+            return Some(counter.total_kj as f32);
+        }
         if self.power_history.len() < 2 {
             return None;
         }
@@ -222,7 +425,17 @@ impl MetricsCalculator {
         Some(np / avg)
     }
 
-    pub fn power_zone(&self, ftp: u16, zones: &[u16; 6]) -> Option<u8> {
+    /// Efficiency Factor: NP ÷ average HR.
+    pub fn efficiency_factor(&self) -> Option<f32> {
+        let np = self.normalized_power()?;
+        let avg_hr = self.avg_hr()?;
+        if avg_hr == 0 {
+            return None;
+        }
+        Some(np / avg_hr as f32)
+    }
+
+    pub fn power_zone(&self, ftp: u16, zones: &[u16]) -> Option<u8> {
         let watts = self.current_power()?;
         let pct = (watts as f32 / ftp.max(1) as f32) * 100.0;
         for (i, &upper) in zones.iter().enumerate() {
@@ -230,7 +443,7 @@ impl MetricsCalculator {
                 return Some((i + 1) as u8);
             }
         }
-        Some(7) // above all zone boundaries
+        Some(zones.len() as u8 + 1) // above all zone boundaries
     }
 
     pub fn hr_zone(&self, zones: &[u8; 5]) -> Option<u8> {
@@ -244,6 +457,192 @@ impl MetricsCalculator {
     }
 }
 
+/// Power metrics for the current lap, kept alongside the whole-ride
+/// `MetricsCalculator` so starting a lap never touches the session totals.
+pub struct LapMetrics {
+    ftp: u16,
+    calc: MetricsCalculator,
+    /// 1-based lap counter
+    number: u32,
+    /// Session active time (ms) when this lap started
+    start_active_ms: u64,
+}
+
+impl LapMetrics {
+    pub fn new(ftp: u16) -> Self {
+        Self {
+            ftp,
+            calc: MetricsCalculator::new(ftp),
+            number: 1,
+            start_active_ms: 0,
+        }
+    }
+
+    pub fn record_power(&mut self, watts: u16, epoch_ms: u64) {
+        self.calc.record_power(watts, epoch_ms);
+    }
+
+    /// Start a new lap at `active_elapsed_ms` of session time.
+    pub fn new_lap(&mut self, active_elapsed_ms: u64) {
+        self.calc = MetricsCalculator::new(self.ftp);
+        self.number += 1;
+        self.start_active_ms = active_elapsed_ms;
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn elapsed_secs(&self, active_elapsed_ms: u64) -> u64 {
+        active_elapsed_ms.saturating_sub(self.start_active_ms) / 1000
+    }
+
+    pub fn avg_power(&self) -> Option<f32> {
+        self.calc.avg_power(usize::MAX)
+    }
+
+    pub fn normalized_power(&self) -> Option<f32> {
+        self.calc.normalized_power()
+    }
+}
+
+/// Compute Skiba's xPower: the 1-second power array is smoothed with a 25s
+/// exponentially-weighted moving average, then averaged as a 4th-power mean.
+/// Returns `None` with less than 25 seconds of power.
+pub fn compute_xpower(readings: &[SensorReading]) -> Option<u16> {
+    let power = super::analysis::resample_power_1s(readings);
+    if power.len() < XPOWER_TIME_CONSTANT_SECS as usize {
+        return None;
+    }
+    let alpha = 1.0 - (-1.0 / XPOWER_TIME_CONSTANT_SECS).exp();
+    let mut ewma = 0.0f64;
+    let mut fourth_power_sum = 0.0f64;
+    for &watts in &power {
+        ewma += (watts as f64 - ewma) * alpha;
+        fourth_power_sum += ewma.powi(4);
+    }
+    Some((fourth_power_sum / power.len() as f64).powf(0.25).round() as u16)
+}
+
+/// Integrated work checked against average power over the same span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkValidation {
+    pub integrated_kj: f32,
+    /// `avg_power * duration_secs / 1000`
+    pub expected_kj: f32,
+    pub discrepancy_pct: f32,
+    /// 100 when the two agree, one point lower per percent of discrepancy
+    pub quality_score: u8,
+    /// Discrepancy large enough that dropouts likely skewed `work_kj`
+    pub flagged: bool,
+}
+
+/// Sanity-check power integration. Integration holds each reading until the
+/// next one, so a dropout stretches whatever value preceded it, while the
+/// per-sample average is unaffected. Returns `None` without at least two power
+/// readings spanning some time.
+pub fn validate_work(readings: &[SensorReading]) -> Option<WorkValidation> {
+    let mut calc = MetricsCalculator::new(1);
+    let mut watts_sum = 0u64;
+    let mut count = 0u64;
+    let mut span: Option<(u64, u64)> = None;
+    for reading in readings {
+        if let SensorReading::Power {
+            watts, epoch_ms, ..
+        } = reading
+        {
+            calc.record_power(*watts, *epoch_ms);
+            watts_sum += *watts as u64;
+            count += 1;
+            span = Some(span.map_or((*epoch_ms, *epoch_ms), |(first, last)| {
+                (first.min(*epoch_ms), last.max(*epoch_ms))
+            }));
+        }
+    }
+    let (first, last) = span?;
+    let duration_secs = (last - first) as f64 / 1000.0;
+    if duration_secs <= 0.0 {
+        return None;
+    }
+    let integrated_kj = calc.work_kj()?;
+    let avg_power = watts_sum as f64 / count as f64;
+    let expected_kj = (avg_power * duration_secs / 1000.0) as f32;
+    let discrepancy_pct = if expected_kj > 0.0 {
+        (integrated_kj - expected_kj).abs() / expected_kj * 100.0
+    } else if integrated_kj > 0.0 {
+        100.0
+    } else {
+        0.0
+    };
+    Some(WorkValidation {
+        integrated_kj,
+        expected_kj,
+        discrepancy_pct,
+        quality_score: (100.0 - discrepancy_pct).clamp(0.0, 100.0).round() as u8,
+        flagged: discrepancy_pct > WORK_DISCREPANCY_FLAG_PCT,
+    })
+}
+
+/// Age in whole years on `on`, from a `YYYY-MM-DD` date of birth.
+pub fn age_on(date_of_birth: &str, on: NaiveDate) -> Option<u32> {
+    let dob = NaiveDate::parse_from_str(date_of_birth, "%Y-%m-%d").ok()?;
+    on.years_since(dob)
+}
+
+/// Mechanical work (kJ) behind a device-reported energy expenditure (kcal);
+/// the inverse of the power-based estimate in `estimate_calories`.
+pub fn kcal_to_work_kj(kcal: f32) -> f32 {
+    (kcal as f64 * KJ_PER_KCAL * GROSS_EFFICIENCY) as f32
+}
+
+/// Estimate energy expenditure (kcal) for a ride.
+///
+/// With power data, mechanical work is scaled by ~24% gross efficiency. Without
+/// it, falls back to the Keytel et al. (2005) heart-rate equation over the span
+/// of HR readings, which needs `sex` ("male"/"female") and `age`.
+pub fn estimate_calories(
+    readings: &[SensorReading],
+    weight_kg: f32,
+    avg_hr: Option<u8>,
+    sex: Option<&str>,
+    age: Option<u32>,
+) -> Option<u32> {
+    let mut work = MetricsCalculator::new(1);
+    for reading in readings {
+        match reading {
+            SensorReading::Power {
+                watts, epoch_ms, ..
+            } => work.record_power(*watts, *epoch_ms),
+            SensorReading::Energy {
+                kilojoules,
+                device_id,
+                ..
+            } => work.record_energy(device_id, *kilojoules),
+            _ => {}
+        }
+    }
+    if let Some(kj) = work.work_kj() {
+        return Some((kj as f64 / GROSS_EFFICIENCY / KJ_PER_KCAL).round() as u32);
+    }
+
+    let mut hr_epochs = readings.iter().filter_map(|r| match r {
+        SensorReading::HeartRate { epoch_ms, .. } => Some(*epoch_ms),
+        _ => None,
+    });
+    let first = hr_epochs.next()?;
+    let last = hr_epochs.next_back()?;
+    let minutes = last.saturating_sub(first) as f64 / 60_000.0;
+    let hr = avg_hr? as f64;
+    let age = age? as f64;
+    let weight = weight_kg as f64;
+    let kj_per_min = match sex? {
+        "male" => -55.0969 + 0.6309 * hr + 0.1988 * weight + 0.2017 * age,
+        "female" => -20.4022 + 0.4472 * hr - 0.1263 * weight + 0.074 * age,
+        _ => return None,
+    };
+    Some((kj_per_min.max(0.0) * minutes / KJ_PER_KCAL).round() as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +660,111 @@ mod tests {
         );
     }
 
+    // --- Live NP / IF ---
+
+    /// Reference NP: 30s rolling average, then 4th root of the mean of 4th powers.
+    fn reference_np(per_second: &[u16]) -> f32 {
+        let rolling: Vec<f64> = per_second
+            .windows(30)
+            .map(|w| w.iter().map(|&p| p as f64).sum::<f64>() / 30.0)
+            .collect();
+        let mean_fourth = rolling.iter().map(|r| r.powi(4)).sum::<f64>() / rolling.len() as f64;
+        mean_fourth.powf(0.25) as f32
+    }
+
+    #[test]
+    fn current_np_none_before_30_seconds() {
+        let mut calc = MetricsCalculator::new(200);
+        // 30 readings flush 29 complete seconds — one short of a full window
+        feed_constant_power(&mut calc, 250, 30, 0);
+        assert!(calc.current_np().is_none());
+        assert!(calc.current_if(200).is_none());
+        calc.record_power(250, 30_000);
+        assert_approx(calc.current_np().unwrap(), 250.0, 0.01, "first full window");
+    }
+
+    #[test]
+    fn current_np_matches_reference_computation() {
+        // Alternating 20s blocks of 150W / 350W with a ramp in between
+        let powers: Vec<u16> = (0..150u16)
+            .map(|i| match (i / 20) % 3 {
+                0 => 150,
+                1 => 350,
+                _ => 150 + (i % 20) * 10,
+            })
+            .collect();
+        let mut calc = MetricsCalculator::new(250);
+        for (i, &w) in powers.iter().enumerate() {
+            calc.record_power(w, i as u64 * 1000);
+        }
+        // The last second is still open, so only the first n-1 seconds count
+        let expected = reference_np(&powers[..powers.len() - 1]);
+        assert_approx(calc.current_np().unwrap(), expected, 0.01, "live NP");
+        assert_approx(calc.current_if(250).unwrap(), expected / 250.0, 0.0001, "live IF");
+        assert_approx(calc.current_if(200).unwrap(), expected / 200.0, 0.0001, "IF vs other FTP");
+    }
+
+    // --- Calories ---
+
+    /// 30 minutes at 200W with HR steady at 140 bpm.
+    fn steady_ride(with_power: bool) -> Vec<SensorReading> {
+        let mut readings = Vec::new();
+        for i in 0..=1800u64 {
+            let epoch_ms = i * 1000;
+            readings.push(SensorReading::HeartRate {
+                bpm: 140,
+                timestamp: None,
+                epoch_ms,
+                device_id: String::new(),
+            });
+            if with_power {
+                readings.push(SensorReading::Power {
+                    watts: 200,
+                    timestamp: None,
+                    epoch_ms,
+                    device_id: String::new(),
+                    pedal_balance: None,
+                });
+            }
+        }
+        readings
+    }
+
+    #[test]
+    fn calories_from_power_uses_gross_efficiency() {
+        // 200W * 1800s = 360 kJ → 360 / 0.24 / 4.184 ≈ 358.5 kcal
+        let kcal = estimate_calories(&steady_ride(true), 75.0, Some(140), None, None);
+        assert_eq!(kcal, Some(359));
+    }
+
+    #[test]
+    fn calories_fall_back_to_heart_rate_without_power() {
+        let kcal = estimate_calories(&steady_ride(false), 75.0, Some(140), Some("male"), Some(35))
+            .unwrap();
+        // Keytel (male): (-55.0969 + 0.6309*140 + 0.1988*75 + 0.2017*35) / 4.184 ≈ 13.19 kcal/min over 30 min
+        assert_eq!(kcal, 396);
+        // Same ride with power: both paths land in the same ballpark
+        let power_kcal = estimate_calories(&steady_ride(true), 75.0, Some(140), Some("male"), Some(35))
+            .unwrap();
+        assert!((kcal as f32 / power_kcal as f32 - 1.0).abs() < 0.15);
+    }
+
+    #[test]
+    fn calories_hr_fallback_needs_profile() {
+        let ride = steady_ride(false);
+        assert_eq!(estimate_calories(&ride, 75.0, Some(140), None, Some(35)), None);
+        assert_eq!(estimate_calories(&ride, 75.0, Some(140), Some("male"), None), None);
+        assert_eq!(estimate_calories(&[], 75.0, None, Some("male"), Some(35)), None);
+    }
+
+    #[test]
+    fn age_counts_whole_years() {
+        let on = NaiveDate::from_ymd_opt(2025, 6, 14).unwrap();
+        assert_eq!(age_on("1990-06-15", on), Some(34));
+        assert_eq!(age_on("1990-06-14", on), Some(35));
+        assert_eq!(age_on("not a date", on), None);
+    }
+
     // --- Empty State ---
 
     #[test]
@@ -598,6 +1102,39 @@ mod tests {
         assert_approx(calc.avg_speed().unwrap(), 30.0, 0.1, "avg speed excluding zeros");
     }
 
+    #[test]
+    fn avg_cadence_active_ignores_coasting() {
+        let mut calc = MetricsCalculator::new(200);
+        for _ in 0..10 {
+            calc.record_cadence(90.0);
+        }
+        // Coasting: crank winding down, then stopped
+        for rpm in [0.8, 0.5, 0.3, 0.0, 0.0, 0.0] {
+            calc.record_cadence(rpm);
+        }
+        let active = calc.avg_cadence_active().unwrap();
+        assert_approx(active, 90.0, 0.01, "active cadence");
+        assert!(active > calc.avg_cadence().unwrap());
+    }
+
+    #[test]
+    fn avg_cadence_active_none_when_only_coasting() {
+        let mut calc = MetricsCalculator::new(200);
+        calc.record_cadence(0.0);
+        calc.record_cadence(0.5);
+        assert!(calc.avg_cadence_active().is_none());
+    }
+
+    #[test]
+    fn avg_temp_c_averages_readings() {
+        let mut calc = MetricsCalculator::new(200);
+        assert!(calc.avg_temp_c().is_none());
+        for c in [21.5, 22.0, 23.5] {
+            calc.record_temperature(c);
+        }
+        assert_approx(calc.avg_temp_c().unwrap(), 22.333, 0.001, "avg temp");
+    }
+
     #[test]
     fn avg_cadence_all_zeros_returns_none() {
         let mut calc = MetricsCalculator::new(200);
@@ -631,6 +1168,55 @@ mod tests {
         assert!(calc.work_kj().is_none());
     }
 
+    #[test]
+    fn work_kj_prefers_accumulated_energy() {
+        let mut calc = MetricsCalculator::new(200);
+        // Integrated power would be 12.0 kJ; the device counter says 15 kJ
+        feed_constant_power(&mut calc, 200, 61, 0);
+        calc.record_energy("pm", 100.0);
+        calc.record_energy("pm", 115.0);
+        assert_approx(calc.work_kj().unwrap(), 15.0, 0.001, "device energy delta");
+    }
+
+    #[test]
+    fn work_kj_accumulated_energy_handles_rollover() {
+        let mut calc = MetricsCalculator::new(200);
+        calc.record_energy("pm", 65530.0);
+        // 65530 → 65535 is 5 kJ, wrap to 0 is 1 kJ, then 0 → 4 is 4 kJ = 10 kJ
+        calc.record_energy("pm", 4.0);
+        assert_approx(calc.work_kj().unwrap(), 10.0, 0.001, "rollover energy delta");
+    }
+
+    #[test]
+    fn work_kj_energy_counter_reset_restarts_count() {
+        let mut calc = MetricsCalculator::new(200);
+        calc.record_energy("pm", 300.0);
+        calc.record_energy("pm", 310.0);
+        // Reconnect: the counter starts over instead of wrapping
+        calc.record_energy("pm", 2.0);
+        calc.record_energy("pm", 7.0);
+        assert_approx(calc.work_kj().unwrap(), 15.0, 0.001, "energy across reset");
+    }
+
+    #[test]
+    fn work_kj_keeps_device_energy_counters_apart() {
+        let mut calc = MetricsCalculator::new(200);
+        calc.record_energy("pm", 100.0);
+        calc.record_energy("trainer", 5.0);
+        calc.record_energy("pm", 110.0);
+        calc.record_energy("trainer", 6.0);
+        calc.record_energy("pm", 120.0);
+        assert_approx(calc.work_kj().unwrap(), 20.0, 0.001, "longest-reporting device");
+    }
+
+    #[test]
+    fn work_kj_single_energy_reading_falls_back_to_power() {
+        let mut calc = MetricsCalculator::new(200);
+        feed_constant_power(&mut calc, 200, 61, 0);
+        calc.record_energy("pm", 100.0);
+        assert_approx(calc.work_kj().unwrap(), 12.0, 0.1, "integrated power fallback");
+    }
+
     // --- Variability Index ---
 
     #[test]
@@ -657,6 +1243,29 @@ mod tests {
         assert!(vi > 1.0, "VI ({vi}) should exceed 1.0 for variable power");
     }
 
+    // --- Efficiency Factor ---
+
+    #[test]
+    fn efficiency_factor_np_over_avg_hr() {
+        let mut calc = MetricsCalculator::new(200);
+        feed_constant_power(&mut calc, 200, 35, 0);
+        calc.record_hr(150);
+        calc.record_hr(170);
+        // EF = 200 / 160 = 1.25
+        assert_approx(calc.efficiency_factor().unwrap(), 1.25, 0.01, "EF");
+    }
+
+    #[test]
+    fn efficiency_factor_none_without_np_or_hr() {
+        let mut calc = MetricsCalculator::new(200);
+        calc.record_hr(150);
+        assert!(calc.efficiency_factor().is_none(), "no NP yet");
+
+        let mut calc = MetricsCalculator::new(200);
+        feed_constant_power(&mut calc, 200, 35, 0);
+        assert!(calc.efficiency_factor().is_none(), "no HR");
+    }
+
     // --- Distance ---
 
     #[test]
@@ -696,4 +1305,186 @@ mod tests {
         let km = calc.distance_km().unwrap();
         assert_approx(km, 0.5, 0.01, "zero speed then 30 km/h for 60s");
     }
+
+    #[test]
+    fn distance_caps_gap_between_speed_samples() {
+        let mut calc = MetricsCalculator::new(200);
+        // 60s at 36 km/h, a 10 minute dropout, then 60s more
+        for i in 0..=60 {
+            calc.record_speed(36.0, i * 1000);
+        }
+        for i in 660..=720 {
+            calc.record_speed(36.0, i * 1000);
+        }
+        // 120s of riding plus the capped gap: 36 km/h * 125s = 1.25 km
+        let km = calc.distance_km().unwrap();
+        assert_approx(km, 1.25, 0.01, "dropout counted up to the gap cap");
+    }
+
+    #[test]
+    fn distance_gap_cap_follows_config() {
+        let mut calc = MetricsCalculator::new(200).with_max_gap_ms(20_000);
+        calc.record_speed(36.0, 0);
+        calc.record_speed(36.0, 600_000);
+        // A 10 minute dropout counts as 20s at 36 km/h
+        assert_approx(calc.distance_km().unwrap(), 0.2, 0.001, "configured gap cap");
+    }
+
+    #[test]
+    fn xpower_differs_from_np_on_variable_ride() {
+        // 20 minutes alternating 30s at 400W and 30s at 100W (avg 250W)
+        let mut readings = Vec::new();
+        let mut calc = MetricsCalculator::new(250);
+        for i in 0..1200u64 {
+            let watts = if (i / 30) % 2 == 0 { 400 } else { 100 };
+            calc.record_power(watts, i * 1000);
+            readings.push(SensorReading::Power {
+                watts,
+                timestamp: None,
+                epoch_ms: i * 1000,
+                device_id: String::new(),
+                pedal_balance: None,
+            });
+        }
+        let np = calc.normalized_power().unwrap();
+        let xpower = compute_xpower(&readings).unwrap() as f32;
+        assert!(xpower > 250.0, "xPower {xpower} should exceed average power");
+        assert!((xpower - np).abs() >= 2.0, "xPower {xpower} should differ from NP {np}");
+    }
+
+    #[test]
+    fn xpower_none_without_enough_power() {
+        let readings: Vec<SensorReading> = (0..10u64)
+            .map(|i| SensorReading::Power {
+                watts: 200,
+                timestamp: None,
+                epoch_ms: i * 1000,
+                device_id: String::new(),
+                pedal_balance: None,
+            })
+            .collect();
+        assert!(compute_xpower(&readings).is_none());
+    }
+
+    #[test]
+    fn balance_rolling_average_over_30s() {
+        let mut calc = MetricsCalculator::new(200);
+        // 40s of alternating 48/54 right-pedal balance
+        for i in 0..40u64 {
+            let ts = i * 1000;
+            calc.record_power(250, ts);
+            calc.record_balance(if i % 2 == 0 { 48 } else { 54 }, ts);
+        }
+        assert_eq!(calc.current_balance(), Some(54));
+        // Window 9s..39s holds 31 readings: 15 even (48) and 16 odd (54)
+        let expected = (15.0 * 48.0 + 16.0 * 54.0) / 31.0;
+        assert_approx(calc.avg_balance_30s().unwrap(), expected, 0.01, "30s balance");
+    }
+
+    #[test]
+    fn balance_none_without_dual_sided_meter() {
+        let mut calc = MetricsCalculator::new(200);
+        feed_constant_power(&mut calc, 250, 10, 0);
+        assert_eq!(calc.current_balance(), None);
+        assert_eq!(calc.avg_balance_30s(), None);
+
+        // Balance ages out once power continues without it
+        calc.record_balance(50, 10_000);
+        feed_constant_power(&mut calc, 250, 40, 11);
+        assert_eq!(calc.current_balance(), None);
+        assert_eq!(calc.avg_balance_30s(), None);
+    }
+
+    fn power_at(watts: u16, epoch_ms: u64) -> SensorReading {
+        SensorReading::Power {
+            watts,
+            timestamp: None,
+            epoch_ms,
+            device_id: String::new(),
+            pedal_balance: None,
+        }
+    }
+
+    #[test]
+    fn steady_power_validates_cleanly() {
+        let readings: Vec<_> = (0..600u64).map(|i| power_at(200, i * 1000)).collect();
+        let check = validate_work(&readings).unwrap();
+        assert_approx(check.integrated_kj, 119.8, 0.1, "integrated");
+        assert_approx(check.expected_kj, 119.8, 0.1, "expected");
+        assert_eq!(check.quality_score, 100);
+        assert!(!check.flagged);
+    }
+
+    #[test]
+    fn dropout_after_spike_lowers_quality_score() {
+        // 10 min at 100W, a 400W reading, then 5 min of silence and 5 min at 100W.
+        // Integration holds 400W across the gap: ~210 kJ against ~120 kJ expected.
+        let mut readings: Vec<_> = (0..600u64).map(|i| power_at(100, i * 1000)).collect();
+        readings.push(power_at(400, 600_000));
+        readings.extend((0..300u64).map(|i| power_at(100, 900_000 + i * 1000)));
+
+        let check = validate_work(&readings).unwrap();
+        assert!(check.integrated_kj > 200.0, "integrated {}", check.integrated_kj);
+        assert!(check.expected_kj < 125.0, "expected {}", check.expected_kj);
+        assert!(check.flagged);
+        assert!(check.quality_score < 50, "score {}", check.quality_score);
+    }
+
+    #[test]
+    fn validate_work_none_without_power() {
+        assert!(validate_work(&[]).is_none());
+        assert!(validate_work(&[power_at(200, 0)]).is_none());
+    }
+
+    #[test]
+    fn smoothed_power_3s_time_weighted_over_irregular_samples() {
+        let mut calc = MetricsCalculator::new(200);
+        // Outside the window: must not count
+        calc.record_power(500, 0);
+        // 1Hz stretch then a 4Hz burst
+        calc.record_power(100, 1000);
+        calc.record_power(100, 2000);
+        calc.record_power(200, 3000);
+        calc.record_power(300, 3250);
+        calc.record_power(300, 3500);
+        calc.record_power(300, 3750);
+        calc.record_power(300, 4000);
+        // Window (1000, 4000]: 100W for 1000ms, 200W for 1000ms, 300W for 1000ms
+        assert_approx(calc.smoothed_power_3s().unwrap(), 200.0, 0.01, "time-weighted 3s");
+        // The sample mean over-weights the burst
+        assert!(calc.avg_power(3).unwrap() > 220.0);
+    }
+
+    #[test]
+    fn smoothed_power_3s_single_sample() {
+        let mut calc = MetricsCalculator::new(200);
+        assert!(calc.smoothed_power_3s().is_none());
+        calc.record_power(180, 5000);
+        assert_approx(calc.smoothed_power_3s().unwrap(), 180.0, 0.01, "lone sample");
+    }
+
+    #[test]
+    fn lap_resets_lap_averages_but_not_overall() {
+        let mut overall = MetricsCalculator::new(250);
+        let mut lap = LapMetrics::new(250);
+        for i in 0..60u64 {
+            overall.record_power(150, i * 1000);
+            lap.record_power(150, i * 1000);
+        }
+        assert_approx(lap.avg_power().unwrap(), 150.0, 0.01, "first lap avg");
+
+        lap.new_lap(60_000);
+        assert_eq!(lap.number(), 2);
+        assert!(lap.avg_power().is_none());
+        assert!(lap.normalized_power().is_none());
+
+        for i in 60..120u64 {
+            overall.record_power(300, i * 1000);
+            lap.record_power(300, i * 1000);
+        }
+        assert_approx(lap.avg_power().unwrap(), 300.0, 0.01, "second lap avg");
+        assert_approx(lap.normalized_power().unwrap(), 300.0, 0.01, "second lap NP");
+        assert_eq!(lap.elapsed_secs(120_000), 60);
+        assert_approx(overall.avg_power(usize::MAX).unwrap(), 225.0, 0.01, "overall avg");
+    }
 }
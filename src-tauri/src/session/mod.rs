@@ -1,7 +1,15 @@
 pub mod analysis;
+pub mod csv_export;
+pub mod export_resample;
 pub mod fit_export;
+pub mod fit_import;
+pub mod hr_fusion;
 pub mod manager;
 pub mod metrics;
+pub mod physics;
+pub mod ramp_test;
 pub mod storage;
+pub mod strava;
 pub mod types;
+pub mod workout;
 pub mod zone_control;
@@ -0,0 +1,163 @@
+use crate::device::types::SensorReading;
+use crate::session::types::SessionConfig;
+
+const AIR_DENSITY_KG_M3: f32 = 1.225;
+const GRAVITY_M_S2: f32 = 9.81;
+/// Share of pedal power lost in the drivetrain before it reaches the road
+const DRIVETRAIN_LOSS: f32 = 0.03;
+/// Upper bound for the speed search (m/s), well past any descent
+const MAX_SPEED_M_S: f32 = 40.0;
+
+/// Steady-state outdoor speed (km/h) for `watts` at the pedals on a `grade`
+/// (percent, negative downhill), with rolling resistance `crr`, drag area
+/// `cda` (m²) and total rider + bike mass `mass_kg`. Solved by bisection;
+/// coasting downhill gives the terminal speed at 0W.
+pub fn virtual_speed(watts: u16, grade: f32, crr: f32, cda: f32, mass_kg: f32) -> f32 {
+    let angle = (grade / 100.0).atan();
+    let resist_n = mass_kg * GRAVITY_M_S2 * (crr * angle.cos() + angle.sin());
+    let drag = 0.5 * AIR_DENSITY_KG_M3 * cda;
+    let wheel_watts = watts as f32 * (1.0 - DRIVETRAIN_LOSS);
+    let power_at = |v: f32| v * (resist_n + drag * v * v);
+    let (mut lo, mut hi) = (0.0_f32, MAX_SPEED_M_S);
+    for _ in 0..40 {
+        let mid = (lo + hi) / 2.0;
+        if power_at(mid) < wheel_watts {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo * 3.6
+}
+
+/// Per-session virtual speed state: tracks the trainer's simulated grade and
+/// produces a `Speed` reading for each power reading until a real speed
+/// sensor reports.
+pub struct VirtualSpeed {
+    crr: f32,
+    cda: f32,
+    mass_kg: f32,
+    grade: f32,
+    speed_sensor_seen: bool,
+}
+
+impl VirtualSpeed {
+    /// The model for `config`, or `None` when virtual speed is off.
+    pub fn from_config(config: &SessionConfig) -> Option<Self> {
+        config.virtual_speed.then_some(Self {
+            crr: config.virtual_crr,
+            cda: config.virtual_cda,
+            mass_kg: config.weight_kg + config.bike_weight_kg,
+            grade: 0.0,
+            speed_sensor_seen: false,
+        })
+    }
+
+    /// Feed a recorded reading; returns the synthetic `Speed` for a power reading.
+    pub fn observe(&mut self, reading: &SensorReading) -> Option<SensorReading> {
+        match reading {
+            SensorReading::Grade { percent, .. } => self.grade = *percent,
+            SensorReading::Speed { .. } => self.speed_sensor_seen = true,
+            SensorReading::Power {
+                watts,
+                epoch_ms,
+                device_id,
+                ..
+            } if !self.speed_sensor_seen => {
+                return Some(SensorReading::Speed {
+                    kmh: virtual_speed(*watts, self.grade, self.crr, self.cda, self.mass_kg),
+                    timestamp: None,
+                    epoch_ms: *epoch_ms,
+                    device_id: device_id.clone(),
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRR: f32 = 0.005;
+    const CDA: f32 = 0.36;
+    const MASS_KG: f32 = 83.0;
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32, what: &str) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{what}: expected {expected}±{tolerance} km/h, got {actual}"
+        );
+    }
+
+    #[test]
+    fn flat_road_reference_points() {
+        assert_near(virtual_speed(200, 0.0, CRR, CDA, MASS_KG), 32.0, 1.5, "200W flat");
+        assert_near(virtual_speed(100, 0.0, CRR, CDA, MASS_KG), 25.0, 1.5, "100W flat");
+        assert_near(virtual_speed(300, 0.0, CRR, CDA, MASS_KG), 37.0, 1.5, "300W flat");
+        assert_eq!(virtual_speed(0, 0.0, CRR, CDA, MASS_KG), 0.0);
+    }
+
+    #[test]
+    fn grade_slows_climbs_and_coasts_descents() {
+        assert_near(virtual_speed(250, 5.0, CRR, CDA, MASS_KG), 17.5, 1.5, "250W at 5%");
+        // Freewheeling down 5% settles where drag balances gravity
+        assert_near(virtual_speed(0, -5.0, CRR, CDA, MASS_KG), 46.0, 2.0, "0W at -5%");
+    }
+
+    #[test]
+    fn heavier_rider_and_more_drag_are_slower() {
+        let base = virtual_speed(200, 0.0, CRR, CDA, MASS_KG);
+        assert!(virtual_speed(200, 4.0, CRR, CDA, MASS_KG + 20.0) < virtual_speed(200, 4.0, CRR, CDA, MASS_KG));
+        assert!(virtual_speed(200, 0.0, CRR, CDA + 0.1, MASS_KG) < base);
+        assert!(virtual_speed(200, 0.0, CRR * 2.0, CDA, MASS_KG) < base);
+    }
+
+    fn power(watts: u16, epoch_ms: u64) -> SensorReading {
+        SensorReading::Power {
+            watts,
+            timestamp: None,
+            epoch_ms,
+            device_id: "trainer".to_string(),
+            pedal_balance: None,
+        }
+    }
+
+    fn kmh(reading: Option<SensorReading>) -> f32 {
+        match reading {
+            Some(SensorReading::Speed { kmh, .. }) => kmh,
+            other => panic!("expected Speed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn tracks_grade_and_yields_to_real_speed_sensor() {
+        let config = SessionConfig {
+            virtual_speed: true,
+            ..SessionConfig::default()
+        };
+        let mut model = VirtualSpeed::from_config(&config).unwrap();
+        let flat = kmh(model.observe(&power(200, 0)));
+        assert!(model
+            .observe(&SensorReading::Grade { percent: 6.0, epoch_ms: 500 })
+            .is_none());
+        let climbing = kmh(model.observe(&power(200, 1000)));
+        assert!(climbing < flat, "{climbing} should be below {flat}");
+
+        let real = SensorReading::Speed {
+            kmh: 20.0,
+            timestamp: None,
+            epoch_ms: 1500,
+            device_id: "spd".to_string(),
+        };
+        assert!(model.observe(&real).is_none());
+        assert!(model.observe(&power(200, 2000)).is_none());
+    }
+
+    #[test]
+    fn off_unless_configured() {
+        assert!(VirtualSpeed::from_config(&SessionConfig::default()).is_none());
+    }
+}
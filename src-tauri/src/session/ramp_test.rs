@@ -0,0 +1,536 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::device::manager::DeviceManager;
+use crate::device::types::{CommandSource, SensorReading};
+use crate::error::AppError;
+use crate::session::storage::Storage;
+
+/// FTP estimate as a fraction of best 1-minute power during the ramp.
+const FTP_FRACTION_OF_BEST_MINUTE: f64 = 0.75;
+/// Window for the best-power figure the FTP estimate is based on.
+const BEST_POWER_WINDOW_SECS: usize = 60;
+
+/// Stepped ERG ramp: `start_watts`, then `step_watts` more every `step_secs`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RampTestParams {
+    pub start_watts: u16,
+    pub step_watts: u16,
+    pub step_secs: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RampTestResult {
+    /// Target of the last step held for its full duration
+    pub last_completed_watts: Option<u16>,
+    /// Best rolling 60s average power; None if the test ended within a minute
+    pub best_minute_watts: Option<u16>,
+    /// 75% of best 1-minute power
+    pub ftp_estimate: Option<u16>,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RampTestStatus {
+    pub active: bool,
+    pub elapsed_secs: u64,
+    pub step_index: Option<u32>,
+    pub target_watts: Option<u16>,
+    /// Set once the test has ended, by cadence collapse or by the user
+    pub result: Option<RampTestResult>,
+    /// Why the test ended early, e.g. the trainer disconnected. `result` then
+    /// holds the partial result and no FTP was recorded.
+    pub failure: Option<String>,
+}
+
+/// Position within a running ramp test. Like `WorkoutProgress`, time only moves
+/// when `advance` is called, so tests can drive it with a fake clock.
+struct RampProgress {
+    params: RampTestParams,
+    elapsed_ms: u64,
+    /// One sample per elapsed second: the last power reading seen during it
+    power_samples: Vec<u16>,
+    last_power: Option<u16>,
+    cadence_zero_since_ms: Option<u64>,
+    cadence_zero_limit_ms: u64,
+}
+
+impl RampProgress {
+    fn new(params: RampTestParams, cadence_zero_secs: u64) -> Self {
+        Self {
+            params,
+            elapsed_ms: 0,
+            power_samples: Vec::new(),
+            last_power: None,
+            cadence_zero_since_ms: None,
+            cadence_zero_limit_ms: cadence_zero_secs * 1000,
+        }
+    }
+
+    fn advance(&mut self, delta_ms: u64) {
+        let before_secs = self.elapsed_ms / 1000;
+        self.elapsed_ms += delta_ms;
+        for _ in before_secs..self.elapsed_ms / 1000 {
+            self.power_samples.push(self.last_power.unwrap_or(0));
+        }
+    }
+
+    fn record_power(&mut self, watts: u16) {
+        self.last_power = Some(watts);
+    }
+
+    /// Same rule as zone control's cadence-zero safety: below 1 rpm starts the clock.
+    fn record_cadence(&mut self, rpm: f32) {
+        if rpm < 1.0 {
+            if self.cadence_zero_since_ms.is_none() {
+                self.cadence_zero_since_ms = Some(self.elapsed_ms);
+            }
+        } else {
+            self.cadence_zero_since_ms = None;
+        }
+    }
+
+    /// The rider has stopped pedalling for long enough to end the test.
+    fn cadence_collapsed(&self) -> bool {
+        self.cadence_zero_since_ms
+            .is_some_and(|since| self.elapsed_ms - since >= self.cadence_zero_limit_ms)
+    }
+
+    fn step_ms(&self) -> u64 {
+        self.params.step_secs as u64 * 1000
+    }
+
+    fn step_index(&self) -> u32 {
+        (self.elapsed_ms / self.step_ms()) as u32
+    }
+
+    fn watts_for_step(&self, index: u32) -> u16 {
+        let watts = self.params.start_watts as u32 + self.params.step_watts as u32 * index;
+        watts.min(u16::MAX as u32) as u16
+    }
+
+    fn target_watts(&self) -> u16 {
+        self.watts_for_step(self.step_index())
+    }
+
+    /// Summarise the test. When cadence collapsed, the effort ended when pedalling
+    /// stopped, not when the collapse was confirmed.
+    fn result(&self) -> RampTestResult {
+        let end_ms = match self.cadence_zero_since_ms {
+            Some(since) if self.cadence_collapsed() => since,
+            _ => self.elapsed_ms,
+        };
+        let completed_steps = (end_ms / self.step_ms()) as u32;
+        let last_completed_watts = completed_steps
+            .checked_sub(1)
+            .map(|index| self.watts_for_step(index));
+        let best_minute_watts = best_window_average(&self.power_samples, BEST_POWER_WINDOW_SECS);
+        RampTestResult {
+            last_completed_watts,
+            best_minute_watts,
+            ftp_estimate: best_minute_watts
+                .map(|w| (w as f64 * FTP_FRACTION_OF_BEST_MINUTE).round() as u16),
+            duration_secs: end_ms / 1000,
+        }
+    }
+}
+
+/// Highest rolling average over `window` consecutive samples.
+fn best_window_average(samples: &[u16], window: usize) -> Option<u16> {
+    if window == 0 || samples.len() < window {
+        return None;
+    }
+    let mut sum: u64 = samples[..window].iter().map(|&w| w as u64).sum();
+    let mut best = sum;
+    for i in window..samples.len() {
+        sum = sum + samples[i] as u64 - samples[i - window] as u64;
+        best = best.max(sum);
+    }
+    Some((best as f64 / window as f64).round() as u16)
+}
+
+struct RunnerState {
+    active: bool,
+    progress: Option<RampProgress>,
+    commanded_power: Option<u16>,
+    result: Option<RampTestResult>,
+    failure: Option<String>,
+    /// Instant of the last processed tick, for measuring actual elapsed time
+    last_tick_at: Option<Instant>,
+}
+
+impl RunnerState {
+    fn new() -> Self {
+        Self {
+            active: false,
+            progress: None,
+            commanded_power: None,
+            result: None,
+            failure: None,
+            last_tick_at: None,
+        }
+    }
+
+    fn catch_up(&mut self) {
+        let now = Instant::now();
+        let delta_ms = self
+            .last_tick_at
+            .map(|t| now.duration_since(t).as_millis() as u64)
+            .unwrap_or(0);
+        self.last_tick_at = Some(now);
+        if let Some(progress) = self.progress.as_mut() {
+            progress.advance(delta_ms);
+        }
+    }
+
+    /// End the test and return the result if this call is the one that ended it.
+    fn finish(&mut self) -> Option<RampTestResult> {
+        if !self.active {
+            return None;
+        }
+        self.active = false;
+        let result = self.progress.as_ref()?.result();
+        self.result = Some(result.clone());
+        Some(result)
+    }
+
+    /// End the test early for `reason`, keeping the partial result.
+    fn fail(&mut self, reason: &str) {
+        if self.finish().is_some() {
+            self.failure = Some(reason.to_string());
+        }
+    }
+}
+
+/// Runs a ramp test in ERG mode until cadence collapses or the user stops it,
+/// then records the FTP estimate in the FTP history.
+pub struct RampTestRunner {
+    state: Arc<Mutex<RunnerState>>,
+    storage: Option<Arc<Storage>>,
+    shutdown_tx: Option<watch::Sender<bool>>,
+    task_handle: Option<JoinHandle<()>>,
+}
+
+impl RampTestRunner {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RunnerState::new())),
+            storage: None,
+            shutdown_tx: None,
+            task_handle: None,
+        }
+    }
+
+    pub async fn start(
+        &mut self,
+        params: RampTestParams,
+        cadence_zero_secs: u64,
+        device_manager: Arc<Mutex<DeviceManager>>,
+        sensor_tx: broadcast::Sender<SensorReading>,
+        storage: Arc<Storage>,
+    ) -> Result<(), AppError> {
+        if params.step_watts == 0 || params.step_secs == 0 {
+            return Err(AppError::Session(
+                "Ramp step size and duration must be positive".into(),
+            ));
+        }
+        {
+            let dm = device_manager.lock().await;
+            if dm.connected_trainer_id().is_none() {
+                return Err(AppError::Session("No trainer connected".into()));
+            }
+        }
+
+        self.stop_internal().await;
+
+        info!(
+            "Ramp test started: {}W +{}W every {}s",
+            params.start_watts, params.step_watts, params.step_secs
+        );
+        {
+            let mut state = self.state.lock().await;
+            state.active = true;
+            state.progress = Some(RampProgress::new(params, cadence_zero_secs));
+            state.commanded_power = None;
+            state.result = None;
+            state.failure = None;
+            state.last_tick_at = Some(Instant::now());
+        }
+        self.storage = Some(storage.clone());
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        self.shutdown_tx = Some(shutdown_tx);
+        let sensor_rx = sensor_tx.subscribe();
+        let handle = tokio::spawn(run_loop(
+            self.state.clone(),
+            device_manager,
+            sensor_tx,
+            sensor_rx,
+            shutdown_rx,
+            storage,
+        ));
+        self.task_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stop the test early. The result so far is still recorded.
+    pub async fn stop(&mut self) -> Option<RampTestResult> {
+        self.stop_internal().await;
+        let finished = {
+            let mut state = self.state.lock().await;
+            state.catch_up();
+            state.finish()
+        };
+        if let (Some(result), Some(storage)) = (&finished, &self.storage) {
+            info!("Ramp test stopped by user");
+            record_result(storage, result).await;
+        }
+        self.state.lock().await.result.clone()
+    }
+
+    async fn stop_internal(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(true);
+        }
+        if let Some(handle) = self.task_handle.take() {
+            let _ = handle.await;
+        }
+    }
+
+    pub async fn status(&self) -> RampTestStatus {
+        let state = self.state.lock().await;
+        let progress = state.progress.as_ref().filter(|_| state.active);
+        RampTestStatus {
+            active: state.active,
+            elapsed_secs: progress.map_or(0, |p| p.elapsed_ms / 1000),
+            step_index: progress.map(|p| p.step_index()),
+            target_watts: progress.map(|p| p.target_watts()),
+            result: state.result.clone(),
+            failure: state.failure.clone(),
+        }
+    }
+}
+
+fn now_epoch_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+async fn record_result(storage: &Storage, result: &RampTestResult) {
+    info!(
+        "Ramp test result: last step {:?}W, best 1-min {:?}W, FTP estimate {:?}W",
+        result.last_completed_watts, result.best_minute_watts, result.ftp_estimate
+    );
+    let Some(ftp) = result.ftp_estimate else {
+        return;
+    };
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if let Err(e) = storage.record_ftp(ftp, &today, "ramp_test").await {
+        warn!("Failed to record ramp test FTP: {}", e);
+    }
+}
+
+async fn run_loop(
+    state: Arc<Mutex<RunnerState>>,
+    device_manager: Arc<Mutex<DeviceManager>>,
+    sensor_tx: broadcast::Sender<SensorReading>,
+    mut sensor_rx: broadcast::Receiver<SensorReading>,
+    mut shutdown_rx: watch::Receiver<bool>,
+    storage: Arc<Storage>,
+) {
+    let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(1));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => break,
+            result = sensor_rx.recv() => {
+                match result {
+                    Ok(SensorReading::Power { watts, .. }) => {
+                        if let Some(p) = state.lock().await.progress.as_mut() {
+                            p.record_power(watts);
+                        }
+                    }
+                    Ok(SensorReading::Cadence { rpm, .. }) => {
+                        if let Some(p) = state.lock().await.progress.as_mut() {
+                            p.record_cadence(rpm);
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            _ = tick.tick() => {
+                if process_tick(&state, &device_manager, &sensor_tx, &storage).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Advance the ramp, end it on cadence collapse, and command the next step.
+/// Returns true when the loop should stop.
+async fn process_tick(
+    state: &Arc<Mutex<RunnerState>>,
+    device_manager: &Arc<Mutex<DeviceManager>>,
+    sensor_tx: &broadcast::Sender<SensorReading>,
+    storage: &Storage,
+) -> bool {
+    let target = {
+        let mut s = state.lock().await;
+        if !s.active {
+            return true;
+        }
+        s.catch_up();
+        let Some(progress) = s.progress.as_ref() else {
+            return true;
+        };
+        if progress.cadence_collapsed() {
+            info!("Ramp test complete: cadence collapsed");
+            if let Some(result) = s.finish() {
+                drop(s);
+                record_result(storage, &result).await;
+            }
+            return true;
+        }
+        let target = progress.target_watts();
+        if s.commanded_power == Some(target) {
+            return false;
+        }
+        target
+    };
+
+    let mut dm = device_manager.lock().await;
+    let Some(trainer_id) = dm.connected_trainer_id() else {
+        warn!("Ramp test stopped: trainer disconnected");
+        let mut s = state.lock().await;
+        s.catch_up();
+        s.fail("Trainer disconnected");
+        return true;
+    };
+    if let Err(e) = dm.set_target_power(&trainer_id, target as i16).await {
+        warn!("Ramp test trainer power command failed: {}", e);
+        return false;
+    }
+    drop(dm);
+
+    state.lock().await.commanded_power = Some(target);
+    let _ = sensor_tx.send(SensorReading::TrainerCommand {
+        target_watts: target,
+        epoch_ms: now_epoch_ms(),
+        source: CommandSource::RampTest,
+    });
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> RampTestParams {
+        RampTestParams {
+            start_watts: 100,
+            step_watts: 20,
+            step_secs: 60,
+        }
+    }
+
+    #[test]
+    fn target_steps_up_each_interval() {
+        let mut p = RampProgress::new(params(), 3);
+        assert_eq!(p.target_watts(), 100);
+        p.advance(59_999);
+        assert_eq!(p.target_watts(), 100);
+        p.advance(1);
+        assert_eq!(p.target_watts(), 120);
+        p.advance(120_000);
+        assert_eq!(p.target_watts(), 160);
+    }
+
+    #[test]
+    fn cadence_collapse_ends_test_with_ftp_estimate() {
+        // Rider holds each target until cadence drops to zero at t=360s,
+        // the start of step 6 (220W). Steps 0-5 (100..200W) were completed.
+        let mut p = RampProgress::new(params(), 3);
+        let mut guard = 0;
+        while !p.cadence_collapsed() {
+            let t = p.elapsed_ms / 1000;
+            if t < 360 {
+                p.record_cadence(90.0);
+                p.record_power(p.target_watts());
+            } else {
+                p.record_cadence(0.0);
+                p.record_power(0);
+            }
+            p.advance(1000);
+            guard += 1;
+            assert!(guard < 1000, "cadence collapse never detected");
+        }
+        // Collapse confirmed after the 3s cadence-zero window
+        assert_eq!(p.elapsed_ms, 363_000);
+
+        let result = p.result();
+        assert_eq!(result.last_completed_watts, Some(200));
+        assert_eq!(result.best_minute_watts, Some(200));
+        assert_eq!(result.ftp_estimate, Some(150));
+        assert_eq!(result.duration_secs, 360);
+    }
+
+    #[test]
+    fn brief_cadence_dip_does_not_end_test() {
+        let mut p = RampProgress::new(params(), 3);
+        p.record_cadence(0.0);
+        p.advance(2000);
+        p.record_cadence(85.0);
+        p.advance(5000);
+        assert!(!p.cadence_collapsed());
+    }
+
+    #[test]
+    fn test_shorter_than_a_minute_has_no_estimate() {
+        let mut p = RampProgress::new(params(), 3);
+        p.record_power(100);
+        p.advance(30_000);
+        let result = p.result();
+        assert_eq!(result.last_completed_watts, None);
+        assert_eq!(result.best_minute_watts, None);
+        assert_eq!(result.ftp_estimate, None);
+    }
+
+    #[test]
+    fn trainer_disconnect_keeps_partial_result_and_reports_failure() {
+        let mut s = RunnerState::new();
+        s.active = true;
+        let mut p = RampProgress::new(params(), 3);
+        p.record_power(120);
+        p.advance(150_000);
+        s.progress = Some(p);
+
+        s.fail("Trainer disconnected");
+        assert!(!s.active);
+        assert_eq!(s.failure.as_deref(), Some("Trainer disconnected"));
+        let result = s.result.clone().expect("partial result kept");
+        assert_eq!(result.last_completed_watts, Some(120));
+        assert_eq!(result.duration_secs, 150);
+
+        // A later stop doesn't overwrite the failure
+        assert!(s.finish().is_none());
+        assert_eq!(s.failure.as_deref(), Some("Trainer disconnected"));
+    }
+
+    #[test]
+    fn best_window_average_finds_highest_window() {
+        let samples = [100, 100, 300, 300, 100];
+        assert_eq!(best_window_average(&samples, 2), Some(300));
+        assert_eq!(best_window_average(&samples, 3), Some(233));
+        assert_eq!(best_window_average(&samples, 6), None);
+    }
+}
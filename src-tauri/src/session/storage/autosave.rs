@@ -1,5 +1,6 @@
 use log::{info, warn};
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 use super::Storage;
 use crate::commands::validate_session_id;
@@ -125,4 +126,58 @@ impl Storage {
 
         Ok(count)
     }
+
+    /// Delete autosave files older than `max_age_hours` whose session is already
+    /// in the database — leftovers from a recovery that saved the session but
+    /// failed to remove the checkpoint. Returns the count of deleted files.
+    pub async fn prune_stale_autosaves(&self, max_age_hours: u64) -> Result<usize, AppError> {
+        let sessions_dir = Path::new(&self.data_dir).join("sessions");
+        if !sessions_dir.exists() {
+            return Ok(0);
+        }
+        let max_age = Duration::from_secs(max_age_hours * 3600);
+
+        let mut count = 0;
+        let entries = std::fs::read_dir(&sessions_dir)
+            .map_err(|e| AppError::Serialization(format!("Failed to read sessions dir: {}", e)))?;
+
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            let Some(session_id) = name_str
+                .strip_prefix(".autosave_")
+                .and_then(|s| s.strip_suffix(".bin"))
+            else {
+                continue;
+            };
+
+            let age = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|mtime| SystemTime::now().duration_since(mtime).ok());
+            if !age.is_some_and(|age| age > max_age) {
+                continue;
+            }
+
+            let saved: Option<(String,)> = sqlx::query_as("SELECT id FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::Database)?;
+            if saved.is_none() {
+                continue;
+            }
+
+            match std::fs::remove_file(entry.path()) {
+                Ok(()) => {
+                    info!("Pruned stale autosave for saved session {}", session_id);
+                    count += 1;
+                }
+                Err(e) => warn!("Failed to prune autosave {}: {}", name_str, e),
+            }
+        }
+
+        Ok(count)
+    }
 }
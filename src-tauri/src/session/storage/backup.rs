@@ -0,0 +1,250 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::Storage;
+use crate::error::AppError;
+
+const DB_FILE: &str = "training.db";
+const SESSIONS_DIR: &str = "sessions";
+/// SQLite companion files that belong to the database and move with it.
+const DB_COMPANIONS: [&str; 3] = ["training.db", "training.db-wal", "training.db-shm"];
+const STAGING_DIR: &str = ".restore_staging";
+const PREVIOUS_DIR: &str = ".restore_previous";
+
+fn io_err(context: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::Serialization(format!("{}: {}", context, e))
+}
+
+/// Remove the stored Strava credentials from a database snapshot so backups
+/// never carry them. Secure delete overwrites the freed pages as well.
+async fn scrub_credentials(snapshot: &Path) -> Result<(), AppError> {
+    let options = SqliteConnectOptions::new().filename(snapshot);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(AppError::Database)?;
+    let result = sqlx::raw_sql("PRAGMA secure_delete = ON; DELETE FROM strava_auth;")
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database);
+    pool.close().await;
+    result.map(|_| ())
+}
+
+impl Storage {
+    /// Write a zip archive containing a consistent snapshot of `training.db` and
+    /// every raw session file under `sessions/`.
+    pub async fn export_backup(&self, path: &Path) -> Result<(), AppError> {
+        // VACUUM INTO gives a consistent copy even while the pool is in use
+        let snapshot = Path::new(&self.data_dir).join(".backup_snapshot.db");
+        let _ = tokio::fs::remove_file(&snapshot).await;
+        sqlx::query("VACUUM INTO ?")
+            .bind(snapshot.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if let Err(e) = scrub_credentials(&snapshot).await {
+            let _ = tokio::fs::remove_file(&snapshot).await;
+            return Err(e);
+        }
+
+        let sessions_dir = Path::new(&self.data_dir).join(SESSIONS_DIR);
+        let archive_path = path.to_path_buf();
+        let snapshot_path = snapshot.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            write_archive(&archive_path, &snapshot_path, &sessions_dir)
+        })
+        .await
+        .map_err(|e| AppError::Session(format!("Backup task failed: {}", e)))?;
+        let _ = tokio::fs::remove_file(&snapshot).await;
+        if result.is_ok() {
+            info!("Exported backup to {}", path.display());
+        }
+        result
+    }
+
+    /// Extract a backup into a staging directory next to `data_dir` and check
+    /// that its database opens and migrates. Leaves the live data untouched.
+    pub(crate) async fn stage_backup(data_dir: &str, archive: &Path) -> Result<PathBuf, AppError> {
+        let staging = Path::new(data_dir).join(STAGING_DIR);
+        let archive_path = archive.to_path_buf();
+        let staging_path = staging.clone();
+        let extracted =
+            tokio::task::spawn_blocking(move || extract_archive(&archive_path, &staging_path))
+                .await
+                .map_err(|e| AppError::Session(format!("Restore task failed: {}", e)))?;
+
+        let validated = match extracted {
+            Ok(()) => Self::validate_staged(data_dir, &staging).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = validated {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        Ok(staging)
+    }
+
+    async fn validate_staged(data_dir: &str, staging: &Path) -> Result<(), AppError> {
+        // Opening migrates the database and rebuilds its search index against
+        // the snapshot's rowids, which VACUUM INTO may have renumbered
+        let staged = Storage::new(&staging.to_string_lossy()).await?;
+        let (check,): (String,) = sqlx::query_as("PRAGMA integrity_check")
+            .fetch_one(&staged.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if check != "ok" {
+            staged.pool.close().await;
+            return Err(AppError::Session(format!("Backup database is corrupt: {}", check)));
+        }
+        // Raw file paths are absolute; point them at the restored location
+        let sessions_dir = Path::new(data_dir).join(SESSIONS_DIR);
+        sqlx::query("UPDATE sessions SET raw_file_path = ? || '/' || id || '.bin'")
+            .bind(sessions_dir.to_string_lossy().to_string())
+            .execute(&staged.pool)
+            .await
+            .map_err(AppError::Database)?;
+        staged.pool.close().await;
+        Ok(())
+    }
+
+    /// Move the staged database and sessions into `data_dir`. The previous data is
+    /// set aside first and put back if any step fails. No `Storage` may have
+    /// `data_dir` open while this runs.
+    pub(crate) fn swap_in_backup(data_dir: &str, staging: &Path) -> Result<(), AppError> {
+        let data_dir = Path::new(data_dir);
+        let previous = data_dir.join(PREVIOUS_DIR);
+        let _ = std::fs::remove_dir_all(&previous);
+        std::fs::create_dir_all(&previous).map_err(|e| io_err("Failed to prepare restore", e))?;
+
+        let mut entries: Vec<&str> = DB_COMPANIONS.to_vec();
+        entries.push(SESSIONS_DIR);
+
+        let mut moved_aside = Vec::new();
+        let mut moved_in = Vec::new();
+        let result = (|| {
+            for name in &entries {
+                let live = data_dir.join(name);
+                if live.exists() {
+                    std::fs::rename(&live, previous.join(name))
+                        .map_err(|e| io_err("Failed to set aside current data", e))?;
+                    moved_aside.push(*name);
+                }
+            }
+            for name in &entries {
+                let staged = staging.join(name);
+                if staged.exists() {
+                    std::fs::rename(&staged, data_dir.join(name))
+                        .map_err(|e| io_err("Failed to move restored data into place", e))?;
+                    moved_in.push(*name);
+                }
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            warn!("Restore failed, rolling back: {}", e);
+            for name in moved_in {
+                let _ = std::fs::rename(data_dir.join(name), staging.join(name));
+            }
+            for name in moved_aside {
+                let _ = std::fs::rename(previous.join(name), data_dir.join(name));
+            }
+            let _ = std::fs::remove_dir_all(staging);
+            return Err(e);
+        }
+
+        let _ = std::fs::remove_dir_all(staging);
+        let _ = std::fs::remove_dir_all(&previous);
+        info!("Restored backup into {}", data_dir.display());
+        Ok(())
+    }
+}
+
+fn write_archive(archive: &Path, snapshot: &Path, sessions_dir: &Path) -> Result<(), AppError> {
+    let file = File::create(archive).map_err(|e| io_err("Failed to create backup", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let db = std::fs::read(snapshot).map_err(|e| io_err("Failed to read database snapshot", e))?;
+    zip.start_file(DB_FILE, options)
+        .map_err(|e| io_err("Failed to write backup", e))?;
+    zip.write_all(&db)
+        .map_err(|e| io_err("Failed to write backup", e))?;
+
+    if sessions_dir.exists() {
+        let entries = std::fs::read_dir(sessions_dir)
+            .map_err(|e| io_err("Failed to read sessions directory", e))?;
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            // Autosave files are crash-recovery scratch, not finished sessions
+            .filter(|name| name.ends_with(".bin") && !name.starts_with('.'))
+            .collect();
+        names.sort();
+        for name in names {
+            let data = std::fs::read(sessions_dir.join(&name))
+                .map_err(|e| io_err("Failed to read session file", e))?;
+            zip.start_file(format!("{}/{}", SESSIONS_DIR, name), options)
+                .map_err(|e| io_err("Failed to write backup", e))?;
+            zip.write_all(&data)
+                .map_err(|e| io_err("Failed to write backup", e))?;
+        }
+    }
+
+    zip.finish().map_err(|e| io_err("Failed to write backup", e))?;
+    Ok(())
+}
+
+/// Unpack a backup into a fresh `staging` directory. Only `training.db` and
+/// `sessions/*.bin` entries are accepted.
+fn extract_archive(archive: &Path, staging: &Path) -> Result<(), AppError> {
+    let file = File::open(archive).map_err(|e| io_err("Failed to open backup", e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| io_err("Invalid backup archive", e))?;
+
+    let _ = std::fs::remove_dir_all(staging);
+    std::fs::create_dir_all(staging.join(SESSIONS_DIR))
+        .map_err(|e| io_err("Failed to prepare restore", e))?;
+
+    let mut has_db = false;
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| io_err("Invalid backup archive", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let is_session_file = name
+            .strip_prefix("sessions/")
+            .is_some_and(|f| f.ends_with(".bin") && !f.contains(['/', '\\']) && !f.starts_with('.'));
+        if name != DB_FILE && !is_session_file {
+            return Err(AppError::Serialization(format!(
+                "Unexpected entry in backup archive: {}",
+                name
+            )));
+        }
+        has_db |= name == DB_FILE;
+
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| io_err("Failed to read backup entry", e))?;
+        std::fs::write(staging.join(&name), data)
+            .map_err(|e| io_err("Failed to extract backup", e))?;
+    }
+
+    if !has_db {
+        return Err(AppError::Serialization(
+            "Backup archive does not contain training.db".into(),
+        ));
+    }
+    Ok(())
+}
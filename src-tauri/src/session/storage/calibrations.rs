@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use super::Storage;
+use crate::error::AppError;
+
+/// One trainer calibration result. Trainers report a zero offset, a spin-down
+/// time, or both depending on the calibration they support.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationEntry {
+    pub id: i64,
+    pub device_id: String,
+    /// RFC 3339 timestamp of the calibration
+    pub date: String,
+    pub zero_offset: Option<u16>,
+    pub spindown_ms: Option<u16>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CalibrationRow {
+    id: i64,
+    device_id: String,
+    date: String,
+    zero_offset: Option<i32>,
+    spindown_ms: Option<i32>,
+}
+
+impl Storage {
+    /// Store a calibration result taken at `date` (RFC 3339). Returns the row id.
+    pub async fn record_calibration(
+        &self,
+        device_id: &str,
+        date: &str,
+        zero_offset: Option<u16>,
+        spindown_ms: Option<u16>,
+    ) -> Result<i64, AppError> {
+        chrono::DateTime::parse_from_rfc3339(date).map_err(|e| {
+            AppError::Session(format!("Invalid calibration date '{}': {}", date, e))
+        })?;
+        let result = sqlx::query(
+            "INSERT INTO calibrations (device_id, date, zero_offset, spindown_ms) VALUES (?, ?, ?, ?)",
+        )
+        .bind(device_id)
+        .bind(date)
+        .bind(zero_offset.map(|v| v as i32))
+        .bind(spindown_ms.map(|v| v as i32))
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Calibrations recorded for a device, newest first.
+    pub async fn get_calibration_history(
+        &self,
+        device_id: &str,
+    ) -> Result<Vec<CalibrationEntry>, AppError> {
+        let rows = sqlx::query_as::<_, CalibrationRow>(
+            "SELECT id, device_id, date, zero_offset, spindown_ms FROM calibrations \
+             WHERE device_id = ? ORDER BY date DESC, id DESC",
+        )
+        .bind(device_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| CalibrationEntry {
+                id: row.id,
+                device_id: row.device_id,
+                date: row.date,
+                zero_offset: row.zero_offset.map(|v| v as u16),
+                spindown_ms: row.spindown_ms.map(|v| v as u16),
+            })
+            .collect())
+    }
+}
@@ -1,6 +1,6 @@
 use super::Storage;
 use crate::error::AppError;
-use crate::session::types::SessionConfig;
+use crate::session::types::{PowerZoneModel, SessionConfig};
 
 #[derive(sqlx::FromRow)]
 struct ConfigRow {
@@ -18,10 +18,34 @@ struct ConfigRow {
     power_zone_4: i32,
     power_zone_5: i32,
     power_zone_6: i32,
+    power_zone_count: i32,
     date_of_birth: Option<String>,
     sex: Option<String>,
     resting_hr: Option<i32>,
     max_hr: Option<i32>,
+    cadence_zone_1: i32,
+    cadence_zone_2: i32,
+    cadence_zone_3: i32,
+    cadence_zone_4: i32,
+    critical_power: Option<i32>,
+    w_prime: Option<i64>,
+    crank_length_mm: f64,
+    battery_low_threshold: i32,
+    autosave_interval_secs: i32,
+    connection_check_interval_secs: i32,
+    pace_zone_1: i32,
+    pace_zone_2: i32,
+    pace_zone_3: i32,
+    pace_zone_4: i32,
+    max_gap_secs: i32,
+    ftp_running: Option<i32>,
+    ftp_rowing: Option<i32>,
+    auto_connect_on_start: bool,
+    auto_pause_secs: Option<i32>,
+    virtual_speed: bool,
+    bike_weight_kg: f64,
+    virtual_crr: f64,
+    virtual_cda: f64,
 }
 
 impl Storage {
@@ -29,7 +53,13 @@ impl Storage {
         let row = sqlx::query_as::<_, ConfigRow>(
             "SELECT ftp, weight_kg, hr_zone_1, hr_zone_2, hr_zone_3, hr_zone_4, hr_zone_5, \
              units, power_zone_1, power_zone_2, power_zone_3, power_zone_4, power_zone_5, \
-             power_zone_6, date_of_birth, sex, resting_hr, max_hr \
+             power_zone_6, power_zone_count, date_of_birth, sex, resting_hr, max_hr, \
+             cadence_zone_1, cadence_zone_2, cadence_zone_3, cadence_zone_4, \
+             critical_power, w_prime, crank_length_mm, battery_low_threshold, \
+             autosave_interval_secs, connection_check_interval_secs, \
+             pace_zone_1, pace_zone_2, pace_zone_3, pace_zone_4, max_gap_secs, \
+             ftp_running, ftp_rowing, auto_connect_on_start, auto_pause_secs, virtual_speed, \
+             bike_weight_kg, virtual_crr, virtual_cda \
              FROM user_config WHERE id = 1",
         )
         .fetch_one(&self.pool)
@@ -54,10 +84,39 @@ impl Storage {
                 row.power_zone_5 as u16,
                 row.power_zone_6 as u16,
             ],
+            power_zone_model: PowerZoneModel::from_zone_count(row.power_zone_count as u8)
+                .unwrap_or_default(),
             date_of_birth: row.date_of_birth,
             sex: row.sex,
             resting_hr: row.resting_hr.map(|v| v as u8),
             max_hr: row.max_hr.map(|v| v as u8),
+            cadence_zones: [
+                row.cadence_zone_1 as u16,
+                row.cadence_zone_2 as u16,
+                row.cadence_zone_3 as u16,
+                row.cadence_zone_4 as u16,
+            ],
+            critical_power: row.critical_power.map(|v| v as u16),
+            w_prime: row.w_prime.map(|v| v as u32),
+            crank_length_mm: row.crank_length_mm as f32,
+            battery_low_threshold: row.battery_low_threshold as u8,
+            autosave_interval_secs: row.autosave_interval_secs as u32,
+            connection_check_interval_secs: row.connection_check_interval_secs as u32,
+            pace_zones: [
+                row.pace_zone_1 as u16,
+                row.pace_zone_2 as u16,
+                row.pace_zone_3 as u16,
+                row.pace_zone_4 as u16,
+            ],
+            max_gap_secs: row.max_gap_secs as u32,
+            ftp_running: row.ftp_running.map(|v| v as u16),
+            ftp_rowing: row.ftp_rowing.map(|v| v as u16),
+            auto_connect_on_start: row.auto_connect_on_start,
+            auto_pause_secs: row.auto_pause_secs.map(|v| v as u32),
+            virtual_speed: row.virtual_speed,
+            bike_weight_kg: row.bike_weight_kg as f32,
+            virtual_crr: row.virtual_crr as f32,
+            virtual_cda: row.virtual_cda as f32,
         })
     }
 
@@ -65,8 +124,16 @@ impl Storage {
         sqlx::query(
             "INSERT INTO user_config (id, ftp, weight_kg, hr_zone_1, hr_zone_2, hr_zone_3, \
              hr_zone_4, hr_zone_5, units, power_zone_1, power_zone_2, power_zone_3, \
-             power_zone_4, power_zone_5, power_zone_6, date_of_birth, sex, resting_hr, max_hr) \
-             VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             power_zone_4, power_zone_5, power_zone_6, power_zone_count, date_of_birth, sex, \
+             resting_hr, max_hr, \
+             cadence_zone_1, cadence_zone_2, cadence_zone_3, cadence_zone_4, \
+             critical_power, w_prime, crank_length_mm, battery_low_threshold, \
+             autosave_interval_secs, connection_check_interval_secs, \
+             pace_zone_1, pace_zone_2, pace_zone_3, pace_zone_4, max_gap_secs, \
+             ftp_running, ftp_rowing, auto_connect_on_start, auto_pause_secs, virtual_speed, \
+             bike_weight_kg, virtual_crr, virtual_cda) \
+             VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, \
+             ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
              ON CONFLICT(id) DO UPDATE SET \
              ftp = excluded.ftp, weight_kg = excluded.weight_kg, \
              hr_zone_1 = excluded.hr_zone_1, hr_zone_2 = excluded.hr_zone_2, \
@@ -75,8 +142,25 @@ impl Storage {
              power_zone_1 = excluded.power_zone_1, power_zone_2 = excluded.power_zone_2, \
              power_zone_3 = excluded.power_zone_3, power_zone_4 = excluded.power_zone_4, \
              power_zone_5 = excluded.power_zone_5, power_zone_6 = excluded.power_zone_6, \
+             power_zone_count = excluded.power_zone_count, \
              date_of_birth = excluded.date_of_birth, sex = excluded.sex, \
-             resting_hr = excluded.resting_hr, max_hr = excluded.max_hr",
+             resting_hr = excluded.resting_hr, max_hr = excluded.max_hr, \
+             cadence_zone_1 = excluded.cadence_zone_1, cadence_zone_2 = excluded.cadence_zone_2, \
+             cadence_zone_3 = excluded.cadence_zone_3, cadence_zone_4 = excluded.cadence_zone_4, \
+             critical_power = excluded.critical_power, w_prime = excluded.w_prime, \
+             crank_length_mm = excluded.crank_length_mm, \
+             battery_low_threshold = excluded.battery_low_threshold, \
+             autosave_interval_secs = excluded.autosave_interval_secs, \
+             connection_check_interval_secs = excluded.connection_check_interval_secs, \
+             pace_zone_1 = excluded.pace_zone_1, pace_zone_2 = excluded.pace_zone_2, \
+             pace_zone_3 = excluded.pace_zone_3, pace_zone_4 = excluded.pace_zone_4, \
+             max_gap_secs = excluded.max_gap_secs, \
+             ftp_running = excluded.ftp_running, ftp_rowing = excluded.ftp_rowing, \
+             auto_connect_on_start = excluded.auto_connect_on_start, \
+             auto_pause_secs = excluded.auto_pause_secs, \
+             virtual_speed = excluded.virtual_speed, \
+             bike_weight_kg = excluded.bike_weight_kg, virtual_crr = excluded.virtual_crr, \
+             virtual_cda = excluded.virtual_cda",
         )
         .bind(config.ftp as i32)
         .bind(config.weight_kg as f64)
@@ -92,10 +176,34 @@ impl Storage {
         .bind(config.power_zones[3] as i32)
         .bind(config.power_zones[4] as i32)
         .bind(config.power_zones[5] as i32)
+        .bind(config.power_zone_model.zone_count() as i32)
         .bind(&config.date_of_birth)
         .bind(&config.sex)
         .bind(config.resting_hr.map(|v| v as i32))
         .bind(config.max_hr.map(|v| v as i32))
+        .bind(config.cadence_zones[0] as i32)
+        .bind(config.cadence_zones[1] as i32)
+        .bind(config.cadence_zones[2] as i32)
+        .bind(config.cadence_zones[3] as i32)
+        .bind(config.critical_power.map(|v| v as i32))
+        .bind(config.w_prime.map(|v| v as i64))
+        .bind(config.crank_length_mm as f64)
+        .bind(config.battery_low_threshold as i32)
+        .bind(config.autosave_interval_secs as i32)
+        .bind(config.connection_check_interval_secs as i32)
+        .bind(config.pace_zones[0] as i32)
+        .bind(config.pace_zones[1] as i32)
+        .bind(config.pace_zones[2] as i32)
+        .bind(config.pace_zones[3] as i32)
+        .bind(config.max_gap_secs as i32)
+        .bind(config.ftp_running.map(|v| v as i32))
+        .bind(config.ftp_rowing.map(|v| v as i32))
+        .bind(config.auto_connect_on_start)
+        .bind(config.auto_pause_secs.map(|v| v as i32))
+        .bind(config.virtual_speed)
+        .bind(config.bike_weight_kg as f64)
+        .bind(config.virtual_crr as f64)
+        .bind(config.virtual_cda as f64)
         .execute(&self.pool)
         .await
         .map_err(AppError::Database)?;
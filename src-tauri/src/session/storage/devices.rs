@@ -1,4 +1,6 @@
+use chrono::{DateTime, FixedOffset};
 use log::warn;
+use std::collections::BTreeMap;
 
 use super::Storage;
 use crate::device::types::{ConnectionStatus, DeviceInfo, DeviceType, Transport};
@@ -17,6 +19,7 @@ struct KnownDeviceRow {
     model_number: Option<String>,
     serial_number: Option<String>,
     device_group: Option<String>,
+    user_alias: Option<String>,
 }
 
 impl From<KnownDeviceRow> for DeviceInfo {
@@ -26,6 +29,9 @@ impl From<KnownDeviceRow> for DeviceInfo {
             "Power" => DeviceType::Power,
             "CadenceSpeed" => DeviceType::CadenceSpeed,
             "FitnessTrainer" => DeviceType::FitnessTrainer,
+            "MuscleOxygen" => DeviceType::MuscleOxygen,
+            "Radar" => DeviceType::Radar,
+            "Environment" => DeviceType::Environment,
             other => {
                 warn!("Unknown device_type '{}' for device '{}', defaulting to HeartRate", other, row.id);
                 DeviceType::HeartRate
@@ -48,11 +54,32 @@ impl From<KnownDeviceRow> for DeviceInfo {
             model_number: row.model_number,
             serial_number: row.serial_number,
             device_group: row.device_group,
+            alias: row.user_alias,
             in_range: true,
         }
     }
 }
 
+/// The devices to reconnect on startup: for each device type, the one connected
+/// most recently. Rows are `(id, device_type, last_connected)`; devices whose
+/// timestamp doesn't parse are skipped. Ordered by device type for stable output.
+fn pick_auto_connect(rows: Vec<(String, String, String)>) -> Vec<String> {
+    let mut newest: BTreeMap<String, (DateTime<FixedOffset>, String)> = BTreeMap::new();
+    for (id, device_type, last_connected) in rows {
+        let Ok(at) = DateTime::parse_from_rfc3339(&last_connected) else {
+            warn!("Ignoring unparsable last_connected '{}' for {}", last_connected, id);
+            continue;
+        };
+        match newest.get(&device_type) {
+            Some((best, _)) if *best >= at => {}
+            _ => {
+                newest.insert(device_type, (at, id));
+            }
+        }
+    }
+    newest.into_values().map(|(_, id)| id).collect()
+}
+
 impl Storage {
     #[cfg(test)]
     pub async fn upsert_known_device(&self, device: &DeviceInfo) -> Result<(), AppError> {
@@ -64,8 +91,8 @@ impl Storage {
             .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
         sqlx::query(
             "INSERT INTO known_devices (id, name, device_type, transport, rssi, battery_level, \
-             last_seen, manufacturer, model_number, serial_number, device_group) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             last_seen, manufacturer, model_number, serial_number, device_group, user_alias) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
              ON CONFLICT(id) DO UPDATE SET \
                name = COALESCE(excluded.name, known_devices.name), \
                rssi = COALESCE(excluded.rssi, known_devices.rssi), \
@@ -74,7 +101,8 @@ impl Storage {
                manufacturer = COALESCE(excluded.manufacturer, known_devices.manufacturer), \
                model_number = COALESCE(excluded.model_number, known_devices.model_number), \
                serial_number = COALESCE(excluded.serial_number, known_devices.serial_number), \
-               device_group = COALESCE(excluded.device_group, known_devices.device_group)",
+               device_group = COALESCE(excluded.device_group, known_devices.device_group), \
+               user_alias = COALESCE(excluded.user_alias, known_devices.user_alias)",
         )
         .bind(&device.id)
         .bind(&device.name)
@@ -87,6 +115,7 @@ impl Storage {
         .bind(&device.model_number)
         .bind(&device.serial_number)
         .bind(&device.device_group)
+        .bind(&device.alias)
         .execute(&self.pool)
         .await
         .map_err(AppError::Database)?;
@@ -104,8 +133,8 @@ impl Storage {
                 .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
             sqlx::query(
                 "INSERT INTO known_devices (id, name, device_type, transport, rssi, battery_level, \
-                 last_seen, manufacturer, model_number, serial_number, device_group) \
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+                 last_seen, manufacturer, model_number, serial_number, device_group, user_alias) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
                  ON CONFLICT(id) DO UPDATE SET \
                    name = COALESCE(excluded.name, known_devices.name), \
                    rssi = COALESCE(excluded.rssi, known_devices.rssi), \
@@ -114,7 +143,8 @@ impl Storage {
                    manufacturer = COALESCE(excluded.manufacturer, known_devices.manufacturer), \
                    model_number = COALESCE(excluded.model_number, known_devices.model_number), \
                    serial_number = COALESCE(excluded.serial_number, known_devices.serial_number), \
-                   device_group = COALESCE(excluded.device_group, known_devices.device_group)",
+                   device_group = COALESCE(excluded.device_group, known_devices.device_group), \
+               user_alias = COALESCE(excluded.user_alias, known_devices.user_alias)",
             )
             .bind(&device.id)
             .bind(&device.name)
@@ -127,6 +157,7 @@ impl Storage {
             .bind(&device.model_number)
             .bind(&device.serial_number)
             .bind(&device.device_group)
+            .bind(&device.alias)
             .execute(&mut *tx)
             .await
             .map_err(AppError::Database)?;
@@ -135,6 +166,31 @@ impl Storage {
         Ok(())
     }
 
+    /// Record a successful connection, adding the device to the known list if a
+    /// scan never saw it (e.g. an ANT+ sensor paired by number).
+    pub async fn mark_device_connected(&self, device: &DeviceInfo) -> Result<(), AppError> {
+        self.upsert_known_devices_batch(std::slice::from_ref(device)).await?;
+        sqlx::query("UPDATE known_devices SET last_connected = ? WHERE id = ?")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(&device.id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(())
+    }
+
+    /// Ids of the sensors to reconnect on startup (see `pick_auto_connect`).
+    pub async fn auto_connect_device_ids(&self) -> Result<Vec<String>, AppError> {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT id, device_type, last_connected FROM known_devices \
+             WHERE last_connected IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(pick_auto_connect(rows))
+    }
+
     pub async fn clear_device_group(&self, device_id: &str) -> Result<(), AppError> {
         sqlx::query("UPDATE known_devices SET device_group = NULL WHERE id = ?")
             .bind(device_id)
@@ -144,10 +200,37 @@ impl Storage {
         Ok(())
     }
 
+    /// Set or clear (with `None`) the user-assigned alias for a known device.
+    pub async fn set_device_alias(
+        &self,
+        device_id: &str,
+        alias: Option<String>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE known_devices SET user_alias = ? WHERE id = ?")
+            .bind(&alias)
+            .bind(device_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::DeviceNotFound(device_id.to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_known_device(&self, device_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM known_devices WHERE id = ?")
+            .bind(device_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(())
+    }
+
     pub async fn list_known_devices(&self) -> Result<Vec<DeviceInfo>, AppError> {
         let rows = sqlx::query_as::<_, KnownDeviceRow>(
             "SELECT id, name, device_type, transport, rssi, battery_level, last_seen, \
-             manufacturer, model_number, serial_number, device_group \
+             manufacturer, model_number, serial_number, device_group, user_alias \
              FROM known_devices ORDER BY last_seen DESC",
         )
         .fetch_all(&self.pool)
@@ -155,4 +238,66 @@ impl Storage {
         .map_err(AppError::Database)?;
         Ok(rows.into_iter().map(|r| r.into()).collect())
     }
+
+    /// Stored wheel circumference for a speed sensor, if one has been configured.
+    pub async fn get_wheel_circumference(&self, device_id: &str) -> Result<Option<u32>, AppError> {
+        let row: Option<(Option<i64>,)> = sqlx::query_as(
+            "SELECT wheel_circumference_mm FROM device_settings WHERE device_id = ?",
+        )
+        .bind(device_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(row.and_then(|(mm,)| mm).map(|v| v as u32))
+    }
+
+    pub async fn set_wheel_circumference(&self, device_id: &str, mm: u32) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO device_settings (device_id, wheel_circumference_mm) VALUES (?, ?) \
+             ON CONFLICT(device_id) DO UPDATE SET \
+             wheel_circumference_mm = excluded.wheel_circumference_mm",
+        )
+        .bind(device_id)
+        .bind(mm as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, device_type: &str, last_connected: &str) -> (String, String, String) {
+        (id.to_string(), device_type.to_string(), last_connected.to_string())
+    }
+
+    #[test]
+    fn auto_connect_picks_newest_device_per_type() {
+        let rows = vec![
+            row("hr-old", "HeartRate", "2025-05-01T07:00:00+00:00"),
+            row("hr-new", "HeartRate", "2025-06-01T07:00:00+00:00"),
+            row("pm-1", "Power", "2025-06-01T07:00:05+00:00"),
+            // Same sensor over ANT+, connected on an earlier ride
+            row("ant:12:11", "Power", "2025-04-20T07:00:00+00:00"),
+            row("trainer", "FitnessTrainer", "2025-06-01T06:59:58+00:00"),
+            row("broken", "CadenceSpeed", "yesterday"),
+        ];
+        let mut picked = pick_auto_connect(rows);
+        picked.sort();
+        assert_eq!(picked, vec!["hr-new", "pm-1", "trainer"]);
+    }
+
+    #[test]
+    fn auto_connect_compares_timestamps_across_offsets() {
+        // 08:00+02:00 is 06:00 UTC, earlier than 07:00 UTC
+        let rows = vec![
+            row("hr-a", "HeartRate", "2025-06-01T08:00:00+02:00"),
+            row("hr-b", "HeartRate", "2025-06-01T07:00:00+00:00"),
+        ];
+        assert_eq!(pick_auto_connect(rows), vec!["hr-b"]);
+        assert!(pick_auto_connect(Vec::new()).is_empty());
+    }
 }
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::training_load::parse_date;
+use super::Storage;
+use crate::error::AppError;
+
+/// One FTP change. `source` records where the value came from, e.g. `manual`
+/// for a settings edit or `estimate` for an accepted ride estimate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FtpHistoryEntry {
+    pub id: i64,
+    pub date: String,
+    pub ftp: u16,
+    pub source: String,
+}
+
+impl Storage {
+    /// Append an FTP value effective from `date` (`YYYY-MM-DD`). Returns the row id.
+    pub async fn record_ftp(&self, ftp: u16, date: &str, source: &str) -> Result<i64, AppError> {
+        parse_date(date)?;
+        let result = sqlx::query("INSERT INTO ftp_history (date, ftp, source) VALUES (?, ?, ?)")
+            .bind(date)
+            .bind(ftp as i32)
+            .bind(source)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// All recorded FTP values, oldest first.
+    pub async fn get_ftp_history(&self) -> Result<Vec<FtpHistoryEntry>, AppError> {
+        let rows: Vec<(i64, String, i32, String)> =
+            sqlx::query_as("SELECT id, date, ftp, source FROM ftp_history ORDER BY date, id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(AppError::Database)?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, date, ftp, source)| FtpHistoryEntry {
+                id,
+                date,
+                ftp: ftp as u16,
+                source,
+            })
+            .collect())
+    }
+}
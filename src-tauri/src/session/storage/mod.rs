@@ -1,8 +1,23 @@
 mod autosave;
+mod backup;
+mod calibrations;
 mod config;
 mod devices;
+mod ftp_history;
+mod period_summary;
 mod power_curves;
+mod profiles;
+mod segments;
 mod sessions;
+mod strava;
+mod training_load;
+mod zone_control;
+
+pub use calibrations::CalibrationEntry;
+pub use ftp_history::FtpHistoryEntry;
+pub use period_summary::{PeriodGranularity, PeriodSummary};
+pub use profiles::{SharedStorage, DEFAULT_PROFILE};
+pub use training_load::LoadPoint;
 
 use log::info;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
@@ -110,6 +125,273 @@ impl Storage {
             "ALTER TABLE sessions ADD COLUMN distance_km REAL",
         )
         .await?;
+        // Migration 010: critical power model for W′ balance
+        let migration_010_stmts = [
+            "ALTER TABLE user_config ADD COLUMN critical_power INTEGER",
+            "ALTER TABLE user_config ADD COLUMN w_prime INTEGER",
+        ];
+        for stmt in migration_010_stmts {
+            run_alter_ignore_duplicate(&pool, stmt).await?;
+        }
+        // Migration 011: efficiency factor (NP / avg HR)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN efficiency_factor REAL",
+        )
+        .await?;
+        // Migration 012: per-device settings (wheel circumference for speed sensors)
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS device_settings (
+                device_id TEXT PRIMARY KEY,
+                wheel_circumference_mm INTEGER
+            )"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        // Migration 013: user-assigned device aliases
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE known_devices ADD COLUMN user_alias TEXT",
+        )
+        .await?;
+        // Migration 014: full-text index over session titles and notes, kept in
+        // sync by triggers. It is keyed on the implicit rowid, which VACUUM and
+        // backup restores may renumber, so it is rebuilt on every open.
+        sqlx::raw_sql(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS sessions_fts
+                USING fts5(title, notes, content='sessions', content_rowid='rowid');
+            CREATE TRIGGER IF NOT EXISTS sessions_fts_insert AFTER INSERT ON sessions BEGIN
+                INSERT INTO sessions_fts(rowid, title, notes)
+                VALUES (new.rowid, new.title, new.notes);
+            END;
+            CREATE TRIGGER IF NOT EXISTS sessions_fts_delete AFTER DELETE ON sessions BEGIN
+                INSERT INTO sessions_fts(sessions_fts, rowid, title, notes)
+                VALUES ('delete', old.rowid, old.title, old.notes);
+            END;
+            CREATE TRIGGER IF NOT EXISTS sessions_fts_update AFTER UPDATE OF title, notes ON sessions BEGIN
+                INSERT INTO sessions_fts(sessions_fts, rowid, title, notes)
+                VALUES ('delete', old.rowid, old.title, old.notes);
+                INSERT INTO sessions_fts(rowid, title, notes)
+                VALUES (new.rowid, new.title, new.notes);
+            END;"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        sqlx::raw_sql("INSERT INTO sessions_fts(sessions_fts) VALUES ('rebuild')")
+            .execute(&pool)
+            .await
+            .map_err(AppError::Database)?;
+        // Migration 015: FTP history for charting threshold changes over time
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS ftp_history (
+                id INTEGER PRIMARY KEY,
+                date TEXT NOT NULL,
+                ftp INTEGER NOT NULL,
+                source TEXT NOT NULL
+            )"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        // Migration 016: estimated energy expenditure per session
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE sessions ADD COLUMN calories INTEGER").await?;
+        // Migration 017: average cadence over pedalling time only
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN avg_cadence_active REAL",
+        )
+        .await?;
+        // Migration 018: crank length for quadrant analysis
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN crank_length_mm REAL NOT NULL DEFAULT 172.5",
+        )
+        .await?;
+        // Migration 019: average ambient temperature from an environment sensor
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE sessions ADD COLUMN avg_temp_c REAL").await?;
+        // Migration 020: climbing derived from simulation-mode grade
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE sessions ADD COLUMN total_ascent_m REAL").await?;
+        // Migration 021: low-battery warning threshold (percent)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN battery_low_threshold INTEGER NOT NULL DEFAULT 15",
+        )
+        .await?;
+        // Migration 022: zone control tuning (single row, absent = defaults)
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS zone_control_config (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                max_watts_up_per_tick REAL NOT NULL,
+                max_watts_down_per_tick REAL NOT NULL,
+                min_power INTEGER NOT NULL,
+                safety_power INTEGER NOT NULL,
+                hr_sensor_warn_secs INTEGER NOT NULL,
+                hr_sensor_stop_secs INTEGER NOT NULL,
+                power_sensor_warn_secs INTEGER NOT NULL,
+                cadence_zero_secs INTEGER NOT NULL
+            )"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        // Migration 023: starred sessions
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        // Migration 024: xPower
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE sessions ADD COLUMN xpower INTEGER").await?;
+        // Migration 025: background task intervals (seconds)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN autosave_interval_secs INTEGER NOT NULL DEFAULT 30",
+        )
+        .await?;
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN connection_check_interval_secs INTEGER NOT NULL DEFAULT 5",
+        )
+        .await?;
+        // Migration 026: ERG power-match bias (percent)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE zone_control_config ADD COLUMN power_bias_pct INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        // Migration 027: trainer calibration results, for spotting drift
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS calibrations (
+                id INTEGER PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                date TEXT NOT NULL,
+                zero_offset INTEGER,
+                spindown_ms INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_calibrations_device ON calibrations(device_id, date)"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        // Migration 028: running pace zone band edges (seconds per km)
+        let migration_028_stmts = [
+            "ALTER TABLE user_config ADD COLUMN pace_zone_1 INTEGER NOT NULL DEFAULT 390",
+            "ALTER TABLE user_config ADD COLUMN pace_zone_2 INTEGER NOT NULL DEFAULT 330",
+            "ALTER TABLE user_config ADD COLUMN pace_zone_3 INTEGER NOT NULL DEFAULT 300",
+            "ALTER TABLE user_config ADD COLUMN pace_zone_4 INTEGER NOT NULL DEFAULT 270",
+        ];
+        for stmt in migration_028_stmts {
+            run_alter_ignore_duplicate(&pool, stmt).await?;
+        }
+        // Migration 029: labelled time ranges within a session
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS session_segments (
+                id INTEGER PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                start_secs INTEGER NOT NULL,
+                end_secs INTEGER NOT NULL,
+                label TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_segments_session ON session_segments(session_id)"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        // Migration 030: power zone model, stored as its zone count (3, 5 or 7)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN power_zone_count INTEGER NOT NULL DEFAULT 7",
+        )
+        .await?;
+        // Migration 031: Strava OAuth credentials (single row) and uploaded activity ids
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS strava_auth (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                client_id TEXT NOT NULL,
+                client_secret TEXT NOT NULL,
+                access_token TEXT NOT NULL,
+                refresh_token TEXT NOT NULL,
+                expires_at INTEGER NOT NULL
+            )"
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::Database)?;
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN strava_activity_id INTEGER",
+        )
+        .await?;
+        // Migration 032: analysis gap cap (seconds)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN max_gap_secs INTEGER NOT NULL DEFAULT 5",
+        )
+        .await?;
+        // Migration 033: per-sport power thresholds (NULL = use cycling ftp)
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE user_config ADD COLUMN ftp_running INTEGER")
+            .await?;
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE user_config ADD COLUMN ftp_rowing INTEGER")
+            .await?;
+        // Migration 034: work-validation quality score (0–100)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN data_quality_score INTEGER",
+        )
+        .await?;
+        // Migration 035: average right-pedal balance (%)
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE sessions ADD COLUMN avg_pedal_balance INTEGER",
+        )
+        .await?;
+        // Migration 036: reconnect last-used sensors on startup
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN auto_connect_on_start INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE known_devices ADD COLUMN last_connected TEXT")
+            .await?;
+        // Migration 037: auto-pause threshold (NULL = off)
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE user_config ADD COLUMN auto_pause_secs INTEGER")
+            .await?;
+        // Migration 038: opt-in speed derived from trainer power
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE user_config ADD COLUMN virtual_speed INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        // Migration 039: virtual speed model parameters
+        let migration_039_stmts = [
+            "ALTER TABLE user_config ADD COLUMN bike_weight_kg REAL NOT NULL DEFAULT 8.0",
+            "ALTER TABLE user_config ADD COLUMN virtual_crr REAL NOT NULL DEFAULT 0.005",
+            "ALTER TABLE user_config ADD COLUMN virtual_cda REAL NOT NULL DEFAULT 0.36",
+        ];
+        for stmt in migration_039_stmts {
+            run_alter_ignore_duplicate(&pool, stmt).await?;
+        }
+        // Migration 040: per-aspect RPE as JSON
+        run_alter_ignore_duplicate(&pool, "ALTER TABLE sessions ADD COLUMN structured_rpe TEXT")
+            .await?;
+        // Migration 041: zone control HR ceiling as a share of HR reserve
+        run_alter_ignore_duplicate(
+            &pool,
+            "ALTER TABLE zone_control_config ADD COLUMN hr_reserve_ceiling_pct INTEGER NOT NULL DEFAULT 95",
+        )
+        .await?;
+        // Migration 042: cadence zone band edges (RPM)
+        let migration_042_stmts = [
+            "ALTER TABLE user_config ADD COLUMN cadence_zone_1 INTEGER NOT NULL DEFAULT 60",
+            "ALTER TABLE user_config ADD COLUMN cadence_zone_2 INTEGER NOT NULL DEFAULT 75",
+            "ALTER TABLE user_config ADD COLUMN cadence_zone_3 INTEGER NOT NULL DEFAULT 90",
+            "ALTER TABLE user_config ADD COLUMN cadence_zone_4 INTEGER NOT NULL DEFAULT 105",
+        ];
+        for stmt in migration_042_stmts {
+            run_alter_ignore_duplicate(&pool, stmt).await?;
+        }
         // Power curve cache table (idempotent CREATE IF NOT EXISTS)
         sqlx::raw_sql(
             "CREATE TABLE IF NOT EXISTS session_power_curves (
@@ -139,7 +421,7 @@ mod tests {
     use super::*;
     use crate::device::types::{ConnectionStatus, DeviceType, SensorReading, Transport};
     use crate::session::analysis::PowerCurvePoint;
-    use crate::session::types::{SessionConfig, SessionSummary};
+    use crate::session::types::{PowerZoneModel, SessionConfig, SessionSummary, StructuredRpe};
 
     async fn test_storage() -> (Storage, tempfile::TempDir) {
         let tmp = tempfile::TempDir::new().unwrap();
@@ -171,6 +453,15 @@ mod tests {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: None,
+            calories: None,
+            avg_cadence_active: None,
+            avg_temp_c: None,
+            total_ascent_m: None,
+            is_favorite: false,
+            xpower: None,
+            data_quality_score: None,
+            avg_pedal_balance: None,
         }
     }
 
@@ -188,6 +479,7 @@ mod tests {
             model_number: None,
             serial_number: None,
             device_group: None,
+            alias: None,
             in_range: true,
         }
     }
@@ -264,6 +556,15 @@ mod tests {
             activity_type: None,
             rpe: None,
             notes: None,
+            efficiency_factor: None,
+            calories: None,
+            avg_cadence_active: None,
+            avg_temp_c: None,
+            total_ascent_m: None,
+            is_favorite: false,
+            xpower: None,
+            data_quality_score: None,
+            avg_pedal_balance: None,
         };
         storage.save_session(&summary, b"").await.unwrap();
 
@@ -274,6 +575,206 @@ mod tests {
         assert_eq!(sessions[0].tss, None);
     }
 
+    #[tokio::test]
+    async fn efficiency_factor_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        let mut summary = make_summary("ef-1");
+        // NP 190 / avg HR 145 ≈ 1.31
+        summary.efficiency_factor = Some(1.31);
+        storage.save_session(&summary, b"raw").await.unwrap();
+
+        let loaded = storage.get_session("ef-1").await.unwrap();
+        let ef = loaded.efficiency_factor.expect("EF should round-trip");
+        assert!((ef - 1.31).abs() < 0.01, "EF should be ~1.31, got {ef}");
+    }
+
+    fn steady_ride(watts: u16, secs: u64) -> Vec<u8> {
+        let readings: Vec<SensorReading> = (0..secs)
+            .map(|s| SensorReading::Power {
+                watts,
+                timestamp: None,
+                epoch_ms: s * 1000,
+                device_id: "pm-1".to_string(),
+                pedal_balance: None,
+            })
+            .collect();
+        bincode::serialize(&readings).unwrap()
+    }
+
+    #[tokio::test]
+    async fn raising_ftp_lowers_recomputed_if_and_tss() {
+        let (storage, _tmp) = test_storage().await;
+        let mut summary = make_summary("ftp-1");
+        summary.duration_secs = 600;
+        storage.save_session(&summary, &steady_ride(200, 600)).await.unwrap();
+
+        let at_200 = storage.recompute_session_metrics("ftp-1", 200).await.unwrap();
+        let at_250 = storage.recompute_session_metrics("ftp-1", 250).await.unwrap();
+        assert!((at_200.intensity_factor.unwrap() - 1.0).abs() < 0.01);
+        assert!(at_250.intensity_factor.unwrap() < at_200.intensity_factor.unwrap());
+        assert!(at_250.tss.unwrap() < at_200.tss.unwrap());
+        assert_eq!(at_250.normalized_power, at_200.normalized_power);
+
+        let stored = storage.get_session("ftp-1").await.unwrap();
+        assert_eq!(stored.ftp, Some(250));
+        assert_eq!(stored.tss, at_250.tss);
+        assert_eq!(stored.intensity_factor, at_250.intensity_factor);
+    }
+
+    #[tokio::test]
+    async fn recompute_all_after_only_touches_later_sessions() {
+        let (storage, _tmp) = test_storage().await;
+        let mut early = make_summary("early");
+        early.start_time = "2026-03-01T08:00:00Z".parse().unwrap();
+        let mut late = make_summary("late");
+        late.start_time = "2026-03-10T08:00:00Z".parse().unwrap();
+        for s in [&early, &late] {
+            storage.save_session(s, &steady_ride(200, 120)).await.unwrap();
+        }
+
+        let updated = storage.recompute_all_after("2026-03-05", 250).await.unwrap();
+        assert_eq!(updated, 1);
+        assert_eq!(storage.get_session("early").await.unwrap().ftp, Some(200));
+        assert_eq!(storage.get_session("late").await.unwrap().ftp, Some(250));
+        assert!(storage.recompute_all_after("March 5", 250).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn split_session_recomputes_both_halves() {
+        let (storage, _tmp) = test_storage().await;
+        let mut summary = make_summary("split-1");
+        summary.duration_secs = 120;
+        summary.title = Some("Two rides".into());
+        let readings: Vec<SensorReading> = (0..120)
+            .map(|s| SensorReading::Power {
+                watts: if s < 60 { 150 } else { 250 },
+                timestamp: None,
+                epoch_ms: s * 1000,
+                device_id: "pm-1".to_string(),
+                pedal_balance: None,
+            })
+            .collect();
+        storage
+            .save_session(&summary, &bincode::serialize(&readings).unwrap())
+            .await
+            .unwrap();
+        storage
+            .save_power_curve(
+                "split-1",
+                &crate::session::analysis::compute_power_curve_from_readings(&readings),
+            )
+            .await
+            .unwrap();
+
+        let structured = StructuredRpe { overall: 7, legs: 9, breathing: 5 };
+        storage
+            .update_session_metadata("split-1", None, None, None, None, Some(structured))
+            .await
+            .unwrap();
+
+        assert!(storage.split_session("split-1", 0).await.is_err());
+        assert!(storage.split_session("split-1", 120).await.is_err());
+        let (first, second) = storage.split_session("split-1", 60).await.unwrap();
+
+        assert!(storage.get_session("split-1").await.is_err());
+        assert!(!storage.has_power_curve("split-1").await.unwrap());
+        let a = storage.get_session(&first).await.unwrap();
+        let b = storage.get_session(&second).await.unwrap();
+        assert_eq!(a.duration_secs, 60);
+        assert_eq!(b.duration_secs, 60);
+        assert_eq!((a.avg_power, b.avg_power), (Some(150), Some(250)));
+        assert_eq!(a.title.as_deref(), Some("Two rides"));
+        assert!(b.title.is_none());
+        assert_eq!(storage.get_structured_rpe(&first).await.unwrap(), Some(structured));
+        assert_eq!(storage.get_structured_rpe(&second).await.unwrap(), None);
+        assert_eq!(b.start_time, a.start_time + chrono::Duration::seconds(60));
+        assert_eq!(storage.load_sensor_data(&first).unwrap().len(), 60);
+        assert_eq!(storage.load_sensor_data(&second).unwrap().len(), 60);
+
+        let best_of = |curve: Vec<PowerCurvePoint>| {
+            curve.iter().find(|p| p.duration_secs == 1).map(|p| p.watts)
+        };
+        let first_curve = storage.get_best_power_curve_excluding(&second).await.unwrap();
+        let second_curve = storage.get_best_power_curve_excluding(&first).await.unwrap();
+        assert_eq!(best_of(first_curve), Some(150));
+        assert_eq!(best_of(second_curve), Some(250));
+    }
+
+    #[tokio::test]
+    async fn concat_sessions_joins_two_rides_into_one_timeseries() {
+        let (storage, _tmp) = test_storage().await;
+        let start: chrono::DateTime<chrono::Utc> = "2026-03-01T08:00:00Z".parse().unwrap();
+        let mut a = make_summary("join-a");
+        a.start_time = start;
+        a.duration_secs = 60;
+        a.notes = Some("Dropped at the turnaround".into());
+        let mut b = make_summary("join-b");
+        b.start_time = start + chrono::Duration::seconds(90);
+        b.duration_secs = 60;
+        b.title = Some("Morning loop".into());
+        // Second recording restarted its clock from zero
+        storage.save_session(&a, &steady_ride(200, 60)).await.unwrap();
+        storage.save_session(&b, &steady_ride(200, 60)).await.unwrap();
+        let structured = StructuredRpe { overall: 5, legs: 6, breathing: 3 };
+        storage
+            .update_session_metadata("join-b", None, None, None, None, Some(structured))
+            .await
+            .unwrap();
+
+        assert!(storage.concat_sessions("join-b", "join-a", false).await.is_err());
+        let id = storage.concat_sessions("join-a", "join-b", false).await.unwrap();
+
+        let joined = storage.get_session(&id).await.unwrap();
+        assert_eq!(joined.duration_secs, 120);
+        assert_eq!(joined.start_time, start);
+        assert_eq!(joined.avg_power, Some(200));
+        assert_eq!(joined.notes.as_deref(), Some("Dropped at the turnaround"));
+        assert_eq!(joined.title.as_deref(), Some("Morning loop"));
+        assert_eq!(joined.rpe, Some(5));
+        assert_eq!(storage.get_structured_rpe(&id).await.unwrap(), Some(structured));
+        let epochs: Vec<u64> = storage
+            .load_sensor_data(&id)
+            .unwrap()
+            .iter()
+            .map(|r| r.epoch_ms())
+            .collect();
+        assert_eq!(epochs, (0..120).map(|s| s * 1000).collect::<Vec<_>>());
+        assert!(storage.has_power_curve(&id).await.unwrap());
+        assert!(storage.get_session("join-a").await.is_err());
+        assert!(storage.get_session("join-b").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn concat_sessions_rejects_long_gap_unless_forced() {
+        let (storage, _tmp) = test_storage().await;
+        let start: chrono::DateTime<chrono::Utc> = "2026-03-01T08:00:00Z".parse().unwrap();
+        let mut a = make_summary("gap-a");
+        a.start_time = start;
+        a.duration_secs = 60;
+        let mut b = make_summary("gap-b");
+        b.start_time = start + chrono::Duration::hours(3);
+        b.duration_secs = 60;
+        storage.save_session(&a, &steady_ride(200, 60)).await.unwrap();
+        storage.save_session(&b, &steady_ride(200, 60)).await.unwrap();
+
+        assert!(storage.concat_sessions("gap-a", "gap-b", false).await.is_err());
+        assert!(storage.get_session("gap-a").await.is_ok());
+        let id = storage.concat_sessions("gap-a", "gap-b", true).await.unwrap();
+        assert_eq!(storage.get_session(&id).await.unwrap().duration_secs, 120);
+    }
+
+    #[tokio::test]
+    async fn avg_pedal_balance_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        let mut summary = make_summary("balance-1");
+        summary.avg_pedal_balance = Some(52);
+        storage.save_session(&summary, b"raw").await.unwrap();
+        storage.save_session(&make_summary("balance-2"), b"raw").await.unwrap();
+
+        assert_eq!(storage.get_session("balance-1").await.unwrap().avg_pedal_balance, Some(52));
+        assert_eq!(storage.get_session("balance-2").await.unwrap().avg_pedal_balance, None);
+    }
+
     #[tokio::test]
     async fn save_session_duplicate_id_is_ignored() {
         let (storage, _tmp) = test_storage().await;
@@ -297,6 +798,11 @@ mod tests {
         assert_eq!(config.hr_zones, [120, 140, 160, 175, 190]);
         assert_eq!(config.units, "metric");
         assert_eq!(config.power_zones, [55, 75, 90, 105, 120, 150]);
+        assert_eq!(config.power_zone_model, PowerZoneModel::Seven);
+        assert_eq!(config.cadence_zones, [60, 75, 90, 105]);
+        assert_eq!(config.pace_zones, [390, 330, 300, 270]);
+        assert_eq!(config.crank_length_mm, 172.5);
+        assert_eq!(config.virtual_cda, crate::config::DEFAULT_VIRTUAL_CDA_M2);
     }
 
     #[tokio::test]
@@ -308,10 +814,28 @@ mod tests {
             hr_zones: [130, 150, 165, 180, 195],
             units: "imperial".to_string(),
             power_zones: [60, 80, 95, 110, 125, 155],
+            power_zone_model: PowerZoneModel::Five,
             date_of_birth: Some("1990-01-15".to_string()),
             sex: Some("male".to_string()),
             resting_hr: Some(55),
             max_hr: Some(195),
+            cadence_zones: [65, 80, 95, 110],
+            critical_power: Some(240),
+            w_prime: Some(20000),
+            crank_length_mm: 170.0,
+            battery_low_threshold: 20,
+            autosave_interval_secs: 10,
+            connection_check_interval_secs: 3,
+            pace_zones: [400, 340, 310, 280],
+            max_gap_secs: 12,
+            ftp_running: Some(280),
+            ftp_rowing: None,
+            auto_connect_on_start: true,
+            auto_pause_secs: Some(4),
+            virtual_speed: true,
+            bike_weight_kg: 9.5,
+            virtual_crr: 0.004,
+            virtual_cda: 0.3,
         };
         storage.save_user_config(&config).await.unwrap();
 
@@ -321,6 +845,24 @@ mod tests {
         assert_eq!(loaded.units, "imperial");
         assert_eq!(loaded.date_of_birth, Some("1990-01-15".to_string()));
         assert_eq!(loaded.resting_hr, Some(55));
+        assert_eq!(loaded.cadence_zones, [65, 80, 95, 110]);
+        assert_eq!(loaded.critical_power, Some(240));
+        assert_eq!(loaded.w_prime, Some(20000));
+        assert_eq!(loaded.crank_length_mm, 170.0);
+        assert_eq!(loaded.battery_low_threshold, 20);
+        assert_eq!(loaded.autosave_interval_secs, 10);
+        assert_eq!(loaded.connection_check_interval_secs, 3);
+        assert_eq!(loaded.max_gap_secs, 12);
+        assert_eq!(loaded.ftp_running, Some(280));
+        assert_eq!(loaded.ftp_rowing, None);
+        assert!(loaded.auto_connect_on_start);
+        assert_eq!(loaded.auto_pause_secs, Some(4));
+        assert!(loaded.virtual_speed);
+        assert_eq!(loaded.bike_weight_kg, 9.5);
+        assert_eq!(loaded.virtual_crr, 0.004);
+        assert_eq!(loaded.virtual_cda, 0.3);
+        assert_eq!(loaded.pace_zones, [400, 340, 310, 280]);
+        assert_eq!(loaded.power_zone_model, PowerZoneModel::Five);
     }
 
     #[tokio::test]
@@ -337,6 +879,23 @@ mod tests {
         assert_eq!(loaded.ftp, 350);
     }
 
+    #[tokio::test]
+    async fn zone_control_config_defaults_then_round_trips() {
+        use crate::session::zone_control::types::ZoneControlConfig;
+        let (storage, _tmp) = test_storage().await;
+        assert_eq!(storage.get_zone_control_config().await.unwrap(), ZoneControlConfig::default());
+
+        let config = ZoneControlConfig {
+            max_watts_up_per_tick: 6.0,
+            min_power: 80,
+            hr_sensor_stop_secs: 45,
+            hr_reserve_ceiling_pct: 90,
+            ..Default::default()
+        };
+        storage.save_zone_control_config(&config).await.unwrap();
+        assert_eq!(storage.get_zone_control_config().await.unwrap(), config);
+    }
+
     #[tokio::test]
     async fn upsert_and_list_devices() {
         let (storage, _tmp) = test_storage().await;
@@ -401,6 +960,44 @@ mod tests {
         assert!(!autosave_path.exists());
     }
 
+    #[tokio::test]
+    async fn prune_stale_autosaves_removes_only_old_saved_sessions() {
+        let (storage, _tmp) = test_storage().await;
+        let saved_id = "aaaaaaaa-0000-0000-0000-000000000001";
+        let unknown_id = "bbbbbbbb-0000-0000-0000-000000000002";
+        storage.save_session(&make_summary(saved_id), b"raw").await.unwrap();
+        storage
+            .write_autosave(saved_id, &make_summary(saved_id), &[])
+            .await
+            .unwrap();
+        storage
+            .write_autosave(unknown_id, &make_summary(unknown_id), &[])
+            .await
+            .unwrap();
+
+        let sessions_dir = std::path::Path::new(storage.data_dir()).join("sessions");
+        let stale_path = sessions_dir.join(format!(".autosave_{}.bin", saved_id));
+        let fresh_path = sessions_dir.join(format!(".autosave_{}.bin", unknown_id));
+
+        // Both are fresh: nothing pruned yet
+        assert_eq!(storage.prune_stale_autosaves(24).await.unwrap(), 0);
+
+        let two_days_ago =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(48 * 3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&stale_path)
+            .unwrap()
+            .set_modified(two_days_ago)
+            .unwrap();
+
+        assert_eq!(storage.prune_stale_autosaves(24).await.unwrap(), 1);
+        assert!(!stale_path.exists());
+        assert!(fresh_path.exists());
+        // The saved session's raw data is untouched
+        assert!(sessions_dir.join(format!("{}.bin", saved_id)).exists());
+    }
+
     #[tokio::test]
     async fn autosave_recovery_rejects_path_traversal_id() {
         let (storage, _tmp) = test_storage().await;
@@ -481,6 +1078,83 @@ mod tests {
         assert_eq!(devices[0].serial_number, Some("99999".to_string()));
     }
 
+    #[tokio::test]
+    async fn device_alias_survives_scan_upsert() {
+        let (storage, _tmp) = test_storage().await;
+        let device = make_device("ble-pm", Some("4F2A-91C3"), "2024-01-01T00:00:00Z");
+        storage.upsert_known_device(&device).await.unwrap();
+        storage
+            .set_device_alias("ble-pm", Some("Assioma".to_string()))
+            .await
+            .unwrap();
+
+        // Subsequent scan reports no alias — COALESCE keeps the stored one
+        let rescanned = make_device("ble-pm", Some("4F2A-91C3"), "2024-01-02T00:00:00Z");
+        storage.upsert_known_devices_batch(&[rescanned]).await.unwrap();
+
+        let devices = storage.list_known_devices().await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].alias, Some("Assioma".to_string()));
+        assert_eq!(devices[0].name, Some("4F2A-91C3".to_string()));
+    }
+
+    #[tokio::test]
+    async fn connected_devices_become_auto_connect_candidates() {
+        let (storage, _tmp) = test_storage().await;
+        let scanned = make_device("pm-scanned", Some("Pedals"), "2025-06-01T07:00:00+00:00");
+        storage.upsert_known_devices_batch(&[scanned]).await.unwrap();
+        assert!(storage.auto_connect_device_ids().await.unwrap().is_empty());
+
+        // A device paired without a scan is added to the known list
+        let mut hr = make_device("ant:7:120", None, "2025-06-01T07:00:00+00:00");
+        hr.device_type = DeviceType::HeartRate;
+        storage.mark_device_connected(&hr).await.unwrap();
+        assert_eq!(storage.auto_connect_device_ids().await.unwrap(), vec!["ant:7:120"]);
+        assert!(storage.list_known_devices().await.unwrap().iter().any(|d| d.id == "ant:7:120"));
+    }
+
+    #[tokio::test]
+    async fn set_device_alias_none_clears_alias() {
+        let (storage, _tmp) = test_storage().await;
+        let device = make_device("ble-hr", Some("HRM"), "2024-01-01T00:00:00Z");
+        storage.upsert_known_device(&device).await.unwrap();
+        storage
+            .set_device_alias("ble-hr", Some("Chest strap".to_string()))
+            .await
+            .unwrap();
+        storage.set_device_alias("ble-hr", None).await.unwrap();
+
+        let devices = storage.list_known_devices().await.unwrap();
+        assert_eq!(devices[0].alias, None);
+    }
+
+    #[tokio::test]
+    async fn set_device_alias_unknown_device_errors() {
+        let (storage, _tmp) = test_storage().await;
+        let result = storage.set_device_alias("nope", Some("X".to_string())).await;
+        assert!(matches!(result, Err(AppError::DeviceNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn delete_known_device_removes_from_list() {
+        let (storage, _tmp) = test_storage().await;
+        let keep = make_device("ble-keep", Some("Kickr"), "2024-01-01T00:00:00Z");
+        let gone = make_device("ble-gone", Some("Old Kickr"), "2024-01-02T00:00:00Z");
+        storage.upsert_known_device(&keep).await.unwrap();
+        storage.upsert_known_device(&gone).await.unwrap();
+
+        storage.delete_known_device("ble-gone").await.unwrap();
+
+        let ids: Vec<String> = storage
+            .list_known_devices()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        assert_eq!(ids, vec!["ble-keep".to_string()]);
+    }
+
     #[tokio::test]
     async fn clear_device_group_removes_group() {
         let (storage, _tmp) = test_storage().await;
@@ -501,7 +1175,7 @@ mod tests {
         storage.save_session(&summary, b"raw").await.unwrap();
 
         storage
-            .update_session_metadata("meta-1", Some("Morning Ride".into()), Some("endurance".into()), Some(6), Some("Felt good".into()))
+            .update_session_metadata("meta-1", Some("Morning Ride".into()), Some("endurance".into()), Some(6), Some("Felt good".into()), None)
             .await
             .unwrap();
 
@@ -512,6 +1186,26 @@ mod tests {
         assert_eq!(loaded.notes, Some("Felt good".to_string()));
     }
 
+    #[tokio::test]
+    async fn favorite_toggle_and_filter() {
+        let (storage, _tmp) = test_storage().await;
+        storage.save_session(&make_summary("fav-1"), b"raw").await.unwrap();
+        storage.save_session(&make_summary("fav-2"), b"raw").await.unwrap();
+        assert!(!storage.get_session("fav-1").await.unwrap().is_favorite);
+        assert!(storage.list_sessions_filtered(true).await.unwrap().is_empty());
+
+        storage.set_session_favorite("fav-1", true).await.unwrap();
+        assert!(storage.get_session("fav-1").await.unwrap().is_favorite);
+        let favorites = storage.list_sessions_filtered(true).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].id, "fav-1");
+        assert_eq!(storage.list_sessions().await.unwrap().len(), 2);
+
+        storage.set_session_favorite("fav-1", false).await.unwrap();
+        assert!(storage.list_sessions_filtered(true).await.unwrap().is_empty());
+        assert!(storage.set_session_favorite("no-such-id", true).await.is_err());
+    }
+
     #[tokio::test]
     async fn update_session_metadata_coalesce() {
         let (storage, _tmp) = test_storage().await;
@@ -520,13 +1214,13 @@ mod tests {
 
         // First update: set title only
         storage
-            .update_session_metadata("meta-2", Some("Evening Ride".into()), None, None, None)
+            .update_session_metadata("meta-2", Some("Evening Ride".into()), None, None, None, None)
             .await
             .unwrap();
 
         // Second update: set rpe only — title should be preserved
         storage
-            .update_session_metadata("meta-2", None, None, Some(8), None)
+            .update_session_metadata("meta-2", None, None, Some(8), None, None)
             .await
             .unwrap();
 
@@ -535,6 +1229,55 @@ mod tests {
         assert_eq!(loaded.rpe, Some(8));
     }
 
+    #[tokio::test]
+    async fn structured_rpe_round_trips_and_sets_overall() {
+        let (storage, _tmp) = test_storage().await;
+        storage.save_session(&make_summary("rpe-1"), b"raw").await.unwrap();
+        assert_eq!(storage.get_structured_rpe("rpe-1").await.unwrap(), None);
+
+        let structured = StructuredRpe {
+            overall: 7,
+            legs: 9,
+            breathing: 5,
+        };
+        storage
+            .update_session_metadata("rpe-1", None, None, Some(3), None, Some(structured))
+            .await
+            .unwrap();
+        assert_eq!(storage.get_structured_rpe("rpe-1").await.unwrap(), Some(structured));
+        assert_eq!(storage.get_session("rpe-1").await.unwrap().rpe, Some(7));
+
+        // A later scalar-only update leaves the breakdown in place
+        storage
+            .update_session_metadata("rpe-1", Some("Hills".into()), None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(storage.get_structured_rpe("rpe-1").await.unwrap(), Some(structured));
+
+        // A scalar RPE becomes the breakdown's overall score
+        storage
+            .update_session_metadata("rpe-1", None, None, Some(4), None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_structured_rpe("rpe-1").await.unwrap(),
+            Some(StructuredRpe { overall: 4, ..structured })
+        );
+        assert_eq!(storage.get_session("rpe-1").await.unwrap().rpe, Some(4));
+
+        let out_of_range = StructuredRpe {
+            overall: 7,
+            legs: 11,
+            breathing: 5,
+        };
+        let err = storage
+            .update_session_metadata("rpe-1", None, None, None, None, Some(out_of_range))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Legs RPE 11"), "{}", err);
+    }
+
     #[tokio::test]
     async fn delete_session_removes_row() {
         let (storage, _tmp) = test_storage().await;
@@ -551,13 +1294,196 @@ mod tests {
     async fn update_metadata_nonexistent_session_returns_error() {
         let (storage, _tmp) = test_storage().await;
         let result = storage
-            .update_session_metadata("no-such-id", Some("Title".into()), None, None, None)
+            .update_session_metadata("no-such-id", Some("Title".into()), None, None, None, None)
             .await;
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("Session not found"), "expected 'Session not found', got: {}", err);
     }
 
+    #[tokio::test]
+    async fn search_sessions_matches_notes_and_titles() {
+        let (storage, _tmp) = test_storage().await;
+        let mut a = make_summary("fts-1");
+        a.notes = Some("Legs heavy after the alpine climb".into());
+        let mut b = make_summary("fts-2");
+        b.title = Some("Recovery spin".into());
+        b.notes = Some("Easy, flat route".into());
+        storage.save_session(&a, b"raw").await.unwrap();
+        storage.save_session(&b, b"raw").await.unwrap();
+
+        let hits = storage.search_sessions("alpine").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "fts-1");
+
+        // Prefix match on the title
+        let hits = storage.search_sessions("recov").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "fts-2");
+
+        assert!(storage.search_sessions("tempo").await.unwrap().is_empty());
+        assert!(storage.search_sessions("   ").await.unwrap().is_empty());
+        // FTS operators in user input are treated as plain text
+        assert!(storage.search_sessions("\"alpine OR -").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_sessions_tracks_metadata_edits_and_deletes() {
+        let (storage, _tmp) = test_storage().await;
+        let mut summary = make_summary("fts-3");
+        summary.notes = Some("Windy coastal loop".into());
+        storage.save_session(&summary, b"raw").await.unwrap();
+
+        storage
+            .update_session_metadata("fts-3", None, None, None, Some("Sprint practice on the velodrome".into()), None)
+            .await
+            .unwrap();
+        assert!(storage.search_sessions("coastal").await.unwrap().is_empty());
+        let hits = storage.search_sessions("velodrome").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "fts-3");
+
+        storage.delete_session("fts-3").await.unwrap();
+        assert!(storage.search_sessions("velodrome").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_index_backfills_existing_sessions() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let storage = Storage::new(&dir).await.unwrap();
+        let mut summary = make_summary("fts-4");
+        summary.notes = Some("Gravel detour".into());
+        storage.save_session(&summary, b"raw").await.unwrap();
+        // Simulate a database created before the index existed
+        sqlx::raw_sql("DROP TABLE sessions_fts").execute(&storage.pool).await.unwrap();
+        drop(storage);
+
+        let storage = Storage::new(&dir).await.unwrap();
+        assert_eq!(storage.search_sessions("gravel").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn search_index_follows_renumbered_rowids() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let storage = Storage::new(&dir).await.unwrap();
+        for (id, notes) in [("fts-5", "Hill repeats"), ("fts-6", "Easy spin")] {
+            let mut summary = make_summary(id);
+            summary.notes = Some(notes.into());
+            storage.save_session(&summary, b"raw").await.unwrap();
+        }
+        // What VACUUM may do to a table without an INTEGER PRIMARY KEY
+        sqlx::raw_sql("UPDATE sessions SET rowid = rowid + 100")
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+        storage.pool.close().await;
+
+        let storage = Storage::new(&dir).await.unwrap();
+        let hits = storage.search_sessions("hill").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "fts-5");
+    }
+
+    #[tokio::test]
+    async fn ftp_history_ordered_by_date() {
+        let (storage, _tmp) = test_storage().await;
+        storage.record_ftp(230, "2025-06-01", "estimate").await.unwrap();
+        storage.record_ftp(210, "2025-02-15", "manual").await.unwrap();
+        storage.record_ftp(220, "2025-04-10", "manual").await.unwrap();
+
+        let history = storage.get_ftp_history().await.unwrap();
+        let dates: Vec<&str> = history.iter().map(|h| h.date.as_str()).collect();
+        assert_eq!(dates, vec!["2025-02-15", "2025-04-10", "2025-06-01"]);
+        assert_eq!(history[0].ftp, 210);
+        assert_eq!(history[2].source, "estimate");
+    }
+
+    #[tokio::test]
+    async fn record_ftp_rejects_invalid_date() {
+        let (storage, _tmp) = test_storage().await;
+        assert!(storage.record_ftp(200, "June 1st", "manual").await.is_err());
+        assert!(storage.get_ftp_history().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_segments_crud() {
+        let (storage, _tmp) = test_storage().await;
+        storage.save_session(&make_summary("seg-1"), b"raw").await.unwrap();
+
+        let work = storage.add_session_segment("seg-1", 600, 2400, "work").await.unwrap();
+        let warmup = storage.add_session_segment("seg-1", 0, 600, " warmup ").await.unwrap();
+        assert_eq!(warmup.label, "warmup");
+        assert!(storage.add_session_segment("seg-1", 300, 300, "empty").await.is_err());
+        assert!(storage.add_session_segment("seg-1", 0, 60, "  ").await.is_err());
+
+        let segments = storage.list_session_segments("seg-1").await.unwrap();
+        assert_eq!(segments, vec![warmup.clone(), work.clone()]);
+
+        storage.delete_session_segment(work.id).await.unwrap();
+        assert_eq!(storage.list_session_segments("seg-1").await.unwrap(), vec![warmup]);
+        assert!(storage.delete_session_segment(work.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn delete_session_removes_segments() {
+        let (storage, _tmp) = test_storage().await;
+        storage.save_session(&make_summary("seg-2"), b"raw").await.unwrap();
+        storage.save_session(&make_summary("seg-3"), b"raw").await.unwrap();
+        storage.add_session_segment("seg-2", 0, 300, "warmup").await.unwrap();
+        storage.add_session_segment("seg-3", 0, 300, "warmup").await.unwrap();
+
+        storage.delete_session("seg-2").await.unwrap();
+
+        assert!(storage.list_session_segments("seg-2").await.unwrap().is_empty());
+        assert_eq!(storage.list_session_segments("seg-3").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn calibration_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        let id = storage
+            .record_calibration("ant:17:1234", "2025-05-01T08:00:00+00:00", Some(1021), Some(4380))
+            .await
+            .unwrap();
+        storage
+            .record_calibration("ble:kickr", "2025-05-02T08:00:00+00:00", None, Some(5100))
+            .await
+            .unwrap();
+
+        let history = storage.get_calibration_history("ant:17:1234").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, id);
+        assert_eq!(history[0].zero_offset, Some(1021));
+        assert_eq!(history[0].spindown_ms, Some(4380));
+        let other = storage.get_calibration_history("ble:kickr").await.unwrap();
+        assert_eq!(other[0].zero_offset, None);
+        assert!(storage
+            .record_calibration("ble:kickr", "yesterday", None, Some(5000))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn calibration_history_newest_first() {
+        let (storage, _tmp) = test_storage().await;
+        for (date, spindown) in [
+            ("2025-03-10T07:30:00+00:00", 4400),
+            ("2025-06-01T18:00:00+00:00", 4520),
+            ("2025-01-05T09:15:00+00:00", 4350),
+        ] {
+            storage
+                .record_calibration("ant:17:1234", date, None, Some(spindown))
+                .await
+                .unwrap();
+        }
+
+        let history = storage.get_calibration_history("ant:17:1234").await.unwrap();
+        let spindowns: Vec<Option<u16>> = history.iter().map(|c| c.spindown_ms).collect();
+        assert_eq!(spindowns, vec![Some(4520), Some(4400), Some(4350)]);
+    }
+
     #[tokio::test]
     async fn load_sensor_data_round_trip() {
         let (storage, _tmp) = test_storage().await;
@@ -655,6 +1581,209 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn training_load_ctl_rises_through_two_week_block() {
+        let (storage, _tmp) = test_storage().await;
+        let base = chrono::DateTime::parse_from_rfc3339("2025-03-01T07:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        // Two weeks of daily training with a rest day each Sunday; day 3 has two rides
+        let daily_tss = [80.0, 60.0, 100.0, 50.0, 90.0, 120.0, 0.0, 80.0, 60.0, 100.0, 50.0, 90.0, 120.0, 0.0];
+        for (day, tss) in daily_tss.iter().enumerate() {
+            if *tss == 0.0 {
+                continue;
+            }
+            let mut s = make_summary(&format!("tl-{}", day));
+            s.start_time = base + chrono::Duration::days(day as i64);
+            s.tss = Some(*tss as f32);
+            if day == 2 {
+                // Split day 3 into two sessions that must be summed
+                s.tss = Some(60.0);
+                let mut second = make_summary("tl-2b");
+                second.start_time = s.start_time + chrono::Duration::hours(8);
+                second.tss = Some(40.0);
+                storage.save_session(&second, b"").await.unwrap();
+            }
+            storage.save_session(&s, b"").await.unwrap();
+        }
+
+        let points = storage
+            .get_training_load("2025-03-01", "2025-03-14")
+            .await
+            .unwrap();
+        assert_eq!(points.len(), 14);
+        assert_eq!(points[0].date, "2025-03-01");
+        assert_eq!(points[13].date, "2025-03-14");
+
+        // Day 1: CTL = 80/42, ATL = 80/7
+        assert!((points[0].ctl - 80.0 / 42.0).abs() < 1e-9);
+        assert!((points[0].atl - 80.0 / 7.0).abs() < 1e-9);
+        // Day 3 sums to 100 TSS: CTL2 + (100 − CTL2)/42
+        let expected_ctl3 = points[1].ctl + (100.0 - points[1].ctl) / 42.0;
+        assert!((points[2].ctl - expected_ctl3).abs() < 1e-9);
+
+        // CTL rises monotonically on every training day
+        for (i, pair) in points.windows(2).enumerate() {
+            if daily_tss[i + 1] > 0.0 {
+                assert!(pair[1].ctl > pair[0].ctl, "CTL fell on day {}", i + 2);
+            }
+        }
+        for p in &points {
+            assert!((p.tsb - (p.ctl - p.atl)).abs() < 1e-9);
+        }
+    }
+
+    #[tokio::test]
+    async fn period_summary_sums_sessions_per_week_and_month() {
+        let (storage, _tmp) = test_storage().await;
+        // (start, tss, work_kj, distance_km); 2025-03-03 is a Monday
+        let rides = [
+            ("2025-02-27T18:00:00Z", 40.0, 400.0, Some(20.0)),
+            ("2025-03-02T09:00:00Z", 60.0, 600.0, None),
+            ("2025-03-03T07:00:00Z", 80.0, 800.0, Some(35.0)),
+            ("2025-03-09T16:00:00Z", 100.0, 1000.0, Some(45.0)),
+            ("2025-03-10T07:00:00Z", 50.0, 500.0, Some(25.0)),
+        ];
+        for (i, (start, tss, work, distance)) in rides.iter().enumerate() {
+            let mut s = make_summary(&format!("ps-{}", i));
+            s.start_time = start.parse().unwrap();
+            s.tss = Some(*tss);
+            s.work_kj = Some(*work);
+            s.distance_km = *distance;
+            storage.save_session(&s, b"").await.unwrap();
+        }
+
+        let weeks = storage
+            .get_period_summary(PeriodGranularity::Week, "2025-02-24", "2025-03-09")
+            .await
+            .unwrap();
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].period_start, "2025-02-24");
+        assert_eq!(weeks[0].session_count, 2);
+        assert_eq!(weeks[0].total_duration_secs, 7200);
+        assert!((weeks[0].total_tss - 100.0).abs() < 1e-6);
+        assert!((weeks[0].total_work_kj - 1000.0).abs() < 1e-6);
+        assert!((weeks[0].total_distance_km - 20.0).abs() < 1e-6);
+        // Sunday the 9th closes the week that began Monday the 3rd
+        assert_eq!(weeks[1].period_start, "2025-03-03");
+        assert_eq!(weeks[1].session_count, 2);
+        assert!((weeks[1].total_tss - 180.0).abs() < 1e-6);
+        assert!((weeks[1].total_distance_km - 80.0).abs() < 1e-6);
+
+        let months = storage
+            .get_period_summary(PeriodGranularity::Month, "2025-02-01", "2025-03-31")
+            .await
+            .unwrap();
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[0].period_start, "2025-02-01");
+        assert_eq!(months[0].session_count, 1);
+        assert_eq!(months[1].period_start, "2025-03-01");
+        assert_eq!(months[1].session_count, 4);
+        assert!((months[1].total_tss - 290.0).abs() < 1e-6);
+
+        assert!(storage
+            .get_period_summary(PeriodGranularity::Week, "2025-03-09", "2025-03-01")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn training_load_rejects_reversed_range() {
+        let (storage, _tmp) = test_storage().await;
+        assert!(storage
+            .get_training_load("2025-03-14", "2025-03-01")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn wheel_circumference_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        assert_eq!(storage.get_wheel_circumference("ant:123:1").await.unwrap(), None);
+
+        storage.set_wheel_circumference("ant:123:1", 2060).await.unwrap();
+        assert_eq!(storage.get_wheel_circumference("ant:123:1").await.unwrap(), Some(2060));
+
+        // Overwrite keeps a single row per device
+        storage.set_wheel_circumference("ant:123:1", 2096).await.unwrap();
+        assert_eq!(storage.get_wheel_circumference("ant:123:1").await.unwrap(), Some(2096));
+        assert_eq!(storage.get_wheel_circumference("ant:123:2").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn load_sensor_data_hrv_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        let readings = vec![SensorReading::Hrv {
+            rr_ms: vec![812, 798, 805],
+            epoch_ms: 3000,
+            device_id: "hr-1".to_string(),
+        }];
+        let raw = bincode::serialize(&readings).unwrap();
+        let summary = make_summary("rt-hrv");
+        storage.save_session(&summary, &raw).await.unwrap();
+
+        let loaded = storage.load_sensor_data("rt-hrv").unwrap();
+        assert_eq!(loaded.len(), 1);
+        match &loaded[0] {
+            SensorReading::Hrv { rr_ms, epoch_ms, device_id } => {
+                assert_eq!(rr_ms, &vec![812, 798, 805]);
+                assert_eq!(*epoch_ms, 3000);
+                assert_eq!(device_id, "hr-1");
+            }
+            other => panic!("expected Hrv, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn load_sensor_data_muscle_oxygen_round_trip() {
+        let (storage, _tmp) = test_storage().await;
+        let readings = vec![SensorReading::MuscleOxygen {
+            smo2: 58.7,
+            thb: 12.31,
+            epoch_ms: 4000,
+            device_id: "moxy-1".to_string(),
+        }];
+        let raw = bincode::serialize(&readings).unwrap();
+        let summary = make_summary("rt-smo2");
+        storage.save_session(&summary, &raw).await.unwrap();
+
+        let loaded = storage.load_sensor_data("rt-smo2").unwrap();
+        assert_eq!(loaded.len(), 1);
+        match &loaded[0] {
+            SensorReading::MuscleOxygen { smo2, thb, epoch_ms, device_id } => {
+                assert_eq!(*smo2, 58.7);
+                assert_eq!(*thb, 12.31);
+                assert_eq!(*epoch_ms, 4000);
+                assert_eq!(device_id, "moxy-1");
+            }
+            other => panic!("expected MuscleOxygen, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn temperature_round_trips_through_summary_and_sensor_log() {
+        let (storage, _tmp) = test_storage().await;
+        let readings = vec![SensorReading::Temperature {
+            celsius: 23.4,
+            epoch_ms: 5000,
+            device_id: "env-1".to_string(),
+        }];
+        let raw = bincode::serialize(&readings).unwrap();
+        let mut summary = make_summary("rt-temp");
+        summary.avg_temp_c = Some(23.4);
+        storage.save_session(&summary, &raw).await.unwrap();
+
+        let loaded = storage.get_session("rt-temp").await.unwrap();
+        assert_eq!(loaded.avg_temp_c, Some(23.4));
+        match &storage.load_sensor_data("rt-temp").unwrap()[0] {
+            SensorReading::Temperature { celsius, device_id, .. } => {
+                assert_eq!(*celsius, 23.4);
+                assert_eq!(device_id, "env-1");
+            }
+            other => panic!("expected Temperature, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn load_sensor_data_empty_round_trip() {
         let (storage, _tmp) = test_storage().await;
@@ -667,6 +1796,200 @@ mod tests {
         assert!(loaded.is_empty());
     }
 
+    #[tokio::test]
+    async fn streamed_analysis_matches_full_load() {
+        use crate::session::analysis::{compute_analysis, AnalysisSamples};
+
+        let (storage, _tmp) = test_storage().await;
+        let base_ms = 1_718_445_600_000u64;
+        // 45 min: 4 Hz power, 1 Hz HR/cadence/speed, sparser pedaling and SmO2,
+        // with a 10s power dropout. ~20k readings spans several chunks.
+        let mut readings = Vec::new();
+        for sec in 0..2700u64 {
+            let ms = base_ms + sec * 1000;
+            if !(1200..1210).contains(&sec) {
+                for q in 0..4 {
+                    readings.push(SensorReading::Power {
+                        watts: 150 + ((sec * 7 + q * 13) % 220) as u16,
+                        timestamp: None,
+                        epoch_ms: ms + q * 250,
+                        device_id: "pm-1".to_string(),
+                        pedal_balance: None,
+                    });
+                }
+            }
+            readings.push(SensorReading::HeartRate {
+                bpm: 120 + (sec / 60) as u8,
+                timestamp: None,
+                epoch_ms: ms + 100,
+                device_id: "hr-1".to_string(),
+            });
+            readings.push(SensorReading::Cadence {
+                rpm: 80.0 + (sec % 25) as f32,
+                timestamp: None,
+                epoch_ms: ms + 300,
+                device_id: "cad-1".to_string(),
+            });
+            readings.push(SensorReading::Speed {
+                kmh: 30.0 + (sec % 9) as f32 * 0.5,
+                timestamp: None,
+                epoch_ms: ms + 600,
+                device_id: "spd-1".to_string(),
+            });
+            if sec % 5 == 0 {
+                readings.push(SensorReading::Pedaling {
+                    torque_effectiveness: Some(70.0 + (sec % 11) as f32),
+                    smoothness: (sec % 10 == 0).then_some(20.0 + (sec % 7) as f32),
+                    top_dead_spot_deg: None,
+                    bottom_dead_spot_deg: None,
+                    epoch_ms: ms + 700,
+                    device_id: "pm-1".to_string(),
+                });
+            }
+            if sec % 2 == 0 {
+                readings.push(SensorReading::MuscleOxygen {
+                    smo2: 60.0 - (sec % 30) as f32 * 0.2,
+                    thb: 12.5,
+                    epoch_ms: ms + 800,
+                    device_id: "moxy-1".to_string(),
+                });
+            }
+        }
+
+        let raw = bincode::serialize(&readings).unwrap();
+        let mut summary = make_summary("rt-stream");
+        summary.duration_secs = 2700;
+        summary.ftp = Some(250);
+        storage.save_session(&summary, &raw).await.unwrap();
+
+        let config = SessionConfig {
+            critical_power: Some(260),
+            w_prime: Some(20_000),
+            ..SessionConfig::default()
+        };
+        let analyse = |samples: &AnalysisSamples| {
+            serde_json::to_string(&compute_analysis(samples, &summary, &config, Some(10), "metric"))
+                .unwrap()
+        };
+        let full = analyse(&AnalysisSamples::from_readings(
+            &storage.load_sensor_data("rt-stream").unwrap(),
+        ));
+        let streamed = analyse(&storage.load_analysis_samples("rt-stream").unwrap());
+        assert_eq!(streamed, full);
+        assert!(full.contains("\"gaps\":[{"), "fixture should produce a gap");
+    }
+
+    #[tokio::test]
+    async fn raw_csv_keeps_every_reading_in_log_order() {
+        use crate::device::types::CommandSource;
+        use crate::session::csv_export::raw_sensor_csv;
+
+        let (storage, _tmp) = test_storage().await;
+        let base_ms = 1_718_445_600_000u64;
+        let mut readings = Vec::new();
+        for sec in 0..10u64 {
+            let ms = base_ms + sec * 1000;
+            readings.push(SensorReading::Power {
+                watts: 200 + sec as u16,
+                timestamp: None,
+                epoch_ms: ms,
+                device_id: "pm-1".to_string(),
+                pedal_balance: Some(51),
+            });
+            readings.push(SensorReading::HeartRate {
+                bpm: 130,
+                timestamp: None,
+                epoch_ms: ms + 200,
+                device_id: "hr-1".to_string(),
+            });
+        }
+        readings.push(SensorReading::MuscleOxygen {
+            smo2: 62.5,
+            thb: 12.25,
+            epoch_ms: base_ms + 3_500,
+            device_id: "moxy-1".to_string(),
+        });
+        readings.push(SensorReading::TrainerCommand {
+            target_watts: 210,
+            epoch_ms: base_ms + 4_000,
+            source: CommandSource::Workout,
+        });
+        readings.push(SensorReading::Lap {
+            epoch_ms: base_ms + 5_000,
+        });
+
+        let raw = bincode::serialize(&readings).unwrap();
+        storage.save_session(&make_summary("raw-csv"), &raw).await.unwrap();
+        let csv = raw_sensor_csv(&storage.load_sensor_data("raw-csv").unwrap());
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "epoch_ms,type,device_id,value1,value2");
+        assert_eq!(lines.len(), readings.len() + 1);
+        assert_eq!(lines[3], "1718445601000,Power,pm-1,201,51");
+        assert_eq!(lines[21], "1718445603500,MuscleOxygen,moxy-1,62.5,12.25");
+        assert_eq!(lines[22], "1718445604000,TrainerCommand,,210,Workout");
+        assert_eq!(lines[23], "1718445605000,Lap,,,");
+    }
+
+    #[tokio::test]
+    async fn strava_tokens_and_activity_id_round_trip() {
+        use crate::session::strava::StravaTokens;
+
+        let (storage, _tmp) = test_storage().await;
+        assert!(storage.get_strava_tokens().await.unwrap().is_none());
+        let mut tokens = StravaTokens {
+            client_id: "1234".to_string(),
+            client_secret: "secret".to_string(),
+            access_token: "a1".to_string(),
+            refresh_token: "r1".to_string(),
+            expires_at: 1_700_000_000,
+        };
+        storage.save_strava_tokens(&tokens).await.unwrap();
+        tokens.access_token = "a2".to_string();
+        storage.save_strava_tokens(&tokens).await.unwrap();
+        assert_eq!(storage.get_strava_tokens().await.unwrap(), Some(tokens));
+        storage.clear_strava_tokens().await.unwrap();
+        assert!(storage.get_strava_tokens().await.unwrap().is_none());
+
+        storage.save_session(&make_summary("strava-1"), &[]).await.unwrap();
+        assert_eq!(storage.get_session_strava_activity("strava-1").await.unwrap(), None);
+        storage.set_session_strava_activity("strava-1", 9001).await.unwrap();
+        assert_eq!(storage.get_session_strava_activity("strava-1").await.unwrap(), Some(9001));
+        assert!(storage.set_session_strava_activity("missing", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn overlapping_sessions_are_flagged_and_merged() {
+        let (storage, tmp) = test_storage().await;
+        let original = make_summary("dup-a");
+        let mut recovered = make_summary("dup-b");
+        recovered.start_time = original.start_time + chrono::Duration::seconds(20);
+        recovered.duration_secs = original.duration_secs - 25;
+        recovered.title = Some("Recovered ride".to_string());
+        let mut later = make_summary("other");
+        later.start_time = original.start_time + chrono::Duration::hours(3);
+        for s in [&original, &recovered, &later] {
+            storage.save_session(s, &[]).await.unwrap();
+        }
+
+        let pairs = storage.find_duplicate_sessions().await.unwrap();
+        assert_eq!(pairs, vec![("dup-a".to_string(), "dup-b".to_string())]);
+
+        let structured = StructuredRpe { overall: 6, legs: 8, breathing: 4 };
+        storage
+            .update_session_metadata("dup-b", None, None, None, None, Some(structured))
+            .await
+            .unwrap();
+        storage.merge_sessions("dup-a", "dup-b").await.unwrap();
+        assert!(storage.get_session("dup-b").await.is_err());
+        assert!(!tmp.path().join("sessions").join("dup-b.bin").exists());
+        let kept = storage.get_session("dup-a").await.unwrap();
+        assert_eq!(kept.title.as_deref(), Some("Recovered ride"));
+        assert_eq!(kept.rpe, Some(6));
+        assert_eq!(storage.get_structured_rpe("dup-a").await.unwrap(), Some(structured));
+        assert!(storage.find_duplicate_sessions().await.unwrap().is_empty());
+    }
+
     // --- Power curve storage tests ---
 
     #[tokio::test]
@@ -676,9 +1999,9 @@ mod tests {
         storage.save_session(&summary, b"raw").await.unwrap();
 
         let curve = vec![
-            PowerCurvePoint { duration_secs: 1, watts: 400 },
-            PowerCurvePoint { duration_secs: 5, watts: 350 },
-            PowerCurvePoint { duration_secs: 60, watts: 280 },
+            PowerCurvePoint { duration_secs: 1, watts: 400, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 5, watts: 350, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 280, pct_ftp: None },
         ];
         storage.save_power_curve("pc-1", &curve).await.unwrap();
 
@@ -697,15 +2020,15 @@ mod tests {
         let s1 = make_summary("pc-max-1");
         storage.save_session(&s1, b"raw").await.unwrap();
         storage.save_power_curve("pc-max-1", &[
-            PowerCurvePoint { duration_secs: 1, watts: 400 },
-            PowerCurvePoint { duration_secs: 60, watts: 250 },
+            PowerCurvePoint { duration_secs: 1, watts: 400, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 250, pct_ftp: None },
         ]).await.unwrap();
 
         let s2 = make_summary("pc-max-2");
         storage.save_session(&s2, b"raw").await.unwrap();
         storage.save_power_curve("pc-max-2", &[
-            PowerCurvePoint { duration_secs: 1, watts: 350 },
-            PowerCurvePoint { duration_secs: 60, watts: 300 },
+            PowerCurvePoint { duration_secs: 1, watts: 350, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 300, pct_ftp: None },
         ]).await.unwrap();
 
         let best = storage.get_best_power_curve(None).await.unwrap();
@@ -717,6 +2040,32 @@ mod tests {
         assert_eq!(p60.watts, 300);
     }
 
+    #[tokio::test]
+    async fn power_curve_csv_uses_best_across_sessions() {
+        let (storage, _tmp) = test_storage().await;
+
+        storage.save_session(&make_summary("csv-1"), b"raw").await.unwrap();
+        storage.save_power_curve("csv-1", &[
+            PowerCurvePoint { duration_secs: 1, watts: 600, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 300, watts: 280, pct_ftp: None },
+        ]).await.unwrap();
+
+        storage.save_session(&make_summary("csv-2"), b"raw").await.unwrap();
+        storage.save_power_curve("csv-2", &[
+            PowerCurvePoint { duration_secs: 1, watts: 450, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 300, watts: 300, pct_ftp: None },
+        ]).await.unwrap();
+
+        let best = storage.get_best_power_curve(None).await.unwrap();
+        let csv = crate::session::csv_export::power_curve_csv(&best, 75.0).unwrap();
+
+        // 1s: max(600, 450) = 600 → 8.00 W/kg; 300s: max(280, 300) = 300 → 4.00 W/kg
+        assert_eq!(
+            csv,
+            "duration_secs,watts,watts_per_kg\n1,600,8.00\n300,300,4.00\n"
+        );
+    }
+
     #[tokio::test]
     async fn has_power_curve_detects_presence() {
         let (storage, _tmp) = test_storage().await;
@@ -726,20 +2075,70 @@ mod tests {
         assert!(!storage.has_power_curve("pc-has-1").await.unwrap());
 
         storage.save_power_curve("pc-has-1", &[
-            PowerCurvePoint { duration_secs: 1, watts: 300 },
+            PowerCurvePoint { duration_secs: 1, watts: 300, pct_ftp: None },
         ]).await.unwrap();
 
         assert!(storage.has_power_curve("pc-has-1").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn best_power_curve_excluding_skips_session() {
+        let (storage, _tmp) = test_storage().await;
+        for id in ["pc-ex-1", "pc-ex-2"] {
+            storage.save_session(&make_summary(id), b"raw").await.unwrap();
+        }
+        storage.save_power_curve("pc-ex-1", &[
+            PowerCurvePoint { duration_secs: 5, watts: 500, pct_ftp: None },
+        ]).await.unwrap();
+        storage.save_power_curve("pc-ex-2", &[
+            PowerCurvePoint { duration_secs: 5, watts: 420, pct_ftp: None },
+        ]).await.unwrap();
+
+        let others = storage.get_best_power_curve_excluding("pc-ex-1").await.unwrap();
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].watts, 420);
+    }
+
+    #[tokio::test]
+    async fn record_breaking_curve_reports_fallen_durations() {
+        let (storage, _tmp) = test_storage().await;
+        for id in ["pb-1", "pb-2"] {
+            storage.save_session(&make_summary(id), b"raw").await.unwrap();
+        }
+        let first = storage.save_power_curve_with_records("pb-1", &[
+            PowerCurvePoint { duration_secs: 5, watts: 600, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 350, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 300, watts: 280, pct_ftp: None },
+        ]).await.unwrap();
+        assert!(first.is_empty(), "nothing to beat on the first ride");
+
+        let records = storage.save_power_curve_with_records("pb-2", &[
+            PowerCurvePoint { duration_secs: 5, watts: 580, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 60, watts: 370, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 300, watts: 300, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 1200, watts: 250, pct_ftp: None },
+        ]).await.unwrap();
+        let durations: Vec<u32> = records.iter().map(|r| r.duration_secs).collect();
+        assert_eq!(durations, vec![60, 300]);
+        assert_eq!(records[0].previous_watts, 350);
+        assert_eq!(records[0].watts, 370);
+
+        // Re-saving the same session doesn't compare it against itself
+        let again = storage.save_power_curve_with_records("pb-2", &[
+            PowerCurvePoint { duration_secs: 60, watts: 370, pct_ftp: None },
+        ]).await.unwrap();
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].previous_watts, 350);
+    }
+
     #[tokio::test]
     async fn delete_session_removes_power_curves() {
         let (storage, _tmp) = test_storage().await;
         let summary = make_summary("pc-del-1");
         storage.save_session(&summary, b"raw").await.unwrap();
         storage.save_power_curve("pc-del-1", &[
-            PowerCurvePoint { duration_secs: 1, watts: 400 },
-            PowerCurvePoint { duration_secs: 5, watts: 350 },
+            PowerCurvePoint { duration_secs: 1, watts: 400, pct_ftp: None },
+            PowerCurvePoint { duration_secs: 5, watts: 350, pct_ftp: None },
         ]).await.unwrap();
 
         assert!(storage.has_power_curve("pc-del-1").await.unwrap());
@@ -829,4 +2228,123 @@ mod tests {
         assert_eq!(devices[1].id, "d2");
         assert_eq!(devices[2].id, "d1");
     }
+
+    #[tokio::test]
+    async fn backup_round_trips_into_fresh_data_dir() {
+        let (storage, _tmp) = test_storage().await;
+        let mut first = make_summary("s1");
+        first.notes = Some("Tempo intervals".into());
+        storage.save_session(&first, b"first-raw").await.unwrap();
+        storage.save_session(&make_summary("s2"), b"second-raw").await.unwrap();
+        storage
+            .save_strava_tokens(&crate::session::strava::StravaTokens {
+                client_id: "123".into(),
+                client_secret: "client-secret".into(),
+                access_token: "access".into(),
+                refresh_token: "refresh".into(),
+                expires_at: 0,
+            })
+            .await
+            .unwrap();
+        let archive_dir = tempfile::TempDir::new().unwrap();
+        let archive = archive_dir.path().join("backup.zip");
+        storage.export_backup(&archive).await.unwrap();
+        assert!(storage.get_strava_tokens().await.unwrap().is_some());
+
+        let restore_tmp = tempfile::TempDir::new().unwrap();
+        let restore_dir = restore_tmp.path().to_string_lossy().to_string();
+        let staging = Storage::stage_backup(&restore_dir, &archive).await.unwrap();
+        Storage::swap_in_backup(&restore_dir, &staging).unwrap();
+
+        let restored = Storage::new(&restore_dir).await.unwrap();
+        let mut ids: Vec<String> = restored
+            .list_sessions()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["s1", "s2"]);
+        let restored_s1 = restored.get_session("s1").await.unwrap();
+        assert_eq!(restored_s1.avg_power, Some(180));
+        assert!(restored.get_strava_tokens().await.unwrap().is_none());
+        let hits = restored.search_sessions("tempo").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "s1");
+        for (id, raw) in [("s1", &b"first-raw"[..]), ("s2", &b"second-raw"[..])] {
+            let path = restore_tmp.path().join("sessions").join(format!("{}.bin", id));
+            assert_eq!(std::fs::read(path).unwrap(), raw);
+        }
+        assert!(!restore_tmp.path().join(".restore_staging").exists());
+        assert!(!restore_tmp.path().join(".restore_previous").exists());
+    }
+
+    #[tokio::test]
+    async fn import_backup_rejects_archive_without_database() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let archive = tmp.path().join("bad.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        zip.start_file("notes.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+
+        let data_dir = tempfile::TempDir::new().unwrap();
+        let result = Storage::stage_backup(&data_dir.path().to_string_lossy(), &archive).await;
+        assert!(result.is_err());
+        assert!(!data_dir.path().join("training.db").exists());
+    }
+
+    #[tokio::test]
+    async fn profiles_have_disjoint_sessions() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let app_dir = tmp.path().to_string_lossy().to_string();
+        let alice = Storage::open_profile(&app_dir, "alice").await.unwrap();
+        let bob = Storage::open_profile(&app_dir, "bob").await.unwrap();
+        alice.save_session(&make_summary("a1"), b"a").await.unwrap();
+        bob.save_session(&make_summary("b1"), b"b").await.unwrap();
+        bob.save_session(&make_summary("b2"), b"b").await.unwrap();
+
+        let ids = |sessions: Vec<SessionSummary>| {
+            let mut ids: Vec<String> = sessions.into_iter().map(|s| s.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(alice.list_sessions().await.unwrap()), vec!["a1"]);
+        assert_eq!(ids(bob.list_sessions().await.unwrap()), vec!["b1", "b2"]);
+        assert!(tmp.path().join("profiles/alice/sessions/a1.bin").exists());
+        assert_eq!(
+            Storage::list_profiles(&app_dir),
+            vec!["default", "alice", "bob"]
+        );
+
+        let shared = SharedStorage::new("alice", std::sync::Arc::new(alice));
+        shared.replace("bob", std::sync::Arc::new(bob));
+        assert_eq!(shared.profile(), "bob");
+        assert_eq!(ids(shared.current().list_sessions().await.unwrap()), vec!["b1", "b2"]);
+    }
+
+    #[tokio::test]
+    async fn open_profile_rejects_path_traversal() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let app_dir = tmp.path().to_string_lossy().to_string();
+        for name in ["../escape", "a/b", "", ".", "x".repeat(33).as_str()] {
+            assert!(Storage::open_profile(&app_dir, name).await.is_err(), "{:?}", name);
+        }
+        assert!(!tmp.path().join("escape").exists());
+    }
+
+    #[test]
+    fn active_profile_persists_across_restarts() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let app_dir = tmp.path().to_string_lossy().to_string();
+        assert_eq!(Storage::load_active_profile(&app_dir), DEFAULT_PROFILE);
+
+        Storage::save_active_profile(&app_dir, "alice").unwrap();
+        assert_eq!(Storage::load_active_profile(&app_dir), "alice");
+
+        assert!(Storage::save_active_profile(&app_dir, "../escape").is_err());
+        std::fs::write(tmp.path().join("active_profile"), "../escape").unwrap();
+        assert_eq!(Storage::load_active_profile(&app_dir), DEFAULT_PROFILE);
+    }
 }
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use super::training_load::parse_date;
+use super::Storage;
+use crate::error::AppError;
+
+/// Bucket size for `get_period_summary`. Weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodGranularity {
+    Week,
+    Month,
+}
+
+impl PeriodGranularity {
+    /// SQLite expression mapping a `YYYY-MM-DD` day to the first day of its bucket.
+    fn bucket_sql(self) -> &'static str {
+        match self {
+            // 'weekday 0' advances to Sunday (or stays on it); back 6 days is Monday
+            PeriodGranularity::Week => "date(day, 'weekday 0', '-6 days')",
+            PeriodGranularity::Month => "date(day, 'start of month')",
+        }
+    }
+}
+
+/// Totals for one week or month of training.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodSummary {
+    /// First day of the bucket, `YYYY-MM-DD`
+    pub period_start: String,
+    pub session_count: u32,
+    pub total_duration_secs: u64,
+    pub total_tss: f64,
+    pub total_work_kj: f64,
+    pub total_distance_km: f64,
+}
+
+impl Storage {
+    /// Per-week or per-month totals for sessions between `from` and `to`
+    /// (inclusive, `YYYY-MM-DD`), oldest first. Only buckets with sessions are
+    /// returned. A session counts on the calendar day of its stored start time,
+    /// i.e. in the offset it was recorded with, not converted to UTC.
+    pub async fn get_period_summary(
+        &self,
+        granularity: PeriodGranularity,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<PeriodSummary>, AppError> {
+        let from = parse_date(from)?;
+        let to = parse_date(to)?;
+        if from > to {
+            return Err(AppError::Session(format!(
+                "Period summary range start {} is after end {}",
+                from, to
+            )));
+        }
+        let sql = format!(
+            "SELECT {} AS period, COUNT(*), SUM(duration_secs), \
+             COALESCE(SUM(tss), 0.0), COALESCE(SUM(work_kj), 0.0), COALESCE(SUM(distance_km), 0.0) \
+             FROM (SELECT substr(start_time, 1, 10) AS day, duration_secs, tss, work_kj, distance_km \
+                   FROM sessions) \
+             WHERE day BETWEEN ? AND ? \
+             GROUP BY period \
+             ORDER BY period",
+            granularity.bucket_sql()
+        );
+        let rows: Vec<(String, i64, i64, f64, f64, f64)> = sqlx::query_as(&sql)
+            .bind(from.format("%Y-%m-%d").to_string())
+            .bind(to.format("%Y-%m-%d").to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(rows
+            .into_iter()
+            .map(
+                |(period_start, count, duration, tss, work_kj, distance_km)| PeriodSummary {
+                    period_start,
+                    session_count: count as u32,
+                    total_duration_secs: duration as u64,
+                    total_tss: tss,
+                    total_work_kj: work_kj,
+                    total_distance_km: distance_km,
+                },
+            )
+            .collect())
+    }
+}
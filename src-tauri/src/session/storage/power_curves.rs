@@ -1,6 +1,6 @@
 use super::Storage;
 use crate::error::AppError;
-use crate::session::analysis::PowerCurvePoint;
+use crate::session::analysis::{self, PersonalBest, PowerCurvePoint};
 
 impl Storage {
     pub async fn save_power_curve(
@@ -25,6 +25,18 @@ impl Storage {
         Ok(())
     }
 
+    /// Save a session's power curve and return the all-time records it broke,
+    /// compared against every other stored session.
+    pub async fn save_power_curve_with_records(
+        &self,
+        session_id: &str,
+        curve: &[PowerCurvePoint],
+    ) -> Result<Vec<PersonalBest>, AppError> {
+        let previous_best = self.get_best_power_curve_excluding(session_id).await?;
+        self.save_power_curve(session_id, curve).await?;
+        Ok(analysis::find_personal_bests(curve, &previous_best))
+    }
+
     pub async fn get_best_power_curve(
         &self,
         after_date: Option<&str>,
@@ -58,6 +70,33 @@ impl Storage {
             .map(|(d, w)| PowerCurvePoint {
                 duration_secs: d as u32,
                 watts: w as u16,
+                pct_ftp: None,
+            })
+            .collect())
+    }
+
+    /// Best power per duration across every session except `session_id`.
+    pub async fn get_best_power_curve_excluding(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<PowerCurvePoint>, AppError> {
+        let rows: Vec<(i32, i32)> = sqlx::query_as(
+            "SELECT duration_secs, MAX(watts) as watts \
+             FROM session_power_curves \
+             WHERE session_id != ? \
+             GROUP BY duration_secs \
+             ORDER BY duration_secs",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(rows
+            .into_iter()
+            .map(|(d, w)| PowerCurvePoint {
+                duration_secs: d as u32,
+                watts: w as u16,
+                pct_ftp: None,
             })
             .collect())
     }
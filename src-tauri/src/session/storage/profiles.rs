@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use super::Storage;
+use crate::error::AppError;
+
+/// Profile whose data lives directly in the app data dir, as before profiles existed.
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+/// File in the app data dir naming the profile to open on startup.
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+const MAX_PROFILE_NAME_LEN: usize = 32;
+
+/// Validate a profile name from the frontend. Like `validate_session_id`, this
+/// keeps crafted names like "../other" from escaping the profiles directory.
+pub(crate) fn validate_profile_name(name: &str) -> Result<(), AppError> {
+    if !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(AppError::Session("Invalid profile name".into()))
+    }
+}
+
+/// Data directory for `profile` under `app_data_dir`.
+pub(crate) fn profile_dir(app_data_dir: &str, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        PathBuf::from(app_data_dir)
+    } else {
+        Path::new(app_data_dir).join(PROFILES_DIR).join(profile)
+    }
+}
+
+impl Storage {
+    /// Open (creating if needed) the storage for `profile` under `app_data_dir`.
+    pub async fn open_profile(app_data_dir: &str, profile: &str) -> Result<Self, AppError> {
+        validate_profile_name(profile)?;
+        Storage::new(&profile_dir(app_data_dir, profile).to_string_lossy()).await
+    }
+
+    /// Profile names with data under `app_data_dir`, default first.
+    pub fn list_profiles(app_data_dir: &str) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(Path::new(app_data_dir).join(PROFILES_DIR))
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .filter(|name| validate_profile_name(name).is_ok() && name != DEFAULT_PROFILE)
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        names
+    }
+
+    /// Profile that was active when the app last ran, or the default profile if
+    /// none was recorded or the recorded name is invalid.
+    pub fn load_active_profile(app_data_dir: &str) -> String {
+        std::fs::read_to_string(Path::new(app_data_dir).join(ACTIVE_PROFILE_FILE))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| validate_profile_name(name).is_ok())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    /// Record `profile` as the one to open on the next startup.
+    pub fn save_active_profile(app_data_dir: &str, profile: &str) -> Result<(), AppError> {
+        validate_profile_name(profile)?;
+        std::fs::write(Path::new(app_data_dir).join(ACTIVE_PROFILE_FILE), profile).map_err(|e| {
+            AppError::Session(format!("Failed to save active profile: {}", e))
+        })
+    }
+}
+
+struct ActiveStorage {
+    profile: String,
+    storage: Arc<Storage>,
+}
+
+/// The active profile's storage, shared by commands and background tasks and
+/// replaced by `switch_profile`.
+///
+/// Callers take a snapshot with `current()` and must not cache it across loop
+/// iterations. The inner lock is never held across an `.await` or while taking
+/// another lock, so it is always last in the lock order: `switch_profile` locks
+/// the device manager first, then swaps the storage.
+#[derive(Clone)]
+pub struct SharedStorage {
+    inner: Arc<RwLock<ActiveStorage>>,
+}
+
+impl SharedStorage {
+    pub fn new(profile: &str, storage: Arc<Storage>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(ActiveStorage {
+                profile: profile.to_string(),
+                storage,
+            })),
+        }
+    }
+
+    pub fn current(&self) -> Arc<Storage> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).storage.clone()
+    }
+
+    pub fn profile(&self) -> String {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).profile.clone()
+    }
+
+    pub fn replace(&self, profile: &str, storage: Arc<Storage>) {
+        let mut active = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        active.profile = profile.to_string();
+        active.storage = storage;
+    }
+}
@@ -0,0 +1,92 @@
+use super::Storage;
+use crate::error::AppError;
+use crate::session::types::SessionSegment;
+
+#[derive(sqlx::FromRow)]
+struct SegmentRow {
+    id: i64,
+    session_id: String,
+    start_secs: i64,
+    end_secs: i64,
+    label: String,
+}
+
+impl Storage {
+    /// Label `start_secs..end_secs` of a session. Returns the new segment.
+    pub async fn add_session_segment(
+        &self,
+        session_id: &str,
+        start_secs: u64,
+        end_secs: u64,
+        label: &str,
+    ) -> Result<SessionSegment, AppError> {
+        if start_secs >= end_secs {
+            return Err(AppError::Session(format!(
+                "Segment start ({}s) must be before its end ({}s)",
+                start_secs, end_secs
+            )));
+        }
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(AppError::Session("Segment label must not be empty".into()));
+        }
+        let result = sqlx::query(
+            "INSERT INTO session_segments (session_id, start_secs, end_secs, label) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(start_secs as i64)
+        .bind(end_secs as i64)
+        .bind(label)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(SessionSegment {
+            id: result.last_insert_rowid(),
+            session_id: session_id.to_string(),
+            start_secs,
+            end_secs,
+            label: label.to_string(),
+        })
+    }
+
+    pub async fn delete_session_segment(&self, segment_id: i64) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM session_segments WHERE id = ?")
+            .bind(segment_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::Session(format!(
+                "Segment not found: {}",
+                segment_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// A session's segments in time order.
+    pub async fn list_session_segments(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<SessionSegment>, AppError> {
+        let rows = sqlx::query_as::<_, SegmentRow>(
+            "SELECT id, session_id, start_secs, end_secs, label FROM session_segments \
+             WHERE session_id = ? ORDER BY start_secs, id",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| SessionSegment {
+                id: row.id,
+                session_id: row.session_id,
+                start_secs: row.start_secs as u64,
+                end_secs: row.end_secs as u64,
+                label: row.label,
+            })
+            .collect())
+    }
+}
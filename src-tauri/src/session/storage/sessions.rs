@@ -1,11 +1,45 @@
-use log::{debug, info};
+use log::{debug, info, warn};
 use serde::Deserialize;
+use std::io::{BufReader, Read};
 use std::path::Path;
 
+use super::training_load::parse_date;
 use super::Storage;
 use crate::device::types::{CommandSource, SensorReading};
 use crate::error::AppError;
-use crate::session::types::SessionSummary;
+use crate::session::analysis::{self, AnalysisSamples};
+use crate::session::fit_import::{self, FitActivity};
+use crate::session::metrics::MetricsCalculator;
+use crate::session::types::{SessionConfig, SessionSummary, StructuredRpe};
+
+/// Readings decoded per step when streaming a sensor log into analysis.
+const SENSOR_CHUNK_LEN: usize = 4096;
+
+/// Sessions starting within this many seconds of each other may be duplicates
+const DUPLICATE_START_WINDOW_SECS: i64 = 60;
+/// Relative difference in duration/TSS still considered "the same ride"
+const DUPLICATE_TOLERANCE: f64 = 0.05;
+/// Longest break between two sessions `concat_sessions` joins without `force`
+const MAX_CONCAT_GAP_SECS: i64 = 3600;
+
+/// True when two values differ by at most `DUPLICATE_TOLERANCE` of the larger,
+/// or by `floor` for small values where a percentage is too strict.
+fn near_equal(a: f64, b: f64, floor: f64) -> bool {
+    (a - b).abs() <= (a.max(b) * DUPLICATE_TOLERANCE).max(floor)
+}
+
+/// Same ride saved twice, e.g. by autosave recovery and the shutdown save.
+fn is_duplicate(a: &SessionSummary, b: &SessionSummary) -> bool {
+    let start_diff = (a.start_time - b.start_time).num_seconds().abs();
+    let tss_match = match (a.tss, b.tss) {
+        (Some(x), Some(y)) => near_equal(x as f64, y as f64, 2.0),
+        (None, None) => true,
+        _ => false,
+    };
+    start_diff <= DUPLICATE_START_WINDOW_SECS
+        && near_equal(a.duration_secs as f64, b.duration_secs as f64, 60.0)
+        && tss_match
+}
 
 /// Legacy sensor reading format: Power variant lacked pedal_balance field because
 /// #[serde(skip_serializing_if)] silently dropped it from bincode output.
@@ -90,6 +124,15 @@ struct SessionRow {
     activity_type: Option<String>,
     rpe: Option<i32>,
     notes: Option<String>,
+    efficiency_factor: Option<f64>,
+    calories: Option<i64>,
+    avg_cadence_active: Option<f64>,
+    avg_temp_c: Option<f64>,
+    total_ascent_m: Option<f64>,
+    is_favorite: bool,
+    xpower: Option<i32>,
+    data_quality_score: Option<i32>,
+    avg_pedal_balance: Option<i32>,
 }
 
 impl TryFrom<SessionRow> for SessionSummary {
@@ -125,10 +168,87 @@ impl TryFrom<SessionRow> for SessionSummary {
             activity_type: row.activity_type,
             rpe: row.rpe.map(|v| v as u8),
             notes: row.notes,
+            efficiency_factor: row.efficiency_factor.map(|v| v as f32),
+            calories: row.calories.map(|v| v as u32),
+            avg_cadence_active: row.avg_cadence_active.map(|v| v as f32),
+            avg_temp_c: row.avg_temp_c.map(|v| v as f32),
+            total_ascent_m: row.total_ascent_m.map(|v| v as f32),
+            is_favorite: row.is_favorite,
+            xpower: row.xpower.map(|v| v as u16),
+            data_quality_score: row.data_quality_score.map(|v| v as u8),
+            avg_pedal_balance: row.avg_pedal_balance.map(|v| v as u8),
         })
     }
 }
 
+fn validate_structured_rpe(rpe: &StructuredRpe) -> Result<(), AppError> {
+    for (label, value) in [
+        ("Overall", rpe.overall),
+        ("Legs", rpe.legs),
+        ("Breathing", rpe.breathing),
+    ] {
+        if !(1..=10).contains(&value) {
+            return Err(AppError::Session(format!(
+                "{} RPE {} is out of range (1–10)",
+                label, value
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Turn free-form user input into an FTS5 expression: each word becomes a
+/// quoted prefix term so punctuation and FTS operators are matched literally.
+fn fts_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Decode a bincode `Vec<SensorReading>` element by element (a `u64` length
+/// prefix, then each reading) and fold it into `AnalysisSamples` in chunks.
+fn stream_analysis_samples(mut reader: impl Read) -> bincode::Result<AnalysisSamples> {
+    let len: u64 = bincode::deserialize_from(&mut reader)?;
+    let mut samples = AnalysisSamples::default();
+    let mut chunk = Vec::with_capacity(SENSOR_CHUNK_LEN);
+    for _ in 0..len {
+        chunk.push(bincode::deserialize_from::<_, SensorReading>(&mut reader)?);
+        if chunk.len() == SENSOR_CHUNK_LEN {
+            samples.push(&chunk);
+            chunk.clear();
+        }
+    }
+    samples.push(&chunk);
+    Ok(samples)
+}
+
+/// Read and decode the raw sensor log of `session_id` under `data_dir`.
+fn read_sensor_log(data_dir: &str, session_id: &str) -> Result<Vec<SensorReading>, AppError> {
+    let raw_file = Path::new(data_dir)
+        .join("sessions")
+        .join(format!("{}.bin", session_id));
+    let data = std::fs::read(&raw_file)
+        .map_err(|e| AppError::Serialization(format!("Failed to read sensor data: {}", e)))?;
+
+    // Try current format first; fall back to legacy format (before pedal_balance
+    // was added to Power). The old code used #[serde(skip_serializing_if)] on
+    // pedal_balance which is broken with bincode — it omitted the field during
+    // serialization but the deserializer always expected it.
+    bincode::deserialize::<Vec<SensorReading>>(&data).or_else(|_| {
+        debug!("Using legacy format fallback for session {}", session_id);
+        let legacy: Vec<LegacySensorReading> = bincode::deserialize(&data)
+            .map_err(|e| {
+                AppError::Serialization(format!(
+                    "Failed to deserialize sensor data: {}",
+                    e
+                ))
+            })?;
+        Ok(legacy.into_iter().map(SensorReading::from).collect())
+    })
+}
+
 impl Storage {
     pub async fn save_session(
         &self,
@@ -157,8 +277,9 @@ impl Storage {
             "INSERT OR IGNORE INTO sessions (id, start_time, duration_secs, ftp, avg_power, max_power, \
              normalized_power, tss, intensity_factor, avg_hr, max_hr, avg_cadence, avg_speed, \
              work_kj, variability_index, distance_km, \
-             raw_file_path, title, activity_type, rpe, notes) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             raw_file_path, title, activity_type, rpe, notes, efficiency_factor, calories, avg_cadence_active, avg_temp_c, total_ascent_m, is_favorite, xpower, \
+             data_quality_score, avg_pedal_balance) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&summary.id)
         .bind(&start_time)
@@ -181,6 +302,15 @@ impl Storage {
         .bind(&summary.activity_type)
         .bind(summary.rpe.map(|v| v as i32))
         .bind(&summary.notes)
+        .bind(summary.efficiency_factor.map(|v| v as f64))
+        .bind(summary.calories.map(|v| v as i64))
+        .bind(summary.avg_cadence_active.map(|v| v as f64))
+        .bind(summary.avg_temp_c.map(|v| v as f64))
+        .bind(summary.total_ascent_m.map(|v| v as f64))
+        .bind(summary.is_favorite)
+        .bind(summary.xpower.map(|v| v as i32))
+        .bind(summary.data_quality_score.map(|v| v as i32))
+        .bind(summary.avg_pedal_balance.map(|v| v as i32))
         .execute(&self.pool)
         .await
         .map_err(AppError::Database)?;
@@ -195,11 +325,21 @@ impl Storage {
     }
 
     pub async fn list_sessions(&self) -> Result<Vec<SessionSummary>, AppError> {
+        self.list_sessions_filtered(false).await
+    }
+
+    /// All sessions, newest first; only starred ones when `favorites_only`.
+    pub async fn list_sessions_filtered(
+        &self,
+        favorites_only: bool,
+    ) -> Result<Vec<SessionSummary>, AppError> {
         let rows = sqlx::query_as::<_, SessionRow>(
             "SELECT id, start_time, duration_secs, ftp, avg_power, max_power, normalized_power, tss, \
              intensity_factor, avg_hr, max_hr, avg_cadence, avg_speed, work_kj, variability_index, \
-             distance_km, title, activity_type, rpe, notes FROM sessions ORDER BY start_time DESC",
+             distance_km, title, activity_type, rpe, notes, efficiency_factor, calories, avg_cadence_active, avg_temp_c, total_ascent_m, is_favorite, xpower, data_quality_score, avg_pedal_balance FROM sessions \
+             WHERE (? = 0 OR is_favorite = 1) ORDER BY start_time DESC",
         )
+        .bind(favorites_only)
         .fetch_all(&self.pool)
         .await
         .map_err(AppError::Database)?;
@@ -210,7 +350,7 @@ impl Storage {
         let row = sqlx::query_as::<_, SessionRow>(
             "SELECT id, start_time, duration_secs, ftp, avg_power, max_power, normalized_power, tss, \
              intensity_factor, avg_hr, max_hr, avg_cadence, avg_speed, work_kj, variability_index, \
-             distance_km, title, activity_type, rpe, notes FROM sessions WHERE id = ?",
+             distance_km, title, activity_type, rpe, notes, efficiency_factor, calories, avg_cadence_active, avg_temp_c, total_ascent_m, is_favorite, xpower, data_quality_score, avg_pedal_balance FROM sessions WHERE id = ?",
         )
         .bind(session_id)
         .fetch_one(&self.pool)
@@ -220,29 +360,125 @@ impl Storage {
     }
 
     pub fn load_sensor_data(&self, session_id: &str) -> Result<Vec<SensorReading>, AppError> {
+        read_sensor_log(&self.data_dir, session_id)
+    }
+
+    /// Stream a session's sensor log into `AnalysisSamples`, decoding
+    /// `SENSOR_CHUNK_LEN` readings at a time. Unlike `load_sensor_data`, neither
+    /// the raw file nor the decoded readings are held in memory, which matters
+    /// for multi-hour rides. Legacy-format logs fall back to a full load.
+    pub fn load_analysis_samples(&self, session_id: &str) -> Result<AnalysisSamples, AppError> {
         let raw_file = Path::new(&self.data_dir)
             .join("sessions")
             .join(format!("{}.bin", session_id));
-        let data = std::fs::read(&raw_file)
+        let file = std::fs::File::open(&raw_file)
             .map_err(|e| AppError::Serialization(format!("Failed to read sensor data: {}", e)))?;
 
-        // Try current format first; fall back to legacy format (before pedal_balance
-        // was added to Power). The old code used #[serde(skip_serializing_if)] on
-        // pedal_balance which is broken with bincode — it omitted the field during
-        // serialization but the deserializer always expected it.
-        bincode::deserialize::<Vec<SensorReading>>(&data).or_else(|_| {
+        stream_analysis_samples(BufReader::new(file)).or_else(|_| {
             debug!("Using legacy format fallback for session {}", session_id);
-            let legacy: Vec<LegacySensorReading> = bincode::deserialize(&data)
-                .map_err(|e| {
-                    AppError::Serialization(format!(
-                        "Failed to deserialize sensor data: {}",
-                        e
-                    ))
-                })?;
-            Ok(legacy.into_iter().map(SensorReading::from).collect())
+            Ok(AnalysisSamples::from_readings(&self.load_sensor_data(session_id)?))
         })
     }
 
+    /// Sessions whose title or notes contain every word of `query` (prefix
+    /// matches), best match first. A blank query returns no rows.
+    pub async fn search_sessions(&self, query: &str) -> Result<Vec<SessionSummary>, AppError> {
+        let Some(fts_query) = fts_match_expression(query) else {
+            return Ok(Vec::new());
+        };
+        let rows = sqlx::query_as::<_, SessionRow>(
+            "SELECT s.id, s.start_time, s.duration_secs, s.ftp, s.avg_power, s.max_power, \
+             s.normalized_power, s.tss, s.intensity_factor, s.avg_hr, s.max_hr, s.avg_cadence, \
+             s.avg_speed, s.work_kj, s.variability_index, s.distance_km, s.title, s.activity_type, \
+             s.rpe, s.notes, s.efficiency_factor, s.calories, \
+             s.avg_cadence_active, s.avg_temp_c, s.total_ascent_m, s.is_favorite, s.xpower, \
+             s.data_quality_score, s.avg_pedal_balance \
+             FROM sessions_fts f JOIN sessions s ON s.rowid = f.rowid \
+             WHERE sessions_fts MATCH ? ORDER BY f.rank",
+        )
+        .bind(fts_query)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Recompute the FTP-dependent metrics (NP, IF, TSS, VI) of a saved ride
+    /// from its sensor log against `ftp`, and store `ftp` as the session's FTP.
+    /// Analysis zones follow automatically since they read the session FTP.
+    pub async fn recompute_session_metrics(
+        &self,
+        session_id: &str,
+        ftp: u16,
+    ) -> Result<SessionSummary, AppError> {
+        let mut summary = self.get_session(session_id).await?;
+        // Decoding the sensor log is blocking file I/O; keep it off the runtime
+        let data_dir = self.data_dir.clone();
+        let sid = session_id.to_string();
+        let metrics = tokio::task::spawn_blocking(move || {
+            let mut metrics = MetricsCalculator::new(ftp);
+            for reading in read_sensor_log(&data_dir, &sid)? {
+                if let SensorReading::Power { watts, epoch_ms, .. } = reading {
+                    metrics.record_power(watts, epoch_ms);
+                }
+            }
+            Ok::<_, AppError>(metrics)
+        })
+        .await
+        .map_err(|e| AppError::Session(format!("Recompute task failed: {}", e)))??;
+        summary.ftp = Some(ftp);
+        summary.normalized_power = metrics.normalized_power().map(|v| v as u16);
+        summary.intensity_factor = metrics.intensity_factor();
+        summary.tss = metrics.tss(summary.duration_secs);
+        summary.variability_index = metrics.variability_index();
+
+        sqlx::query(
+            "UPDATE sessions SET ftp = ?, normalized_power = ?, intensity_factor = ?, tss = ?, \
+             variability_index = ? WHERE id = ?",
+        )
+        .bind(ftp as i32)
+        .bind(summary.normalized_power.map(|v| v as i32))
+        .bind(summary.intensity_factor)
+        .bind(summary.tss)
+        .bind(summary.variability_index.map(|v| v as f64))
+        .bind(session_id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        info!(
+            "Recomputed session {} at FTP {}W: TSS {:?}",
+            session_id, ftp, summary.tss
+        );
+        Ok(summary)
+    }
+
+    /// Recompute every session starting on or after `date` (`YYYY-MM-DD`)
+    /// against `ftp`. Sessions whose sensor log is missing are skipped.
+    /// Returns the number of sessions updated.
+    pub async fn recompute_all_after(&self, date: &str, ftp: u16) -> Result<usize, AppError> {
+        parse_date(date)?;
+        let ids: Vec<(String,)> =
+            sqlx::query_as("SELECT id FROM sessions WHERE start_time >= ? ORDER BY start_time")
+                .bind(date)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(AppError::Database)?;
+        let mut updated = 0;
+        for (id,) in ids {
+            match self.recompute_session_metrics(&id, ftp).await {
+                Ok(_) => updated += 1,
+                Err(AppError::Serialization(e)) => {
+                    warn!("Skipping recompute of session {}: {}", id, e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Update user-entered metadata; `None` fields keep their stored value.
+    /// A `structured_rpe` also sets the scalar `rpe` to its overall score, and
+    /// a scalar-only `rpe` becomes the stored breakdown's overall score.
     pub async fn update_session_metadata(
         &self,
         session_id: &str,
@@ -250,19 +486,36 @@ impl Storage {
         activity_type: Option<String>,
         rpe: Option<u8>,
         notes: Option<String>,
+        structured_rpe: Option<StructuredRpe>,
     ) -> Result<(), AppError> {
+        let structured_json = match &structured_rpe {
+            Some(structured) => {
+                validate_structured_rpe(structured)?;
+                Some(
+                    serde_json::to_string(structured)
+                        .map_err(|e| AppError::Serialization(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+        let rpe = structured_rpe.map(|s| s.overall).or(rpe);
         let result = sqlx::query(
             "UPDATE sessions SET \
                title = COALESCE(?, title), \
                activity_type = COALESCE(?, activity_type), \
                rpe = COALESCE(?, rpe), \
-               notes = COALESCE(?, notes) \
+               notes = COALESCE(?, notes), \
+               structured_rpe = COALESCE(?, CASE WHEN ? IS NULL THEN structured_rpe \
+                 ELSE json_set(structured_rpe, '$.overall', ?) END) \
              WHERE id = ?",
         )
         .bind(&title)
         .bind(&activity_type)
         .bind(rpe.map(|v| v as i32))
         .bind(&notes)
+        .bind(&structured_json)
+        .bind(rpe.map(|v| v as i32))
+        .bind(rpe.map(|v| v as i32))
         .bind(session_id)
         .execute(&self.pool)
         .await
@@ -273,6 +526,23 @@ impl Storage {
         Ok(())
     }
 
+    pub async fn set_session_favorite(
+        &self,
+        session_id: &str,
+        is_favorite: bool,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE sessions SET is_favorite = ? WHERE id = ?")
+            .bind(is_favorite)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::Session(format!("Session not found: {}", session_id)));
+        }
+        Ok(())
+    }
+
     pub async fn delete_session(&self, session_id: &str) -> Result<(), AppError> {
         info!("Deleting session: {}", session_id);
         // Delete file first, then DB rows. A row without a file is visible in
@@ -290,6 +560,11 @@ impl Storage {
             .execute(&self.pool)
             .await
             .map_err(AppError::Database)?;
+        sqlx::query("DELETE FROM session_segments WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
         sqlx::query("DELETE FROM sessions WHERE id = ?")
             .bind(session_id)
             .execute(&self.pool)
@@ -298,6 +573,234 @@ impl Storage {
         Ok(())
     }
 
+    /// Pairs of sessions that look like the same ride saved twice. Each pair is
+    /// `(earlier, later)` by start time.
+    pub async fn find_duplicate_sessions(&self) -> Result<Vec<(String, String)>, AppError> {
+        let mut sessions = self.list_sessions().await?;
+        sessions.reverse();
+        let mut pairs = Vec::new();
+        for (i, a) in sessions.iter().enumerate() {
+            for b in &sessions[i + 1..] {
+                if (b.start_time - a.start_time).num_seconds() > DUPLICATE_START_WINDOW_SECS {
+                    break;
+                }
+                if is_duplicate(a, b) {
+                    pairs.push((a.id.clone(), b.id.clone()));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Fold the duplicate `drop` into `keep`: metadata `keep` lacks (title,
+    /// notes, RPE and its breakdown, type, favorite) is copied over, then
+    /// `drop` is deleted along with its sensor log and any exported FIT file.
+    pub async fn merge_sessions(&self, keep: &str, drop: &str) -> Result<(), AppError> {
+        if keep == drop {
+            return Err(AppError::Session("Cannot merge a session into itself".into()));
+        }
+        let kept = self.get_session(keep).await?;
+        let dropped = self.get_session(drop).await?;
+        let dropped_rpe = match kept.rpe {
+            Some(_) => None,
+            None => self.get_structured_rpe(drop).await?,
+        };
+        self.update_session_metadata(
+            keep,
+            kept.title.is_none().then_some(dropped.title).flatten(),
+            kept.activity_type.is_none().then_some(dropped.activity_type).flatten(),
+            kept.rpe.is_none().then_some(dropped.rpe).flatten(),
+            kept.notes.is_none().then_some(dropped.notes).flatten(),
+            dropped_rpe,
+        )
+        .await?;
+        if dropped.is_favorite && !kept.is_favorite {
+            self.set_session_favorite(keep, true).await?;
+        }
+        info!("Merging duplicate session {} into {}", drop, keep);
+        self.delete_session(drop).await?;
+        self.remove_fit_export(drop)
+    }
+
+    /// Split a ride that should have been two at `at_secs` into its sensor
+    /// log. Both halves are saved as new sessions with summaries and power
+    /// curves recomputed at the original FTP, then the original is deleted.
+    /// Title, type, RPE, notes and favorite stay with the first half.
+    /// Returns the `(first, second)` session IDs.
+    pub async fn split_session(
+        &self,
+        session_id: &str,
+        at_secs: u64,
+    ) -> Result<(String, String), AppError> {
+        let original = self.get_session(session_id).await?;
+        if at_secs == 0 || at_secs >= original.duration_secs {
+            return Err(AppError::Session(format!(
+                "Split point {}s is outside the {}s session",
+                at_secs, original.duration_secs
+            )));
+        }
+        let readings = self.load_sensor_data(session_id)?;
+        let first_ms = readings.first().map(|r| r.epoch_ms()).unwrap_or(0);
+        let boundary_ms = first_ms + at_secs * 1000;
+        let (before, after): (Vec<_>, Vec<_>) =
+            readings.into_iter().partition(|r| r.epoch_ms() < boundary_ms);
+        if before.is_empty() || after.is_empty() {
+            return Err(AppError::Session(format!(
+                "Split point {}s leaves one part without readings",
+                at_secs
+            )));
+        }
+
+        let mut config = self.get_user_config().await?;
+        config.ftp = original.ftp.unwrap_or(config.ftp);
+        let second_start = original.start_time + chrono::Duration::seconds(at_secs as i64);
+        let structured_rpe = self.get_structured_rpe(session_id).await?;
+        let first = self
+            .save_derived_session(
+                before,
+                original.start_time,
+                at_secs,
+                &config,
+                Some((&original, structured_rpe)),
+            )
+            .await?;
+        let second = self
+            .save_derived_session(after, second_start, original.duration_secs - at_secs, &config, None)
+            .await?;
+
+        info!("Split session {} at {}s into {} and {}", session_id, at_secs, first, second);
+        self.delete_session(session_id).await?;
+        self.remove_fit_export(session_id)?;
+        Ok((first, second))
+    }
+
+    /// Save rebuilt readings as a new session, copying the user-entered
+    /// metadata and RPE breakdown from `metadata_from` when given. Returns the
+    /// new ID.
+    async fn save_derived_session(
+        &self,
+        readings: Vec<SensorReading>,
+        start_time: chrono::DateTime<chrono::Utc>,
+        duration_secs: u64,
+        config: &SessionConfig,
+        metadata_from: Option<(&SessionSummary, Option<StructuredRpe>)>,
+    ) -> Result<String, AppError> {
+        let activity = FitActivity {
+            readings,
+            start_time: Some(start_time),
+            timer_secs: Some(duration_secs),
+            sport: None,
+            sub_sport: None,
+            total_ascent_m: None,
+        };
+        let mut summary =
+            fit_import::summarize(uuid::Uuid::new_v4().to_string(), &activity, config)?;
+        if let Some((original, _)) = metadata_from {
+            summary.title = original.title.clone();
+            summary.activity_type = original.activity_type.clone();
+            summary.rpe = original.rpe;
+            summary.notes = original.notes.clone();
+            summary.is_favorite = original.is_favorite;
+        }
+        let raw_data = bincode::serialize(&activity.readings)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+        self.save_session(&summary, &raw_data).await?;
+        if let Some((_, Some(structured))) = metadata_from {
+            self.update_session_metadata(&summary.id, None, None, None, None, Some(structured))
+                .await?;
+        }
+        let curve = analysis::compute_power_curve_from_readings(&activity.readings);
+        if !curve.is_empty() {
+            self.save_power_curve(&summary.id, &curve).await?;
+        }
+        Ok(summary.id)
+    }
+
+    /// Join two recordings of one ride into a new session: the second's
+    /// readings are shifted to continue one second after the first's, and the
+    /// summary and power curve are recomputed at the first's FTP. Metadata is
+    /// taken from `first`, falling back to `second`; the RPE breakdown follows
+    /// whichever RPE is kept. Both originals are
+    /// deleted. Sessions more than an hour apart are rejected unless `force`.
+    pub async fn concat_sessions(
+        &self,
+        first: &str,
+        second: &str,
+        force: bool,
+    ) -> Result<String, AppError> {
+        if first == second {
+            return Err(AppError::Session("Cannot join a session to itself".into()));
+        }
+        let a = self.get_session(first).await?;
+        let b = self.get_session(second).await?;
+        if b.start_time < a.start_time {
+            return Err(AppError::Session(format!(
+                "Session {} starts before {}",
+                second, first
+            )));
+        }
+        let gap_secs = (b.start_time - a.start_time).num_seconds() - a.duration_secs as i64;
+        if gap_secs > MAX_CONCAT_GAP_SECS && !force {
+            return Err(AppError::Session(format!(
+                "Sessions are {} min apart; too far to join",
+                gap_secs / 60
+            )));
+        }
+
+        let mut readings = self.load_sensor_data(first)?;
+        let mut tail = self.load_sensor_data(second)?;
+        if let (Some(last), Some(next)) = (readings.last(), tail.first()) {
+            let shift = (last.epoch_ms() + 1000) as i64 - next.epoch_ms() as i64;
+            for reading in &mut tail {
+                reading.set_epoch_ms((reading.epoch_ms() as i64 + shift).max(0) as u64);
+            }
+        }
+        readings.append(&mut tail);
+
+        let mut config = self.get_user_config().await?;
+        config.ftp = a.ftp.or(b.ftp).unwrap_or(config.ftp);
+        let structured_rpe = match a.rpe {
+            Some(_) => self.get_structured_rpe(first).await?,
+            None => self.get_structured_rpe(second).await?,
+        };
+        let metadata = SessionSummary {
+            title: a.title.clone().or(b.title),
+            activity_type: a.activity_type.clone().or(b.activity_type),
+            rpe: a.rpe.or(b.rpe),
+            notes: a.notes.clone().or(b.notes),
+            is_favorite: a.is_favorite || b.is_favorite,
+            ..a.clone()
+        };
+        let id = self
+            .save_derived_session(
+                readings,
+                a.start_time,
+                a.duration_secs + b.duration_secs,
+                &config,
+                Some((&metadata, structured_rpe)),
+            )
+            .await?;
+
+        info!("Joined sessions {} and {} into {}", first, second, id);
+        for old in [first, second] {
+            self.delete_session(old).await?;
+            self.remove_fit_export(old)?;
+        }
+        Ok(id)
+    }
+
+    /// Remove a session's exported FIT file, if one was written.
+    fn remove_fit_export(&self, session_id: &str) -> Result<(), AppError> {
+        let fit = Path::new(&self.data_dir)
+            .join("sessions")
+            .join(format!("{}.fit", session_id));
+        if fit.exists() {
+            std::fs::remove_file(&fit)
+                .map_err(|e| AppError::Session(format!("Failed to delete FIT export: {}", e)))?;
+        }
+        Ok(())
+    }
+
     pub async fn save_zone_config(
         &self,
         session_id: &str,
@@ -315,6 +818,24 @@ impl Storage {
         Ok(())
     }
 
+    /// The session's per-aspect RPE, if one was recorded.
+    pub async fn get_structured_rpe(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<StructuredRpe>, AppError> {
+        let row: Option<(Option<String>,)> =
+            sqlx::query_as("SELECT structured_rpe FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::Database)?;
+        row.and_then(|(v,)| v)
+            .map(|json| {
+                serde_json::from_str(&json).map_err(|e| AppError::Serialization(e.to_string()))
+            })
+            .transpose()
+    }
+
     pub async fn get_zone_config(&self, session_id: &str) -> Result<Option<String>, AppError> {
         let row: Option<(Option<String>,)> =
             sqlx::query_as("SELECT zone_config FROM sessions WHERE id = ?")
@@ -0,0 +1,93 @@
+use super::Storage;
+use crate::error::AppError;
+use crate::session::strava::StravaTokens;
+
+#[derive(sqlx::FromRow)]
+struct StravaAuthRow {
+    client_id: String,
+    client_secret: String,
+    access_token: String,
+    refresh_token: String,
+    expires_at: i64,
+}
+
+impl Storage {
+    /// The stored Strava credentials, or `None` until the account is connected.
+    pub async fn get_strava_tokens(&self) -> Result<Option<StravaTokens>, AppError> {
+        let row = sqlx::query_as::<_, StravaAuthRow>(
+            "SELECT client_id, client_secret, access_token, refresh_token, expires_at \
+             FROM strava_auth WHERE id = 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(row.map(|row| StravaTokens {
+            client_id: row.client_id,
+            client_secret: row.client_secret,
+            access_token: row.access_token,
+            refresh_token: row.refresh_token,
+            expires_at: row.expires_at,
+        }))
+    }
+
+    /// Store the Strava credentials, replacing any previous ones.
+    pub async fn save_strava_tokens(&self, tokens: &StravaTokens) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO strava_auth \
+             (id, client_id, client_secret, access_token, refresh_token, expires_at) \
+             VALUES (1, ?, ?, ?, ?, ?)",
+        )
+        .bind(&tokens.client_id)
+        .bind(&tokens.client_secret)
+        .bind(&tokens.access_token)
+        .bind(&tokens.refresh_token)
+        .bind(tokens.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(())
+    }
+
+    pub async fn clear_strava_tokens(&self) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM strava_auth")
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        Ok(())
+    }
+
+    /// Strava activity a session was uploaded as, if any.
+    pub async fn get_session_strava_activity(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<u64>, AppError> {
+        let id: Option<i64> =
+            sqlx::query_scalar("SELECT strava_activity_id FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::Database)?
+                .flatten();
+        Ok(id.map(|v| v as u64))
+    }
+
+    pub async fn set_session_strava_activity(
+        &self,
+        session_id: &str,
+        activity_id: u64,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query("UPDATE sessions SET strava_activity_id = ? WHERE id = ?")
+            .bind(activity_id as i64)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::Session(format!(
+                "Session not found: {}",
+                session_id
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::Storage;
+use crate::error::AppError;
+
+/// Chronic Training Load time constant (days) — "fitness".
+const CTL_DAYS: f64 = 42.0;
+/// Acute Training Load time constant (days) — "fatigue".
+const ATL_DAYS: f64 = 7.0;
+
+/// One day on the fitness/fatigue chart. TSB = CTL − ATL ("form").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadPoint {
+    pub date: String,
+    pub ctl: f64,
+    pub atl: f64,
+    pub tsb: f64,
+}
+
+pub(super) fn parse_date(s: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|e| AppError::Session(format!("Invalid date '{}': {}", s, e)))
+}
+
+/// Apply the exponentially-weighted CTL/ATL averages over `daily_tss`, starting
+/// from zero load on the earliest day (history before `from` warms the averages up)
+/// and emitting one point per day in `from..=to`. Missing days count as zero TSS.
+fn compute_training_load(
+    daily_tss: &BTreeMap<NaiveDate, f64>,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<LoadPoint> {
+    let start = daily_tss
+        .keys()
+        .next()
+        .copied()
+        .map_or(from, |first| first.min(from));
+    let mut ctl = 0.0;
+    let mut atl = 0.0;
+    let mut points = Vec::new();
+    for day in start.iter_days().take_while(|d| *d <= to) {
+        let tss = daily_tss.get(&day).copied().unwrap_or(0.0);
+        ctl += (tss - ctl) / CTL_DAYS;
+        atl += (tss - atl) / ATL_DAYS;
+        if day >= from {
+            points.push(LoadPoint {
+                date: day.format("%Y-%m-%d").to_string(),
+                ctl,
+                atl,
+                tsb: ctl - atl,
+            });
+        }
+    }
+    points
+}
+
+impl Storage {
+    /// Daily CTL/ATL/TSB between `from` and `to` (inclusive, `YYYY-MM-DD`).
+    pub async fn get_training_load(&self, from: &str, to: &str) -> Result<Vec<LoadPoint>, AppError> {
+        let from = parse_date(from)?;
+        let to = parse_date(to)?;
+        if from > to {
+            return Err(AppError::Session(format!(
+                "Training load range start {} is after end {}",
+                from, to
+            )));
+        }
+        let rows: Vec<(String, f64)> = sqlx::query_as(
+            "SELECT substr(start_time, 1, 10) AS day, SUM(tss) \
+             FROM sessions \
+             WHERE tss IS NOT NULL AND substr(start_time, 1, 10) <= ? \
+             GROUP BY day \
+             ORDER BY day",
+        )
+        .bind(to.format("%Y-%m-%d").to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+
+        let mut daily_tss = BTreeMap::new();
+        for (day, tss) in rows {
+            daily_tss.insert(parse_date(&day)?, tss);
+        }
+        Ok(compute_training_load(&daily_tss, from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn assert_approx(actual: f64, expected: f64, eps: f64, msg: &str) {
+        assert!(
+            (actual - expected).abs() < eps,
+            "{}: expected {}, got {}",
+            msg,
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn single_day_applies_one_ewma_step() {
+        let daily = BTreeMap::from([(date("2025-03-01"), 84.0)]);
+        let points = compute_training_load(&daily, date("2025-03-01"), date("2025-03-01"));
+        assert_eq!(points.len(), 1);
+        // CTL = 84/42 = 2.0, ATL = 84/7 = 12.0, TSB = -10.0
+        assert_approx(points[0].ctl, 2.0, 1e-9, "ctl");
+        assert_approx(points[0].atl, 12.0, 1e-9, "atl");
+        assert_approx(points[0].tsb, -10.0, 1e-9, "tsb");
+    }
+
+    #[test]
+    fn rest_days_are_filled_and_decay_load() {
+        let daily = BTreeMap::from([(date("2025-03-01"), 70.0)]);
+        let points = compute_training_load(&daily, date("2025-03-01"), date("2025-03-03"));
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].date, "2025-03-02");
+        // ATL: 10.0 → 10 * 6/7 → 10 * (6/7)^2
+        assert_approx(points[2].atl, 10.0 * (6.0 / 7.0_f64).powi(2), 1e-9, "decayed atl");
+        assert!(points[2].ctl < points[0].ctl);
+    }
+
+    #[test]
+    fn history_before_range_warms_up_averages() {
+        let daily = BTreeMap::from([(date("2025-02-01"), 100.0)]);
+        let points = compute_training_load(&daily, date("2025-02-10"), date("2025-02-10"));
+        assert_eq!(points.len(), 1);
+        assert!(points[0].ctl > 0.0);
+    }
+}
@@ -0,0 +1,76 @@
+use super::Storage;
+use crate::error::AppError;
+use crate::session::zone_control::types::ZoneControlConfig;
+
+#[derive(sqlx::FromRow)]
+struct ZoneControlConfigRow {
+    max_watts_up_per_tick: f64,
+    max_watts_down_per_tick: f64,
+    min_power: i32,
+    safety_power: i32,
+    hr_sensor_warn_secs: i64,
+    hr_sensor_stop_secs: i64,
+    power_sensor_warn_secs: i64,
+    cadence_zero_secs: i64,
+    power_bias_pct: i32,
+    hr_reserve_ceiling_pct: i32,
+}
+
+impl Storage {
+    /// Saved zone control tuning, or the defaults if the user never changed it.
+    pub async fn get_zone_control_config(&self) -> Result<ZoneControlConfig, AppError> {
+        let row = sqlx::query_as::<_, ZoneControlConfigRow>(
+            "SELECT max_watts_up_per_tick, max_watts_down_per_tick, min_power, safety_power, \
+             hr_sensor_warn_secs, hr_sensor_stop_secs, power_sensor_warn_secs, cadence_zero_secs, \
+             power_bias_pct, hr_reserve_ceiling_pct FROM zone_control_config WHERE id = 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(row.map_or_else(ZoneControlConfig::default, |row| ZoneControlConfig {
+            max_watts_up_per_tick: row.max_watts_up_per_tick,
+            max_watts_down_per_tick: row.max_watts_down_per_tick,
+            min_power: row.min_power as u16,
+            safety_power: row.safety_power as u16,
+            hr_sensor_warn_secs: row.hr_sensor_warn_secs as u64,
+            hr_sensor_stop_secs: row.hr_sensor_stop_secs as u64,
+            power_sensor_warn_secs: row.power_sensor_warn_secs as u64,
+            cadence_zero_secs: row.cadence_zero_secs as u64,
+            power_bias_pct: row.power_bias_pct as i8,
+            hr_reserve_ceiling_pct: row.hr_reserve_ceiling_pct as u8,
+        }))
+    }
+
+    pub async fn save_zone_control_config(&self, config: &ZoneControlConfig) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO zone_control_config (id, max_watts_up_per_tick, max_watts_down_per_tick, \
+             min_power, safety_power, hr_sensor_warn_secs, hr_sensor_stop_secs, \
+             power_sensor_warn_secs, cadence_zero_secs, power_bias_pct, hr_reserve_ceiling_pct) \
+             VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET \
+             max_watts_up_per_tick = excluded.max_watts_up_per_tick, \
+             max_watts_down_per_tick = excluded.max_watts_down_per_tick, \
+             min_power = excluded.min_power, safety_power = excluded.safety_power, \
+             hr_sensor_warn_secs = excluded.hr_sensor_warn_secs, \
+             hr_sensor_stop_secs = excluded.hr_sensor_stop_secs, \
+             power_sensor_warn_secs = excluded.power_sensor_warn_secs, \
+             cadence_zero_secs = excluded.cadence_zero_secs, \
+             power_bias_pct = excluded.power_bias_pct, \
+             hr_reserve_ceiling_pct = excluded.hr_reserve_ceiling_pct",
+        )
+        .bind(config.max_watts_up_per_tick)
+        .bind(config.max_watts_down_per_tick)
+        .bind(config.min_power as i32)
+        .bind(config.safety_power as i32)
+        .bind(config.hr_sensor_warn_secs as i64)
+        .bind(config.hr_sensor_stop_secs as i64)
+        .bind(config.power_sensor_warn_secs as i64)
+        .bind(config.cadence_zero_secs as i64)
+        .bind(config.power_bias_pct as i32)
+        .bind(config.hr_reserve_ceiling_pct as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Database)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,390 @@
+use std::time::Duration;
+
+use reqwest::multipart::{Form, Part};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+const STRAVA_BASE_URL: &str = "https://www.strava.com";
+
+/// Refresh the access token this long before Strava would reject it
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Strava asks clients to poll an upload no more than once a second; most
+/// FIT files are processed within a few seconds.
+const UPLOAD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const UPLOAD_POLL_ATTEMPTS: u32 = 30;
+
+/// OAuth credentials for the connected Strava account. The app registration
+/// (client id/secret) is the user's own, so it is stored alongside the tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StravaTokens {
+    pub client_id: String,
+    pub client_secret: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix seconds at which `access_token` expires
+    pub expires_at: i64,
+}
+
+impl StravaTokens {
+    /// True when the access token has expired or is about to.
+    pub fn needs_refresh(&self, now_secs: i64) -> bool {
+        now_secs + TOKEN_REFRESH_MARGIN_SECS >= self.expires_at
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_at: i64,
+}
+
+/// State of an upload as reported by `/api/v3/uploads`. Strava processes
+/// uploads asynchronously: `activity_id` is set once the ride is created,
+/// `error` if it was rejected (duplicate, malformed file, ...).
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadStatus {
+    pub id: u64,
+    pub error: Option<String>,
+    pub activity_id: Option<u64>,
+}
+
+impl UploadStatus {
+    /// The created activity id, `None` while still processing.
+    fn outcome(&self) -> Result<Option<u64>, AppError> {
+        match &self.error {
+            Some(error) => Err(AppError::Session(format!(
+                "Strava rejected the upload: {}",
+                error
+            ))),
+            None => Ok(self.activity_id),
+        }
+    }
+}
+
+/// Authorization page the user visits to grant the app `activity:write`.
+/// Strava redirects to localhost with the one-time `code` in the query string.
+pub fn authorize_url(client_id: &str) -> String {
+    format!(
+        "{}/oauth/authorize?client_id={}&response_type=code&redirect_uri=http://localhost\
+         &approval_prompt=auto&scope=activity:write",
+        STRAVA_BASE_URL, client_id
+    )
+}
+
+/// Multipart body for `POST /api/v3/uploads`. The session id doubles as the
+/// `external_id`, so Strava can flag a repeated upload of the same ride.
+pub fn upload_form(fit: Vec<u8>, session_id: &str, name: Option<&str>) -> Form {
+    let file = Part::bytes(fit).file_name(format!("{}.fit", session_id));
+    let form = Form::new()
+        .text("data_type", "fit")
+        .text("external_id", session_id.to_string())
+        .text("trainer", "1")
+        .part("file", file);
+    match name {
+        Some(name) => form.text("name", name.to_string()),
+        None => form,
+    }
+}
+
+pub struct StravaClient {
+    http: reqwest::Client,
+    base_url: String,
+    poll_interval: Duration,
+}
+
+impl StravaClient {
+    pub fn new() -> Self {
+        Self::with_base_url(STRAVA_BASE_URL, UPLOAD_POLL_INTERVAL)
+    }
+
+    fn with_base_url(base_url: &str, poll_interval: Duration) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            poll_interval,
+        }
+    }
+
+    /// Trade the one-time authorization `code` for the account's first tokens.
+    pub async fn exchange_code(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+    ) -> Result<StravaTokens, AppError> {
+        let token = self
+            .request_token(&[
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("code", code),
+                ("grant_type", "authorization_code"),
+            ])
+            .await?;
+        Ok(StravaTokens {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: token.expires_at,
+        })
+    }
+
+    /// Fetch a new access token. Strava may rotate the refresh token too, so
+    /// callers must persist the returned credentials.
+    pub async fn refresh(&self, tokens: &StravaTokens) -> Result<StravaTokens, AppError> {
+        let token = self
+            .request_token(&[
+                ("client_id", tokens.client_id.as_str()),
+                ("client_secret", tokens.client_secret.as_str()),
+                ("refresh_token", tokens.refresh_token.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .await?;
+        Ok(StravaTokens {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: token.expires_at,
+            ..tokens.clone()
+        })
+    }
+
+    async fn request_token(&self, params: &[(&str, &str)]) -> Result<TokenResponse, AppError> {
+        let response = self
+            .http
+            .post(format!("{}/oauth/token", self.base_url))
+            .form(params)
+            .send()
+            .await
+            .map_err(request_error)?;
+        json(check_status(response).await?).await
+    }
+
+    /// Start an upload. Returns as soon as Strava has queued the file.
+    pub async fn upload(&self, access_token: &str, form: Form) -> Result<UploadStatus, AppError> {
+        let response = self
+            .http
+            .post(format!("{}/api/v3/uploads", self.base_url))
+            .bearer_auth(access_token)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(request_error)?;
+        let status: UploadStatus = json(check_status(response).await?).await?;
+        status.outcome()?;
+        Ok(status)
+    }
+
+    pub async fn upload_status(
+        &self,
+        access_token: &str,
+        upload_id: u64,
+    ) -> Result<UploadStatus, AppError> {
+        let response = self
+            .http
+            .get(format!("{}/api/v3/uploads/{}", self.base_url, upload_id))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(request_error)?;
+        json(check_status(response).await?).await
+    }
+
+    /// Poll an upload until Strava has created the activity or rejected the file.
+    pub async fn wait_for_activity(
+        &self,
+        access_token: &str,
+        upload_id: u64,
+    ) -> Result<u64, AppError> {
+        for _ in 0..UPLOAD_POLL_ATTEMPTS {
+            let status = self.upload_status(access_token, upload_id).await?;
+            if let Some(activity_id) = status.outcome()? {
+                return Ok(activity_id);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+        Err(AppError::Session(format!(
+            "Strava is still processing upload {}; check your Strava feed later",
+            upload_id
+        )))
+    }
+}
+
+fn request_error(e: reqwest::Error) -> AppError {
+    AppError::Session(format!("Strava request failed: {}", e))
+}
+
+async fn check_status(response: Response) -> Result<Response, AppError> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(AppError::Session(
+            "Strava rate limit reached; try again in 15 minutes".into(),
+        ));
+    }
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(AppError::Session(
+            "Strava rejected the credentials; reconnect your account".into(),
+        ));
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::Session(format!(
+            "Strava returned {}: {}",
+            status, body
+        )));
+    }
+    Ok(response)
+}
+
+async fn json<T: serde::de::DeserializeOwned>(response: Response) -> Result<T, AppError> {
+    response
+        .json()
+        .await
+        .map_err(|e| AppError::Serialization(format!("Unexpected Strava response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn tokens(expires_at: i64) -> StravaTokens {
+        StravaTokens {
+            client_id: "1234".to_string(),
+            client_secret: "secret".to_string(),
+            access_token: "old-access".to_string(),
+            refresh_token: "old-refresh".to_string(),
+            expires_at,
+        }
+    }
+
+    fn client(server: &MockServer) -> StravaClient {
+        StravaClient::with_base_url(&server.uri(), Duration::from_millis(1))
+    }
+
+    #[test]
+    fn token_refreshes_shortly_before_expiry() {
+        let t = tokens(10_000);
+        assert!(!t.needs_refresh(9_000));
+        assert!(t.needs_refresh(10_000 - TOKEN_REFRESH_MARGIN_SECS));
+        assert!(t.needs_refresh(20_000));
+    }
+
+    #[tokio::test]
+    async fn refresh_posts_refresh_token_and_keeps_client() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=old-refresh"))
+            .and(body_string_contains("client_id=1234"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token_type": "Bearer",
+                "access_token": "new-access",
+                "refresh_token": "new-refresh",
+                "expires_at": 50_000,
+                "expires_in": 21_600,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let refreshed = client(&server).refresh(&tokens(0)).await.unwrap();
+        assert_eq!(refreshed.access_token, "new-access");
+        assert_eq!(refreshed.refresh_token, "new-refresh");
+        assert_eq!(refreshed.expires_at, 50_000);
+        assert_eq!(refreshed.client_id, "1234");
+        assert_eq!(refreshed.client_secret, "secret");
+    }
+
+    #[tokio::test]
+    async fn upload_sends_fit_as_multipart() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/uploads"))
+            .and(header("authorization", "Bearer access"))
+            .and(body_string_contains("name=\"data_type\"\r\n\r\nfit"))
+            .and(body_string_contains("name=\"external_id\"\r\n\r\nsess-1"))
+            .and(body_string_contains(
+                "name=\"file\"; filename=\"sess-1.fit\"",
+            ))
+            .and(body_string_contains(".FIT"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "id": 77,
+                "external_id": "sess-1",
+                "status": "Your activity is still being processed.",
+                "error": null,
+                "activity_id": null,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/uploads/77"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 77,
+                "status": "Your activity is ready.",
+                "error": null,
+                "activity_id": 9001,
+            })))
+            .mount(&server)
+            .await;
+
+        let strava = client(&server);
+        let fit = b"\x0e\x20\x54\x08\0\0\0\0.FIT".to_vec();
+        let upload = strava
+            .upload("access", upload_form(fit, "sess-1", None))
+            .await
+            .unwrap();
+        assert_eq!(upload.id, 77);
+        assert_eq!(
+            strava.wait_for_activity("access", upload.id).await.unwrap(),
+            9001
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limit_is_reported_clearly() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/uploads/5"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let err = client(&server)
+            .upload_status("access", 5)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("rate limit"), "got: {}", err);
+    }
+
+    #[tokio::test]
+    async fn rejected_upload_surfaces_strava_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/uploads/6"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 6,
+                "status": "There was an error processing your activity.",
+                "error": "sess-1.fit duplicate of activity 8000",
+                "activity_id": null,
+            })))
+            .mount(&server)
+            .await;
+
+        let err = client(&server)
+            .wait_for_activity("access", 6)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("duplicate of activity"),
+            "got: {}",
+            err
+        );
+    }
+}
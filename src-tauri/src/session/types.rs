@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::device::types::SensorReading;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SessionStatus {
     Running,
@@ -15,10 +17,184 @@ pub struct SessionConfig {
     pub hr_zones: [u8; 5],
     pub units: String,
     pub power_zones: [u16; 6],
+    /// How many of `power_zones` are in use; the default keeps all seven zones
+    #[serde(default)]
+    pub power_zone_model: PowerZoneModel,
     pub date_of_birth: Option<String>,
     pub sex: Option<String>,
     pub resting_hr: Option<u8>,
     pub max_hr: Option<u8>,
+    /// Cadence band edges in RPM: [z1/z2, z2/z3, z3/z4, z4/z5]
+    #[serde(default = "default_cadence_zones")]
+    pub cadence_zones: [u16; 4],
+    /// Critical power (watts) for the W′ balance model
+    pub critical_power: Option<u16>,
+    /// Anaerobic work capacity above CP (joules)
+    pub w_prime: Option<u32>,
+    /// Crank arm length for pedal-velocity calculations
+    #[serde(default = "default_crank_length_mm")]
+    pub crank_length_mm: f32,
+    /// Battery percentage below which a device_battery_low warning is raised
+    #[serde(default = "default_battery_low_threshold")]
+    pub battery_low_threshold: u8,
+    /// How often the active session is snapshotted to disk
+    #[serde(default = "default_autosave_interval_secs")]
+    pub autosave_interval_secs: u32,
+    /// How often the connection watchdog checks devices
+    #[serde(default = "default_connection_check_interval_secs")]
+    pub connection_check_interval_secs: u32,
+    /// Running pace band edges in seconds per km, slowest first:
+    /// [z1/z2, z2/z3, z3/z4, z4/z5]
+    #[serde(default = "default_pace_zones")]
+    pub pace_zones: [u16; 4],
+    /// Longest interval between two readings that analysis still counts as
+    /// continuous time. Raise it for sensors that report only every 8–10s.
+    #[serde(default = "default_max_gap_secs")]
+    pub max_gap_secs: u32,
+    /// Running power threshold; runs use `ftp` when unset
+    pub ftp_running: Option<u16>,
+    /// Rowing power threshold; rows use `ftp` when unset
+    pub ftp_rowing: Option<u16>,
+    /// Reconnect the most recently used sensors when the app starts
+    #[serde(default)]
+    pub auto_connect_on_start: bool,
+    /// Pause the session after this many seconds without power or cadence;
+    /// off when unset
+    #[serde(default)]
+    pub auto_pause_secs: Option<u32>,
+    /// Derive speed and distance from power when no speed sensor reports
+    #[serde(default)]
+    pub virtual_speed: bool,
+    /// Bike mass added to `weight_kg` by the virtual speed model
+    #[serde(default = "default_bike_weight_kg")]
+    pub bike_weight_kg: f32,
+    /// Rolling resistance coefficient for virtual speed
+    #[serde(default = "default_virtual_crr")]
+    pub virtual_crr: f32,
+    /// Drag area (m²) for virtual speed
+    #[serde(default = "default_virtual_cda")]
+    pub virtual_cda: f32,
+}
+
+/// Power zone scheme. Every model reads its boundaries from the front of
+/// `SessionConfig::power_zones`, so switching models keeps the stored values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerZoneModel {
+    /// Polarized low / moderate / high
+    Three,
+    Five,
+    /// Coggan's seven levels
+    #[default]
+    Seven,
+}
+
+impl PowerZoneModel {
+    pub fn zone_count(self) -> u8 {
+        match self {
+            Self::Three => 3,
+            Self::Five => 5,
+            Self::Seven => 7,
+        }
+    }
+
+    pub fn from_zone_count(count: u8) -> Option<Self> {
+        match count {
+            3 => Some(Self::Three),
+            5 => Some(Self::Five),
+            7 => Some(Self::Seven),
+            _ => None,
+        }
+    }
+
+    /// The `zone_count - 1` upper boundaries (% FTP) this model uses.
+    pub fn boundaries(self, power_zones: &[u16; 6]) -> &[u16] {
+        &power_zones[..self.zone_count() as usize - 1]
+    }
+}
+
+/// Periods of the background autosave and watchdog tasks. Those tasks are
+/// spawned once, so changes reach them through a `watch` channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskIntervals {
+    pub autosave_secs: u64,
+    pub connection_check_secs: u64,
+}
+
+impl Default for TaskIntervals {
+    fn default() -> Self {
+        Self {
+            autosave_secs: crate::config::AUTOSAVE_INTERVAL_SECS,
+            connection_check_secs: crate::config::CONNECTION_CHECK_INTERVAL_SECS,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Task periods, clamped to the minimums `save_user_config` enforces.
+    pub fn task_intervals(&self) -> TaskIntervals {
+        TaskIntervals {
+            autosave_secs: (self.autosave_interval_secs as u64)
+                .max(crate::config::MIN_AUTOSAVE_INTERVAL_SECS),
+            connection_check_secs: (self.connection_check_interval_secs as u64)
+                .max(crate::config::MIN_CONNECTION_CHECK_INTERVAL_SECS),
+        }
+    }
+
+    /// `max_gap_secs` in the milliseconds analysis works with.
+    pub fn max_gap_ms(&self) -> u64 {
+        self.max_gap_secs as u64 * 1000
+    }
+
+    /// The user's threshold for `activity_type`, if they set one for that
+    /// sport. `ftp` stays the cycling (and default) threshold.
+    pub fn sport_ftp(&self, activity_type: Option<&str>) -> Option<u16> {
+        match activity_type {
+            Some("run" | "running") => self.ftp_running,
+            Some("row" | "rowing") => self.ftp_rowing,
+            _ => None,
+        }
+    }
+}
+
+fn default_cadence_zones() -> [u16; 4] {
+    [60, 75, 90, 105]
+}
+
+fn default_pace_zones() -> [u16; 4] {
+    [390, 330, 300, 270]
+}
+
+fn default_crank_length_mm() -> f32 {
+    172.5
+}
+
+fn default_battery_low_threshold() -> u8 {
+    15
+}
+
+fn default_autosave_interval_secs() -> u32 {
+    crate::config::AUTOSAVE_INTERVAL_SECS as u32
+}
+
+fn default_connection_check_interval_secs() -> u32 {
+    crate::config::CONNECTION_CHECK_INTERVAL_SECS as u32
+}
+
+fn default_max_gap_secs() -> u32 {
+    crate::config::DEFAULT_MAX_GAP_SECS as u32
+}
+
+fn default_bike_weight_kg() -> f32 {
+    crate::config::DEFAULT_BIKE_WEIGHT_KG
+}
+
+fn default_virtual_crr() -> f32 {
+    crate::config::DEFAULT_VIRTUAL_CRR
+}
+
+fn default_virtual_cda() -> f32 {
+    crate::config::DEFAULT_VIRTUAL_CDA_M2
 }
 
 impl Default for SessionConfig {
@@ -29,10 +205,28 @@ impl Default for SessionConfig {
             hr_zones: [120, 140, 160, 175, 190],
             units: "metric".to_string(),
             power_zones: [55, 75, 90, 105, 120, 150],
+            power_zone_model: PowerZoneModel::default(),
             date_of_birth: None,
             sex: None,
             resting_hr: None,
             max_hr: None,
+            cadence_zones: default_cadence_zones(),
+            critical_power: None,
+            w_prime: None,
+            crank_length_mm: default_crank_length_mm(),
+            battery_low_threshold: default_battery_low_threshold(),
+            autosave_interval_secs: default_autosave_interval_secs(),
+            connection_check_interval_secs: default_connection_check_interval_secs(),
+            pace_zones: default_pace_zones(),
+            max_gap_secs: default_max_gap_secs(),
+            ftp_running: None,
+            ftp_rowing: None,
+            auto_connect_on_start: false,
+            auto_pause_secs: None,
+            virtual_speed: false,
+            bike_weight_kg: default_bike_weight_kg(),
+            virtual_crr: default_virtual_crr(),
+            virtual_cda: default_virtual_cda(),
         }
     }
 }
@@ -59,6 +253,46 @@ pub struct SessionSummary {
     pub activity_type: Option<String>,
     pub rpe: Option<u8>,
     pub notes: Option<String>,
+    pub efficiency_factor: Option<f32>,
+    pub calories: Option<u32>,
+    pub avg_cadence_active: Option<f32>,
+    pub avg_temp_c: Option<f32>,
+    pub total_ascent_m: Option<f32>,
+    /// Pinned by the user, e.g. benchmark rides
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Skiba's xPower: like NP but smoothed with a 25s exponential average
+    pub xpower: Option<u16>,
+    /// 0–100 agreement between integrated work and average power × duration;
+    /// low when dropouts skewed `work_kj`
+    pub data_quality_score: Option<u8>,
+    /// Mean right-pedal % from a dual-sided meter
+    pub avg_pedal_balance: Option<u8>,
+}
+
+/// Perceived exertion (1–10) broken down by what limited the session.
+/// `overall` is also stored as the session's scalar `rpe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructuredRpe {
+    pub overall: u8,
+    pub legs: u8,
+    pub breathing: u8,
+}
+
+/// Auto-pause transitions reported by `SessionManager::process_reading`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoPauseEvent {
+    Paused,
+    Resumed,
+}
+
+/// What `SessionManager::process_reading` produced besides recording the reading.
+#[derive(Debug, Default)]
+pub struct ReadingOutcome {
+    pub auto_pause: Option<AutoPauseEvent>,
+    /// Speed derived from this power reading by the virtual speed model,
+    /// already recorded; emitted so live displays show it too
+    pub virtual_speed: Option<SensorReading>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +300,8 @@ pub struct LiveMetrics {
     pub elapsed_secs: u64,
     pub current_power: Option<u16>,
     pub avg_power_3s: Option<f32>,
+    /// Time-weighted 3s power for display; recording still uses raw values
+    pub smoothed_power_3s: Option<f32>,
     pub avg_power_10s: Option<f32>,
     pub avg_power_30s: Option<f32>,
     pub normalized_power: Option<f32>,
@@ -84,4 +320,24 @@ pub struct LiveMetrics {
     pub stale_cadence: bool,
     /// True when no speed reading received for >5s
     pub stale_speed: bool,
+    /// Current lap (1-based); the fields above stay whole-ride
+    pub lap_number: u32,
+    pub lap_elapsed_secs: u64,
+    pub lap_avg_power: Option<f32>,
+    pub lap_normalized_power: Option<f32>,
+    /// Right-pedal % from a dual-sided meter; `None` for trainers and
+    /// single-sided meters
+    pub pedal_balance: Option<u8>,
+    pub avg_pedal_balance_30s: Option<f32>,
+}
+
+/// A labelled time range within a saved session (e.g. warmup, work, cooldown),
+/// in seconds from the session start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSegment {
+    pub id: i64,
+    pub session_id: String,
+    pub start_secs: u64,
+    pub end_secs: u64,
+    pub label: String,
 }
@@ -0,0 +1,841 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::{info, warn};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::config;
+use crate::device::manager::DeviceManager;
+use crate::device::types::{CommandSource, SensorReading};
+use crate::error::AppError;
+use crate::session::zone_control::types::apply_power_bias;
+
+/// Power target for a single workout step.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StepTarget {
+    /// Absolute power in watts.
+    Watts(u16),
+    /// Percentage of FTP (e.g. 75.0 = 75% FTP).
+    FtpPct(f32),
+    /// Linear ramp between two FTP percentages over the step duration.
+    Ramp { from: f32, to: f32 },
+    /// No power target; the trainer is left at its last command.
+    FreeRide,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkoutStep {
+    pub duration_secs: u32,
+    pub target: StepTarget,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workout {
+    pub steps: Vec<WorkoutStep>,
+}
+
+impl Workout {
+    pub fn total_secs(&self) -> u64 {
+        self.steps.iter().map(|s| s.duration_secs as u64).sum()
+    }
+
+    /// Locate the step containing `elapsed_ms`: (step index, step start ms).
+    /// Returns None once the workout is complete.
+    fn step_at(&self, elapsed_ms: u64) -> Option<(usize, u64)> {
+        let mut start_ms = 0u64;
+        for (i, step) in self.steps.iter().enumerate() {
+            let end_ms = start_ms + step.duration_secs as u64 * 1000;
+            if elapsed_ms < end_ms {
+                return Some((i, start_ms));
+            }
+            start_ms = end_ms;
+        }
+        None
+    }
+
+    /// Target watts at `elapsed_ms` into the workout. None once complete or
+    /// during a free-ride step.
+    pub fn target_watts_at(&self, elapsed_ms: u64, ftp: u16) -> Option<u16> {
+        let (index, start_ms) = self.step_at(elapsed_ms)?;
+        let step = &self.steps[index];
+        let pct_to_watts = |pct: f32| (ftp as f32 * pct / 100.0).round() as u16;
+        match step.target {
+            StepTarget::Watts(w) => Some(w),
+            StepTarget::FtpPct(pct) => Some(pct_to_watts(pct)),
+            StepTarget::Ramp { from, to } => {
+                let frac = (elapsed_ms - start_ms) as f32 / (step.duration_secs as f32 * 1000.0);
+                Some(pct_to_watts(from + (to - from) * frac))
+            }
+            StepTarget::FreeRide => None,
+        }
+    }
+
+    pub fn is_complete_at(&self, elapsed_ms: u64) -> bool {
+        self.step_at(elapsed_ms).is_none()
+    }
+}
+
+/// Parse a Zwift `.zwo` workout. Power attributes are FTP fractions (0.75 = 75% FTP).
+/// Supports SteadyState, Warmup/Cooldown/Ramp (as ramps), IntervalsT (expanded into
+/// alternating on/off steps) and FreeRide. Elements nested inside a step (e.g.
+/// `<textevent>`) and everything outside `<workout>` are ignored.
+pub fn parse_zwo(xml: &str) -> Result<Workout, AppError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut steps = Vec::new();
+    let mut saw_workout = false;
+    let mut in_workout = false;
+    // Depth below <workout>; steps are only read at depth 0
+    let mut depth = 0usize;
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            AppError::Serialization(format!(
+                "Malformed .zwo XML at byte {}: {}",
+                reader.error_position(),
+                e
+            ))
+        })?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                if !in_workout {
+                    if e.name().as_ref() == b"workout" && !is_empty {
+                        in_workout = true;
+                        saw_workout = true;
+                    }
+                    continue;
+                }
+                if depth == 0 {
+                    parse_zwo_element(e, &mut steps)?;
+                }
+                if !is_empty {
+                    depth += 1;
+                }
+            }
+            Event::End(ref e) if in_workout => {
+                if depth == 0 && e.name().as_ref() == b"workout" {
+                    in_workout = false;
+                } else {
+                    depth = depth.saturating_sub(1);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !saw_workout {
+        return Err(AppError::Serialization(
+            "Invalid .zwo file: missing <workout> element".into(),
+        ));
+    }
+    if steps.is_empty() {
+        return Err(AppError::Serialization(
+            "Invalid .zwo file: workout has no steps".into(),
+        ));
+    }
+    Ok(Workout { steps })
+}
+
+fn parse_zwo_element(e: &BytesStart, steps: &mut Vec<WorkoutStep>) -> Result<(), AppError> {
+    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    match name.as_str() {
+        "SteadyState" => steps.push(WorkoutStep {
+            duration_secs: zwo_duration(e, &name, "Duration")?,
+            target: StepTarget::FtpPct(zwo_power(e, &name, "Power")?),
+        }),
+        "Warmup" | "Cooldown" | "Ramp" => steps.push(WorkoutStep {
+            duration_secs: zwo_duration(e, &name, "Duration")?,
+            target: StepTarget::Ramp {
+                from: zwo_power(e, &name, "PowerLow")?,
+                to: zwo_power(e, &name, "PowerHigh")?,
+            },
+        }),
+        "IntervalsT" => {
+            let repeat = zwo_attr(e, &name, "Repeat")?
+                .parse::<u32>()
+                .map_err(|_| AppError::Serialization(format!("<{}> has invalid Repeat", name)))?;
+            let on = WorkoutStep {
+                duration_secs: zwo_duration(e, &name, "OnDuration")?,
+                target: StepTarget::FtpPct(zwo_power(e, &name, "OnPower")?),
+            };
+            let off = WorkoutStep {
+                duration_secs: zwo_duration(e, &name, "OffDuration")?,
+                target: StepTarget::FtpPct(zwo_power(e, &name, "OffPower")?),
+            };
+            for _ in 0..repeat {
+                steps.push(on.clone());
+                steps.push(off.clone());
+            }
+        }
+        "FreeRide" => steps.push(WorkoutStep {
+            duration_secs: zwo_duration(e, &name, "Duration")?,
+            target: StepTarget::FreeRide,
+        }),
+        other => {
+            return Err(AppError::Serialization(format!(
+                "Unsupported .zwo workout element <{}>",
+                other
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn zwo_attr(e: &BytesStart, element: &str, key: &str) -> Result<String, AppError> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(|err| {
+            AppError::Serialization(format!("<{}> has a malformed attribute: {}", element, err))
+        })?;
+        if attr.key.as_ref() == key.as_bytes() {
+            return attr.unescape_value().map(|v| v.into_owned()).map_err(|err| {
+                AppError::Serialization(format!("<{}> {} is not valid text: {}", element, key, err))
+            });
+        }
+    }
+    Err(AppError::Serialization(format!(
+        "<{}> is missing the {} attribute",
+        element, key
+    )))
+}
+
+fn zwo_number(e: &BytesStart, element: &str, key: &str) -> Result<f32, AppError> {
+    let raw = zwo_attr(e, element, key)?;
+    raw.trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .ok_or_else(|| {
+            AppError::Serialization(format!("<{}> {}=\"{}\" is not a valid number", element, key, raw))
+        })
+}
+
+fn zwo_duration(e: &BytesStart, element: &str, key: &str) -> Result<u32, AppError> {
+    let secs = zwo_number(e, element, key)?.round() as u32;
+    if secs == 0 {
+        return Err(AppError::Serialization(format!("<{}> {} must be positive", element, key)));
+    }
+    Ok(secs)
+}
+
+/// Read an FTP-fraction attribute as a percentage.
+fn zwo_power(e: &BytesStart, element: &str, key: &str) -> Result<f32, AppError> {
+    Ok(zwo_number(e, element, key)? * 100.0)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkoutStatus {
+    pub active: bool,
+    pub paused: bool,
+    pub step_index: Option<usize>,
+    pub step_count: usize,
+    pub elapsed_secs: u64,
+    pub total_secs: u64,
+    pub step_remaining_secs: Option<u64>,
+    pub commanded_power: Option<u16>,
+}
+
+/// Position within a running workout. Time only moves when `advance` is called,
+/// so the runner feeds it wall-clock deltas and tests feed it a fake clock.
+struct WorkoutProgress {
+    workout: Workout,
+    ftp: u16,
+    elapsed_ms: u64,
+    paused: bool,
+}
+
+impl WorkoutProgress {
+    fn new(workout: Workout, ftp: u16) -> Self {
+        Self {
+            workout,
+            ftp,
+            elapsed_ms: 0,
+            paused: false,
+        }
+    }
+
+    fn advance(&mut self, delta_ms: u64) {
+        if !self.paused {
+            self.elapsed_ms += delta_ms;
+        }
+    }
+
+    fn target_watts(&self) -> Option<u16> {
+        self.workout.target_watts_at(self.elapsed_ms, self.ftp)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.workout.is_complete_at(self.elapsed_ms)
+    }
+
+    /// Jump to the start of the next step (or to the end on the last step).
+    fn skip_step(&mut self) {
+        if let Some((index, start_ms)) = self.workout.step_at(self.elapsed_ms) {
+            self.elapsed_ms = start_ms + self.workout.steps[index].duration_secs as u64 * 1000;
+        }
+    }
+
+    fn step_remaining_secs(&self) -> Option<u64> {
+        let (index, start_ms) = self.workout.step_at(self.elapsed_ms)?;
+        let end_ms = start_ms + self.workout.steps[index].duration_secs as u64 * 1000;
+        Some((end_ms - self.elapsed_ms).div_ceil(1000))
+    }
+}
+
+struct RunnerState {
+    active: bool,
+    progress: Option<WorkoutProgress>,
+    commanded_power: Option<u16>,
+    /// ERG power-match bias (percent) applied to what the trainer is sent
+    power_bias_pct: i8,
+    /// Instant of the last processed tick, for measuring actual elapsed time
+    last_tick_at: Option<Instant>,
+    /// When the trainer was found missing; the runner holds until it reconnects
+    trainer_lost_since: Option<Instant>,
+}
+
+impl RunnerState {
+    fn new() -> Self {
+        Self {
+            active: false,
+            progress: None,
+            commanded_power: None,
+            power_bias_pct: 0,
+            last_tick_at: None,
+            trainer_lost_since: None,
+        }
+    }
+
+    /// Clear the lost-trainer hold and force the current target to be re-sent.
+    fn trainer_restored(&mut self) {
+        self.trainer_lost_since = None;
+        self.commanded_power = None;
+    }
+
+    /// Fold wall-clock time since the last tick into the workout position.
+    fn catch_up(&mut self) {
+        let now = Instant::now();
+        let delta_ms = self
+            .last_tick_at
+            .map(|t| now.duration_since(t).as_millis() as u64)
+            .unwrap_or(0);
+        self.last_tick_at = Some(now);
+        if let Some(progress) = self.progress.as_mut() {
+            progress.advance(delta_ms);
+        }
+    }
+}
+
+/// Drives the trainer in ERG mode through a structured workout, one target per second.
+pub struct WorkoutRunner {
+    state: Arc<Mutex<RunnerState>>,
+    shutdown_tx: Option<watch::Sender<bool>>,
+    task_handle: Option<JoinHandle<()>>,
+}
+
+impl WorkoutRunner {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RunnerState::new())),
+            shutdown_tx: None,
+            task_handle: None,
+        }
+    }
+
+    pub async fn start(
+        &mut self,
+        workout: Workout,
+        ftp: u16,
+        device_manager: Arc<Mutex<DeviceManager>>,
+        sensor_tx: broadcast::Sender<SensorReading>,
+    ) -> Result<(), AppError> {
+        if workout.steps.is_empty() {
+            return Err(AppError::Session("Workout has no steps".into()));
+        }
+        if workout.steps.iter().any(|s| s.duration_secs == 0) {
+            return Err(AppError::Session("Workout steps must have a duration".into()));
+        }
+        let trainer_rx = {
+            let dm = device_manager.lock().await;
+            if dm.connected_trainer_id().is_none() {
+                return Err(AppError::Session("No trainer connected".into()));
+            }
+            dm.subscribe_trainer_reconnects()
+        };
+
+        self.stop_internal().await;
+
+        info!(
+            "Workout started: {} steps, {}s total, FTP {}W",
+            workout.steps.len(),
+            workout.total_secs(),
+            ftp
+        );
+        {
+            let mut state = self.state.lock().await;
+            state.active = true;
+            state.progress = Some(WorkoutProgress::new(workout, ftp));
+            state.commanded_power = None;
+            state.last_tick_at = Some(Instant::now());
+            state.trainer_lost_since = None;
+        }
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        self.shutdown_tx = Some(shutdown_tx);
+        let handle = tokio::spawn(run_loop(
+            self.state.clone(),
+            device_manager,
+            sensor_tx,
+            trainer_rx,
+            shutdown_rx,
+        ));
+        self.task_handle = Some(handle);
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) {
+        self.stop_internal().await;
+        let mut state = self.state.lock().await;
+        if state.active {
+            info!("Workout stopped");
+        }
+        state.active = false;
+    }
+
+    async fn stop_internal(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(true);
+        }
+        if let Some(handle) = self.task_handle.take() {
+            let _ = handle.await;
+        }
+    }
+
+    pub async fn pause(&self) {
+        let mut state = self.state.lock().await;
+        if !state.active {
+            return;
+        }
+        state.catch_up();
+        if let Some(progress) = state.progress.as_mut() {
+            progress.paused = true;
+            info!("Workout paused");
+        }
+    }
+
+    pub async fn resume(&self) {
+        let mut state = self.state.lock().await;
+        if !state.active {
+            return;
+        }
+        state.last_tick_at = Some(Instant::now());
+        if let Some(progress) = state.progress.as_mut() {
+            progress.paused = false;
+            info!("Workout resumed");
+        }
+    }
+
+    pub async fn skip_step(&self) {
+        let mut state = self.state.lock().await;
+        if !state.active {
+            return;
+        }
+        state.catch_up();
+        if let Some(progress) = state.progress.as_mut() {
+            progress.skip_step();
+            info!("Workout step skipped");
+        }
+    }
+
+    /// Set the power-match bias. A running workout re-commands the trainer on its next tick.
+    pub async fn set_power_bias(&self, bias_pct: i8) {
+        let mut state = self.state.lock().await;
+        if state.power_bias_pct != bias_pct {
+            state.power_bias_pct = bias_pct;
+            state.commanded_power = None;
+        }
+    }
+
+    pub async fn status(&self) -> WorkoutStatus {
+        let state = self.state.lock().await;
+        let progress = state.progress.as_ref();
+        WorkoutStatus {
+            active: state.active,
+            paused: progress.is_some_and(|p| p.paused),
+            step_index: progress.and_then(|p| p.workout.step_at(p.elapsed_ms).map(|(i, _)| i)),
+            step_count: progress.map_or(0, |p| p.workout.steps.len()),
+            elapsed_secs: progress.map_or(0, |p| p.elapsed_ms / 1000),
+            total_secs: progress.map_or(0, |p| p.workout.total_secs()),
+            step_remaining_secs: progress.and_then(|p| p.step_remaining_secs()),
+            commanded_power: if state.active {
+                state.commanded_power
+            } else {
+                None
+            },
+        }
+    }
+}
+
+fn now_epoch_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+async fn run_loop(
+    state: Arc<Mutex<RunnerState>>,
+    device_manager: Arc<Mutex<DeviceManager>>,
+    sensor_tx: broadcast::Sender<SensorReading>,
+    mut trainer_rx: broadcast::Receiver<String>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(1));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => break,
+            Ok(trainer_id) = trainer_rx.recv() => {
+                info!("[{}] Trainer reconnected — resuming workout", trainer_id);
+                state.lock().await.trainer_restored();
+                if process_tick(&state, &device_manager, &sensor_tx).await {
+                    break;
+                }
+            }
+            _ = tick.tick() => {
+                if process_tick(&state, &device_manager, &sensor_tx).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Advance the workout and command the trainer if the target changed.
+/// Returns true when the loop should stop.
+async fn process_tick(
+    state: &Arc<Mutex<RunnerState>>,
+    device_manager: &Arc<Mutex<DeviceManager>>,
+    sensor_tx: &broadcast::Sender<SensorReading>,
+) -> bool {
+    let target = {
+        let mut s = state.lock().await;
+        if !s.active {
+            return true;
+        }
+        s.catch_up();
+        let Some(progress) = s.progress.as_ref() else {
+            return true;
+        };
+        if progress.paused {
+            return false;
+        }
+        if progress.is_complete() {
+            info!("Workout complete");
+            s.active = false;
+            return true;
+        }
+        match progress.target_watts() {
+            Some(w) => w,
+            None => {
+                // Free ride: leave the trainer alone, re-command on the next targeted step
+                s.commanded_power = None;
+                return false;
+            }
+        }
+    };
+
+    let (trainer_watts, log_entry) = {
+        let s = state.lock().await;
+        if s.commanded_power == Some(target) {
+            return false;
+        }
+        trainer_command(target, s.power_bias_pct)
+    };
+
+    let mut dm = device_manager.lock().await;
+    let Some(trainer_id) = dm.connected_trainer_id() else {
+        drop(dm);
+        let mut s = state.lock().await;
+        let lost_since = *s.trainer_lost_since.get_or_insert_with(|| {
+            warn!("Workout trainer disconnected — waiting for reconnect");
+            Instant::now()
+        });
+        if lost_since.elapsed().as_secs() >= config::TRAINER_RECONNECT_GRACE_SECS {
+            warn!("Workout stopped: trainer did not reconnect");
+            s.active = false;
+            return true;
+        }
+        return false;
+    };
+    if let Err(e) = dm.set_target_power(&trainer_id, trainer_watts as i16).await {
+        warn!("Workout trainer power command failed: {}", e);
+        return false;
+    }
+    drop(dm);
+
+    state.lock().await.commanded_power = Some(target);
+    let _ = sensor_tx.send(log_entry);
+    false
+}
+
+/// Watts to send the trainer for `target`, and the command to record. The
+/// power-match bias only affects the former.
+fn trainer_command(target: u16, bias_pct: i8) -> (u16, SensorReading) {
+    let log_entry = SensorReading::TrainerCommand {
+        target_watts: target,
+        epoch_ms: now_epoch_ms(),
+        source: CommandSource::Workout,
+    };
+    (apply_power_bias(target, bias_pct), log_entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 60s warmup ramp 50→70% FTP, 30s at 300W, 60s at 60% FTP.
+    fn three_step_workout() -> Workout {
+        Workout {
+            steps: vec![
+                WorkoutStep {
+                    duration_secs: 60,
+                    target: StepTarget::Ramp { from: 50.0, to: 70.0 },
+                },
+                WorkoutStep {
+                    duration_secs: 30,
+                    target: StepTarget::Watts(300),
+                },
+                WorkoutStep {
+                    duration_secs: 60,
+                    target: StepTarget::FtpPct(60.0),
+                },
+            ],
+        }
+    }
+
+    /// Advance a fake clock one second at a time, like the 1s runner tick.
+    fn advance_secs(progress: &mut WorkoutProgress, secs: u64) {
+        for _ in 0..secs {
+            progress.advance(1000);
+        }
+    }
+
+    #[test]
+    fn commanded_power_at_step_boundaries() {
+        let mut p = WorkoutProgress::new(three_step_workout(), 200);
+        // t=0: ramp start, 50% of 200W
+        assert_eq!(p.target_watts(), Some(100));
+        // t=30: ramp midpoint, 60% of 200W
+        advance_secs(&mut p, 30);
+        assert_eq!(p.target_watts(), Some(120));
+        // t=59: last second of ramp, 50 + 20 * 59/60 = 69.67% → 139W
+        advance_secs(&mut p, 29);
+        assert_eq!(p.target_watts(), Some(139));
+        // t=60: step 2 begins
+        advance_secs(&mut p, 1);
+        assert_eq!(p.target_watts(), Some(300));
+        // t=89: still step 2
+        advance_secs(&mut p, 29);
+        assert_eq!(p.target_watts(), Some(300));
+        // t=90: step 3, 60% of 200W
+        advance_secs(&mut p, 1);
+        assert_eq!(p.target_watts(), Some(120));
+        // t=150: complete
+        advance_secs(&mut p, 60);
+        assert_eq!(p.target_watts(), None);
+    }
+
+    #[test]
+    fn power_bias_lowers_trainer_watts_but_not_logged_target() {
+        let (trainer_watts, log_entry) = trainer_command(300, -5);
+        assert_eq!(trainer_watts, 285);
+        assert!(matches!(
+            log_entry,
+            SensorReading::TrainerCommand { target_watts: 300, .. }
+        ));
+        // Out-of-range bias is clamped to ±15%
+        assert_eq!(trainer_command(200, -40).0, 170);
+        assert_eq!(trainer_command(200, 0).0, 200);
+    }
+
+    #[test]
+    fn paused_clock_does_not_advance() {
+        let mut p = WorkoutProgress::new(three_step_workout(), 200);
+        advance_secs(&mut p, 55);
+        p.paused = true;
+        advance_secs(&mut p, 30);
+        assert_eq!(p.elapsed_ms, 55_000);
+        p.paused = false;
+        advance_secs(&mut p, 5);
+        assert_eq!(p.target_watts(), Some(300));
+    }
+
+    #[test]
+    fn skip_step_jumps_to_next_step_start() {
+        let mut p = WorkoutProgress::new(three_step_workout(), 200);
+        advance_secs(&mut p, 10);
+        p.skip_step();
+        assert_eq!(p.elapsed_ms, 60_000);
+        assert_eq!(p.target_watts(), Some(300));
+        assert_eq!(p.step_remaining_secs(), Some(30));
+    }
+
+    const INTERVAL_ZWO: &str = r#"<workout_file>
+    <author>Coach</author>
+    <name>4x1 VO2</name>
+    <sportType>bike</sportType>
+    <workout>
+        <Warmup Duration="300" PowerLow="0.40" PowerHigh="0.75"/>
+        <IntervalsT Repeat="2" OnDuration="60" OffDuration="90" OnPower="1.20" OffPower="0.50">
+            <textevent timeoffset="0" message="Go!"/>
+        </IntervalsT>
+        <SteadyState Duration="120" Power="0.65"/>
+        <FreeRide Duration="60" FlatRoad="1"/>
+        <Cooldown Duration="240" PowerLow="0.60" PowerHigh="0.30"/>
+    </workout>
+</workout_file>"#;
+
+    fn assert_pct(target: StepTarget, expected: f32) {
+        match target {
+            StepTarget::FtpPct(pct) => assert!((pct - expected).abs() < 1e-3, "expected {expected}%, got {pct}%"),
+            other => panic!("expected FtpPct, got {:?}", other),
+        }
+    }
+
+    fn assert_ramp(target: StepTarget, expected_from: f32, expected_to: f32) {
+        match target {
+            StepTarget::Ramp { from, to } => {
+                assert!((from - expected_from).abs() < 1e-3, "ramp from {from}");
+                assert!((to - expected_to).abs() < 1e-3, "ramp to {to}");
+            }
+            other => panic!("expected Ramp, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_zwo_interval_workout() {
+        let w = parse_zwo(INTERVAL_ZWO).unwrap();
+        let durations: Vec<u32> = w.steps.iter().map(|s| s.duration_secs).collect();
+        assert_eq!(durations, vec![300, 60, 90, 60, 90, 120, 60, 240]);
+
+        assert_ramp(w.steps[0].target, 40.0, 75.0);
+        assert_pct(w.steps[1].target, 120.0);
+        assert_pct(w.steps[2].target, 50.0);
+        assert_pct(w.steps[3].target, 120.0);
+        assert_pct(w.steps[4].target, 50.0);
+        assert_pct(w.steps[5].target, 65.0);
+        assert_eq!(w.steps[6].target, StepTarget::FreeRide);
+        assert_ramp(w.steps[7].target, 60.0, 30.0);
+    }
+
+    #[test]
+    fn parse_zwo_unknown_element_errors() {
+        let xml = r#"<workout_file><workout><MaxEffort Duration="30"/></workout></workout_file>"#;
+        let err = parse_zwo(xml).unwrap_err().to_string();
+        assert!(err.contains("MaxEffort"), "got: {}", err);
+    }
+
+    #[test]
+    fn parse_zwo_missing_attribute_errors() {
+        let xml = r#"<workout_file><workout><SteadyState Duration="300"/></workout></workout_file>"#;
+        let err = parse_zwo(xml).unwrap_err().to_string();
+        assert!(err.contains("Power"), "got: {}", err);
+    }
+
+    #[test]
+    fn parse_zwo_malformed_xml_errors() {
+        let xml = r#"<workout_file><workout><SteadyState Duration="300" Power="0.5"></workout>"#;
+        assert!(matches!(parse_zwo(xml), Err(AppError::Serialization(_))));
+    }
+
+    #[test]
+    fn free_ride_step_has_no_target_but_is_not_complete() {
+        let w = Workout {
+            steps: vec![WorkoutStep {
+                duration_secs: 30,
+                target: StepTarget::FreeRide,
+            }],
+        };
+        assert_eq!(w.target_watts_at(10_000, 200), None);
+        assert!(!w.is_complete_at(10_000));
+        assert!(w.is_complete_at(30_000));
+    }
+
+    #[test]
+    fn skip_last_step_completes_workout() {
+        let mut p = WorkoutProgress::new(three_step_workout(), 200);
+        advance_secs(&mut p, 100);
+        p.skip_step();
+        assert_eq!(p.target_watts(), None);
+        assert_eq!(p.step_remaining_secs(), None);
+    }
+
+    #[tokio::test]
+    async fn missing_trainer_holds_workout_until_reconnect() {
+        let mut runner = RunnerState::new();
+        runner.active = true;
+        runner.progress = Some(WorkoutProgress::new(three_step_workout(), 200));
+        runner.last_tick_at = Some(Instant::now());
+        let state = Arc::new(Mutex::new(runner));
+        let dm = Arc::new(Mutex::new(DeviceManager::new()));
+        let (tx, _rx) = broadcast::channel(16);
+
+        assert!(!process_tick(&state, &dm, &tx).await);
+        {
+            let s = state.lock().await;
+            assert!(s.active, "workout must not stop on a trainer drop");
+            assert!(s.trainer_lost_since.is_some());
+            assert_eq!(s.commanded_power, None);
+        }
+
+        // Reconnect clears the hold so the next tick re-sends the current target
+        state.lock().await.trainer_restored();
+        assert!(state.lock().await.trainer_lost_since.is_none());
+
+        // Past the grace period the workout gives up
+        state.lock().await.trainer_lost_since = Some(
+            Instant::now()
+                - std::time::Duration::from_secs(config::TRAINER_RECONNECT_GRACE_SECS),
+        );
+        assert!(process_tick(&state, &dm, &tx).await);
+        assert!(!state.lock().await.active);
+    }
+
+    /// Target of the next TrainerCommand broadcast, waiting at most a second.
+    async fn next_trainer_command(rx: &mut broadcast::Receiver<SensorReading>) -> u16 {
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            loop {
+                if let SensorReading::TrainerCommand { target_watts, .. } = rx.recv().await.unwrap() {
+                    return target_watts;
+                }
+            }
+        })
+        .await
+        .expect("no trainer command was sent")
+    }
+
+    #[tokio::test]
+    async fn trainer_reconnect_resends_current_target() {
+        let dm = Arc::new(Mutex::new(DeviceManager::new()));
+        let commands = dm.lock().await.connect_mock_trainer("trainer");
+        let (tx, mut rx) = broadcast::channel(64);
+        let workout = Workout {
+            steps: vec![WorkoutStep { duration_secs: 60, target: StepTarget::Watts(250) }],
+        };
+        let mut runner = WorkoutRunner::new();
+        runner.start(workout, 200, dm.clone(), tx).await.unwrap();
+        assert_eq!(next_trainer_command(&mut rx).await, 250);
+
+        dm.lock().await.announce_trainer_reconnect("trainer");
+        assert_eq!(next_trainer_command(&mut rx).await, 250);
+        assert_eq!(*commands.lock().unwrap(), vec![250, 250]);
+        runner.stop().await;
+    }
+}
@@ -5,30 +5,19 @@ use log::{debug, info, warn};
 use tokio::sync::{broadcast, watch, Mutex};
 use tokio::task::JoinHandle;
 
+use crate::config;
 use crate::device::manager::DeviceManager;
 use crate::device::types::{CommandSource, SensorReading};
 use crate::error::AppError;
 
 use super::pid::{adaptive_gains, HrSmoother, PidController};
-use super::types::{StopReason, ZoneControlStatus, ZoneMode, ZoneTarget};
+use super::types::{
+    apply_power_bias, PidDebug, StopReason, ZoneControlConfig, ZoneControlStatus, ZoneMode,
+    ZoneTarget, FALLBACK_POWER_CEILING,
+};
 
-/// Maximum watts per tick when ramping UP (rate limiter, separate from PID output_limit)
-const HR_MAX_WATTS_UP_PER_TICK: f64 = 10.0;
-/// Maximum watts per tick when ramping DOWN (faster — reducing power is always safe)
-const HR_MAX_WATTS_DOWN_PER_TICK: f64 = 30.0;
 /// Integral decay factor when HR is above zone but already falling
 const INTEGRAL_DECAY_ON_FALLING_HR: f64 = 0.7;
-/// Minimum commanded power (watts)
-const MIN_POWER: u16 = 50;
-/// Safety: reduce to this power when HR ceiling exceeded
-const SAFETY_POWER: u16 = 50;
-/// HR sensor lost thresholds (seconds)
-const HR_SENSOR_WARN_SECS: u64 = 15;
-const HR_SENSOR_STOP_SECS: u64 = 30;
-/// Power sensor lost threshold (seconds)
-const POWER_SENSOR_WARN_SECS: u64 = 15;
-/// Cadence zero threshold (seconds)
-const CADENCE_ZERO_SECS: u64 = 3;
 
 struct ControlLoopState {
     active: bool,
@@ -50,14 +39,21 @@ struct ControlLoopState {
     last_power_seen: Option<Instant>,
     /// FTP from user config, used for HR mode power clamping
     ftp: Option<u16>,
-    /// Max HR from user config, used for HR ceiling safety
-    max_hr: Option<u8>,
+    /// HR safety ceiling (bpm) derived from the rider's profile
+    hr_ceiling: Option<u8>,
     /// Instant of the last processed tick, for measuring actual elapsed time
     last_tick_at: Option<Instant>,
     /// Whether HR was above zone on previous tick (for integral reset on re-entry)
     was_above_zone: bool,
     /// Power zone percentages from user config (for HR mode power banding)
     power_zones: Option<[u16; 6]>,
+    /// Rate limits, power floor, and safety thresholds for this run
+    config: ZoneControlConfig,
+    /// When a trainer command last failed; the loop holds until it reconnects
+    trainer_lost_since: Option<Instant>,
+    /// Record PID internals each HR tick; survives restarts of the loop
+    pid_debug_enabled: bool,
+    pid_debug: Option<PidDebug>,
 }
 
 impl ControlLoopState {
@@ -81,13 +77,38 @@ impl ControlLoopState {
             last_hr_seen: None,
             last_power_seen: None,
             ftp: None,
-            max_hr: None,
+            hr_ceiling: None,
             last_tick_at: None,
             was_above_zone: false,
             power_zones: None,
+            config: ZoneControlConfig::default(),
+            trainer_lost_since: None,
+            pid_debug_enabled: false,
+            pid_debug: None,
         }
     }
 
+    /// Hold the loop until the reconnect engine restores the trainer.
+    fn mark_trainer_lost(&mut self) {
+        if self.trainer_lost_since.is_none() {
+            self.trainer_lost_since = Some(Instant::now());
+            self.phase = "reconnecting".to_string();
+            self.safety_note = Some("Trainer disconnected — waiting for reconnect".to_string());
+        }
+    }
+
+    /// Clear the lost-trainer hold. Returns the power to re-command, if the loop is running.
+    fn trainer_restored(&mut self) -> Option<u16> {
+        if !self.active {
+            return None;
+        }
+        if self.trainer_lost_since.take().is_some() {
+            self.phase = "adjusting".to_string();
+            self.safety_note = None;
+        }
+        Some(self.commanded_power)
+    }
+
     fn elapsed_ms(&self) -> u64 {
         let Some(started) = self.started_at else {
             return 0;
@@ -123,24 +144,36 @@ impl ZoneController {
         device_manager: Arc<Mutex<DeviceManager>>,
         sensor_tx: broadcast::Sender<SensorReading>,
         ftp: Option<u16>,
-        max_hr: Option<u8>,
+        hr_ceiling: Option<u8>,
         initial_power_estimate: Option<u16>,
         power_zones: Option<[u16; 6]>,
+        config: ZoneControlConfig,
     ) -> Result<(), AppError> {
         // Validate
+        config.validate()?;
         if target.lower_bound >= target.upper_bound {
             return Err(AppError::Session(
                 "Zone lower bound must be less than upper bound".into(),
             ));
         }
+        if target.mode == ZoneMode::HeartRate {
+            let (_, ceiling) = hr_power_band(ftp, power_zones, target.zone, config.min_power);
+            if config.min_power > ceiling {
+                return Err(AppError::Session(format!(
+                    "Minimum power {}W is above the {}W ceiling for zone {} at this FTP",
+                    config.min_power, ceiling, target.zone
+                )));
+            }
+        }
 
         // Verify trainer connected
-        {
+        let trainer_rx = {
             let dm = device_manager.lock().await;
             if dm.connected_trainer_id().is_none() {
                 return Err(AppError::Session("No trainer connected".into()));
             }
-        }
+            dm.subscribe_trainer_reconnects()
+        };
 
         // Stop any existing control loop
         self.stop_internal().await;
@@ -152,7 +185,7 @@ impl ZoneController {
                 if let Some(estimate) = initial_power_estimate {
                     // Historical model estimate, clamped to safe range
                     let max = ftp.map(|f| (f as f64 * 1.2) as u16).unwrap_or(300);
-                    estimate.clamp(MIN_POWER, max)
+                    estimate.clamp(config.min_power.min(max), max)
                 } else {
                     // Conservative start: 55% FTP if available, else 100W
                     ftp.map(|f| (f as f64 * 0.55) as u16).unwrap_or(100)
@@ -160,6 +193,7 @@ impl ZoneController {
             }
         };
 
+        let power_bias_pct = config.power_bias_pct;
         {
             let mut state = self.state.lock().await;
             state.active = true;
@@ -182,16 +216,20 @@ impl ZoneController {
             state.last_hr_seen = Some(Instant::now());
             state.last_power_seen = Some(Instant::now());
             state.ftp = ftp;
-            state.max_hr = max_hr;
+            state.hr_ceiling = hr_ceiling;
             state.was_above_zone = false;
             state.power_zones = power_zones;
+            state.config = config;
+            state.trainer_lost_since = None;
+            state.pid_debug = None;
         }
 
         // Command trainer to initial power
         {
             let mut dm = device_manager.lock().await;
             if let Some(trainer_id) = dm.connected_trainer_id() {
-                if let Err(e) = dm.set_target_power(&trainer_id, initial_power as i16).await {
+                let trainer_watts = apply_power_bias(initial_power, power_bias_pct);
+                if let Err(e) = dm.set_target_power(&trainer_id, trainer_watts as i16).await {
                     warn!("Initial trainer power command failed: {}", e);
                 }
             }
@@ -231,6 +269,7 @@ impl ZoneController {
             device_manager,
             sensor_tx,
             sensor_rx,
+            trainer_rx,
             shutdown_rx,
         ));
         self.task_handle = Some(handle);
@@ -280,6 +319,33 @@ impl ZoneController {
         }
     }
 
+    /// Change the power-match bias of a running loop and re-send the current
+    /// target to the trainer with it applied.
+    pub async fn set_power_bias(&self, bias_pct: i8, device_manager: &Arc<Mutex<DeviceManager>>) {
+        let mut state = self.state.lock().await;
+        state.config.power_bias_pct = bias_pct;
+        if !state.active || state.paused {
+            return;
+        }
+        let trainer_watts = apply_power_bias(state.commanded_power, bias_pct);
+        drop(state);
+        let mut dm = device_manager.lock().await;
+        if let Some(trainer_id) = dm.connected_trainer_id() {
+            if let Err(e) = dm.set_target_power(&trainer_id, trainer_watts as i16).await {
+                warn!("Power bias trainer command failed: {}", e);
+            }
+        }
+    }
+
+    /// Turn per-tick PID reporting in `status` on or off.
+    pub async fn set_pid_debug(&self, enabled: bool) {
+        let mut state = self.state.lock().await;
+        state.pid_debug_enabled = enabled;
+        if !enabled {
+            state.pid_debug = None;
+        }
+    }
+
     pub async fn status(&self) -> ZoneControlStatus {
         let state = self.state.lock().await;
         ZoneControlStatus {
@@ -299,6 +365,7 @@ impl ZoneController {
             paused: state.paused,
             phase: state.phase.clone(),
             safety_note: state.safety_note.clone(),
+            pid_debug: state.pid_debug.clone(),
         }
     }
 }
@@ -310,16 +377,19 @@ fn now_epoch_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// Send `watts` (scaled by the power-match bias) to the trainer and log the
+/// unbiased target.
 async fn command_trainer(
     device_manager: &Arc<Mutex<DeviceManager>>,
     watts: u16,
+    bias_pct: i8,
     sensor_tx: &broadcast::Sender<SensorReading>,
 ) -> Result<(), AppError> {
     let mut dm = device_manager.lock().await;
     let trainer_id = dm
         .connected_trainer_id()
         .ok_or_else(|| AppError::Session("Trainer disconnected".into()))?;
-    dm.set_target_power(&trainer_id, watts as i16).await?;
+    dm.set_target_power(&trainer_id, apply_power_bias(watts, bias_pct) as i16).await?;
     drop(dm);
 
     let _ = sensor_tx.send(SensorReading::TrainerCommand {
@@ -336,6 +406,7 @@ async fn control_loop(
     device_manager: Arc<Mutex<DeviceManager>>,
     sensor_tx: broadcast::Sender<SensorReading>,
     mut sensor_rx: broadcast::Receiver<SensorReading>,
+    mut trainer_rx: broadcast::Receiver<String>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
     let tick_interval = match target.mode {
@@ -389,6 +460,9 @@ async fn control_loop(
                     Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
+            Ok(trainer_id) = trainer_rx.recv() => {
+                resume_after_reconnect(&state, &trainer_id, &device_manager, &sensor_tx).await;
+            }
             _ = tick.tick() => {
                 let should_stop = process_tick(
                     &state,
@@ -406,6 +480,27 @@ async fn control_loop(
     }
 }
 
+/// Re-command the last commanded power to a trainer the reconnect engine restored.
+async fn resume_after_reconnect(
+    state: &Arc<Mutex<ControlLoopState>>,
+    trainer_id: &str,
+    device_manager: &Arc<Mutex<DeviceManager>>,
+    sensor_tx: &broadcast::Sender<SensorReading>,
+) {
+    let (watts, bias_pct) = {
+        let mut s = state.lock().await;
+        let Some(watts) = s.trainer_restored() else {
+            return;
+        };
+        (watts, s.config.power_bias_pct)
+    };
+    info!("[{}] Trainer reconnected — resuming zone control at {}W", trainer_id, watts);
+    if let Err(e) = command_trainer(device_manager, watts, bias_pct, sensor_tx).await {
+        warn!("Zone control resume command failed: {}", e);
+        state.lock().await.mark_trainer_lost();
+    }
+}
+
 async fn process_tick(
     state: &Arc<Mutex<ControlLoopState>>,
     target: &ZoneTarget,
@@ -428,20 +523,30 @@ async fn process_tick(
         .unwrap_or(0);
     s.last_tick_at = Some(now);
 
-    // === Safety: cadence zero for >CADENCE_ZERO_SECS → command 0W ===
+    // === Trainer dropped: hold until it reconnects or the grace period runs out ===
+    if let Some(lost_since) = s.trainer_lost_since {
+        if lost_since.elapsed().as_secs() >= config::TRAINER_RECONNECT_GRACE_SECS {
+            warn!("Trainer did not reconnect — stopping zone control");
+            s.stop_reason = Some(StopReason::TrainerDisconnected);
+            s.active = false;
+            return true;
+        }
+        return false;
+    }
+
+    // === Safety: cadence zero for >cadence_zero_secs → command 0W ===
     if let Some(zero_since) = s.last_cadence_zero_since {
-        if zero_since.elapsed().as_secs() >= CADENCE_ZERO_SECS {
+        if zero_since.elapsed().as_secs() >= s.config.cadence_zero_secs {
             if s.commanded_power != 0 {
-                warn!("Cadence zero for >{}s — reducing power to 0W", CADENCE_ZERO_SECS);
+                warn!("Cadence zero for >{}s — reducing power to 0W", s.config.cadence_zero_secs);
                 s.commanded_power = 0;
                 s.safety_note = Some("Cadence zero — power reduced".to_string());
+                let bias_pct = s.config.power_bias_pct;
                 drop(s);
-                if command_trainer(device_manager, 0, sensor_tx).await.is_err() {
-                    warn!("Trainer disconnected during cadence-zero safety command");
-                    let mut s = state.lock().await;
-                    s.stop_reason = Some(StopReason::TrainerDisconnected);
-                    s.active = false;
-                    return true;
+                if command_trainer(device_manager, 0, bias_pct, sensor_tx).await.is_err() {
+                    warn!("Trainer disconnected during cadence-zero safety command — waiting for reconnect");
+                    state.lock().await.mark_trainer_lost();
+                    return false;
                 }
                 return false;
             }
@@ -451,26 +556,26 @@ async fn process_tick(
 
     // === Safety: HR ceiling (HR mode) ===
     if target.mode == ZoneMode::HeartRate {
-        if let Some(max_hr) = s.max_hr {
+        if let Some(ceiling) = s.hr_ceiling {
             if let Some(hr) = s.last_hr {
-                if hr > max_hr {
+                if hr > ceiling {
+                    let safety_power = s.config.safety_power;
                     warn!(
-                        "HR ceiling exceeded: {} bpm > {} max — reducing to {}W",
-                        hr, max_hr, SAFETY_POWER
+                        "HR ceiling exceeded: {} bpm > {} ceiling — reducing to {}W",
+                        hr, ceiling, safety_power
                     );
-                    s.commanded_power = SAFETY_POWER;
+                    s.commanded_power = safety_power;
                     s.safety_note = Some("HR ceiling exceeded".to_string());
                     s.phase = "adjusting".to_string();
+                    let bias_pct = s.config.power_bias_pct;
                     drop(s);
-                    if command_trainer(device_manager, SAFETY_POWER, sensor_tx)
+                    if command_trainer(device_manager, safety_power, bias_pct, sensor_tx)
                         .await
                         .is_err()
                     {
-                        warn!("Trainer disconnected during HR ceiling safety command");
-                        let mut s = state.lock().await;
-                        s.stop_reason = Some(StopReason::TrainerDisconnected);
-                        s.active = false;
-                        return true;
+                        warn!("Trainer disconnected during HR ceiling safety command — waiting for reconnect");
+                        state.lock().await.mark_trainer_lost();
+                        return false;
                     }
                     return false;
                 }
@@ -482,13 +587,13 @@ async fn process_tick(
             .last_hr_seen
             .map(|t| t.elapsed().as_secs())
             .unwrap_or(u64::MAX);
-        if hr_lost_secs >= HR_SENSOR_STOP_SECS {
+        if hr_lost_secs >= s.config.hr_sensor_stop_secs {
             warn!("HR sensor lost for {}s — stopping zone control", hr_lost_secs);
             s.stop_reason = Some(StopReason::SensorLost);
             s.safety_note = Some("HR sensor lost".to_string());
             s.active = false;
             return true;
-        } else if hr_lost_secs >= HR_SENSOR_WARN_SECS {
+        } else if hr_lost_secs >= s.config.hr_sensor_warn_secs {
             warn!("HR sensor not responding for {}s — holding power", hr_lost_secs);
             s.safety_note = Some("HR sensor not responding — holding power".to_string());
             // Hold current power, don't adjust
@@ -502,7 +607,7 @@ async fn process_tick(
             .last_power_seen
             .map(|t| t.elapsed().as_secs())
             .unwrap_or(u64::MAX);
-        if power_lost_secs >= POWER_SENSOR_WARN_SECS {
+        if power_lost_secs >= s.config.power_sensor_warn_secs {
             warn!("Power sensor not responding for {}s", power_lost_secs);
             s.safety_note = Some("Power sensor not responding".to_string());
             // Continue — trainer ERG still works
@@ -528,16 +633,15 @@ async fn process_tick(
             let new_power = process_hr_tick(&mut s, target, pid, hr_smoother, tick_ms);
             if let Some(watts) = new_power {
                 s.commanded_power = watts;
+                let bias_pct = s.config.power_bias_pct;
                 drop(s);
-                if command_trainer(device_manager, watts, sensor_tx)
+                if command_trainer(device_manager, watts, bias_pct, sensor_tx)
                     .await
                     .is_err()
                 {
-                    warn!("Trainer disconnected during HR mode power command");
-                    let mut s = state.lock().await;
-                    s.stop_reason = Some(StopReason::TrainerDisconnected);
-                    s.active = false;
-                    return true;
+                    warn!("Trainer disconnected during HR mode power command — waiting for reconnect");
+                    state.lock().await.mark_trainer_lost();
+                    return false;
                 }
             }
         }
@@ -561,6 +665,35 @@ fn process_power_tick(s: &mut ControlLoopState, target: &ZoneTarget, tick_ms: u6
     }
 }
 
+/// HR-mode power band (floor, ceiling) from the HR zone number and power zone
+/// config. The floor can exceed the ceiling for a high `min_power` and low FTP.
+fn hr_power_band(
+    ftp: Option<u16>,
+    power_zones: Option<[u16; 6]>,
+    zone: u8,
+    min_power: u16,
+) -> (u16, u16) {
+    match (ftp, power_zones) {
+        (Some(ftp), Some(pz)) => {
+            // Floor: one power zone below (zone-2 index, or min_power for zone 1)
+            let floor = if zone >= 2 {
+                let floor_idx = ((zone - 2) as usize).min(5);
+                (ftp as f64 * pz[floor_idx] as f64 / 100.0) as u16
+            } else {
+                min_power
+            };
+            // Ceiling: one power zone above (zone index, capped at array length)
+            let ceil_idx = (zone as usize).min(5);
+            let ceiling = (ftp as f64 * pz[ceil_idx] as f64 / 100.0) as u16;
+            (floor.max(min_power), ceiling)
+        }
+        _ => (
+            min_power,
+            ftp.map(|f| (f as f64 * 1.5) as u16).unwrap_or(FALLBACK_POWER_CEILING),
+        ),
+    }
+}
+
 /// HR mode tick: uses PID controller with adaptive gains to adjust power.
 /// Returns Some(new_watts) if power should be changed, None to hold.
 fn process_hr_tick(
@@ -604,37 +737,32 @@ fn process_hr_tick(
     let dt_secs = tick_ms as f64 / 1000.0;
     let watts_adjustment = pid.update(error, dt_secs);
 
-    // Derive power band from HR zone number and power zone config
-    let (power_floor, power_ceiling) = match (s.ftp, s.power_zones) {
-        (Some(ftp), Some(pz)) => {
-            let zone = target.zone;
-            // Floor: one power zone below (zone-2 index, or MIN_POWER for zone 1)
-            let floor = if zone >= 2 {
-                (ftp as f64 * pz[(zone - 2) as usize] as f64 / 100.0) as u16
-            } else {
-                MIN_POWER
-            };
-            // Ceiling: one power zone above (zone index, capped at array length)
-            let ceil_idx = (zone as usize).min(5);
-            let ceiling = (ftp as f64 * pz[ceil_idx] as f64 / 100.0) as u16;
-            (floor.max(MIN_POWER), ceiling)
-        }
-        _ => (MIN_POWER, s.ftp.map(|f| (f as f64 * 1.5) as u16).unwrap_or(400)),
-    };
+    let (power_floor, power_ceiling) =
+        hr_power_band(s.ftp, s.power_zones, target.zone, s.config.min_power);
 
     // Rate limit: asymmetric — ramp down faster than up, with faster recovery when below band
     let band_midpoint = (power_floor + power_ceiling) / 2;
     let max_up = if error > 0.0 && s.commanded_power < band_midpoint.saturating_sub(20) {
-        HR_MAX_WATTS_UP_PER_TICK * 2.0 // 20W/tick during recovery
+        s.config.max_watts_up_per_tick * 2.0 // 20W/tick by default during recovery
     } else {
-        HR_MAX_WATTS_UP_PER_TICK // 10W/tick normal
+        s.config.max_watts_up_per_tick // 10W/tick by default
     };
     let clamped_adjustment = if watts_adjustment < 0.0 {
-        watts_adjustment.max(-HR_MAX_WATTS_DOWN_PER_TICK)
+        watts_adjustment.max(-s.config.max_watts_down_per_tick)
     } else {
         watts_adjustment.min(max_up)
     };
 
+    let new_power_f = s.commanded_power as f64 + clamped_adjustment;
+
+    // Conditional integration: don't accumulate error the trainer can't act on
+    // because the rate limiter or the power band already capped the change.
+    let rate_limited = clamped_adjustment != watts_adjustment;
+    let band_limited = new_power_f > power_ceiling as f64 || new_power_f < power_floor as f64;
+    if rate_limited || band_limited {
+        pid.hold_integral();
+    }
+
     // Decay integral when HR is above zone but already falling
     if error < 0.0 {
         if let Some(prev_hr) = s.last_hr {
@@ -644,10 +772,23 @@ fn process_hr_tick(
         }
     }
 
-    let new_power_f = s.commanded_power as f64 + clamped_adjustment;
-
     // Clamp to power band [power_floor, power_ceiling]
-    let new_power = (new_power_f as u16).clamp(power_floor, power_ceiling);
+    let new_power = (new_power_f as u16).clamp(power_floor.min(power_ceiling), power_ceiling);
+
+    if s.pid_debug_enabled {
+        let terms = pid.last_terms();
+        s.pid_debug = Some(PidDebug {
+            smoothed_hr,
+            error,
+            p: terms.p,
+            i: terms.i,
+            d: terms.d,
+            unclamped_output: terms.output,
+            clamped_adjustment,
+            commanded_power: new_power,
+            actual_power: s.last_power,
+        });
+    }
 
     if new_power != s.commanded_power {
         debug!(
@@ -664,7 +805,7 @@ fn process_hr_tick(
 mod tests {
     use super::*;
     use super::super::pid::{HrSmoother, PidController};
-    use super::super::types::{ZoneMode, ZoneTarget};
+    use super::super::types::{ZoneControlConfig, ZoneMode, ZoneTarget};
 
     fn assert_approx(actual: f64, expected: f64, epsilon: f64, msg: &str) {
         assert!(
@@ -716,6 +857,60 @@ mod tests {
         assert!(drop > 10, "ramp-down should exceed old 10W limit, got {drop}W drop");
     }
 
+    #[test]
+    fn integral_held_while_rate_limited() {
+        // HR=120 (15 below midpoint) at 250W: PID asks for far more than the
+        // 10W/tick limit, so a sustained minute of ticks must not wind up.
+        let target = hr_target();
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let mut smoother = HrSmoother::new(5);
+        fill_smoother(&mut smoother, 120);
+        let mut s = make_state(250, None);
+
+        for _ in 0..12 {
+            process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        }
+        assert_approx(pid.integral(), 0.0, 0.01, "integral held while saturated");
+    }
+
+    #[test]
+    fn custom_ramp_up_rate_changes_per_tick_clamp() {
+        // Same setup as ramp_up_still_limited_to_10w but with a 4W/tick limit.
+        let target = hr_target();
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let mut smoother = HrSmoother::new(5);
+        fill_smoother(&mut smoother, 120);
+        let mut s = make_state(250, None);
+        s.config.max_watts_up_per_tick = 4.0;
+
+        let new = process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        assert_eq!(new, Some(254), "ramp-up should follow configured 4W/tick limit");
+    }
+
+    #[test]
+    fn zone_control_config_validation() {
+        assert!(ZoneControlConfig::default().validate().is_ok());
+        let bad_rate = ZoneControlConfig { max_watts_up_per_tick: 0.0, ..Default::default() };
+        assert!(bad_rate.validate().is_err());
+        let bad_floor = ZoneControlConfig { min_power: 400, ..Default::default() };
+        assert!(bad_floor.validate().is_err());
+        let bad_timeouts = ZoneControlConfig { hr_sensor_warn_secs: 30, ..Default::default() };
+        assert!(bad_timeouts.validate().is_err());
+        let bad_reserve = ZoneControlConfig { hr_reserve_ceiling_pct: 120, ..Default::default() };
+        assert!(bad_reserve.validate().is_err());
+        let bad_bias = ZoneControlConfig { power_bias_pct: i8::MIN, ..Default::default() };
+        assert!(bad_bias.validate().is_err());
+    }
+
+    #[test]
+    fn hr_ceiling_uses_reserve_when_resting_hr_known() {
+        let config = ZoneControlConfig::default();
+        // 50 + 0.95 × (190 − 50) = 183
+        assert_eq!(config.hr_ceiling(Some(50), Some(190)), Some(183));
+        assert_eq!(config.hr_ceiling(None, Some(190)), Some(190));
+        assert_eq!(config.hr_ceiling(Some(50), None), None);
+    }
+
     #[test]
     fn ramp_up_still_limited_to_10w() {
         // HR=120 (15 below midpoint 135) → error=+15, PID wants large positive.
@@ -761,25 +956,27 @@ mod tests {
 
     #[test]
     fn no_integral_decay_when_hr_below_zone() {
-        // HR=120 → error=+15 (positive), no decay should happen.
+        // HR=128 → error=+7 (positive), no decay should happen.
+        // 1s ticks keep the adjustment under the 20W recovery rate limit, so
+        // the integral isn't held for saturation.
         let target = hr_target();
         let mut pid = PidController::new(2.0, 0.1, 0.5);
         let mut smoother = HrSmoother::new(5);
 
         // First tick to establish integral
-        fill_smoother(&mut smoother, 120);
+        fill_smoother(&mut smoother, 128);
         let mut s = make_state(100, None);
-        process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
         let integral_after_first = pid.integral();
 
-        // Second tick: HR still below zone, last_hr=125 (falling but error > 0)
-        fill_smoother(&mut smoother, 120);
-        s.last_hr = Some(125);
-        process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        // Second tick: HR still below zone, last_hr=130 (falling but error > 0)
+        fill_smoother(&mut smoother, 128);
+        s.last_hr = Some(130);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
         let integral_after_second = pid.integral();
 
-        // Integral should keep growing (no decay), approximately first + 15*5
-        let expected_no_decay = integral_after_first + 15.0 * 5.0;
+        // Integral should keep growing (no decay), approximately first + 7*1
+        let expected_no_decay = integral_after_first + 7.0;
         assert_approx(
             integral_after_second,
             expected_no_decay,
@@ -894,20 +1091,47 @@ mod tests {
         let mut pid = PidController::new(2.0, 0.1, 0.5);
         let mut smoother = HrSmoother::new(5);
 
-        // First tick: HR=130 (below zone, lower_bound=139)
-        fill_smoother(&mut smoother, 130);
-        let mut s = make_zone2_state(150, None);
-        process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        // First tick: HR=138 (below zone, lower_bound=139). 1s tick from 115W
+        // stays under the 20W recovery rate limit, so the integral accumulates.
+        fill_smoother(&mut smoother, 138);
+        let mut s = make_zone2_state(115, None);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
         let integral_after_first = pid.integral();
         assert!(integral_after_first > 0.0, "integral should be positive when HR below target");
 
         // Second tick: HR=145 (in zone). Coming from below, not above → no reset.
         fill_smoother(&mut smoother, 145);
-        process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
         // Integral should have continued accumulating (not reset to 0)
         assert!(pid.integral() != 0.0, "integral should not reset on below→in-zone transition");
     }
 
+    #[test]
+    fn pid_debug_terms_sum_to_unclamped_output() {
+        let target = hr_target();
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let mut smoother = HrSmoother::new(5);
+        let mut s = make_state(150, None);
+
+        fill_smoother(&mut smoother, 125);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
+        assert!(s.pid_debug.is_none(), "debug off by default");
+
+        s.pid_debug_enabled = true;
+        s.last_power = Some(148);
+        fill_smoother(&mut smoother, 110);
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 1000);
+
+        let debug = s.pid_debug.clone().expect("debug populated in HR mode");
+        assert_eq!(debug.smoothed_hr, 110);
+        assert_approx(debug.error, 25.0, 1e-9, "error");
+        assert_approx(debug.p + debug.i + debug.d, debug.unclamped_output, 1e-9, "term sum");
+        // 25 bpm short with a derivative kick: past the 20W recovery ramp limit
+        assert!(debug.unclamped_output > debug.clamped_adjustment);
+        assert_approx(debug.clamped_adjustment, 20.0, 1e-9, "rate-limited adjustment");
+        assert_eq!(debug.actual_power, Some(148));
+    }
+
     #[test]
     fn power_band_clamps_floor() {
         // FTP=200, zone 2, power_zones=[55,75,90,105,120,150]
@@ -960,6 +1184,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn min_power_above_band_ceiling_does_not_panic() {
+        // FTP=100, zone 2: ceiling = 100 * 90% = 90W, below a 120W min_power
+        let target = hr_zone2_target();
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let mut smoother = HrSmoother::new(5);
+        fill_smoother(&mut smoother, 120);
+        let mut s = make_zone2_state(90, None);
+        s.ftp = Some(100);
+        s.config.min_power = 120;
+
+        process_hr_tick(&mut s, &target, &mut pid, &smoother, 5000);
+        assert_eq!(hr_power_band(Some(100), s.power_zones, 2, 120), (120, 90));
+    }
+
     #[test]
     fn faster_ramp_up_when_below_band_midpoint() {
         // FTP=200, zone 2: floor=110, ceiling=180, midpoint=145.
@@ -999,4 +1238,106 @@ mod tests {
             "normal ramp-up should stay <= 10W/tick, got {gain}W gain"
         );
     }
+
+    fn hr_tick_state() -> Arc<Mutex<ControlLoopState>> {
+        let mut s = make_state(180, None);
+        s.last_hr_seen = Some(Instant::now());
+        s.last_tick_at = Some(Instant::now() - std::time::Duration::from_secs(5));
+        Arc::new(Mutex::new(s))
+    }
+
+    #[tokio::test]
+    async fn trainer_drop_holds_loop_until_reconnect_then_recommands_last_power() {
+        let state = hr_tick_state();
+        // No trainer in the manager: every command fails as if it just dropped
+        let dm = Arc::new(Mutex::new(DeviceManager::new()));
+        let (tx, _rx) = broadcast::channel(16);
+        let target = hr_target();
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let mut smoother = HrSmoother::new(5);
+        fill_smoother(&mut smoother, 120);
+
+        // HR below zone → the loop raises power, the command fails, and the loop holds
+        assert!(!process_tick(&state, &target, &dm, &tx, &mut pid, &smoother).await);
+        let held = {
+            let s = state.lock().await;
+            assert!(s.active, "loop must not stop on a trainer drop");
+            assert!(s.trainer_lost_since.is_some());
+            assert_eq!(s.phase, "reconnecting");
+            assert!(s.commanded_power > 180, "got {}", s.commanded_power);
+            s.commanded_power
+        };
+
+        // Further ticks hold without adjusting power
+        fill_smoother(&mut smoother, 110);
+        assert!(!process_tick(&state, &target, &dm, &tx, &mut pid, &smoother).await);
+        assert_eq!(state.lock().await.commanded_power, held);
+
+        // Reconnect: the loop resumes and re-commands the power it last sent
+        let mut s = state.lock().await;
+        assert_eq!(s.trainer_restored(), Some(held));
+        assert!(s.active);
+        assert!(s.trainer_lost_since.is_none());
+        assert!(s.safety_note.is_none());
+        assert_eq!(s.phase, "adjusting");
+    }
+
+    #[tokio::test]
+    async fn trainer_not_back_within_grace_stops_loop() {
+        let state = hr_tick_state();
+        let dm = Arc::new(Mutex::new(DeviceManager::new()));
+        let (tx, _rx) = broadcast::channel(16);
+        let mut pid = PidController::new(2.0, 0.1, 0.5);
+        let smoother = HrSmoother::new(5);
+        state.lock().await.trainer_lost_since = Some(
+            Instant::now()
+                - std::time::Duration::from_secs(config::TRAINER_RECONNECT_GRACE_SECS),
+        );
+
+        assert!(process_tick(&state, &hr_target(), &dm, &tx, &mut pid, &smoother).await);
+        let mut s = state.lock().await;
+        assert!(!s.active);
+        assert!(matches!(s.stop_reason, Some(StopReason::TrainerDisconnected)));
+        assert_eq!(s.trainer_restored(), None);
+    }
+
+    /// Target of the next TrainerCommand broadcast, waiting at most a second.
+    async fn next_trainer_command(rx: &mut broadcast::Receiver<SensorReading>) -> u16 {
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            loop {
+                if let SensorReading::TrainerCommand { target_watts, .. } = rx.recv().await.unwrap() {
+                    return target_watts;
+                }
+            }
+        })
+        .await
+        .expect("no trainer command was sent")
+    }
+
+    #[tokio::test]
+    async fn trainer_reconnect_resends_commanded_power() {
+        let dm = Arc::new(Mutex::new(DeviceManager::new()));
+        let commands = dm.lock().await.connect_mock_trainer("trainer");
+        let (tx, mut rx) = broadcast::channel(64);
+        let mut controller = ZoneController::new();
+        controller
+            .start_with_config(
+                hr_target(),
+                dm.clone(),
+                tx,
+                Some(200),
+                None,
+                Some(150),
+                None,
+                ZoneControlConfig::default(),
+            )
+            .await
+            .unwrap();
+        let initial = next_trainer_command(&mut rx).await;
+
+        dm.lock().await.announce_trainer_reconnect("trainer");
+        assert_eq!(next_trainer_command(&mut rx).await, initial);
+        assert_eq!(*commands.lock().unwrap(), vec![initial as i16, initial as i16]);
+        controller.stop().await;
+    }
 }
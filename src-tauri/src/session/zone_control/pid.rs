@@ -1,11 +1,24 @@
+/// The individual terms of the most recent `update`, for tuning.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PidTerms {
+    pub p: f64,
+    pub i: f64,
+    pub d: f64,
+    /// p + i + d, before the output limit is applied
+    pub output: f64,
+}
+
 pub struct PidController {
     kp: f64,
     ki: f64,
     kd: f64,
     integral: f64,
+    /// Integral before the most recent update, restored by `hold_integral`
+    prev_integral: f64,
     prev_error: Option<f64>,
     integral_limit: f64,
     output_limit: f64,
+    last_terms: PidTerms,
 }
 
 impl PidController {
@@ -19,9 +32,11 @@ impl PidController {
             ki,
             kd,
             integral: 0.0,
+            prev_integral: 0.0,
             prev_error: None,
             integral_limit,
             output_limit,
+            last_terms: PidTerms::default(),
         }
     }
 
@@ -30,8 +45,10 @@ impl PidController {
         let p = self.kp * error;
 
         // Integral with anti-windup
+        self.prev_integral = self.integral;
         self.integral += error * dt_secs;
-        self.integral = self.integral.clamp(-self.integral_limit, self.integral_limit);
+        let bound = self.integral_bound();
+        self.integral = self.integral.clamp(-bound, bound);
         let i = self.ki * self.integral;
 
         // Derivative
@@ -42,15 +59,38 @@ impl PidController {
         self.prev_error = Some(error);
 
         let output = p + i + d;
+        self.last_terms = PidTerms { p, i, d, output };
         output.clamp(-self.output_limit, self.output_limit)
     }
 
+    /// Integral clamp: the configured limit, tightened so the I term alone can
+    /// never exceed the output limit. Follows the current ki, so it tracks
+    /// adaptive gain changes.
+    fn integral_bound(&self) -> f64 {
+        if self.ki > 0.0 {
+            self.integral_limit.min(self.output_limit / self.ki)
+        } else {
+            self.integral_limit
+        }
+    }
+
+    /// Undo the integration from the most recent `update`. Call when the output
+    /// was saturated downstream (rate limiter or power band), so error the
+    /// actuator couldn't act on doesn't wind up the integral.
+    pub fn hold_integral(&mut self) {
+        self.integral = self.prev_integral;
+    }
+
     pub fn set_gains(&mut self, kp: f64, ki: f64, kd: f64) {
         self.kp = kp;
         self.ki = ki;
         self.kd = kd;
     }
 
+    pub fn last_terms(&self) -> PidTerms {
+        self.last_terms
+    }
+
     pub fn integral(&self) -> f64 {
         self.integral
     }
@@ -158,6 +198,50 @@ mod tests {
         assert_approx(out, 200.0, 0.01, "anti-windup clamps integral");
     }
 
+    #[test]
+    fn integral_bounded_by_output_limit_over_ki() {
+        // integral_limit=1000 but output_limit/ki = 30/0.1 = 300 is tighter
+        let mut pid = PidController::with_limits(2.0, 0.1, 0.0, 1000.0, 30.0);
+        for _ in 0..60 {
+            pid.update(50.0, 1.0);
+            assert!(pid.integral() <= 300.0 + 1e-9, "integral {} exceeds clamp", pid.integral());
+        }
+        assert_approx(pid.integral(), 300.0, 0.01, "integral saturates at clamp");
+
+        // Error reversal: I term is at most 30W, so a -10 error (P = -20)
+        // flips the output negative within ~10 ticks instead of hundreds.
+        let mut ticks = 0;
+        while pid.update(-10.0, 1.0) >= 0.0 {
+            ticks += 1;
+            assert!(ticks <= 11, "output should turn negative quickly after reversal");
+        }
+    }
+
+    #[test]
+    fn hold_integral_stops_windup_while_saturated() {
+        // Simulate a 10W downstream rate limiter: whenever the PID asks for
+        // more than the limiter allows, the integral is held.
+        let mut held = PidController::with_limits(2.0, 0.1, 0.0, 1000.0, 30.0);
+        let mut free = PidController::with_limits(2.0, 0.1, 0.0, 1000.0, 30.0);
+        for _ in 0..60 {
+            if held.update(50.0, 1.0) > 10.0 {
+                held.hold_integral();
+            }
+            free.update(50.0, 1.0);
+        }
+        assert_approx(held.integral(), 0.0, 0.01, "held integral never accumulates");
+        assert!(free.integral() > held.integral());
+
+        let settle = |pid: &mut PidController| {
+            let mut ticks = 0;
+            while pid.update(-10.0, 1.0) >= 0.0 {
+                ticks += 1;
+            }
+            ticks
+        };
+        assert!(settle(&mut held) < settle(&mut free), "held controller settles faster");
+    }
+
     #[test]
     fn derivative_responds_to_error_change() {
         // D-only: first tick error=10, second tick error=5, kd=1.0, dt=5
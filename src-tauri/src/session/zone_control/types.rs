@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::AppError;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ZoneMode {
     Power,
@@ -29,6 +31,24 @@ pub struct ZoneControlStatus {
     pub paused: bool,
     pub phase: String,
     pub safety_note: Option<String>,
+    /// Controller internals from the last HR-mode tick, when debugging is on
+    pub pid_debug: Option<PidDebug>,
+}
+
+/// One HR-mode tick of the PID controller, for live tuning.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PidDebug {
+    pub smoothed_hr: u8,
+    pub error: f64,
+    pub p: f64,
+    pub i: f64,
+    pub d: f64,
+    /// p + i + d before any clamping
+    pub unclamped_output: f64,
+    /// Watts change after the output and rate limits
+    pub clamped_adjustment: f64,
+    pub commanded_power: u16,
+    pub actual_power: Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,3 +59,123 @@ pub enum StopReason {
     TrainerDisconnected,
     SensorLost,
 }
+
+/// HR-mode power ceiling when FTP is unknown; `min_power` must stay below it.
+pub const FALLBACK_POWER_CEILING: u16 = 400;
+
+/// Largest ERG power-match bias either way, in percent.
+pub const MAX_POWER_BIAS_PCT: i8 = 15;
+
+/// Scale a target by the power-match bias to get the watts sent to the trainer.
+/// Only the trainer command is biased; recorded targets stay at `watts`.
+pub fn apply_power_bias(watts: u16, bias_pct: i8) -> u16 {
+    let bias = bias_pct.clamp(-MAX_POWER_BIAS_PCT, MAX_POWER_BIAS_PCT) as f64;
+    (watts as f64 * (1.0 + bias / 100.0)).round() as u16
+}
+
+/// Tunable limits for the zone control loop. Defaults match the values the
+/// controller was originally tuned with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneControlConfig {
+    /// Maximum watts per tick when ramping up in HR mode (doubled during recovery)
+    pub max_watts_up_per_tick: f64,
+    /// Maximum watts per tick when ramping down in HR mode
+    pub max_watts_down_per_tick: f64,
+    /// Minimum commanded power (watts)
+    pub min_power: u16,
+    /// Power commanded when the HR ceiling is exceeded (watts)
+    pub safety_power: u16,
+    /// Hold power once the HR sensor has been silent this long (seconds)
+    pub hr_sensor_warn_secs: u64,
+    /// Stop zone control once the HR sensor has been silent this long (seconds)
+    pub hr_sensor_stop_secs: u64,
+    /// Warn once the power sensor has been silent this long (seconds)
+    pub power_sensor_warn_secs: u64,
+    /// Drop to 0W after cadence has been zero this long (seconds)
+    pub cadence_zero_secs: u64,
+    /// ERG power-match bias (percent) applied to trainer commands, for trainers
+    /// that read high or low against the power meter. Owned by `set_power_bias`;
+    /// saving the rest of the config keeps the stored value.
+    #[serde(default)]
+    pub power_bias_pct: i8,
+    /// HR ceiling as a percentage of heart rate reserve, used when both
+    /// resting and max HR are known
+    #[serde(default = "default_hr_reserve_ceiling_pct")]
+    pub hr_reserve_ceiling_pct: u8,
+}
+
+fn default_hr_reserve_ceiling_pct() -> u8 {
+    95
+}
+
+impl Default for ZoneControlConfig {
+    fn default() -> Self {
+        Self {
+            max_watts_up_per_tick: 10.0,
+            max_watts_down_per_tick: 30.0,
+            min_power: 50,
+            safety_power: 50,
+            hr_sensor_warn_secs: 15,
+            hr_sensor_stop_secs: 30,
+            power_sensor_warn_secs: 15,
+            cadence_zero_secs: 3,
+            power_bias_pct: 0,
+            hr_reserve_ceiling_pct: default_hr_reserve_ceiling_pct(),
+        }
+    }
+}
+
+impl ZoneControlConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        for (name, rate) in [
+            ("Ramp-up rate", self.max_watts_up_per_tick),
+            ("Ramp-down rate", self.max_watts_down_per_tick),
+        ] {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(AppError::Session(format!("{} must be positive, got {}", name, rate)));
+            }
+        }
+        if self.min_power >= FALLBACK_POWER_CEILING {
+            return Err(AppError::Session(format!(
+                "Minimum power {}W must be below {}W",
+                self.min_power, FALLBACK_POWER_CEILING
+            )));
+        }
+        if self.hr_sensor_warn_secs == 0 || self.power_sensor_warn_secs == 0 || self.cadence_zero_secs == 0 {
+            return Err(AppError::Session("Sensor timeouts must be positive".into()));
+        }
+        if self.hr_sensor_warn_secs >= self.hr_sensor_stop_secs {
+            return Err(AppError::Session(format!(
+                "HR sensor warning ({}s) must come before stop ({}s)",
+                self.hr_sensor_warn_secs, self.hr_sensor_stop_secs
+            )));
+        }
+        if self.power_bias_pct.unsigned_abs() > MAX_POWER_BIAS_PCT.unsigned_abs() {
+            return Err(AppError::Session(format!(
+                "Power bias {}% must be within ±{}%",
+                self.power_bias_pct, MAX_POWER_BIAS_PCT
+            )));
+        }
+        if !(50..=100).contains(&self.hr_reserve_ceiling_pct) {
+            return Err(AppError::Session(format!(
+                "HR reserve ceiling {}% must be between 50% and 100%",
+                self.hr_reserve_ceiling_pct
+            )));
+        }
+        Ok(())
+    }
+
+    /// HR safety ceiling (bpm) for the rider's profile: a share of heart rate
+    /// reserve when resting HR is known, otherwise the absolute max HR.
+    pub fn hr_ceiling(&self, resting_hr: Option<u8>, max_hr: Option<u8>) -> Option<u8> {
+        let max_hr = max_hr?;
+        match resting_hr {
+            Some(resting) if resting < max_hr => {
+                let reserve = (max_hr - resting) as f64;
+                let ceiling = resting as f64 + reserve * self.hr_reserve_ceiling_pct as f64 / 100.0;
+                Some(ceiling.round() as u8)
+            }
+            _ => Some(max_hr),
+        }
+    }
+}
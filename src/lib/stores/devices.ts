@@ -1,5 +1,5 @@
-import { writable, derived } from 'svelte/store';
-import type { DeviceInfo } from '$lib/tauri';
+import { writable, derived, get } from 'svelte/store';
+import type { DeviceBatteryLow, DeviceInfo, DeviceSignalWeak, PowerMismatch } from '$lib/tauri';
 import { api } from '$lib/tauri';
 
 export const connectedDevices = writable<DeviceInfo[]>([]);
@@ -128,3 +128,71 @@ export function handleDeviceDisconnected(deviceId: string) {
     return d;
   });
 }
+
+// --- Low battery warnings ---
+
+export interface LowBatteryDevice {
+  device_id: string;
+  device_type: string;
+  level: number;
+}
+
+export const lowBatteryDevices = writable<Record<string, LowBatteryDevice>>({});
+
+export function handleDeviceBatteryLow(payload: DeviceBatteryLow) {
+  const device = get(connectedDevices).find((d) => d.id === payload.device_id);
+  connectedDevices.update((devices) =>
+    devices.map((d) => (d.id === payload.device_id ? { ...d, battery_level: payload.level } : d))
+  );
+  lowBatteryDevices.update((d) => ({
+    ...d,
+    [payload.device_id]: { ...payload, device_type: device?.device_type ?? '' },
+  }));
+}
+
+export function dismissBatteryWarning(deviceId: string) {
+  lowBatteryDevices.update((d) => {
+    const next = { ...d };
+    delete next[deviceId];
+    return next;
+  });
+}
+
+// --- Weak signal warnings ---
+
+export interface WeakSignalDevice {
+  device_id: string;
+  device_type: string;
+  rssi: number;
+}
+
+export const weakSignalDevices = writable<Record<string, WeakSignalDevice>>({});
+
+export function handleDeviceSignalWeak(payload: DeviceSignalWeak) {
+  const device = get(connectedDevices).find((d) => d.id === payload.device_id);
+  weakSignalDevices.update((d) => ({
+    ...d,
+    [payload.device_id]: { ...payload, device_type: device?.device_type ?? '' },
+  }));
+}
+
+export function dismissSignalWarning(deviceId: string) {
+  weakSignalDevices.update((d) => {
+    const next = { ...d };
+    delete next[deviceId];
+    return next;
+  });
+}
+
+// --- Trainer vs power meter mismatch ---
+
+/** Latest sustained disagreement between trainer and power meter, until dismissed. */
+export const powerMismatch = writable<PowerMismatch | null>(null);
+
+export function handlePowerMismatch(payload: PowerMismatch) {
+  powerMismatch.set(payload);
+}
+
+export function dismissPowerMismatch() {
+  powerMismatch.set(null);
+}
@@ -1,6 +1,6 @@
 import { writable, get } from 'svelte/store';
 import { listen } from '@tauri-apps/api/event';
-import type { SensorReading, LiveMetrics } from '$lib/tauri';
+import type { SensorReading, LiveMetrics, RadarThreat, RadarUpdate } from '$lib/tauri';
 import { sessionActive, sessionPaused } from '$lib/stores/session';
 
 export const currentPower = writable<number | null>(null);
@@ -8,6 +8,7 @@ export const currentHR = writable<number | null>(null);
 export const currentCadence = writable<number | null>(null);
 export const currentSpeed = writable<number | null>(null);
 export const liveMetrics = writable<LiveMetrics | null>(null);
+export const radarThreats = writable<RadarThreat[]>([]);
 
 export interface MetricHistoryEntry {
   t: number;
@@ -27,6 +28,7 @@ let latestSpeed: number | null = null;
 
 let unlistenSensor: (() => void) | null = null;
 let unlistenMetrics: (() => void) | null = null;
+let unlistenRadar: (() => void) | null = null;
 let generation = 0;
 
 export async function startSensorListening() {
@@ -34,7 +36,7 @@ export async function startSensorListening() {
 
   const myGen = ++generation;
 
-  const [sensorUn, metricsUn] = await Promise.all([
+  const [sensorUn, metricsUn, radarUn] = await Promise.all([
     listen<SensorReading>('sensor_reading', (event) => {
       const reading = event.payload;
       if (reading.Power) { latestPower = reading.Power.watts; currentPower.set(latestPower); }
@@ -52,6 +54,9 @@ export async function startSensorListening() {
     listen<LiveMetrics>('live_metrics', (event) => {
       liveMetrics.set(event.payload);
     }),
+    listen<RadarUpdate>('radar_update', (event) => {
+      radarThreats.set(event.payload.threats);
+    }),
   ]);
 
   // If stop was called (or another start began) while we were awaiting,
@@ -59,11 +64,13 @@ export async function startSensorListening() {
   if (myGen !== generation) {
     sensorUn();
     metricsUn();
+    radarUn();
     return;
   }
 
   unlistenSensor = sensorUn;
   unlistenMetrics = metricsUn;
+  unlistenRadar = radarUn;
 }
 
 export function stopSensorListening() {
@@ -76,7 +83,12 @@ export function stopSensorListening() {
     unlistenMetrics();
     unlistenMetrics = null;
   }
+  if (unlistenRadar) {
+    unlistenRadar();
+    unlistenRadar = null;
+  }
   liveMetrics.set(null);
+  radarThreats.set([]);
   metricHistory.set([]);
   latestPower = null;
   latestHR = null;
@@ -1,10 +1,14 @@
 import { writable, get } from 'svelte/store';
-import { api, type SessionSummary } from '$lib/tauri';
+import { api, type NewPersonalBests, type SessionSummary } from '$lib/tauri';
 
 export const sessionActive = writable(false);
 export const sessionId = writable<string | null>(null);
 export const sessionPaused = writable(false);
+/** Set while the backend has paused the session for lack of pedalling. */
+export const sessionAutoPaused = writable(false);
 export const dashboardView = writable<'gauges' | 'graphs' | 'retro'>('gauges');
+/** Power records set by the last saved ride, until dismissed. */
+export const newPersonalBests = writable<NewPersonalBests | null>(null);
 
 /** In-flight start promise — ensures only one start request at a time. */
 let startInflight: Promise<string> | null = null;
@@ -30,6 +34,7 @@ export async function requestStart(): Promise<string> {
     sessionId.set(newId);
     sessionActive.set(true);
     sessionPaused.set(false);
+    sessionAutoPaused.set(false);
     return newId;
   } finally {
     startInflight = null;
@@ -53,8 +58,28 @@ export async function requestStop(): Promise<SessionSummary | null> {
     sessionActive.set(false);
     sessionId.set(null);
     sessionPaused.set(false);
+    sessionAutoPaused.set(false);
     return result ?? null;
   } finally {
     stopInflight = null;
   }
 }
+
+/**
+ * Discard the active session without saving it. Waits for any in-flight stop
+ * so the two can't race. Returns the discarded session ID, or null.
+ */
+export async function requestDiscard(): Promise<string | null> {
+  if (!get(sessionActive)) return null;
+  if (stopInflight) {
+    await stopInflight;
+    return null;
+  }
+
+  const id = await api.discardSession();
+  sessionActive.set(false);
+  sessionId.set(null);
+  sessionPaused.set(false);
+  sessionAutoPaused.set(false);
+  return id;
+}
@@ -3,7 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 export interface DeviceInfo {
   id: string;
   name: string | null;
-  device_type: 'HeartRate' | 'Power' | 'CadenceSpeed' | 'FitnessTrainer';
+  device_type: 'HeartRate' | 'Power' | 'CadenceSpeed' | 'FitnessTrainer' | 'MuscleOxygen' | 'Radar' | 'Environment';
   status: 'Disconnected' | 'Connecting' | 'Connected' | 'Reconnecting';
   transport: 'Ble' | 'AntPlus';
   rssi: number | null;
@@ -13,6 +13,7 @@ export interface DeviceInfo {
   model_number?: string | null;
   serial_number?: string | null;
   device_group?: string | null;
+  alias?: string | null;
   in_range?: boolean;
 }
 
@@ -21,12 +22,72 @@ export interface SensorReading {
   HeartRate?: { bpm: number; epoch_ms: number; device_id: string };
   Cadence?: { rpm: number; epoch_ms: number; device_id: string };
   Speed?: { kmh: number; epoch_ms: number; device_id: string };
+  Hrv?: { rr_ms: number[]; epoch_ms: number; device_id: string };
+  Energy?: { kilojoules: number; epoch_ms: number; device_id: string };
+  MuscleOxygen?: { smo2: number; thb: number; epoch_ms: number; device_id: string };
+  RadarTargets?: { threats: RadarThreat[]; epoch_ms: number; device_id: string };
+  Temperature?: { celsius: number; epoch_ms: number; device_id: string };
+  Gear?: { front: number; rear: number; epoch_ms: number; device_id: string };
+  SecondaryPower?: { watts: number; epoch_ms: number; device_id: string };
+  AveragePower?: { watts: number; epoch_ms: number; device_id: string };
+  Grade?: { percent: number; epoch_ms: number };
+  Pedaling?: {
+    torque_effectiveness: number | null;
+    smoothness: number | null;
+    top_dead_spot_deg: number | null;
+    bottom_dead_spot_deg: number | null;
+    epoch_ms: number;
+    device_id: string;
+  };
+  Lap?: { epoch_ms: number };
+}
+
+export interface RadarThreat {
+  id: number;
+  distance_m: number;
+  speed_kmh: number;
+}
+
+/** Payload of the `radar_update` event. */
+export interface RadarUpdate {
+  device_id: string;
+  epoch_ms: number;
+  threats: RadarThreat[];
+}
+
+/** Payload of the `device_battery_low` event. */
+export interface DeviceBatteryLow {
+  device_id: string;
+  level: number;
+}
+
+/** Payload of the `device_signal_weak` event. */
+export interface DeviceSignalWeak {
+  device_id: string;
+  rssi: number;
+}
+
+/** Payload of the `power_mismatch` event: trainer vs power meter, averaged over 10s. */
+export interface PowerMismatch {
+  trainer_id: string;
+  meter_id: string;
+  trainer_watts: number;
+  meter_watts: number;
+  /** Trainer relative to the meter, e.g. 12.5 = trainer reads 12.5% high */
+  diff_pct: number;
+}
+
+/** Payload of the `primary_changed` event. */
+export interface PrimaryChanged {
+  device_type: DeviceInfo['device_type'];
+  device_id: string;
 }
 
 export interface LiveMetrics {
   elapsed_secs: number;
   current_power: number | null;
   avg_power_3s: number | null;
+  smoothed_power_3s: number | null;
   avg_power_10s: number | null;
   avg_power_30s: number | null;
   normalized_power: number | null;
@@ -41,6 +102,19 @@ export interface LiveMetrics {
   stale_hr: boolean;
   stale_cadence: boolean;
   stale_speed: boolean;
+  lap_number: number;
+  lap_elapsed_secs: number;
+  lap_avg_power: number | null;
+  lap_normalized_power: number | null;
+  /** Right-pedal % from a dual-sided meter */
+  pedal_balance?: number | null;
+  avg_pedal_balance_30s?: number | null;
+}
+
+export interface StructuredRpe {
+  overall: number;
+  legs: number;
+  breathing: number;
 }
 
 export interface SessionSummary {
@@ -64,18 +138,70 @@ export interface SessionSummary {
   activity_type?: string;
   rpe?: number;
   notes?: string;
+  efficiency_factor?: number | null;
+  calories?: number | null;
+  avg_cadence_active?: number | null;
+  avg_temp_c?: number | null;
+  total_ascent_m?: number | null;
+  is_favorite: boolean;
+  xpower?: number | null;
+  /** 0–100; low when power dropouts skewed the integrated work */
+  data_quality_score?: number | null;
+  /** Mean right-pedal % from a dual-sided meter */
+  avg_pedal_balance?: number | null;
 }
 
+/** Power zone scheme; boundaries come from the front of `power_zones` */
+export type PowerZoneModel = 'three' | 'five' | 'seven';
+
 export interface SessionConfig {
   ftp: number;
   weight_kg: number;
   hr_zones: [number, number, number, number, number];
   units: 'metric' | 'imperial';
   power_zones: [number, number, number, number, number, number];
+  power_zone_model?: PowerZoneModel;
   date_of_birth: string | null;
   sex: string | null;
   resting_hr: number | null;
   max_hr: number | null;
+  cadence_zones: [number, number, number, number];
+  /** Running pace band edges in seconds per km, slowest first */
+  pace_zones?: [number, number, number, number];
+  critical_power: number | null;
+  w_prime: number | null;
+  crank_length_mm: number;
+  battery_low_threshold: number;
+  autosave_interval_secs: number;
+  connection_check_interval_secs: number;
+  /** Longest reading interval analysis counts as continuous time (1–30s) */
+  max_gap_secs: number;
+  /** Running power threshold; runs use `ftp` when null */
+  ftp_running?: number | null;
+  /** Rowing power threshold; rows use `ftp` when null */
+  ftp_rowing?: number | null;
+  /** Reconnect the most recently used sensors when the app starts */
+  auto_connect_on_start: boolean;
+  /** Pause the session after this many seconds without power or cadence; off when null */
+  auto_pause_secs?: number | null;
+  /** Derive speed and distance from power when no speed sensor reports */
+  virtual_speed: boolean;
+  /** Bike mass added to rider weight by the virtual speed model */
+  bike_weight_kg: number;
+  /** Rolling resistance coefficient for virtual speed */
+  virtual_crr: number;
+  /** Drag area (m²) for virtual speed */
+  virtual_cda: number;
+}
+
+export interface TrainerCapabilities {
+  min_power_w: number;
+  max_power_w: number;
+  power_increment_w: number;
+  min_resistance: number;
+  max_resistance: number;
+  resistance_increment: number;
+  supports_sim: boolean;
 }
 
 export interface CharacteristicInfo {
@@ -90,6 +216,14 @@ export interface ServiceInfo {
   characteristics: CharacteristicInfo[];
 }
 
+/** Connection reliability of one device since the app started. */
+export interface DeviceHealth {
+  /** Seconds on the current connection; null while disconnected */
+  uptime_secs: number | null;
+  /** Watchdog-detected drops since the last explicit connect */
+  drop_count: number;
+}
+
 export interface DeviceDetails {
   id: string;
   name: string | null;
@@ -117,12 +251,62 @@ export interface TimeseriesPoint {
 export interface PowerCurvePoint {
   duration_secs: number;
   watts: number;
+  /** Watts as % of the session's FTP; only set when requested */
+  pct_ftp?: number | null;
+}
+
+export interface BestEffort {
+  duration_secs: number;
+  watts: number;
+  session_id: string;
+}
+
+export interface Interval {
+  start_secs: number;
+  end_secs: number;
+  avg_power: number;
+  duration_secs: number;
+}
+
+export interface LoadPoint {
+  date: string;
+  ctl: number;
+  atl: number;
+  tsb: number;
+}
+
+export type PeriodGranularity = 'week' | 'month';
+
+export interface PeriodSummary {
+  period_start: string;
+  session_count: number;
+  total_duration_secs: number;
+  total_tss: number;
+  total_work_kj: number;
+  total_distance_km: number;
+}
+
+export interface FtpHistoryEntry {
+  id: number;
+  date: string;
+  ftp: number;
+  source: string;
+}
+
+export interface CalibrationEntry {
+  id: number;
+  device_id: string;
+  date: string;
+  zero_offset: number | null;
+  spindown_ms: number | null;
 }
 
 export interface ZoneBucket {
   zone: number;
   duration_secs: number;
   percentage: number;
+  /** Time-weighted average watts (power zones) or bpm (HR zones) */
+  avg_value?: number | null;
 }
 
 export interface PwcMarkers {
@@ -137,7 +321,85 @@ export interface SessionAnalysis {
   power_curve: PowerCurvePoint[];
   power_zone_distribution: ZoneBucket[];
   hr_zone_distribution: ZoneBucket[];
+  cadence_zone_distribution: ZoneBucket[];
+  pace_zone_distribution: ZoneBucket[];
   pwc: PwcMarkers | null;
+  decoupling_pct: number | null;
+  wbal: number[] | null;
+  quadrants: QuadrantPoint[];
+  muscle_oxygen: MuscleOxygenPoint[];
+  hr_recovery_bpm: number | null;
+  avg_torque_effectiveness: number | null;
+  avg_pedal_smoothness: number | null;
+  gaps: Gap[];
+  flatlines: Flatline[];
+  segments: SessionSegment[];
+}
+
+export interface Gap {
+  channel: 'power' | 'heart_rate' | 'cadence' | 'speed';
+  start_secs: number;
+  end_secs: number;
+  duration_secs: number;
+}
+
+export interface Flatline {
+  channel: Gap['channel'];
+  start_secs: number;
+  end_secs: number;
+}
+
+export interface SessionSegment {
+  id: number;
+  session_id: string;
+  start_secs: number;
+  end_secs: number;
+  label: string;
+}
+
+export interface MuscleOxygenPoint {
+  elapsed_secs: number;
+  smo2: number;
+  thb: number;
+}
+
+export interface PowerCurveComparisonPoint {
+  duration_secs: number;
+  session_watts: number | null;
+  best_watts: number | null;
+  new_best: boolean;
+}
+
+export interface PowerCurveComparison {
+  points: PowerCurveComparisonPoint[];
+}
+
+export interface PersonalBest {
+  duration_secs: number;
+  previous_watts: number;
+  watts: number;
+}
+
+/** Payload of the `new_personal_best` event, sent after a ride is saved. */
+export interface NewPersonalBests {
+  session_id: string;
+  records: PersonalBest[];
+}
+
+export interface PowerSourceComparison {
+  reference_device_id: string;
+  compared_device_id: string;
+  paired_secs: number;
+  mean_delta_watts: number;
+  mean_pct_diff: number;
+  max_pct_diff: number;
+}
+
+export interface QuadrantPoint {
+  elapsed_secs: number;
+  cpv: number;
+  aepf: number;
+  quadrant: number;
 }
 
 export type ZoneMode = 'Power' | 'HeartRate';
@@ -150,6 +412,35 @@ export interface ZoneTarget {
   duration_secs: number | null;
 }
 
+export type FecCommandResult =
+  | 'pass'
+  | 'fail'
+  | 'not_supported'
+  | 'rejected'
+  | 'pending'
+  | 'uninitialized';
+
+export interface FecCommandStatus {
+  last_command_id: number;
+  sequence: number | null;
+  result: FecCommandResult;
+  data: number[];
+}
+
+export interface ZoneControlConfig {
+  max_watts_up_per_tick: number;
+  max_watts_down_per_tick: number;
+  min_power: number;
+  safety_power: number;
+  hr_sensor_warn_secs: number;
+  hr_sensor_stop_secs: number;
+  power_sensor_warn_secs: number;
+  cadence_zero_secs: number;
+  /** Ignored on save; use setPowerBias */
+  power_bias_pct?: number;
+  hr_reserve_ceiling_pct?: number;
+}
+
 export interface ZoneControlStatus {
   active: boolean;
   mode: ZoneMode | null;
@@ -163,6 +454,19 @@ export interface ZoneControlStatus {
   paused: boolean;
   phase: string;
   safety_note: string | null;
+  pid_debug: PidDebug | null;
+}
+
+export interface PidDebug {
+  smoothed_hr: number;
+  error: number;
+  p: number;
+  i: number;
+  d: number;
+  unclamped_output: number;
+  clamped_adjustment: number;
+  commanded_power: number;
+  actual_power: number | null;
 }
 
 export type StopReason =
@@ -183,6 +487,48 @@ export interface ZoneRideConfig {
   time_to_zone_secs: number | null;
 }
 
+export type StepTarget =
+  | { Watts: number }
+  | { FtpPct: number }
+  | { Ramp: { from: number; to: number } }
+  | 'FreeRide';
+
+export interface WorkoutStep {
+  duration_secs: number;
+  target: StepTarget;
+}
+
+export interface Workout {
+  steps: WorkoutStep[];
+}
+
+export interface WorkoutStatus {
+  active: boolean;
+  paused: boolean;
+  step_index: number | null;
+  step_count: number;
+  elapsed_secs: number;
+  total_secs: number;
+  step_remaining_secs: number | null;
+  commanded_power: number | null;
+}
+
+export interface RampTestResult {
+  last_completed_watts: number | null;
+  best_minute_watts: number | null;
+  ftp_estimate: number | null;
+  duration_secs: number;
+}
+
+export interface RampTestStatus {
+  active: boolean;
+  elapsed_secs: number;
+  step_index: number | null;
+  target_watts: number | null;
+  result: RampTestResult | null;
+  failure: string | null;
+}
+
 export type SimProfile = 'SteadyState' | 'Intervals' | 'Ramp' | 'Stochastic';
 
 export interface SimStatusResponse {
@@ -196,6 +542,8 @@ export interface PrereqStatus {
   bluetooth_service: boolean;
   all_met: boolean;
   pkexec_available: boolean;
+  ble_available: boolean;
+  bluetooth_permission: boolean;
 }
 
 export interface FixResult {
@@ -213,36 +561,102 @@ export function extractError(e: unknown): string {
 
 export const api = {
   getKnownDevices: () => invoke<DeviceInfo[]>('get_known_devices'),
-  scanDevices: () => invoke<DeviceInfo[]>('scan_devices'),
+  scanDevices: (scanDurationSecs?: number, antRetries?: number) =>
+    invoke<DeviceInfo[]>('scan_devices', { scanDurationSecs, antRetries }),
   connectDevice: (deviceId: string) => invoke<DeviceInfo>('connect_device', { deviceId }),
+  pairAntDevice: (deviceType: DeviceInfo['device_type'], deviceNumber: number) =>
+    invoke<DeviceInfo>('pair_ant_device', { deviceType, deviceNumber }),
   getDeviceDetails: (deviceId: string) => invoke<DeviceDetails>('get_device_details', { deviceId }),
+  getDeviceHealth: (deviceId: string) => invoke<DeviceHealth>('get_device_health', { deviceId }),
+  setPowerZeroOffset: (deviceId: string) => invoke<number>('set_power_zero_offset', { deviceId }),
+  getTrainerCommandStatus: (deviceId: string) =>
+    invoke<FecCommandStatus | null>('get_trainer_command_status', { deviceId }),
   disconnectDevice: (deviceId: string) => invoke<void>('disconnect_device', { deviceId }),
+  forgetDevice: (deviceId: string) => invoke<void>('forget_device', { deviceId }),
   startSession: () => invoke<string>('start_session'),
   stopSession: () => invoke<SessionSummary | null>('stop_session'),
+  discardSession: () => invoke<string | null>('discard_session'),
+  setDualRecord: (enabled: boolean) => invoke<void>('set_dual_record', { enabled }),
+  setHrFusion: (enabled: boolean) => invoke<void>('set_hr_fusion', { enabled }),
+  getHrFusion: () => invoke<boolean>('get_hr_fusion'),
+  setPacketTrace: (enabled: boolean) => invoke<void>('set_packet_trace', { enabled }),
   pauseSession: () => invoke<void>('pause_session'),
+  newLap: () => invoke<number>('new_lap'),
   resumeSession: () => invoke<void>('resume_session'),
-  listSessions: () => invoke<SessionSummary[]>('list_sessions'),
+  listSessions: (favoritesOnly?: boolean) =>
+    invoke<SessionSummary[]>('list_sessions', { favoritesOnly: favoritesOnly ?? null }),
+  setSessionFavorite: (sessionId: string, isFavorite: boolean) =>
+    invoke<void>('set_session_favorite', { sessionId, isFavorite }),
+  searchSessions: (query: string) => invoke<SessionSummary[]>('search_sessions', { query }),
   getSession: (sessionId: string) => invoke<SessionSummary>('get_session', { sessionId }),
-  getSessionAnalysis: (sessionId: string) => invoke<SessionAnalysis>('get_session_analysis', { sessionId }),
+  /**
+   * `units` defaults to the configured display units; speed comes back in km/h or mph.
+   * `asFtpPct` fills `pct_ftp` on each power curve point.
+   */
+  getSessionAnalysis: (
+    sessionId: string,
+    smoothingSecs?: number,
+    units?: 'metric' | 'imperial',
+    asFtpPct?: boolean,
+  ) =>
+    invoke<SessionAnalysis>('get_session_analysis', {
+      sessionId,
+      smoothingSecs: smoothingSecs ?? null,
+      units: units ?? null,
+      asFtpPct: asFtpPct ?? null,
+    }),
+  addSessionSegment: (sessionId: string, startSecs: number, endSecs: number, label: string) =>
+    invoke<SessionSegment>('add_session_segment', { sessionId, startSecs, endSecs, label }),
+  deleteSessionSegment: (segmentId: number) => invoke<void>('delete_session_segment', { segmentId }),
+  getSessionSegments: (sessionId: string) =>
+    invoke<SessionSegment[]>('get_session_segments', { sessionId }),
+  getSessionIntervals: (sessionId: string) => invoke<Interval[]>('get_session_intervals', { sessionId }),
+  getPowerSourceComparison: (sessionId: string) =>
+    invoke<PowerSourceComparison | null>('get_power_source_comparison', { sessionId }),
   getUserConfig: () => invoke<SessionConfig>('get_user_config'),
   saveUserConfig: (config: SessionConfig) => invoke<void>('save_user_config', { config }),
   setTrainerPower: (watts: number) => invoke<void>('set_trainer_power', { watts }),
   setTrainerResistance: (level: number) => invoke<void>('set_trainer_resistance', { level }),
   setTrainerSimulation: (grade: number, crr: number, cw: number) =>
     invoke<void>('set_trainer_simulation', { grade, crr, cw }),
+  getTrainerCapabilities: (deviceId: string) =>
+    invoke<TrainerCapabilities>('get_trainer_capabilities', { deviceId }),
   startTrainer: () => invoke<void>('start_trainer'),
   stopTrainer: () => invoke<void>('stop_trainer'),
-  exportSessionFit: (sessionId: string) => invoke<string>('export_session_fit', { sessionId }),
+  exportSessionFit: (sessionId: string, fillGaps?: boolean) =>
+    invoke<string>('export_session_fit', { sessionId, fillGaps }),
+  exportSessionRawCsv: (sessionId: string) =>
+    invoke<string>('export_session_raw_csv', { sessionId }),
+  findDuplicateSessions: () => invoke<[string, string][]>('find_duplicate_sessions'),
+  mergeSessions: (keep: string, drop: string) => invoke<void>('merge_sessions', { keep, drop }),
+  splitSession: (sessionId: string, atSecs: number) =>
+    invoke<[string, string]>('split_session', { sessionId, atSecs }),
+  concatSessions: (first: string, second: string, force?: boolean) =>
+    invoke<string>('concat_sessions', { first, second, force }),
+  getStravaAuthorizeUrl: (clientId: string) =>
+    invoke<string>('get_strava_authorize_url', { clientId }),
+  connectStrava: (clientId: string, clientSecret: string, code: string) =>
+    invoke<void>('connect_strava', { clientId, clientSecret, code }),
+  disconnectStrava: () => invoke<void>('disconnect_strava'),
+  getStravaConnected: () => invoke<boolean>('get_strava_connected'),
+  uploadSessionStrava: (sessionId: string) =>
+    invoke<number>('upload_session_strava', { sessionId }),
+  importFit: (path: string) => invoke<string>('import_fit', { path }),
   setPrimaryDevice: (deviceType: string, deviceId: string) =>
     invoke<void>('set_primary_device', { deviceType, deviceId }),
   getPrimaryDevices: () => invoke<Record<string, string>>('get_primary_devices'),
+  setWheelCircumference: (deviceId: string, mm: number) =>
+    invoke<void>('set_wheel_circumference', { deviceId, mm }),
   unlinkDevices: (deviceId: string) => invoke<void>('unlink_devices', { deviceId }),
+  setDeviceAlias: (deviceId: string, alias: string | null) =>
+    invoke<void>('set_device_alias', { deviceId, alias }),
   updateSessionMetadata: (
     sessionId: string,
     title: string | null,
     activityType: string | null,
     rpe: number | null,
     notes: string | null,
+    structuredRpe: StructuredRpe | null = null,
   ) =>
     invoke<void>('update_session_metadata', {
       sessionId,
@@ -250,21 +664,65 @@ export const api = {
       activityType,
       rpe,
       notes,
+      structuredRpe,
     }),
+  getStructuredRpe: (sessionId: string) =>
+    invoke<StructuredRpe | null>('get_structured_rpe', { sessionId }),
   deleteSession: (sessionId: string) => invoke<void>('delete_session', { sessionId }),
   startZoneControl: (target: ZoneTarget) => invoke<void>('start_zone_control', { target }),
+  getZoneControlConfig: () => invoke<ZoneControlConfig>('get_zone_control_config'),
+  saveZoneControlConfig: (config: ZoneControlConfig) =>
+    invoke<void>('save_zone_control_config', { config }),
+  setPowerBias: (pct: number) => invoke<number>('set_power_bias', { pct }),
   stopZoneControl: () => invoke<StopReason | null>('stop_zone_control'),
   pauseZoneControl: () => invoke<void>('pause_zone_control'),
   resumeZoneControl: () => invoke<void>('resume_zone_control'),
   getZoneControlStatus: () => invoke<ZoneControlStatus>('get_zone_control_status'),
+  setZoneControlDebug: (enabled: boolean) => invoke<void>('set_zone_control_debug', { enabled }),
+  startWorkout: (workout: Workout) => invoke<void>('start_workout', { workout }),
+  importWorkout: (path: string) => invoke<Workout>('import_workout', { path }),
+  stopWorkout: () => invoke<void>('stop_workout'),
+  pauseWorkout: () => invoke<void>('pause_workout'),
+  resumeWorkout: () => invoke<void>('resume_workout'),
+  skipWorkoutStep: () => invoke<void>('skip_workout_step'),
+  getWorkoutStatus: () => invoke<WorkoutStatus>('get_workout_status'),
+  startRampTest: (startWatts: number, stepWatts: number, stepSecs: number) =>
+    invoke<void>('start_ramp_test', { startWatts, stepWatts, stepSecs }),
+  stopRampTest: () => invoke<RampTestResult | null>('stop_ramp_test'),
+  getRampTestStatus: () => invoke<RampTestStatus>('get_ramp_test_status'),
   estimateInitialPower: (targetHr: number) => invoke<number | null>('estimate_initial_power', { targetHr }),
   saveZoneRideConfig: (sessionId: string, zoneConfig: string) =>
     invoke<void>('save_zone_ride_config', { sessionId, zoneConfig }),
   getZoneRideConfig: (sessionId: string) => invoke<string | null>('get_zone_ride_config', { sessionId }),
   getBestPowerCurve: (period: string) =>
     invoke<PowerCurvePoint[]>('get_best_power_curve', { period }),
+  getPowerCurveWithBest: (sessionId: string) =>
+    invoke<PowerCurveComparison>('get_power_curve_with_best', { sessionId }),
+  getBestEfforts: (durations: number[]) => invoke<BestEffort[]>('get_best_efforts', { durations }),
+  getTrainingLoad: (from: string, to: string) => invoke<LoadPoint[]>('get_training_load', { from, to }),
+  getPeriodSummary: (granularity: PeriodGranularity, from: string, to: string) =>
+    invoke<PeriodSummary[]>('get_period_summary', { granularity, from, to }),
+  estimateFtp: (sessionId: string) => invoke<number | null>('estimate_ftp', { sessionId }),
+  acceptFtpEstimate: (sessionId: string) => invoke<number>('accept_ftp_estimate', { sessionId }),
+  getFtpHistory: () => invoke<FtpHistoryEntry[]>('get_ftp_history'),
+  recomputeSessionMetrics: (sessionId: string, ftp: number) =>
+    invoke<SessionSummary>('recompute_session_metrics', { sessionId, ftp }),
+  recomputeAllAfter: (date: string, ftp: number) =>
+    invoke<number>('recompute_all_after', { date, ftp }),
+  recordCalibration: (deviceId: string, zeroOffset: number | null, spindownMs: number | null) =>
+    invoke<void>('record_calibration', { deviceId, zeroOffset, spindownMs }),
+  getCalibrationHistory: (deviceId: string) =>
+    invoke<CalibrationEntry[]>('get_calibration_history', { deviceId }),
+  exportPowerCurveCsv: (afterDate?: string, path?: string) =>
+    invoke<string>('export_power_curve_csv', { afterDate: afterDate ?? null, path: path ?? null }),
+  exportBackup: (path: string) => invoke<void>('export_backup', { path }),
+  importBackup: (path: string) => invoke<void>('import_backup', { path }),
+  listProfiles: () => invoke<string[]>('list_profiles'),
+  getActiveProfile: () => invoke<string>('get_active_profile'),
+  switchProfile: (name: string) => invoke<void>('switch_profile', { name }),
   backfillPowerCurves: () => invoke<number>('backfill_power_curves'),
   checkPrerequisites: () => invoke<PrereqStatus>('check_prerequisites'),
+  retryBluetooth: () => invoke<PrereqStatus>('retry_bluetooth'),
   fixPrerequisites: () => invoke<FixResult>('fix_prerequisites'),
   simStart: (profile: SimProfile) => invoke<void>('sim_start', { profile }),
   simStop: () => invoke<void>('sim_stop'),
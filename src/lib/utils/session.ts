@@ -15,6 +15,9 @@ export const TYPE_LABELS: Record<string, string> = {
   warmup: 'Warmup',
   group_ride: 'Group Ride',
   free_ride: 'Free Ride',
+  outdoor_sim: 'Outdoor Sim',
+  run: 'Run',
+  row: 'Row',
   other: 'Other',
 };
 